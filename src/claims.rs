@@ -13,16 +13,19 @@
 // limitations under the License.
 
 use crate::keys::extract_keypair;
-use crate::util::{format_output, Output, OutputKind};
+use crate::par::looks_like_archive;
+use crate::reg::{strip_oci_scheme, AuthOpts, WASM_MAGIC};
+use crate::util::{format_error, format_output, parse_duration, Output, OutputKind, WashError};
 use nkeys::{KeyPair, KeyPairType};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::Read;
-use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use structopt::clap::AppSettings;
 use structopt::StructOpt;
 use term_table::{
@@ -34,7 +37,15 @@ use wascap::caps::*;
 use wascap::jwt::{
     Account, Actor, CapabilityProvider, Claims, Operator, Token, TokenValidation, WascapEntity,
 };
-use wascap::wasm::{days_from_now_to_jwt_time, sign_buffer_with_claims};
+use wascap::wasm::{days_from_now_to_jwt_time, embed_claims};
+
+/// Prefix of the claims tag used to embed the OCI reference of a signed module
+const OCI_REFERENCE_TAG_PREFIX: &str = "oci_reference:";
+/// Custom section a module's claims token is embedded in
+const JWT_SECTION: &str = "jwt";
+/// Target the hash of a wasm-based provider module is signed under, in place of the native
+/// targets of a provider archive
+const WASM_PROVIDER_TARGET: &str = "wasm32-unknown";
 
 #[derive(Debug, StructOpt, Clone)]
 #[structopt(
@@ -67,7 +78,7 @@ pub(crate) enum ClaimsCliCommand {
 
 #[derive(StructOpt, Debug, Clone)]
 pub(crate) struct InspectCommand {
-    /// Path to signed actor module or OCI URL of signed actor module
+    /// Path to signed actor module or OCI URL of signed actor module. Prefix the URL with oci:// to always pull from a registry
     pub(crate) module: String,
 
     /// Extract the raw JWT from the file and print to stdout
@@ -82,32 +93,38 @@ pub(crate) struct InspectCommand {
     #[structopt(long = "allow-latest")]
     allow_latest: bool,
 
-    /// OCI username, if omitted anonymous authentication will be used
-    #[structopt(
-        short = "u",
-        long = "user",
-        env = "WASH_REG_USER",
-        hide_env_values = true
-    )]
-    user: Option<String>,
-
-    /// OCI password, if omitted anonymous authentication will be used
-    #[structopt(
-        short = "p",
-        long = "password",
-        env = "WASH_REG_PASSWORD",
-        hide_env_values = true
-    )]
-    password: Option<String>,
+    #[structopt(flatten)]
+    opts: AuthOpts,
 
-    /// Allow insecure (HTTP) registry connections
-    #[structopt(long = "insecure")]
-    insecure: bool,
+    #[structopt(flatten)]
+    revocations: RevocationOpts,
 
     #[structopt(flatten)]
     pub(crate) output: Output,
 }
 
+/// Options to flag tokens signed by revoked issuers
+#[derive(StructOpt, Debug, Clone, Default)]
+pub(crate) struct RevocationOpts {
+    /// Path to a list of revoked issuer public keys, one per line. Tokens signed by a revoked issuer are marked REVOKED
+    #[structopt(long = "revocations")]
+    pub(crate) revocations: Option<PathBuf>,
+
+    /// Fail if a token was signed by a revoked issuer
+    #[structopt(long = "strict", requires = "revocations")]
+    pub(crate) strict: bool,
+}
+
+impl RevocationOpts {
+    /// Returns the revoked issuers among `issuers`, failing under `--strict` if there are any
+    pub(crate) fn revoked(
+        &self,
+        issuers: &[&str],
+    ) -> Result<Vec<String>, Box<dyn ::std::error::Error>> {
+        revoked_issuers(self.revocations.as_deref(), issuers, self.strict)
+    }
+}
+
 #[derive(StructOpt, Debug, Clone)]
 pub(crate) struct SignCommand {
     /// File to read
@@ -117,6 +134,42 @@ pub(crate) struct SignCommand {
     #[structopt(short = "d", long = "destination")]
     destination: Option<String>,
 
+    /// OCI reference the signed module will be published to, embedded in the module's claims
+    #[structopt(long = "oci-reference")]
+    oci_reference: Option<String>,
+
+    /// Sign the module as a wasm-based capability provider, embedding provider claims with its capability contract ID, vendor, revision and version like `par create` does for native providers. The subject key is a service key
+    #[structopt(
+        long = "provider",
+        name = "provider-module",
+        requires_all = &["capid", "vendor"],
+        conflicts_with_all = &[
+            "provider",
+            "capabilities",
+            "caps-file",
+            "keyvalue",
+            "msg-broker",
+            "http-server",
+            "http-client",
+            "blob-store",
+            "extras",
+            "logging",
+            "eventstream",
+            "tags",
+            "call-alias",
+            "oci-reference",
+        ]
+    )]
+    provider_module: bool,
+
+    /// Capability contract ID that the provider module implements (with --provider)
+    #[structopt(long = "capid", requires = "provider-module")]
+    capid: Option<String>,
+
+    /// A human-readable string identifying the vendor of the provider module (with --provider)
+    #[structopt(long = "vendor", requires = "provider-module")]
+    vendor: Option<String>,
+
     #[structopt(flatten)]
     metadata: ActorMetadata,
 }
@@ -151,6 +204,22 @@ struct GenerateCommon {
     #[structopt(short = "b", long = "nbf")]
     not_before_days: Option<u64>,
 
+    /// Indicates the token expires after the given duration, e.g. 12h or 30d. Alternative to --expires for finer-grained windows
+    #[structopt(
+        long = "expires-in",
+        parse(try_from_str = parse_duration),
+        conflicts_with = "expires-in-days"
+    )]
+    expires_in: Option<Duration>,
+
+    /// Duration that must elapse before this token is valid, e.g. 12h or 30d. Alternative to --nbf for finer-grained windows
+    #[structopt(
+        long = "not-before",
+        parse(try_from_str = parse_duration),
+        conflicts_with = "not-before-days"
+    )]
+    not_before: Option<Duration>,
+
     /// Disables autogeneration of keys if seed(s) are not provided
     #[structopt(long = "disable-keygen")]
     disable_keygen: bool,
@@ -159,6 +228,24 @@ struct GenerateCommon {
     pub(crate) output: Output,
 }
 
+impl GenerateCommon {
+    /// JWT `exp` time, from either --expires-in or --expires
+    fn expires(&self) -> Option<u64> {
+        match self.expires_in {
+            Some(duration) => Some(duration_from_now_to_jwt_time(duration)),
+            None => days_from_now_to_jwt_time(self.expires_in_days),
+        }
+    }
+
+    /// JWT `nbf` time, from either --not-before or --nbf
+    fn not_before(&self) -> Option<u64> {
+        match self.not_before {
+            Some(duration) => Some(duration_from_now_to_jwt_time(duration)),
+            None => days_from_now_to_jwt_time(self.not_before_days),
+        }
+    }
+}
+
 #[derive(Debug, Clone, StructOpt)]
 pub(crate) struct OperatorMetadata {
     /// A descriptive name for the operator
@@ -292,6 +379,9 @@ pub(crate) struct ActorMetadata {
     /// Add custom capabilities
     #[structopt(short = "c", long = "cap", name = "capabilities")]
     custom_caps: Vec<String>,
+    /// Path to a file of capability contract IDs to add, separated by newlines or commas. Lines starting with # are ignored
+    #[structopt(long = "caps-file")]
+    caps_file: Option<PathBuf>,
     /// A list of arbitrary tags to be embedded in the token
     #[structopt(short = "t", long = "tag")]
     tags: Vec<String>,
@@ -335,7 +425,10 @@ pub(crate) async fn handle_command(
 ) -> Result<String, Box<dyn ::std::error::Error>> {
     match command {
         ClaimsCliCommand::Inspect(inspectcmd) => render_caps(inspectcmd).await,
-        ClaimsCliCommand::Sign(signcmd) => sign_file(signcmd),
+        ClaimsCliCommand::Sign(signcmd) => {
+            let output_kind = signcmd.metadata.common.output.kind;
+            sign_file(signcmd).map_err(|e| format_error(e, &output_kind))
+        }
         ClaimsCliCommand::Token(gencmd) => generate_token(gencmd),
     }
 }
@@ -410,7 +503,8 @@ fn generate_actor(actor: ActorMetadata) -> Result<String, Box<dyn ::std::error::
     if actor.extras {
         caps_list.push(wascap::caps::EXTRAS.to_string());
     }
-    caps_list.extend(actor.custom_caps.iter().cloned());
+    caps_list.extend(custom_capabilities(&actor)?);
+    let caps_list = dedup_capabilities(caps_list);
 
     if actor.provider && caps_list.len() > 1 {
         return Err("Capability providers cannot provide multiple capabilities at once.".into());
@@ -421,8 +515,8 @@ fn generate_actor(actor: ActorMetadata) -> Result<String, Box<dyn ::std::error::
         subject.public_key(),
         Some(caps_list),
         Some(actor.tags.clone()),
-        days_from_now_to_jwt_time(actor.common.expires_in_days),
-        days_from_now_to_jwt_time(actor.common.not_before_days),
+        actor.common.expires(),
+        actor.common.not_before(),
         actor.provider,
         actor.rev,
         actor.ver.clone(),
@@ -462,8 +556,8 @@ fn generate_operator(operator: OperatorMetadata) -> Result<String, Box<dyn ::std
         operator.name.clone(),
         self_sign_key.public_key(),
         self_sign_key.public_key(),
-        days_from_now_to_jwt_time(operator.common.not_before_days),
-        days_from_now_to_jwt_time(operator.common.expires_in_days),
+        operator.common.not_before(),
+        operator.common.expires(),
         if !additional_keys.is_empty() {
             additional_keys.iter().map(|k| k.public_key()).collect()
         } else {
@@ -509,8 +603,8 @@ fn generate_account(account: AccountMetadata) -> Result<String, Box<dyn ::std::e
         account.name.clone(),
         issuer.public_key(),
         subject.public_key(),
-        days_from_now_to_jwt_time(account.common.not_before_days),
-        days_from_now_to_jwt_time(account.common.expires_in_days),
+        account.common.not_before(),
+        account.common.expires(),
         if !additional_keys.is_empty() {
             additional_keys.iter().map(|k| k.public_key()).collect()
         } else {
@@ -551,8 +645,8 @@ fn generate_provider(provider: ProviderMetadata) -> Result<String, Box<dyn ::std
         provider.revision,
         provider.version.clone(),
         HashMap::new(),
-        days_from_now_to_jwt_time(provider.common.not_before_days),
-        days_from_now_to_jwt_time(provider.common.expires_in_days),
+        provider.common.not_before(),
+        provider.common.expires(),
     );
     let jwt = claims.encode(&issuer)?;
     let out = format_output(
@@ -564,9 +658,11 @@ fn generate_provider(provider: ProviderMetadata) -> Result<String, Box<dyn ::std
 }
 
 fn sign_file(cmd: SignCommand) -> Result<String, Box<dyn ::std::error::Error>> {
-    let mut sfile = File::open(&cmd.source).unwrap();
-    let mut buf = Vec::new();
-    sfile.read_to_end(&mut buf).unwrap();
+    let buf = std::fs::read(&cmd.source)
+        .map_err(|e| format!("Unable to read module {}: {}", cmd.source, e))?;
+    if cmd.provider_module {
+        return sign_provider_module(cmd, buf);
+    }
 
     let issuer = extract_keypair(
         cmd.metadata.issuer.clone(),
@@ -608,28 +704,126 @@ fn sign_file(cmd: SignCommand) -> Result<String, Box<dyn ::std::error::Error>> {
     if cmd.metadata.eventstream {
         caps_list.push(wascap::caps::EVENTSTREAMS.to_string());
     }
-    caps_list.extend(cmd.metadata.custom_caps.iter().cloned());
+    caps_list.extend(custom_capabilities(&cmd.metadata)?);
+    let caps_list = dedup_capabilities(caps_list);
 
     if cmd.metadata.provider && caps_list.len() > 1 {
         return Err("Capability providers cannot provide multiple capabilities at once.".into());
     }
 
-    let signed = sign_buffer_with_claims(
+    let mut tags = cmd.metadata.tags.clone();
+    if let Some(oci_reference) = &cmd.oci_reference {
+        crate::reg::parse_reference(oci_reference)?;
+        let oci_reference = strip_oci_scheme(oci_reference).unwrap_or(oci_reference);
+        tags.push(format!("{}{}", OCI_REFERENCE_TAG_PREFIX, oci_reference));
+    }
+
+    let claims = Claims::<Actor>::with_dates(
         cmd.metadata.name.clone(),
-        &buf,
-        subject,
-        issuer,
-        cmd.metadata.common.expires_in_days,
-        cmd.metadata.common.not_before_days,
-        caps_list.clone(),
-        cmd.metadata.tags.clone(),
+        issuer.public_key(),
+        subject.public_key(),
+        Some(caps_list.clone()),
+        Some(tags),
+        cmd.metadata.common.expires(),
+        cmd.metadata.common.not_before(),
         cmd.metadata.provider,
         cmd.metadata.rev,
         cmd.metadata.ver.clone(),
-        sanitize_alias(cmd.metadata.call_alias)?,
+        sanitize_alias(cmd.metadata.call_alias.clone())?,
+    );
+    let signed = embed_claims(&buf, &claims, &issuer)?;
+
+    let destination = signed_destination(&cmd);
+    std::fs::write(&destination, &signed)
+        .map_err(|e| format!("Unable to write signed module {}: {}", destination, e))?;
+    Ok(format_output(
+        format!(
+            "Successfully signed {} with capabilities: {}",
+            destination,
+            caps_list.join(",")
+        ),
+        json!({
+            "result": "success",
+            "output": destination,
+            "destination": destination,
+            "subject": subject.public_key(),
+            "issuer": issuer.public_key(),
+            "capabilities": caps_list,
+        }),
+        &cmd.metadata.common.output.kind,
+    ))
+}
+
+/// Signs a wasm-based capability provider with provider claims, whose target hashes hold the
+/// hash of the module without its claims
+fn sign_provider_module(
+    cmd: SignCommand,
+    buf: Vec<u8>,
+) -> Result<String, Box<dyn ::std::error::Error>> {
+    let mut module = strip_custom_section(&buf, JWT_SECTION)
+        .map_err(|e| format!("Unable to sign {}: {}", cmd.source, e))?;
+
+    let issuer = extract_keypair(
+        cmd.metadata.issuer.clone(),
+        Some(cmd.source.clone()),
+        cmd.metadata.common.directory.clone(),
+        KeyPairType::Account,
+        cmd.metadata.common.disable_keygen,
     )?;
+    let subject = extract_keypair(
+        cmd.metadata.subject.clone(),
+        Some(cmd.source.clone()),
+        cmd.metadata.common.directory.clone(),
+        KeyPairType::Service,
+        cmd.metadata.common.disable_keygen,
+    )?;
+
+    // Both are required by --provider
+    let capid = cmd.capid.clone().unwrap_or_default();
+    let vendor = cmd.vendor.clone().unwrap_or_default();
+    let mut target_hashes = HashMap::new();
+    target_hashes.insert(
+        WASM_PROVIDER_TARGET.to_string(),
+        format!("{:X}", Sha256::digest(&module)),
+    );
+    let claims = Claims::<CapabilityProvider>::with_dates(
+        cmd.metadata.name.clone(),
+        issuer.public_key(),
+        subject.public_key(),
+        capid.clone(),
+        vendor.clone(),
+        cmd.metadata.rev,
+        cmd.metadata.ver.clone(),
+        target_hashes,
+        cmd.metadata.common.not_before(),
+        cmd.metadata.common.expires(),
+    );
+    append_custom_section(&mut module, JWT_SECTION, claims.encode(&issuer)?.as_bytes());
+
+    let destination = signed_destination(&cmd);
+    std::fs::write(&destination, &module)
+        .map_err(|e| format!("Unable to write signed module {}: {}", destination, e))?;
+    Ok(format_output(
+        format!(
+            "Successfully signed {} as a {} provider by {}",
+            destination, capid, vendor
+        ),
+        json!({
+            "result": "success",
+            "output": destination,
+            "destination": destination,
+            "subject": subject.public_key(),
+            "issuer": issuer.public_key(),
+            "capid": capid,
+            "vendor": vendor,
+        }),
+        &cmd.metadata.common.output.kind,
+    ))
+}
 
-    let destination = match cmd.destination.clone() {
+/// Destination of a signed module, by default next to the source with a "_s" suffix
+fn signed_destination(cmd: &SignCommand) -> String {
+    match cmd.destination.clone() {
         Some(d) => d,
         None => {
             let path = PathBuf::from(cmd.source.clone())
@@ -651,65 +845,231 @@ fn sign_file(cmd: SignCommand) -> Result<String, Box<dyn ::std::error::Error>> {
                 format!("{}/{}_s.wasm", path, module_name)
             }
         }
-    };
+    }
+}
 
-    let mut outfile = File::create(&destination).unwrap();
-    let output = match outfile.write(&signed) {
-        Ok(_) => Ok(format_output(
-            format!(
-                "Successfully signed {} with capabilities: {}",
-                destination,
-                caps_list.join(",")
-            ),
-            json!({"result": "success", "destination": destination, "capabilities": caps_list}),
-            &cmd.metadata.common.output.kind,
-        )),
-        Err(e) => Err(Box::new(e)),
-    }?;
+/// Removes every custom section called `name` from a module, such as the claims of an earlier
+/// signature
+fn strip_custom_section(
+    module: &[u8],
+    name: &str,
+) -> Result<Vec<u8>, Box<dyn ::std::error::Error>> {
+    let sections = module_sections(module)?;
+    let mut stripped = module[..8].to_vec();
+    for section in sections.iter().filter(|s| !s.is_custom(name)) {
+        stripped.extend_from_slice(section.bytes);
+    }
+    Ok(stripped)
+}
+
+/// Returns the contents of the first custom section of a module called `name`
+fn custom_section<'a>(
+    module: &'a [u8],
+    name: &str,
+) -> Result<Option<&'a [u8]>, Box<dyn ::std::error::Error>> {
+    Ok(module_sections(module)?
+        .into_iter()
+        .find(|s| s.is_custom(name))
+        .and_then(|s| s.custom.map(|(_, contents)| contents)))
+}
+
+/// A section of a module, with its name and contents if it's a custom section
+struct Section<'a> {
+    bytes: &'a [u8],
+    custom: Option<(&'a [u8], &'a [u8])>,
+}
+
+impl Section<'_> {
+    fn is_custom(&self, name: &str) -> bool {
+        matches!(self.custom, Some((n, _)) if n == name.as_bytes())
+    }
+}
+
+/// Splits a module into the sections following its header
+fn module_sections(module: &[u8]) -> Result<Vec<Section<'_>>, Box<dyn ::std::error::Error>> {
+    // The magic number is followed by a four byte version
+    if module.len() < 8 || !module.starts_with(WASM_MAGIC) {
+        return Err("not a WebAssembly module".into());
+    }
+    let mut sections = vec![];
+    let mut pos = 8;
+    while pos < module.len() {
+        let start = pos;
+        let id = module[pos];
+        pos += 1;
+        let size = read_leb128(module, &mut pos)? as usize;
+        let end = pos + size;
+        if end > module.len() {
+            return Err("module has a truncated section".into());
+        }
+        let custom = if id == 0 {
+            let section = &module[..end];
+            let len = read_leb128(section, &mut pos)? as usize;
+            let name = section
+                .get(pos..pos + len)
+                .ok_or("module has a truncated section")?;
+            Some((name, &section[pos + len..]))
+        } else {
+            None
+        };
+        sections.push(Section {
+            bytes: &module[start..end],
+            custom,
+        });
+        pos = end;
+    }
+    Ok(sections)
+}
 
-    Ok(output)
+/// Reports whether a module is signed with the claims of a wasm-based capability provider,
+/// as embedded by `claims sign --provider`, rather than actor claims
+pub(crate) fn is_provider_module(module: &[u8]) -> bool {
+    custom_section(module, JWT_SECTION)
+        .ok()
+        .flatten()
+        .and_then(|jwt| std::str::from_utf8(jwt).ok())
+        .and_then(|jwt| Claims::<CapabilityProvider>::decode(jwt).ok())
+        .and_then(|claims| claims.metadata)
+        .is_some()
+}
+
+/// Appends a custom section to the end of a module
+fn append_custom_section(module: &mut Vec<u8>, name: &str, content: &[u8]) {
+    let mut payload = vec![];
+    write_leb128(&mut payload, name.len());
+    payload.extend_from_slice(name.as_bytes());
+    payload.extend_from_slice(content);
+    module.push(0);
+    write_leb128(module, payload.len());
+    module.extend(payload);
+}
+
+/// Reads an unsigned LEB128 section or name size at `pos`, advancing past it
+fn read_leb128(buf: &[u8], pos: &mut usize) -> Result<u32, Box<dyn ::std::error::Error>> {
+    let mut value = 0u32;
+    // A u32 takes at most five bytes
+    for shift in (0..35).step_by(7) {
+        let byte = *buf.get(*pos).ok_or("module has a truncated section")?;
+        *pos += 1;
+        value |= u32::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err("module has a malformed section size".into())
+}
+
+fn write_leb128(buf: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf.push(byte);
+            return;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+/// Content digest and size of an inspected module. The digest matches the layer digest
+/// of the module once pushed to a registry
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ModuleDigest {
+    pub(crate) sha256: String,
+    pub(crate) size: usize,
+}
+
+impl ModuleDigest {
+    pub(crate) fn of(module: &[u8]) -> ModuleDigest {
+        ModuleDigest {
+            sha256: format!("sha256:{:x}", Sha256::digest(module)),
+            size: module.len(),
+        }
+    }
 }
 
 async fn get_caps(
     cmd: &InspectCommand,
-) -> Result<Option<Token<Actor>>, Box<dyn ::std::error::Error>> {
-    let module_bytes = match File::open(&cmd.module) {
-        Ok(mut f) => {
+) -> Result<(Option<Token<Actor>>, ModuleDigest), Box<dyn ::std::error::Error>> {
+    // Anything that isn't an existing file is treated as an OCI reference
+    let local = match strip_oci_scheme(&cmd.module) {
+        Some(_) => None,
+        None => File::open(&cmd.module).ok(),
+    };
+    let module_bytes = match local {
+        Some(mut f) => {
             let mut buf = Vec::new();
-            f.read_to_end(&mut buf).unwrap();
+            f.read_to_end(&mut buf)?;
             buf
         }
-        Err(_) => {
+        None => {
+            let url = strip_oci_scheme(&cmd.module).unwrap_or(&cmd.module);
             crate::reg::pull_artifact(
-                cmd.module.to_string(),
+                url.to_string(),
                 cmd.digest.clone(),
                 cmd.allow_latest,
-                cmd.user.clone(),
-                cmd.password.clone(),
-                cmd.insecure,
+                cmd.opts.user.clone(),
+                cmd.opts.password()?,
+                cmd.opts.insecure,
+                cmd.opts.proxy.clone(),
             )
             .await?
         }
     };
 
+    check_module_format(&module_bytes, &cmd.module)?;
+    if is_provider_module(&module_bytes) {
+        return Err(format!(
+            "{} is a wasm-based capability provider, only actor claims can be inspected",
+            cmd.module
+        )
+        .into());
+    }
     // Extract will return an error if it encounters an invalid hash in the claims
     let claims = wascap::wasm::extract_claims(&module_bytes);
     match claims {
-        Ok(token) => Ok(token),
+        Ok(token) => Ok((token, ModuleDigest::of(&module_bytes))),
         Err(e) => Err(Box::new(e)),
     }
 }
 
+/// Rejects anything without the WebAssembly header up front, as extracting claims from it
+/// fails with an unhelpful parse error. Provider archives are the most common mix-up
+fn check_module_format(bytes: &[u8], name: &str) -> Result<(), Box<dyn ::std::error::Error>> {
+    if bytes.starts_with(WASM_MAGIC) {
+        Ok(())
+    } else if looks_like_archive(bytes) {
+        Err(format!(
+            "{} is a provider archive, not a WebAssembly module; did you mean `wash par inspect {}`?",
+            name, name
+        )
+        .into())
+    } else {
+        Err(format!(
+            "{} is not a WebAssembly module; did you mean `wash par inspect`?",
+            name
+        )
+        .into())
+    }
+}
+
 async fn render_caps(cmd: InspectCommand) -> Result<String, Box<dyn ::std::error::Error>> {
-    let caps = get_caps(&cmd).await?;
+    let (caps, module) = get_caps(&cmd).await?;
 
     let out = match caps {
         Some(token) => {
+            let revoked = cmd.revocations.revoked(&[&token.claims.issuer])?;
             if cmd.jwt_only {
                 token.jwt
             } else {
                 let validation = wascap::jwt::validate_token::<Actor>(&token.jwt)?;
-                render_actor_claims(token.claims, validation, &cmd.output)
+                render_actor_claims(
+                    token.claims,
+                    validation,
+                    &module,
+                    !revoked.is_empty(),
+                    &cmd.output,
+                )
             }
         }
         None => format!("No capabilities discovered in : {}", &cmd.module),
@@ -717,10 +1077,12 @@ async fn render_caps(cmd: InspectCommand) -> Result<String, Box<dyn ::std::error
     Ok(out)
 }
 
-/// Renders actor claims into provided output format
+/// Renders actor claims into provided output format, flagging them if their issuer is revoked
 pub(crate) fn render_actor_claims(
     claims: Claims<Actor>,
     validation: TokenValidation,
+    module: &ModuleDigest,
+    revoked: bool,
     output: &Output,
 ) -> String {
     let md = claims.metadata.clone().unwrap();
@@ -733,21 +1095,16 @@ pub(crate) fn render_actor_claims(
         "Capabilities"
     };
 
-    let tags = if let Some(tags) = &claims.metadata.as_ref().unwrap().tags {
-        if tags.is_empty() {
-            "None".to_string()
-        } else {
-            tags.join(",")
-        }
-    } else {
+    let (oci_reference, tags) = split_oci_reference(md.tags.clone().unwrap_or_default());
+    let tags = if tags.is_empty() {
         "None".to_string()
-    };
-
-    let friendly_caps: Vec<String> = if let Some(caps) = &claims.metadata.as_ref().unwrap().caps {
-        caps.iter().map(|c| capability_name(&c)).collect()
     } else {
-        vec![]
+        tags.join(",")
     };
+    let oci_reference = oci_reference.unwrap_or_else(|| "(Not set)".to_string());
+
+    let contract_ids = md.caps.clone().unwrap_or_default();
+    let friendly_caps: Vec<String> = contract_ids.iter().map(|c| capability_name(c)).collect();
 
     let call_alias = claims
         .metadata
@@ -766,13 +1123,21 @@ pub(crate) fn render_actor_claims(
                 "{}",
                 json!({ iss_label: claims.issuer,
                 sub_label: claims.subject,
+                "name": claims.name(),
                 "expires": validation.expires_human,
                 "can_be_used": validation.not_before_human,
+                "expires_at": claims.expires,
+                "not_before": claims.not_before,
                 "version": friendly_ver,
                 "revision": friendly_rev,
                 provider_json: friendly_caps,
+                "contract_ids": contract_ids,
                 "tags": tags,
                 "call_alias": call_alias,
+                "oci_reference": oci_reference,
+                "sha256": module.sha256,
+                "size": module.size,
+                "revoked": revoked,
                 })
             )
         }
@@ -789,6 +1154,21 @@ pub(crate) fn render_actor_claims(
                 TableCell::new_with_alignment(call_alias, 1, Alignment::Right),
             ]));
 
+            table.add_row(Row::new(vec![
+                TableCell::new("OCI Reference"),
+                TableCell::new_with_alignment(oci_reference, 1, Alignment::Right),
+            ]));
+
+            table.add_row(Row::new(vec![
+                TableCell::new("SHA256"),
+                TableCell::new_with_alignment(&module.sha256, 1, Alignment::Right),
+            ]));
+
+            table.add_row(Row::new(vec![
+                TableCell::new("Size (bytes)"),
+                TableCell::new_with_alignment(module.size, 1, Alignment::Right),
+            ]));
+
             table.add_row(Row::new(vec![TableCell::new_with_alignment(
                 provider,
                 2,
@@ -813,9 +1193,123 @@ pub(crate) fn render_actor_claims(
                 Alignment::Left,
             )]));
 
-            table.render()
+            if revoked {
+                format!(
+                    "REVOKED: issuer {} is on the revocation list\n{}",
+                    claims.issuer,
+                    table.render()
+                )
+            } else {
+                table.render()
+            }
+        }
+    }
+}
+
+/// Returns the issuers that appear in the revocation list at `path`, failing if there are any
+/// and `strict` is set
+pub(crate) fn revoked_issuers(
+    path: Option<&Path>,
+    issuers: &[&str],
+    strict: bool,
+) -> Result<Vec<String>, Box<dyn ::std::error::Error>> {
+    let path = match path {
+        Some(path) => path,
+        None => return Ok(vec![]),
+    };
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Unable to read revocation list {}: {}", path.display(), e))?;
+    let revocations = parse_revocations(&contents);
+    let mut revoked: Vec<String> = issuers
+        .iter()
+        .filter(|issuer| revocations.contains(**issuer))
+        .map(|issuer| issuer.to_string())
+        .collect();
+    revoked.sort();
+    revoked.dedup();
+    if strict && !revoked.is_empty() {
+        return Err(Box::new(WashError::Validation(format!(
+            "Signed by revoked issuer(s): {}",
+            revoked.join(", ")
+        ))));
+    }
+    Ok(revoked)
+}
+
+/// Parses a revocation list of public keys, one per line. Blank lines and `#` comments are ignored
+fn parse_revocations(contents: &str) -> HashSet<String> {
+    contents
+        .lines()
+        .filter_map(|line| line.split('#').next()?.split_whitespace().next())
+        .map(String::from)
+        .collect()
+}
+
+/// Collects the custom capabilities given with `--cap` and `--caps-file`, ensuring each is a
+/// `namespace:capability` contract ID
+fn custom_capabilities(actor: &ActorMetadata) -> Result<Vec<String>, Box<dyn ::std::error::Error>> {
+    let mut caps = actor.custom_caps.clone();
+    if let Some(path) = &actor.caps_file {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read capabilities file {}: {}", path.display(), e))?;
+        caps.extend(
+            contents
+                .lines()
+                .filter(|l| !l.trim_start().starts_with('#'))
+                .flat_map(|l| l.split(','))
+                .map(|c| c.trim().to_string())
+                .filter(|c| !c.is_empty()),
+        );
+    }
+    for cap in caps.iter() {
+        if !is_contract_id(cap) {
+            return Err(format!(
+                "Invalid capability '{}', expected a contract ID like namespace:capability (e.g. wasmcloud:keyvalue)",
+                cap
+            )
+            .into());
         }
     }
+    Ok(caps)
+}
+
+fn is_contract_id(cap: &str) -> bool {
+    let valid_part = |p: &str| {
+        !p.is_empty()
+            && p.chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+    };
+    let mut parts = cap.splitn(2, ':');
+    matches!((parts.next(), parts.next()), (Some(ns), Some(name)) if valid_part(ns) && valid_part(name))
+}
+
+/// Removes repeated capabilities, keeping the first occurrence of each
+fn dedup_capabilities(caps: Vec<String>) -> Vec<String> {
+    let mut unique: Vec<String> = Vec::with_capacity(caps.len());
+    for cap in caps {
+        if !unique.contains(&cap) {
+            unique.push(cap);
+        }
+    }
+    unique
+}
+
+fn duration_from_now_to_jwt_time(duration: Duration) -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    (now + duration).as_secs()
+}
+
+/// Separates the embedded OCI reference, if any, from the rest of an actor's tags
+fn split_oci_reference(tags: Vec<String>) -> (Option<String>, Vec<String>) {
+    let (oci, tags): (Vec<String>, Vec<String>) = tags
+        .into_iter()
+        .partition(|t| t.starts_with(OCI_REFERENCE_TAG_PREFIX));
+    let oci_reference = oci
+        .first()
+        .map(|t| t.trim_start_matches(OCI_REFERENCE_TAG_PREFIX).to_string());
+    (oci_reference, tags)
 }
 
 // * - we don't need render impls for Operator or Account because those tokens are never embedded into a module,
@@ -900,6 +1394,247 @@ mod test {
     use super::*;
     const SUBSCRIBER_OCI: &str = "wasmcloud.azurecr.io/subscriber:0.2.0";
 
+    #[test]
+    fn test_split_oci_reference() {
+        let (oci_reference, tags) = split_oci_reference(vec![
+            "testtag".to_string(),
+            format!("{}{}", OCI_REFERENCE_TAG_PREFIX, SUBSCRIBER_OCI),
+        ]);
+        assert_eq!(oci_reference.unwrap(), SUBSCRIBER_OCI);
+        assert_eq!(tags, vec!["testtag".to_string()]);
+
+        let (oci_reference, tags) = split_oci_reference(vec![]);
+        assert!(oci_reference.is_none());
+        assert!(tags.is_empty());
+    }
+
+    #[test]
+    fn test_check_module_format() {
+        assert!(check_module_format(b"\0asm\x01\0\0\0", "echo.wasm").is_ok());
+
+        let err = check_module_format(&[0x1f, 0x8b, 0x08, 0x00], "httpserver.par.gz")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("is a provider archive"));
+        assert!(err.contains("wash par inspect httpserver.par.gz"));
+
+        let mut tar = vec![0u8; 512];
+        tar[257..262].copy_from_slice(b"ustar");
+        assert!(check_module_format(&tar, "httpserver.par")
+            .unwrap_err()
+            .to_string()
+            .contains("is a provider archive"));
+
+        let err = check_module_format(b"hello world", "notes.txt")
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            err,
+            "notes.txt is not a WebAssembly module; did you mean `wash par inspect`?"
+        );
+    }
+
+    #[test]
+    fn test_module_digest() {
+        assert_eq!(
+            ModuleDigest::of(b""),
+            ModuleDigest {
+                sha256: "sha256:e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+                    .to_string(),
+                size: 0,
+            }
+        );
+        assert_eq!(ModuleDigest::of(b"\0asm\x01\0\0\0").size, 8);
+    }
+
+    #[test]
+    fn test_custom_capabilities_from_file() {
+        let dir = std::env::temp_dir().join("wash_claims_caps_file");
+        std::fs::create_dir_all(&dir).unwrap();
+        let caps_file = dir.join("caps.txt");
+        std::fs::write(
+            &caps_file,
+            "# extra capabilities\nwasmcloud:keyvalue, test:custom\n\nacme:widgets\n",
+        )
+        .unwrap();
+
+        let cmd = ClaimsCli::from_iter_safe(&[
+            "claims",
+            "sign",
+            "./actor.wasm",
+            "--name",
+            "CapsFile",
+            "-c",
+            "test:custom",
+            "--caps-file",
+            caps_file.to_str().unwrap(),
+        ])
+        .unwrap();
+        let metadata = match cmd.command {
+            ClaimsCliCommand::Sign(SignCommand { metadata, .. }) => metadata,
+            cmd => panic!("claims constructed incorrect command: {:?}", cmd),
+        };
+        let caps = dedup_capabilities(custom_capabilities(&metadata).unwrap());
+        assert_eq!(
+            caps,
+            vec!["test:custom", "wasmcloud:keyvalue", "acme:widgets"]
+        );
+
+        std::fs::write(&caps_file, "wasmcloud:keyvalue\nnotacontract\n").unwrap();
+        assert!(custom_capabilities(&metadata).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+
+        assert!(is_contract_id("wasmcloud:httpserver"));
+        assert!(!is_contract_id("wasmcloud:"));
+        assert!(!is_contract_id(":httpserver"));
+        assert!(!is_contract_id("wasmcloud httpserver"));
+    }
+
+    #[test]
+    fn test_claims_sign_validity_window() {
+        let cmd = ClaimsCli::from_iter_safe(&[
+            "claims",
+            "sign",
+            "./actor.wasm",
+            "--name",
+            "Windowed",
+            "--expires-in",
+            "12h",
+            "--not-before",
+            "30m",
+        ])
+        .unwrap();
+        let common = match cmd.command {
+            ClaimsCliCommand::Sign(SignCommand { metadata, .. }) => metadata.common,
+            cmd => panic!("claims constructed incorrect command: {:?}", cmd),
+        };
+        assert_eq!(common.expires_in.unwrap(), Duration::from_secs(43_200));
+        assert_eq!(common.not_before.unwrap(), Duration::from_secs(1_800));
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let expires = common.expires().unwrap();
+        assert!(expires >= now + 43_200 && expires <= now + 43_260);
+
+        assert!(ClaimsCli::from_iter_safe(&[
+            "claims",
+            "sign",
+            "./actor.wasm",
+            "--name",
+            "Windowed",
+            "--expires-in",
+            "12h",
+            "--expires",
+            "1",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_claims_sign_provider_module() {
+        let cmd = ClaimsCli::from_iter_safe(&[
+            "claims",
+            "sign",
+            "./kvprovider.wasm",
+            "--name",
+            "KV Provider",
+            "--provider",
+            "--capid",
+            "wasmcloud:keyvalue",
+            "--vendor",
+            "Acme",
+            "--rev",
+            "3",
+            "--ver",
+            "0.3.0",
+        ])
+        .unwrap();
+        match cmd.command {
+            ClaimsCliCommand::Sign(SignCommand {
+                provider_module,
+                capid,
+                vendor,
+                metadata,
+                ..
+            }) => {
+                assert!(provider_module);
+                assert_eq!(capid.unwrap(), "wasmcloud:keyvalue");
+                assert_eq!(vendor.unwrap(), "Acme");
+                assert_eq!(metadata.rev.unwrap(), 3);
+                assert_eq!(metadata.ver.unwrap(), "0.3.0");
+            }
+            cmd => panic!("claims constructed incorrect command: {:?}", cmd),
+        }
+
+        let sign = |args: &[&str]| {
+            let mut argv = vec!["claims", "sign", "./kvprovider.wasm", "--name", "KV"];
+            argv.extend_from_slice(args);
+            ClaimsCli::from_iter_safe(argv)
+        };
+        assert!(sign(&["--provider", "--capid", "wasmcloud:keyvalue"]).is_err());
+        assert!(sign(&["--capid", "wasmcloud:keyvalue", "--vendor", "Acme"]).is_err());
+        assert!(sign(&[
+            "--provider",
+            "--capid",
+            "wasmcloud:keyvalue",
+            "--vendor",
+            "Acme",
+            "--keyvalue"
+        ])
+        .is_err());
+        assert!(sign(&[
+            "--provider",
+            "--capid",
+            "wasmcloud:keyvalue",
+            "--vendor",
+            "Acme",
+            "--prov"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_provider_module_sections() {
+        // Header, an empty type section and a "name" custom section
+        let mut module = b"\0asm\x01\0\0\0\x01\x01\0".to_vec();
+        append_custom_section(&mut module, "name", b"\0");
+        let unsigned = module.clone();
+
+        append_custom_section(&mut module, JWT_SECTION, b"old.token");
+        assert_ne!(module, unsigned);
+        assert_eq!(
+            custom_section(&module, JWT_SECTION).unwrap(),
+            Some(&b"old.token"[..])
+        );
+        assert_eq!(custom_section(&unsigned, JWT_SECTION).unwrap(), None);
+        assert_eq!(
+            strip_custom_section(&module, JWT_SECTION).unwrap(),
+            unsigned
+        );
+        assert_eq!(
+            strip_custom_section(&unsigned, JWT_SECTION).unwrap(),
+            unsigned
+        );
+
+        // Section sizes past 127 bytes take several LEB128 bytes
+        let token = vec![b'a'; 300];
+        let mut signed = unsigned.clone();
+        append_custom_section(&mut signed, JWT_SECTION, &token);
+        assert_eq!(
+            &signed[unsigned.len()..unsigned.len() + 3],
+            &[0, 0xb0, 0x02]
+        );
+        assert!(signed.ends_with(&token));
+        assert_eq!(
+            strip_custom_section(&signed, JWT_SECTION).unwrap(),
+            unsigned
+        );
+
+        assert!(strip_custom_section(&signed[..signed.len() - 1], JWT_SECTION).is_err());
+        assert!(strip_custom_section(b"hello world", JWT_SECTION).is_err());
+    }
+
     #[test]
     fn test_claims_sanitize_alias() {
         const VALID_ALPHANUMERIC: &str = "abc123";
@@ -970,6 +1705,9 @@ mod test {
             "--allow-latest",
             "--insecure",
             "--jwt-only",
+            "--revocations",
+            "./revoked.txt",
+            "--strict",
         ])
         .unwrap();
 
@@ -979,21 +1717,25 @@ mod test {
                 jwt_only,
                 digest,
                 allow_latest,
-                user,
-                password,
-                insecure,
+                opts,
+                revocations,
                 output,
             }) => {
+                assert_eq!(
+                    revocations.revocations,
+                    Some(PathBuf::from("./revoked.txt"))
+                );
+                assert!(revocations.strict);
                 assert_eq!(module, SUBSCRIBER_OCI);
                 assert_eq!(
                     digest.unwrap(),
                     "sha256:5790f650cff526fcbc1271107a05111a6647002098b74a9a5e2e26e3c0a116b8"
                 );
                 assert_eq!(output.kind, OutputKind::Text { max_width: 0 });
-                assert_eq!(user.unwrap(), "name");
-                assert_eq!(password.unwrap(), "opensesame");
+                assert_eq!(opts.user.unwrap(), "name");
+                assert_eq!(opts.password.unwrap(), "opensesame");
                 assert!(allow_latest);
-                assert!(insecure);
+                assert!(opts.insecure);
                 assert!(jwt_only);
             }
             cmd => panic!("claims constructed incorrect command: {:?}", cmd),
@@ -1023,25 +1765,95 @@ mod test {
                 jwt_only,
                 digest,
                 allow_latest,
-                user,
-                password,
-                insecure,
+                opts,
+                revocations,
                 output,
             }) => {
+                assert!(revocations.revocations.is_none());
+                assert!(!revocations.strict);
                 assert_eq!(module, SUBSCRIBER_OCI);
                 assert_eq!(
                     digest.unwrap(),
                     "sha256:5790f650cff526fcbc1271107a05111a6647002098b74a9a5e2e26e3c0a116b8"
                 );
                 assert_eq!(output.kind, OutputKind::Text { max_width: 0 });
-                assert_eq!(user.unwrap(), "name");
-                assert_eq!(password.unwrap(), "opensesame");
+                assert_eq!(opts.user.unwrap(), "name");
+                assert_eq!(opts.password.unwrap(), "opensesame");
                 assert!(allow_latest);
-                assert!(insecure);
+                assert!(opts.insecure);
                 assert!(jwt_only);
             }
             cmd => panic!("claims constructed incorrect command: {:?}", cmd),
         }
+
+        // --strict only applies to a revocation list
+        assert!(
+            ClaimsCli::from_iter_safe(&["claims", "inspect", SUBSCRIBER_OCI, "--strict"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_revoked_issuers() {
+        const REVOKED: &str = "ACOJJN6WUP4ODD75XEBKKTCCUJJCY5ZKQ56XVKYK4BEJWGVAOOQHZMCW";
+        const VALID: &str = "AAJE5CFZJDBPKGGXSMVTGBRO3TXRU5N6L4B4ZX44HG2RTMJQQSFBYBBQ";
+        let revocations = parse_revocations(&format!(
+            "# compromised build key\n\n  {}  # rotated 2021-03\n",
+            REVOKED
+        ));
+        assert_eq!(revocations.len(), 1);
+        assert!(revocations.contains(REVOKED));
+
+        let path = std::env::temp_dir().join(format!("wash-revocations-{}", std::process::id()));
+        std::fs::write(&path, format!("{}\n", REVOKED)).unwrap();
+        assert!(revoked_issuers(None, &[REVOKED], true).unwrap().is_empty());
+        assert!(revoked_issuers(Some(&path), &[VALID], true)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            revoked_issuers(Some(&path), &[REVOKED, VALID, REVOKED], false).unwrap(),
+            vec![REVOKED.to_string()]
+        );
+        let err = revoked_issuers(Some(&path), &[VALID, REVOKED], true).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("Signed by revoked issuer(s): {}", REVOKED)
+        );
+        std::fs::remove_file(&path).unwrap();
+        assert!(revoked_issuers(Some(&path), &[VALID], false).is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_claims_sign_json_error() {
+        let cmd = ClaimsCli::from_iter_safe(&[
+            "claims",
+            "sign",
+            "./missing-module.wasm",
+            "--name",
+            "MyActor",
+            "--output",
+            "json",
+        ])
+        .unwrap();
+        let err = handle_command(cmd.command()).await.unwrap_err();
+        let json: serde_json::Value = serde_json::from_str(&err.to_string()).unwrap();
+        assert_eq!(json["result"], "error");
+        assert!(json["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("Unable to read module ./missing-module.wasm"));
+
+        let cmd = ClaimsCli::from_iter_safe(&[
+            "claims",
+            "sign",
+            "./missing-module.wasm",
+            "--name",
+            "MyActor",
+        ])
+        .unwrap();
+        let err = handle_command(cmd.command()).await.unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Unable to read module ./missing-module.wasm"));
     }
 
     #[test]
@@ -1061,6 +1873,8 @@ mod test {
             "test:custom",
             "--destination",
             "./myactor_s.wasm",
+            "--oci-reference",
+            SUBSCRIBER_OCI,
             "--directory",
             "./dir",
             "--expires",
@@ -1089,6 +1903,8 @@ mod test {
             "--msg",
             "--prov",
             "--disable-keygen",
+            "--caps-file",
+            "./caps.txt",
         ])
         .unwrap();
 
@@ -1096,10 +1912,18 @@ mod test {
             ClaimsCliCommand::Sign(SignCommand {
                 source,
                 destination,
+                oci_reference,
+                provider_module,
+                capid,
+                vendor,
                 metadata,
             }) => {
                 assert_eq!(source, LOCAL_WASM);
                 assert_eq!(destination.unwrap(), "./myactor_s.wasm");
+                assert_eq!(oci_reference.unwrap(), SUBSCRIBER_OCI);
+                assert!(!provider_module);
+                assert!(capid.is_none());
+                assert!(vendor.is_none());
                 assert_eq!(metadata.common.directory.unwrap(), "./dir");
                 assert_eq!(metadata.common.expires_in_days.unwrap(), 3);
                 assert_eq!(metadata.common.not_before_days.unwrap(), 1);
@@ -1116,6 +1940,7 @@ mod test {
                 assert_eq!(metadata.name, "MyActor");
                 assert!(!metadata.custom_caps.is_empty());
                 assert_eq!(metadata.custom_caps[0], "test:custom");
+                assert_eq!(metadata.caps_file.unwrap(), PathBuf::from("./caps.txt"));
                 assert!(!metadata.tags.is_empty());
                 assert_eq!(metadata.tags[0], "testtag");
                 assert!(metadata.provider);
@@ -1169,10 +1994,13 @@ mod test {
             ClaimsCliCommand::Sign(SignCommand {
                 source,
                 destination,
+                oci_reference,
                 metadata,
+                ..
             }) => {
                 assert_eq!(source, LOCAL_WASM);
                 assert_eq!(destination.unwrap(), "./myactor_s.wasm");
+                assert!(oci_reference.is_none());
                 assert_eq!(metadata.common.directory.unwrap(), "./dir");
                 assert_eq!(metadata.common.expires_in_days.unwrap(), 3);
                 assert_eq!(metadata.common.not_before_days.unwrap(), 1);
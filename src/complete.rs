@@ -0,0 +1,265 @@
+use crate::ctl::{get_hosts, ConnectionOpts, GetHostsCommand};
+use crate::keys::resolve_keys_dir;
+use crate::util::{Output, Result};
+use std::fs;
+use std::path::Path;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct CompleteCli {
+    #[structopt(flatten)]
+    command: CompleteCliCommand,
+}
+
+impl CompleteCli {
+    pub(crate) fn command(self) -> CompleteCliCommand {
+        self.command
+    }
+}
+
+/// Candidate generators invoked by shell completion scripts. Each command prints one
+/// candidate per line and never fails, so a completion attempt can't print an error.
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) enum CompleteCliCommand {
+    /// List the names of keys stored in the keys directory
+    #[structopt(name = "keys")]
+    Keys {
+        #[structopt(
+            short = "d",
+            long = "directory",
+            env = "WASH_KEYS",
+            hide_env_values = true
+        )]
+        directory: Option<String>,
+    },
+    /// List the references of artifacts stored in the local OCI cache
+    #[structopt(name = "refs")]
+    Refs,
+    /// List the IDs of hosts responding on the lattice
+    #[structopt(name = "hosts")]
+    Hosts {
+        #[structopt(flatten)]
+        opts: ConnectionOpts,
+    },
+    /// List the candidates for the last of the words of a command line, which the completion
+    /// scripts pass after `--`. Prints nothing if the value isn't one of the above
+    #[structopt(name = "words")]
+    Words {
+        #[structopt(last = true)]
+        words: Vec<String>,
+    },
+}
+
+pub(crate) async fn handle_command(command: CompleteCliCommand) -> Result<String> {
+    let candidates = match command {
+        CompleteCliCommand::Keys { directory } => key_candidates(directory),
+        CompleteCliCommand::Refs => crate::reg::cached_references(),
+        CompleteCliCommand::Hosts { opts } => host_candidates(opts).await,
+        CompleteCliCommand::Words { words } => match completed_value(&words) {
+            Some(CompletedValue::Key { directory }) => key_candidates(directory),
+            Some(CompletedValue::Reference) => crate::reg::cached_references(),
+            Some(CompletedValue::HostId) => {
+                // Connection flags aren't parsed from the line, but their variables apply
+                match ConnectionOpts::from_iter_safe(&["hosts"]) {
+                    Ok(opts) => host_candidates(opts).await,
+                    Err(_) => vec![],
+                }
+            }
+            None => vec![],
+        },
+    };
+    Ok(candidates.join("\n"))
+}
+
+/// Kinds of values that are completed from local state or the lattice
+#[derive(Debug, PartialEq)]
+enum CompletedValue {
+    Key { directory: Option<String> },
+    Reference,
+    HostId,
+}
+
+/// Commands whose first argument is the name of a stored key
+const KEY_COMMANDS: &[&[&str]] = &[&["keys", "get"], &["keys", "delete"]];
+/// Commands whose first argument is an OCI reference
+const REFERENCE_COMMANDS: &[&[&str]] = &[
+    &["reg", "pull"],
+    &["par", "inspect"],
+    &["claims", "inspect"],
+];
+/// Commands whose first argument is a host ID
+const HOST_ID_COMMANDS: &[&[&str]] = &[
+    &["ctl", "get", "inventory"],
+    &["ctl", "stop", "actor"],
+    &["ctl", "stop", "provider"],
+    &["ctl", "stop", "host"],
+    &["ctl", "update", "actor"],
+];
+
+/// Determines what the last word of a command line is, given all of its words starting
+/// with `wash`. Only the first argument of a command is completed, where a word following
+/// a flag is taken to be the flag's value
+fn completed_value(words: &[String]) -> Option<CompletedValue> {
+    let preceding = words.get(1..words.len().checked_sub(1)?)?;
+    let command = preceding
+        .iter()
+        .map(String::as_str)
+        .take_while(|w| !w.starts_with('-'))
+        .collect::<Vec<_>>();
+    let flags = &preceding[command.len()..];
+    if command.first() == Some(&"ctl")
+        && matches!(
+            flags.last().map(String::as_str),
+            Some("-h") | Some("--host-id")
+        )
+    {
+        return Some(CompletedValue::HostId);
+    }
+    let has_argument = flags
+        .iter()
+        .enumerate()
+        .any(|(i, w)| !w.starts_with('-') && (i == 0 || !flags[i - 1].starts_with('-')));
+    let is = |commands: &[&[&str]]| !has_argument && commands.contains(&command.as_slice());
+    if is(KEY_COMMANDS) {
+        let directory = flags
+            .iter()
+            .position(|w| w == "-d" || w == "--directory")
+            .and_then(|i| flags.get(i + 1).cloned())
+            .or_else(|| {
+                flags
+                    .iter()
+                    .find_map(|w| w.strip_prefix("--directory=").map(String::from))
+            });
+        Some(CompletedValue::Key { directory })
+    } else if is(REFERENCE_COMMANDS) {
+        Some(CompletedValue::Reference)
+    } else if is(HOST_ID_COMMANDS) {
+        Some(CompletedValue::HostId)
+    } else {
+        None
+    }
+}
+
+/// Returns the file names of all keys (.nk) in the keys directory
+fn key_candidates(directory: Option<String>) -> Vec<String> {
+    let dir = match resolve_keys_dir(directory) {
+        Ok(d) => d,
+        Err(_) => return vec![],
+    };
+    let mut keys = dir_entry_names(&dir)
+        .into_iter()
+        .filter(|f| f.ends_with(".nk"))
+        .collect::<Vec<_>>();
+    keys.sort();
+    keys
+}
+
+/// Returns the IDs of hosts on the lattice, or nothing if the lattice can't be reached
+async fn host_candidates(opts: ConnectionOpts) -> Vec<String> {
+    get_hosts(GetHostsCommand::new(opts, Output::default(), 1))
+        .await
+        .map(|hosts| hosts.into_iter().map(|h| h.id).collect())
+        .unwrap_or_default()
+}
+
+fn dir_entry_names(dir: &Path) -> Vec<String> {
+    match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| e.file_name().to_str().map(|s| s.to_string()))
+            .collect(),
+        Err(_) => vec![],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    /// Enumerates the hidden `__complete` generators to ensure the API used by
+    /// completion scripts doesn't change between versions
+    fn test_complete_comprehensive() {
+        let keys =
+            CompleteCli::from_iter_safe(&["__complete", "keys", "-d", "./tests/fixtures"]).unwrap();
+        match keys.command {
+            CompleteCliCommand::Keys { directory } => {
+                assert_eq!(directory.unwrap(), "./tests/fixtures")
+            }
+            cmd => panic!("__complete constructed incorrect command {:?}", cmd),
+        }
+        let refs = CompleteCli::from_iter_safe(&["__complete", "refs"]).unwrap();
+        assert!(matches!(refs.command, CompleteCliCommand::Refs));
+        let hosts =
+            CompleteCli::from_iter_safe(&["__complete", "hosts", "--rpc-port", "4222"]).unwrap();
+        assert!(matches!(hosts.command, CompleteCliCommand::Hosts { .. }));
+    }
+
+    #[test]
+    fn test_completed_value() {
+        let completed = |line: &str| {
+            let mut words = line
+                .split_whitespace()
+                .map(String::from)
+                .collect::<Vec<_>>();
+            if line.ends_with(' ') {
+                words.push(String::new());
+            }
+            completed_value(&words)
+        };
+        assert_eq!(
+            completed("wash keys get "),
+            Some(CompletedValue::Key { directory: None })
+        );
+        assert_eq!(
+            completed("wash keys delete -d ./keys my"),
+            Some(CompletedValue::Key {
+                directory: Some("./keys".to_string())
+            })
+        );
+        assert_eq!(
+            completed("wash keys get --directory=./keys "),
+            Some(CompletedValue::Key {
+                directory: Some("./keys".to_string())
+            })
+        );
+        assert_eq!(
+            completed("wash reg pull --insecure "),
+            Some(CompletedValue::Reference)
+        );
+        assert_eq!(
+            completed("wash par inspect loc"),
+            Some(CompletedValue::Reference)
+        );
+        assert_eq!(
+            completed("wash ctl get inventory -o json "),
+            Some(CompletedValue::HostId)
+        );
+        assert_eq!(
+            completed("wash ctl start actor echo.wasm -h "),
+            Some(CompletedValue::HostId)
+        );
+        assert_eq!(
+            completed("wash ctl stop actor NHOST "),
+            None,
+            "only the first argument of a command is completed"
+        );
+        assert_eq!(completed("wash keys get mykey.nk "), None);
+        assert_eq!(completed("wash keys list "), None);
+        assert_eq!(completed("wash ctl get "), None);
+        assert_eq!(completed("wash"), None);
+        assert_eq!(completed_value(&[]), None);
+
+        let words =
+            CompleteCli::from_iter_safe(&["__complete", "words", "--", "wash", "keys"]).unwrap();
+        match words.command {
+            CompleteCliCommand::Words { words } => assert_eq!(words, vec!["wash", "keys"]),
+            cmd => panic!("__complete constructed incorrect command {:?}", cmd),
+        }
+    }
+
+    #[test]
+    fn test_key_candidates_missing_dir() {
+        assert!(key_candidates(Some("./this/dir/does/not/exist".to_string())).is_empty());
+    }
+}
@@ -0,0 +1,121 @@
+use crate::util::Result;
+use structopt::clap::{App, Shell};
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct CompletionsCli {
+    #[structopt(flatten)]
+    command: CompletionsCommand,
+}
+
+impl CompletionsCli {
+    pub(crate) fn command(self) -> CompletionsCommand {
+        self.command
+    }
+}
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct CompletionsCommand {
+    /// Shell to generate a completion script for
+    #[structopt(
+        name = "shell",
+        possible_values = &Shell::variants(),
+        case_insensitive = true
+    )]
+    shell: Shell,
+}
+
+/// Completes key names, cached references and host IDs in bash, ahead of the generated
+/// completions for flags and subcommands
+const BASH_DYNAMIC: &str = r#"
+_wash_dynamic() {
+    local candidates
+    candidates="$(wash __complete words -- "${COMP_WORDS[@]:0:COMP_CWORD+1}" 2>/dev/null)"
+    if [[ -n "${candidates}" ]]; then
+        COMPREPLY=( $(compgen -W "${candidates}" -- "${COMP_WORDS[COMP_CWORD]}") )
+        return 0
+    fi
+    _wash "$@"
+}
+
+complete -F _wash_dynamic -o bashdefault -o default wash
+"#;
+
+/// Replaces the call to the generated zsh completion function
+const ZSH_DYNAMIC: &str = r#"_wash_dynamic() {
+    local -a candidates
+    candidates=(${(f)"$(wash __complete words -- "${(@)words[1,CURRENT]}" 2>/dev/null)"})
+    if (( ${#candidates} )); then
+        compadd -a candidates
+    else
+        _wash "$@"
+    fi
+}
+
+_wash_dynamic "$@""#;
+
+const FISH_DYNAMIC: &str = r#"
+function __wash_dynamic
+    wash __complete words -- (commandline -opc) (commandline -ct) 2>/dev/null
+end
+
+complete -c wash -f -n 'count (__wash_dynamic) >/dev/null' -a '(__wash_dynamic)'
+"#;
+
+/// Renders the completion script for the requested shell from the definition of the `wash` CLI.
+/// Scripts for bash, zsh and fish also complete values such as key names, cached references
+/// and host IDs by calling the hidden `wash __complete` command
+pub(crate) fn handle_command(command: CompletionsCommand, mut app: App) -> Result<String> {
+    let mut script = Vec::new();
+    app.gen_completions_to("wash", command.shell, &mut script);
+    let script = String::from_utf8(script)?;
+    Ok(match command.shell {
+        Shell::Bash => script + BASH_DYNAMIC,
+        Shell::Zsh => match script.rfind("_wash \"$@\"") {
+            Some(call) => format!("{}{}\n", &script[..call], ZSH_DYNAMIC),
+            None => script,
+        },
+        Shell::Fish => script + FISH_DYNAMIC,
+        _ => script,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_completions_comprehensive() {
+        for shell in &["bash", "zsh", "fish", "powershell", "elvish", "BASH"] {
+            let cmd = CompletionsCli::from_iter_safe(&["completions", shell]).unwrap();
+            assert_eq!(
+                cmd.command().shell.to_string().to_lowercase(),
+                shell.to_lowercase()
+            );
+        }
+        assert!(CompletionsCli::from_iter_safe(&["completions", "tcsh"]).is_err());
+        assert!(CompletionsCli::from_iter_safe(&["completions"]).is_err());
+    }
+
+    #[test]
+    fn test_completions_script() {
+        let app = App::new("wash").subcommand(App::new("drain"));
+        let cmd = CompletionsCli::from_iter_safe(&["completions", "bash"]).unwrap();
+        let script = handle_command(cmd.command(), app).unwrap();
+        assert!(script.contains("_wash()"));
+        assert!(script.contains("drain"));
+        assert!(script.ends_with("complete -F _wash_dynamic -o bashdefault -o default wash\n"));
+
+        for shell in &["zsh", "fish"] {
+            let cmd = CompletionsCli::from_iter_safe(&["completions", shell]).unwrap();
+            let app = App::new("wash").subcommand(App::new("drain"));
+            let script = handle_command(cmd.command(), app).unwrap();
+            assert!(script.contains("wash __complete words --"));
+        }
+        let cmd = CompletionsCli::from_iter_safe(&["completions", "zsh"]).unwrap();
+        let app = App::new("wash").subcommand(App::new("drain"));
+        let script = handle_command(cmd.command(), app).unwrap();
+        assert!(script.ends_with("_wash_dynamic \"$@\"\n"));
+        assert!(!script.contains("\n_wash \"$@\""));
+    }
+}
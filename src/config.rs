@@ -0,0 +1,271 @@
+use crate::util::{with_env_defaults, Result};
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use structopt::clap::App;
+
+/// Environment variable holding the location of the config file, overridden by `--config`
+const CONFIG_ENV: &str = "WASH_CONFIG";
+const CONFIG_FLAG: &str = "--config";
+
+/// Defaults loaded from the config file for environment variables that aren't set, paired
+/// with the variable they stand in for
+static DEFAULTS: OnceCell<Vec<(&'static str, String)>> = OnceCell::new();
+
+/// Defaults for wash flags read from `~/.wash/config.toml`. Each value stands in for the
+/// environment variable of its flag, so explicit flags and environment variables take precedence
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct WashConfig {
+    /// Output format, `text` or `json`
+    output: Option<String>,
+    #[serde(default)]
+    keys: KeysConfig,
+    #[serde(default)]
+    reg: RegConfig,
+    #[serde(default)]
+    ctl: CtlConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeysConfig {
+    directory: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RegConfig {
+    user: Option<String>,
+    password: Option<String>,
+    /// Location of the credentials stored by `wash reg login`
+    credentials: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CtlConfig {
+    rpc_host: Option<String>,
+    rpc_port: Option<u16>,
+    rpc_credsfile: Option<String>,
+    rpc_timeout: Option<u64>,
+    lattice_prefix: Option<String>,
+}
+
+impl WashConfig {
+    /// Pairs each configured value with the environment variable of the flag it's a default for
+    fn env_defaults(&self) -> Vec<(&'static str, String)> {
+        let path = |p: &Option<String>| p.as_deref().map(expand_home);
+        vec![
+            ("WASH_OUTPUT", self.output.clone()),
+            ("WASH_KEYS", path(&self.keys.directory)),
+            ("WASH_REG_USER", self.reg.user.clone()),
+            ("WASH_REG_PASSWORD", self.reg.password.clone()),
+            ("WASH_REG_CREDENTIALS", path(&self.reg.credentials)),
+            ("WASH_RPC_HOST", self.ctl.rpc_host.clone()),
+            ("WASH_RPC_PORT", self.ctl.rpc_port.map(|p| p.to_string())),
+            ("WASH_RPC_CREDS", path(&self.ctl.rpc_credsfile)),
+            (
+                "WASH_RPC_TIMEOUT",
+                self.ctl.rpc_timeout.map(|t| t.to_string()),
+            ),
+            ("WASH_LATTICE_PREFIX", self.ctl.lattice_prefix.clone()),
+        ]
+        .into_iter()
+        .filter_map(|(var, value)| value.map(|v| (var, v)))
+        .collect()
+    }
+}
+
+/// Loads the config file given with `--config` or `$WASH_CONFIG`, falling back to
+/// `~/.wash/config.toml` if it exists, and keeps the defaults it supplies for environment
+/// variables that aren't set. They're applied with `with_config_defaults` and `env_var`
+pub(crate) fn load_config_defaults(args: &[OsString]) -> Result<()> {
+    let explicit = config_arg(args).or_else(|| env::var_os(CONFIG_ENV).map(PathBuf::from));
+    let config = match explicit {
+        Some(path) => load_config(&path)?,
+        None => match default_config_path() {
+            Some(path) if path.is_file() => load_config(&path)?,
+            _ => return Ok(()),
+        },
+    };
+    let _ = DEFAULTS.set(unset_defaults(&config, |var| env::var_os(var).is_some()));
+    Ok(())
+}
+
+/// Keeps the defaults of a config for the environment variables that aren't set
+fn unset_defaults(
+    config: &WashConfig,
+    is_set: impl Fn(&str) -> bool,
+) -> Vec<(&'static str, String)> {
+    config
+        .env_defaults()
+        .into_iter()
+        .filter(|(var, _)| !is_set(var))
+        .collect()
+}
+
+fn config_defaults() -> &'static [(&'static str, String)] {
+    DEFAULTS.get().map(Vec::as_slice).unwrap_or_default()
+}
+
+/// Makes the config defaults the defaults of the flags reading the environment variables they
+/// stand in for, see `util::with_env_defaults`
+pub(crate) fn with_config_defaults<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+    with_env_defaults(app, config_defaults())
+}
+
+/// Reads an environment variable that isn't the environment variable of a flag, such as
+/// WASH_REG_USER, falling back to the config default for it
+pub(crate) fn env_var(var: &str) -> Option<String> {
+    env::var(var).ok().or_else(|| {
+        config_defaults()
+            .iter()
+            .find(|(default_var, _)| *default_var == var)
+            .map(|(_, value)| value.clone())
+    })
+}
+
+fn load_config(path: &Path) -> Result<WashConfig> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Unable to read config file {}: {}", path.display(), e))?;
+    serdeconv::from_toml_str(&contents)
+        .map_err(|e| format!("Invalid config file {}: {}", path.display(), e).into())
+}
+
+fn default_config_path() -> Option<PathBuf> {
+    env::var("HOME")
+        .ok()
+        .map(|home| PathBuf::from(home).join(".wash").join("config.toml"))
+}
+
+/// Finds the value of `--config` among the top level flags, which precede the subcommand.
+/// The config has to be loaded before the command line is parsed, as it supplies defaults
+fn config_arg(args: &[OsString]) -> Option<PathBuf> {
+    let mut args = args.iter().skip(1).map(|a| a.to_string_lossy());
+    while let Some(arg) = args.next() {
+        if arg == CONFIG_FLAG {
+            return args.next().map(|path| PathBuf::from(path.as_ref()));
+        } else if let Some(path) = arg.strip_prefix("--config=") {
+            return Some(PathBuf::from(path));
+        } else if !arg.starts_with('-') {
+            break;
+        }
+    }
+    None
+}
+
+/// Expands a leading `~/` to the home directory
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{}/{}", home, rest),
+        _ => path.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use structopt::StructOpt;
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn test_config_arg() {
+        assert_eq!(
+            config_arg(&args(&["wash", "--config", "wash.toml", "keys", "list"])),
+            Some(PathBuf::from("wash.toml"))
+        );
+        assert_eq!(
+            config_arg(&args(&["wash", "-q", "--config=wash.toml", "keys", "list"])),
+            Some(PathBuf::from("wash.toml"))
+        );
+        // Subcommands may have flags of the same name
+        assert_eq!(
+            config_arg(&args(&["wash", "reg", "push", "--config", "config.json"])),
+            None
+        );
+        assert_eq!(config_arg(&args(&["wash", "--config"])), None);
+    }
+
+    #[test]
+    fn test_config_env_defaults() {
+        let config: WashConfig = serdeconv::from_toml_str(
+            r#"
+            output = "json"
+
+            [keys]
+            directory = "/etc/wash/keys"
+
+            [reg]
+            credentials = "/etc/wash/credentials.json"
+
+            [ctl]
+            rpc_host = "nats.example.com"
+            rpc_port = 4223
+            lattice_prefix = "staging"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.env_defaults(),
+            vec![
+                ("WASH_OUTPUT", "json".to_string()),
+                ("WASH_KEYS", "/etc/wash/keys".to_string()),
+                (
+                    "WASH_REG_CREDENTIALS",
+                    "/etc/wash/credentials.json".to_string()
+                ),
+                ("WASH_RPC_HOST", "nats.example.com".to_string()),
+                ("WASH_RPC_PORT", "4223".to_string()),
+                ("WASH_LATTICE_PREFIX", "staging".to_string()),
+            ]
+        );
+        assert!(WashConfig::default().env_defaults().is_empty());
+
+        // Variables set in the environment take precedence over the config
+        assert_eq!(
+            unset_defaults(&config, |var| var != "WASH_RPC_HOST"),
+            vec![("WASH_RPC_HOST", "nats.example.com".to_string())]
+        );
+        let app = with_env_defaults(
+            crate::ctl::CtlCli::clap(),
+            &unset_defaults(&config, |var| var == "WASH_RPC_HOST"),
+        );
+        let ctl = crate::ctl::CtlCli::from_clap(
+            &app.get_matches_from_safe(vec!["ctl", "get", "hosts", "-o", "json"])
+                .unwrap(),
+        );
+        match ctl.command() {
+            crate::ctl::CtlCliCommand::Get(crate::ctl::GetCommand::Hosts(cmd)) => {
+                assert_eq!(cmd.opts.rpc_port, "4223");
+                assert_eq!(cmd.opts.ns_prefix, "staging");
+            }
+            cmd => panic!("ctl get hosts constructed incorrect command {:?}", cmd),
+        }
+        assert!(serdeconv::from_toml_str::<WashConfig>("[keys]\ndir = \"keys\"").is_err());
+    }
+
+    #[test]
+    fn test_load_config() {
+        let path = std::env::temp_dir().join("wash_load_config_test.toml");
+        std::fs::write(&path, "[ctl]\nlattice_prefix = \"dev\"\n").unwrap();
+        let config = load_config(&path).unwrap();
+        assert_eq!(config.ctl.lattice_prefix.as_deref(), Some("dev"));
+
+        std::fs::write(&path, "output = 1\n").unwrap();
+        assert!(load_config(&path)
+            .unwrap_err()
+            .to_string()
+            .starts_with("Invalid config file"));
+        std::fs::remove_file(&path).unwrap();
+        assert!(load_config(&path)
+            .unwrap_err()
+            .to_string()
+            .starts_with("Unable to read config file"));
+    }
+}
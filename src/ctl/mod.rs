@@ -1,16 +1,27 @@
 extern crate wasmcloud_control_interface;
 use crate::util::{
-    convert_error, extract_arg_value, json_str_to_msgpack_bytes, labels_vec_to_hashmap,
-    output_destination, Output, OutputDestination, OutputKind, Result, WASH_CMD_INFO,
+    convert_network_error, extract_arg_value, format_output, json_str_to_msgpack_bytes,
+    labels_vec_to_hashmap, print_or_log, show_progress, start_spinner, Output, OutputKind, Result,
+    WashError, WASH_CMD_INFO,
 };
+use indicatif::ProgressBar;
 use log::debug;
-use spinners::{Spinner, Spinners};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::time::Duration;
 use structopt::StructOpt;
 use wasmcloud_control_interface::*;
 mod output;
 pub(crate) use output::*;
 
+/// Subject prefix the lattice publishes control interface events on, followed by the namespace prefix
+const EVENT_TOPIC_PREFIX: &str = "wasmbus.evt";
+/// Subject prefix hosts receive control interface commands on, followed by the namespace prefix
+const CTL_TOPIC_PREFIX: &str = "wasmbus.ctl";
+
 #[derive(Debug, Clone, StructOpt)]
 pub(crate) struct CtlCli {
     #[structopt(flatten)]
@@ -32,7 +43,7 @@ pub(crate) struct ConnectionOpts {
         default_value = "0.0.0.0",
         env = "WASH_RPC_HOST"
     )]
-    rpc_host: String,
+    pub(crate) rpc_host: String,
 
     /// RPC Port for connections, defaults to 4222 for local nats
     #[structopt(
@@ -41,7 +52,7 @@ pub(crate) struct ConnectionOpts {
         default_value = "4222",
         env = "WASH_RPC_PORT"
     )]
-    rpc_port: String,
+    pub(crate) rpc_port: String,
 
     /// JWT file for RPC authentication. Must be supplied with rpc_seed.
     #[structopt(long = "rpc-jwt", env = "WASH_RPC_JWT", hide_env_values = true)]
@@ -54,11 +65,21 @@ pub(crate) struct ConnectionOpts {
     /// Credsfile for RPC authentication. Combines rpc_seed and rpc_jwt.
     /// See https://docs.nats.io/developing-with-nats/security/creds for details.
     #[structopt(long = "rpc-credsfile", env = "WASH_RPC_CREDS", hide_env_values = true)]
-    rpc_credsfile: Option<String>,
+    pub(crate) rpc_credsfile: Option<String>,
+
+    /// CA certificate used to verify the NATS server, enables TLS for the RPC connection
+    #[structopt(long = "rpc-tls-ca", env = "WASH_RPC_TLS_CA", hide_env_values = true)]
+    rpc_tls_ca: Option<String>,
 
-    /// Namespace prefix for wasmcloud command interface
-    #[structopt(short = "n", long = "ns-prefix", default_value = "default")]
-    ns_prefix: String,
+    /// Namespace prefix for wasmcloud command interface. Lattices sharing a NATS cluster are kept apart by their prefix
+    #[structopt(
+        short = "n",
+        long = "ns-prefix",
+        alias = "lattice-prefix",
+        default_value = "default",
+        env = "WASH_LATTICE_PREFIX"
+    )]
+    pub(crate) ns_prefix: String,
 
     /// Timeout length for RPC, defaults to 1 second
     #[structopt(
@@ -68,6 +89,43 @@ pub(crate) struct ConnectionOpts {
         env = "WASH_RPC_TIMEOUT"
     )]
     rpc_timeout: u64,
+
+    /// Timeout length for RPC in milliseconds, overrides --rpc-timeout when set. Raise this on
+    /// slow or busy lattices, especially for commands like `start` that wait for an acknowledgement
+    #[structopt(long = "rpc-timeout-ms", env = "WASH_CTL_TIMEOUT")]
+    rpc_timeout_ms: Option<u64>,
+}
+
+impl ConnectionOpts {
+    /// Pairs each option with the environment variable of its flag, so commands parsed later
+    /// can default to these options with `util::with_env_defaults`
+    pub(crate) fn env_defaults(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("WASH_RPC_HOST", Some(self.rpc_host.clone())),
+            ("WASH_RPC_PORT", Some(self.rpc_port.clone())),
+            ("WASH_RPC_JWT", self.rpc_jwt.clone()),
+            ("WASH_RPC_SEED", self.rpc_seed.clone()),
+            ("WASH_RPC_CREDS", self.rpc_credsfile.clone()),
+            ("WASH_RPC_TLS_CA", self.rpc_tls_ca.clone()),
+            ("WASH_LATTICE_PREFIX", Some(self.ns_prefix.clone())),
+            ("WASH_RPC_TIMEOUT", Some(self.rpc_timeout.to_string())),
+            (
+                "WASH_CTL_TIMEOUT",
+                self.rpc_timeout_ms.map(|ms| ms.to_string()),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(var, value)| value.map(|v| (var, v)))
+        .collect()
+    }
+
+    /// Request timeout for the control interface client
+    fn timeout(&self) -> Duration {
+        match self.rpc_timeout_ms {
+            Some(ms) => Duration::from_millis(ms),
+            None => Duration::from_secs(self.rpc_timeout),
+        }
+    }
 }
 
 impl Default for ConnectionOpts {
@@ -78,14 +136,24 @@ impl Default for ConnectionOpts {
             rpc_jwt: None,
             rpc_seed: None,
             rpc_credsfile: None,
+            rpc_tls_ca: None,
             ns_prefix: "default".to_string(),
             rpc_timeout: 1,
+            rpc_timeout_ms: None,
         }
     }
 }
 
 #[derive(Debug, Clone, StructOpt)]
 pub(crate) enum CtlCliCommand {
+    /// Start the actors and providers declared in a manifest and establish the links between them
+    #[structopt(name = "apply")]
+    Apply(ApplyCommand),
+
+    /// Find the hosts able to run an actor or a provider, without starting it
+    #[structopt(name = "auction")]
+    Auction(AuctionCommand),
+
     /// Invoke an operation on an actor
     #[structopt(name = "call")]
     Call(CallCommand),
@@ -98,6 +166,10 @@ pub(crate) enum CtlCliCommand {
     #[structopt(name = "link")]
     Link(LinkCommand),
 
+    /// Start or stop instances of an actor until the requested number are running
+    #[structopt(name = "scale")]
+    Scale(ScaleCommand),
+
     /// Start an actor or a provider
     #[structopt(name = "start")]
     Start(StartCommand),
@@ -106,9 +178,17 @@ pub(crate) enum CtlCliCommand {
     #[structopt(name = "stop")]
     Stop(StopCommand),
 
+    /// Summarize the hosts, actors and providers running in the lattice
+    #[structopt(name = "summary")]
+    Summary(SummaryCommand),
+
     /// Update an actor running in a host to a new actor
     #[structopt(name = "update")]
     Update(UpdateCommand),
+
+    /// Stream lattice events (host, actor, provider and link changes) until interrupted
+    #[structopt(name = "watch")]
+    Watch(WatchCommand),
 }
 
 #[derive(StructOpt, Debug, Clone)]
@@ -130,6 +210,52 @@ pub(crate) struct CallCommand {
     /// Payload to send with operation (in the form of '{"field": "value"}' )
     #[structopt(name = "data")]
     pub(crate) data: Vec<String>,
+
+    /// File containing the JSON payload to send with operation, instead of <data>
+    #[structopt(long = "data-file", conflicts_with = "data")]
+    pub(crate) data_file: Option<PathBuf>,
+
+    /// How to display the actor's response: raw (lossy UTF-8), base64, or json (decoded from msgpack)
+    #[structopt(long = "response-format", default_value = "raw")]
+    pub(crate) response_format: CallResponseFormat,
+}
+
+impl CallCommand {
+    /// Returns the msgpack encoded payload from either <data> or --data-file
+    pub(crate) fn payload(&self) -> Result<Vec<u8>> {
+        match &self.data_file {
+            Some(path) => {
+                let contents = std::fs::read_to_string(path)
+                    .map_err(|e| format!("Unable to read data file {}: {}", path.display(), e))?;
+                json_str_to_msgpack_bytes(vec![contents])
+            }
+            None => json_str_to_msgpack_bytes(self.data.clone()),
+        }
+    }
+}
+
+/// Encoding used to display the response of an actor call
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum CallResponseFormat {
+    Raw,
+    Base64,
+    Json,
+}
+
+impl FromStr for CallResponseFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "raw" => Ok(CallResponseFormat::Raw),
+            "base64" => Ok(CallResponseFormat::Base64),
+            "json" => Ok(CallResponseFormat::Json),
+            _ => Err(format!(
+                "Unknown response format '{}', expected one of raw, base64 or json",
+                s
+            )),
+        }
+    }
 }
 
 #[derive(Debug, Clone, StructOpt)]
@@ -138,7 +264,7 @@ pub(crate) enum GetCommand {
     #[structopt(name = "hosts")]
     Hosts(GetHostsCommand),
 
-    /// Query a single host for its inventory of labels, actors and providers
+    /// Query hosts for their inventory of labels, actors and providers
     #[structopt(name = "inventory")]
     HostInventory(GetHostInventoryCommand),
 
@@ -176,6 +302,90 @@ pub(crate) struct LinkCommand {
     pub(crate) values: Vec<String>,
 }
 
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) enum AuctionCommand {
+    /// Find the hosts able to run an actor
+    #[structopt(name = "actor")]
+    Actor(AuctionActorCommand),
+
+    /// Find the hosts able to run a provider
+    #[structopt(name = "provider")]
+    Provider(AuctionProviderCommand),
+}
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct AuctionActorCommand {
+    #[structopt(flatten)]
+    opts: ConnectionOpts,
+
+    #[structopt(flatten)]
+    pub(crate) output: Output,
+
+    /// Actor reference, e.g. the OCI URL for the actor
+    #[structopt(name = "actor-ref")]
+    pub(crate) actor_ref: String,
+
+    /// Constraints for the auction in the form of "label=value"
+    #[structopt(short = "c", long = "constraint", name = "constraints")]
+    constraints: Option<Vec<String>>,
+
+    /// Timeout to wait for hosts to respond to the auction, defaults to 1 second
+    #[structopt(long = "timeout", default_value = "1")]
+    timeout: u64,
+}
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct AuctionProviderCommand {
+    #[structopt(flatten)]
+    opts: ConnectionOpts,
+
+    #[structopt(flatten)]
+    pub(crate) output: Output,
+
+    /// Provider reference, e.g. the OCI URL for the provider
+    #[structopt(name = "provider-ref")]
+    pub(crate) provider_ref: String,
+
+    /// Link name of provider
+    #[structopt(name = "link-name")]
+    pub(crate) link_name: String,
+
+    /// Constraints for the auction in the form of "label=value"
+    #[structopt(short = "c", long = "constraint", name = "constraints")]
+    constraints: Option<Vec<String>>,
+
+    /// Timeout to wait for hosts to respond to the auction, defaults to 1 second
+    #[structopt(long = "timeout", default_value = "1")]
+    timeout: u64,
+}
+
+/// A host that responded to a ping, along with the labels from its inventory
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct HostSummary {
+    pub(crate) id: String,
+    pub(crate) uptime_seconds: u64,
+    /// Labels of the host, empty if it failed to report its inventory
+    pub(crate) labels: BTreeMap<String, String>,
+}
+
+impl From<Host> for HostSummary {
+    fn from(host: Host) -> Self {
+        HostSummary {
+            id: host.id,
+            uptime_seconds: host.uptime_seconds,
+            labels: BTreeMap::new(),
+        }
+    }
+}
+
+/// A host that responded to an auction as able to run the workload
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct AuctionHost {
+    pub(crate) host_id: String,
+    /// Labels of the host, empty if it failed to report its inventory
+    pub(crate) labels: BTreeMap<String, String>,
+}
+
 #[derive(Debug, Clone, StructOpt)]
 pub(crate) enum StartCommand {
     /// Launch an actor in a host
@@ -196,6 +406,10 @@ pub(crate) enum StopCommand {
     /// Stop a provider running in a host
     #[structopt(name = "provider")]
     Provider(StopProviderCommand),
+
+    /// Shut down a host, optionally stopping its actors and providers first
+    #[structopt(name = "host")]
+    Host(StopHostCommand),
 }
 
 #[derive(Debug, Clone, StructOpt)]
@@ -207,16 +421,77 @@ pub(crate) enum UpdateCommand {
 
 #[derive(Debug, Clone, StructOpt)]
 pub(crate) struct GetHostsCommand {
+    #[structopt(flatten)]
+    pub(crate) opts: ConnectionOpts,
+
+    #[structopt(flatten)]
+    pub(crate) output: Output,
+
+    #[structopt(long = "timeout", default_value = "1")]
+    timeout: u64,
+}
+
+impl GetHostsCommand {
+    pub(crate) fn new(opts: ConnectionOpts, output: Output, timeout: u64) -> Self {
+        GetHostsCommand {
+            opts,
+            output,
+            timeout,
+        }
+    }
+}
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct SummaryCommand {
     #[structopt(flatten)]
     opts: ConnectionOpts,
 
     #[structopt(flatten)]
     pub(crate) output: Output,
 
+    /// Timeout in seconds to wait for hosts to respond
     #[structopt(long = "timeout", default_value = "1")]
     timeout: u64,
 }
 
+/// Totals of the lattice's hosts, actors and providers
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub(crate) struct LatticeSummary {
+    /// Number of hosts that responded to the host query
+    pub(crate) hosts: usize,
+    /// Number of hosts whose inventory was included in the totals
+    pub(crate) contributing_hosts: usize,
+    pub(crate) distinct_actors: usize,
+    pub(crate) actor_instances: usize,
+    pub(crate) distinct_providers: usize,
+    pub(crate) provider_instances: usize,
+}
+
+impl LatticeSummary {
+    /// Aggregates the inventories of the lattice's hosts
+    pub(crate) fn new(hosts: usize, inventories: &[HostInventory]) -> Self {
+        let actors = inventories.iter().flat_map(|inv| inv.actors.iter());
+        let providers = inventories.iter().flat_map(|inv| inv.providers.iter());
+        LatticeSummary {
+            hosts,
+            contributing_hosts: inventories.len(),
+            distinct_actors: actors.clone().map(|a| &a.id).collect::<HashSet<_>>().len(),
+            actor_instances: actors.count(),
+            distinct_providers: providers
+                .clone()
+                .map(|p| &p.id)
+                .collect::<HashSet<_>>()
+                .len(),
+            provider_instances: providers.count(),
+        }
+    }
+
+    /// Returns true if some hosts did not report their inventory in time
+    pub(crate) fn is_partial(&self) -> bool {
+        self.contributing_hosts < self.hosts
+    }
+}
+
 #[derive(Debug, Clone, StructOpt)]
 pub(crate) struct GetHostInventoryCommand {
     #[structopt(flatten)]
@@ -225,9 +500,13 @@ pub(crate) struct GetHostInventoryCommand {
     #[structopt(flatten)]
     pub(crate) output: Output,
 
-    /// Id of host
+    /// Id of host, if omitted the inventory of every host in the lattice is queried
     #[structopt(name = "host-id")]
-    pub(crate) host_id: String,
+    pub(crate) host_id: Option<String>,
+
+    /// Timeout in seconds to wait for hosts to respond when querying every host
+    #[structopt(long = "timeout", default_value = "1")]
+    timeout: u64,
 }
 
 #[derive(Debug, Clone, StructOpt)]
@@ -313,6 +592,50 @@ pub(crate) struct StartProviderCommand {
     timeout: u64,
 }
 
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct ScaleCommand {
+    #[structopt(flatten)]
+    opts: ConnectionOpts,
+
+    #[structopt(flatten)]
+    pub(crate) output: Output,
+
+    /// Id of host to scale the actor on, if omitted instances are spread across the hosts in the lattice
+    #[structopt(short = "h", long = "host-id", name = "host-id")]
+    pub(crate) host_id: Option<String>,
+
+    /// Actor Id or OCI reference of the actor. An OCI reference is required to start the first instance
+    #[structopt(name = "actor")]
+    pub(crate) actor: String,
+
+    /// Number of instances that should be running
+    #[structopt(name = "count")]
+    pub(crate) count: usize,
+
+    /// Timeout in seconds to wait for hosts to respond
+    #[structopt(long = "timeout", default_value = "1")]
+    timeout: u64,
+}
+
+/// Control interface request issued to converge on the requested number of actor instances
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ScaleAction {
+    Start { host_id: String, actor_ref: String },
+    Stop { host_id: String, actor_id: String },
+}
+
+/// Outcome of scaling an actor
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct ScaleResult {
+    pub(crate) actor: String,
+    /// Number of instances running before scaling
+    pub(crate) previous: usize,
+    pub(crate) requested: usize,
+    pub(crate) started: usize,
+    pub(crate) stopped: usize,
+    pub(crate) failures: Vec<String>,
+}
+
 #[derive(Debug, Clone, StructOpt)]
 pub(crate) struct StopActorCommand {
     #[structopt(flatten)]
@@ -355,6 +678,47 @@ pub(crate) struct StopProviderCommand {
     pub(crate) contract_id: String,
 }
 
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct StopHostCommand {
+    #[structopt(flatten)]
+    opts: ConnectionOpts,
+
+    #[structopt(flatten)]
+    pub(crate) output: Output,
+
+    /// Id of host
+    #[structopt(name = "host-id")]
+    pub(crate) host_id: String,
+
+    /// Seconds the host is given to shut down gracefully before it stops forcefully
+    #[structopt(long = "timeout")]
+    pub(crate) timeout: Option<u64>,
+
+    /// Stop every actor and provider in the host before asking it to shut down
+    #[structopt(long = "drain")]
+    pub(crate) drain: bool,
+}
+
+/// Outcome of stopping a host
+#[derive(Debug, Clone, Default, Serialize)]
+pub(crate) struct StopHostResult {
+    pub(crate) host_id: String,
+    /// Actors and providers stopped by `--drain` before the host was stopped
+    pub(crate) actors_stopped: usize,
+    pub(crate) providers_stopped: usize,
+    pub(crate) failures: Vec<String>,
+    /// Reason the host rejected the stop request, if it did
+    pub(crate) error: Option<String>,
+}
+
+/// Acknowledgement a host sends in reply to a control interface command
+#[derive(Debug, Deserialize)]
+struct CtlOperationAck {
+    accepted: bool,
+    #[serde(default)]
+    error: String,
+}
+
 #[derive(Debug, Clone, StructOpt)]
 pub(crate) struct UpdateActorCommand {
     #[structopt(flatten)]
@@ -394,34 +758,207 @@ impl UpdateActorCommand {
     }
 }
 
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct ApplyCommand {
+    #[structopt(flatten)]
+    opts: ConnectionOpts,
+
+    #[structopt(flatten)]
+    pub(crate) output: Output,
+
+    /// YAML manifest of the actors and providers to run, by OCI reference, and the links to establish between them
+    #[structopt(name = "manifest", parse(from_os_str))]
+    pub(crate) manifest: PathBuf,
+
+    /// Allow insecure (HTTP) registry connections when pulling link targets to read their claims
+    #[structopt(long = "insecure")]
+    insecure: bool,
+
+    /// Timeout in seconds to wait for hosts to respond and for start acknowledgements
+    #[structopt(long = "timeout", default_value = "1")]
+    timeout: u64,
+}
+
+/// Desired state of a lattice, applied by `ctl apply`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct LatticeManifest {
+    #[serde(default)]
+    pub(crate) actors: Vec<ManifestActor>,
+    #[serde(default)]
+    pub(crate) providers: Vec<ManifestProvider>,
+    #[serde(default)]
+    pub(crate) links: Vec<ManifestLink>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ManifestActor {
+    /// OCI reference of the actor
+    pub(crate) image: String,
+    /// Number of instances that should be running, like `ctl scale`
+    #[serde(default = "default_instances")]
+    pub(crate) instances: usize,
+    /// Host to run the instances on, if omitted they're spread across the lattice
+    pub(crate) host_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ManifestProvider {
+    /// OCI reference of the provider
+    pub(crate) image: String,
+    #[serde(default = "default_link_name")]
+    pub(crate) link_name: String,
+    /// Host to start the provider on, if omitted the provider is auctioned in the lattice
+    pub(crate) host_id: Option<String>,
+    /// Labels a host must have to win the provider auction
+    #[serde(default)]
+    pub(crate) constraints: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ManifestLink {
+    /// Public key or OCI reference of the actor
+    pub(crate) actor: String,
+    /// Public key or OCI reference of the provider
+    pub(crate) provider: String,
+    /// Capability contract ID of the link, read from the provider's claims if omitted
+    pub(crate) contract_id: Option<String>,
+    #[serde(default = "default_link_name")]
+    pub(crate) link_name: String,
+    #[serde(default)]
+    pub(crate) values: BTreeMap<String, String>,
+}
+
+fn default_instances() -> usize {
+    1
+}
+
+fn default_link_name() -> String {
+    "default".to_string()
+}
+
+/// Outcome of applying one item of a manifest
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct ApplyStep {
+    /// Kind of item: actor, provider or link
+    pub(crate) kind: &'static str,
+    pub(crate) item: String,
+    pub(crate) success: bool,
+    /// What was done, or why it failed
+    pub(crate) outcome: String,
+}
+
+impl ApplyStep {
+    fn new(kind: &'static str, item: String, outcome: Result<String>) -> Self {
+        let (success, outcome) = match outcome {
+            Ok(done) => (true, done),
+            Err(e) => (false, e.to_string()),
+        };
+        ApplyStep {
+            kind,
+            item,
+            success,
+            outcome,
+        }
+    }
+}
+
+/// Public key of a link target, along with the contract ID of a provider read from its claims
+#[derive(Debug, Clone, PartialEq)]
+struct LinkTarget {
+    id: String,
+    contract_id: Option<String>,
+}
+
+#[derive(Debug, Clone, StructOpt)]
+pub(crate) struct WatchCommand {
+    #[structopt(flatten)]
+    opts: ConnectionOpts,
+
+    #[structopt(flatten)]
+    pub(crate) output: Output,
+
+    /// Only show events of this type, e.g. actor_started or host_heartbeat. Can be repeated
+    #[structopt(short = "e", long = "event", name = "event")]
+    pub(crate) events: Vec<String>,
+}
+
 pub(crate) async fn handle_command(command: CtlCliCommand) -> Result<String> {
     use CtlCliCommand::*;
-    let mut sp: Option<Spinner> = None;
+    let mut sp: Option<ProgressBar> = None;
     let out = match command {
+        Apply(cmd) => {
+            let output = cmd.output;
+            sp = update_spinner_message(
+                sp,
+                format!(" Applying manifest {} ... ", cmd.manifest.display()),
+                &output,
+            );
+            let manifest = cmd.manifest.display().to_string();
+            let steps = apply_manifest(cmd).await?;
+            let out = apply_output(&manifest, &steps, &output.kind);
+            if let Err(e) = apply_error(&manifest, &steps, &output.kind) {
+                if let Some(sp) = sp.take() {
+                    sp.finish_and_clear()
+                }
+                // The text report is printed before failing, so every step's outcome is shown
+                if let OutputKind::Text { .. } = output.kind {
+                    print_or_log(out);
+                }
+                return Err(e);
+            }
+            out
+        }
+        Auction(AuctionCommand::Actor(cmd)) => {
+            let output = cmd.output;
+            let target = format!("actor {}", cmd.actor_ref);
+            sp = update_spinner_message(sp, format!(" Auctioning {} ... ", target), &output);
+            let hosts = auction_actor(cmd).await?;
+            auction_output(&target, hosts, &output.kind)
+        }
+        Auction(AuctionCommand::Provider(cmd)) => {
+            let output = cmd.output;
+            let target = format!("provider {} ({})", cmd.provider_ref, cmd.link_name);
+            sp = update_spinner_message(sp, format!(" Auctioning {} ... ", target), &output);
+            let hosts = auction_provider(cmd).await?;
+            auction_output(&target, hosts, &output.kind)
+        }
         Call(cmd) => {
             let output = cmd.output;
+            let response_format = cmd.response_format;
             sp =
                 update_spinner_message(sp, format!("Calling actor {} ... ", cmd.actor_id), &output);
             debug!(target: WASH_CMD_INFO, "Calling actor {}", cmd.actor_id);
             let ir = call_actor(cmd).await?;
             debug!(target: WASH_CMD_INFO, "Invocation response {:?}", ir);
-            call_output(ir.error, ir.msg, &output.kind)
+            call_output(ir.error, ir.msg, response_format, &output.kind)
         }
         Get(GetCommand::Hosts(cmd)) => {
             let output = cmd.output;
             sp = update_spinner_message(sp, " Retrieving Hosts ...".to_string(), &output);
-            let hosts = get_hosts(cmd).await?;
+            let hosts = describe_hosts(cmd).await?;
             get_hosts_output(hosts, &output.kind)
         }
         Get(GetCommand::HostInventory(cmd)) => {
             let output = cmd.output;
-            sp = update_spinner_message(
-                sp,
-                format!(" Retrieving inventory for host {} ...", cmd.host_id),
-                &output,
-            );
-            let inv = get_host_inventory(cmd).await?;
-            get_host_inventory_output(inv, &output.kind)
+            let message = match &cmd.host_id {
+                Some(host_id) => format!(" Retrieving inventory for host {} ...", host_id),
+                None => " Retrieving inventories of all hosts ...".to_string(),
+            };
+            sp = update_spinner_message(sp, message, &output);
+            match cmd.host_id.clone() {
+                Some(host_id) => {
+                    let inv = get_host_inventory(cmd.opts, &host_id).await?;
+                    get_host_inventory_output(inv, &output.kind)
+                }
+                None => {
+                    let (_, inventories) = get_inventories(cmd.opts, cmd.timeout).await?;
+                    get_inventories_output(inventories, &output.kind)
+                }
+            }
         }
         Get(GetCommand::Claims(cmd)) => {
             let output = cmd.output;
@@ -485,6 +1022,29 @@ pub(crate) async fn handle_command(command: CtlCliCommand) -> Result<String> {
             debug!(target: WASH_CMD_INFO, "Stop provider ack: {:?}", ack);
             stop_provider_output(&cmd.provider_id, ack.failure, &cmd.output.kind)
         }
+        Stop(StopCommand::Host(cmd)) => {
+            let output = cmd.output;
+            sp =
+                update_spinner_message(sp, format!(" Stopping host {} ... ", cmd.host_id), &output);
+            let result = stop_host(cmd).await?;
+            stop_host_output(result, &output.kind)
+        }
+        Scale(cmd) => {
+            let output = cmd.output;
+            sp = update_spinner_message(
+                sp,
+                format!(" Scaling actor {} to {} ... ", cmd.actor, cmd.count),
+                &output,
+            );
+            let result = scale_actor(cmd).await?;
+            scale_output(result, &output.kind)
+        }
+        Summary(cmd) => {
+            let output = cmd.output;
+            sp = update_spinner_message(sp, " Summarizing lattice ...".to_string(), &output);
+            let summary = get_summary(cmd).await?;
+            summary_output(summary, &output.kind)
+        }
         Update(UpdateCommand::Actor(cmd)) => {
             let output = cmd.output;
             sp = update_spinner_message(
@@ -507,116 +1067,294 @@ pub(crate) async fn handle_command(command: CtlCliCommand) -> Result<String> {
                 &cmd.output.kind,
             )
         }
+        Watch(cmd) => {
+            let output = cmd.output;
+            watch_events(cmd).await?;
+            format_output(
+                "\nLattice event stream closed".to_string(),
+                json!({ "result": "closed" }),
+                &output.kind,
+            )
+        }
     };
 
-    if sp.is_some() {
-        sp.unwrap().stop()
+    if let Some(sp) = sp {
+        sp.finish_and_clear()
     }
 
     Ok(out)
 }
 
-pub(crate) async fn new_ctl_client(
-    host: &str,
-    port: &str,
-    jwt: Option<String>,
-    seed: Option<String>,
-    credsfile: Option<String>,
-    ns_prefix: String,
-    timeout: Duration,
-) -> Result<Client> {
-    let nats_url = format!("{}:{}", host, port);
-    let nc = if let (Some(jwt_file), Some(seed_val)) = (jwt, seed) {
-        let kp = nkeys::KeyPair::from_seed(&extract_arg_value(&seed_val)?)?;
-        let jwt_contents = extract_arg_value(&jwt_file)?;
+/// Connects to NATS using the credentials and TLS settings in the connection options
+pub(crate) async fn nats_connection(opts: &ConnectionOpts) -> Result<nats::asynk::Connection> {
+    let nats_url = format!("{}:{}", opts.rpc_host, opts.rpc_port);
+    debug!(target: WASH_CMD_INFO, "Connecting to NATS at {}", nats_url);
+    let options = if let (Some(jwt_file), Some(seed_val)) = (&opts.rpc_jwt, &opts.rpc_seed) {
+        debug!(target: WASH_CMD_INFO, "Authenticating to NATS with a JWT and seed");
+        let kp = nkeys::KeyPair::from_seed(&extract_arg_value(seed_val)?)?;
+        let jwt_contents = extract_arg_value(jwt_file)?;
         // You must provide the JWT via a closure
         nats::Options::with_jwt(
             move || Ok(jwt_contents.clone()),
             move |nonce| kp.sign(nonce).unwrap(),
         )
-        .connect_async(&nats_url)
-        .await?
-    } else if let Some(credsfile_path) = credsfile {
+    } else if let Some(credsfile_path) = &opts.rpc_credsfile {
+        debug!(
+            target: WASH_CMD_INFO,
+            "Authenticating to NATS with credentials file {}",
+            credsfile_path
+        );
         nats::Options::with_credentials(credsfile_path)
-            .connect_async(&nats_url)
-            .await?
     } else {
-        nats::asynk::connect(&nats_url).await?
+        nats::Options::new()
+    };
+    let options = match &opts.rpc_tls_ca {
+        Some(ca) => options.tls_required(true).add_root_certificate(ca),
+        None => options,
     };
-    Ok(Client::new(nc, Some(ns_prefix), timeout))
+    Ok(options.connect_async(&nats_url).await?)
 }
 
 async fn client_from_opts(opts: ConnectionOpts) -> Result<Client> {
-    new_ctl_client(
-        &opts.rpc_host,
-        &opts.rpc_port,
-        opts.rpc_jwt,
-        opts.rpc_seed,
-        opts.rpc_credsfile,
+    let nc = nats_connection(&opts).await?;
+    debug!(
+        target: WASH_CMD_INFO,
+        "Using lattice prefix {} with a timeout of {:?}",
         opts.ns_prefix,
-        Duration::from_secs(opts.rpc_timeout),
-    )
-    .await
+        opts.timeout()
+    );
+    Ok(Client::new(
+        nc,
+        Some(opts.ns_prefix.clone()),
+        opts.timeout(),
+    ))
 }
 
 pub(crate) async fn call_actor(cmd: CallCommand) -> Result<InvocationResponse> {
+    let bytes = cmd.payload()?;
     let client = client_from_opts(cmd.opts).await?;
-    let bytes = json_str_to_msgpack_bytes(cmd.data)?;
     client
         .call_actor(&cmd.actor_id, &cmd.operation, &bytes)
         .await
-        .map_err(convert_error)
+        .map_err(convert_network_error)
 }
 
 pub(crate) async fn get_hosts(cmd: GetHostsCommand) -> Result<Vec<Host>> {
     let timeout = Duration::from_secs(cmd.timeout);
-    let client = client_from_opts(cmd.opts).await?;
-    client.get_hosts(timeout).await.map_err(convert_error)
-}
-
-pub(crate) async fn get_host_inventory(cmd: GetHostInventoryCommand) -> Result<HostInventory> {
     let client = client_from_opts(cmd.opts).await?;
     client
-        .get_host_inventory(&cmd.host_id)
+        .get_hosts(timeout)
         .await
-        .map_err(convert_error)
+        .map_err(convert_network_error)
 }
 
-pub(crate) async fn get_claims(cmd: GetClaimsCommand) -> Result<ClaimsList> {
+/// Pings the lattice for hosts, waiting up to the command's timeout for responses, and
+/// looks up the labels of each host that responded
+pub(crate) async fn describe_hosts(cmd: GetHostsCommand) -> Result<Vec<HostSummary>> {
     let client = client_from_opts(cmd.opts).await?;
-    client.get_claims().await.map_err(convert_error)
+    let hosts = client
+        .get_hosts(Duration::from_secs(cmd.timeout))
+        .await
+        .map_err(convert_network_error)?;
+    let mut summaries = vec![];
+    for host in hosts {
+        let labels = host_labels(&client, &host.id).await;
+        summaries.push(HostSummary {
+            labels,
+            ..host.into()
+        });
+    }
+    Ok(summaries)
 }
 
-pub(crate) async fn advertise_link(cmd: LinkCommand) -> Result<()> {
-    let client = client_from_opts(cmd.opts).await?;
+pub(crate) async fn get_host_inventory(
+    opts: ConnectionOpts,
+    host_id: &str,
+) -> Result<HostInventory> {
+    let client = client_from_opts(opts.clone()).await?;
+    let host_id = resolve_host_id(&client, &opts, host_id).await?;
     client
-        .advertise_link(
-            &cmd.actor_id,
-            &cmd.provider_id,
-            &cmd.contract_id,
-            &cmd.link_name.unwrap_or_else(|| "default".to_string()),
-            labels_vec_to_hashmap(cmd.values)?,
-        )
+        .get_host_inventory(&host_id)
         .await
-        .map_err(convert_error)
+        .map_err(convert_network_error)
 }
 
-pub(crate) async fn start_actor(cmd: StartActorCommand) -> Result<StartActorAck> {
-    let client = client_from_opts(cmd.opts.clone()).await?;
+/// Resolves a host ID, or a unique prefix of one, to the full ID of a host in the lattice.
+/// Full host IDs are used as they are, without querying the lattice for its hosts
+async fn resolve_host_id(client: &Client, opts: &ConnectionOpts, host_id: &str) -> Result<String> {
+    if is_host_id(host_id) {
+        return Ok(host_id.to_string());
+    }
+    let hosts = client
+        .get_hosts(opts.timeout())
+        .await
+        .map_err(convert_network_error)?;
+    let resolved = match_host_id(host_id, hosts.into_iter().map(|h| h.id).collect())?;
+    debug!(target: WASH_CMD_INFO, "Resolved host {} to {}", host_id, resolved);
+    Ok(resolved)
+}
 
-    let host = match cmd.host_id {
-        Some(host) => host,
-        None => {
-            let suitable_hosts = client
+/// Finds the only host ID starting with the prefix, ignoring case
+fn match_host_id(prefix: &str, host_ids: Vec<String>) -> Result<String> {
+    let prefix = prefix.to_ascii_uppercase();
+    let mut candidates: Vec<String> = host_ids
+        .into_iter()
+        .filter(|id| id.starts_with(&prefix))
+        .collect();
+    candidates.sort();
+    match candidates.len() {
+        0 => Err(Box::new(WashError::NotFound(format!(
+            "No host in the lattice has an ID starting with {}",
+            prefix
+        )))),
+        1 => Ok(candidates.remove(0)),
+        _ => Err(Box::new(WashError::InvalidInput(format!(
+            "Host ID prefix {} is ambiguous, it matches:\n{}",
+            prefix,
+            candidates.join("\n")
+        )))),
+    }
+}
+
+/// Returns true if the input is a complete host ID, a server public key
+fn is_host_id(input: &str) -> bool {
+    input.len() == 56
+        && input.starts_with('N')
+        && input
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+}
+
+/// Queries every host that responds within `timeout` seconds for its inventory, returning
+/// the number of hosts that responded along with the inventories they reported. Hosts that
+/// fail to report their inventory are left out
+pub(crate) async fn get_inventories(
+    opts: ConnectionOpts,
+    timeout: u64,
+) -> Result<(usize, Vec<HostInventory>)> {
+    let client = client_from_opts(opts).await?;
+    let hosts = client
+        .get_hosts(Duration::from_secs(timeout))
+        .await
+        .map_err(convert_network_error)?;
+    let mut inventories = vec![];
+    for host in hosts.iter() {
+        match client.get_host_inventory(&host.id).await {
+            Ok(inv) => inventories.push(inv),
+            Err(e) => debug!(
+                target: WASH_CMD_INFO,
+                "Host {} did not report its inventory: {}", host.id, e
+            ),
+        }
+    }
+    Ok((hosts.len(), inventories))
+}
+
+/// Queries every responding host for its inventory. Hosts that fail to report their
+/// inventory are left out of the totals, which are then flagged as partial
+pub(crate) async fn get_summary(cmd: SummaryCommand) -> Result<LatticeSummary> {
+    let (hosts, inventories) = get_inventories(cmd.opts, cmd.timeout).await?;
+    Ok(LatticeSummary::new(hosts, &inventories))
+}
+
+pub(crate) async fn get_claims(cmd: GetClaimsCommand) -> Result<ClaimsList> {
+    let client = client_from_opts(cmd.opts).await?;
+    client.get_claims().await.map_err(convert_network_error)
+}
+
+pub(crate) async fn advertise_link(cmd: LinkCommand) -> Result<()> {
+    let client = client_from_opts(cmd.opts).await?;
+    client
+        .advertise_link(
+            &cmd.actor_id,
+            &cmd.provider_id,
+            &cmd.contract_id,
+            &cmd.link_name.unwrap_or_else(|| "default".to_string()),
+            labels_vec_to_hashmap(cmd.values)?,
+        )
+        .await
+        .map_err(convert_network_error)
+}
+
+/// Auctions an actor in the lattice, returning the hosts that responded as able to run it
+pub(crate) async fn auction_actor(cmd: AuctionActorCommand) -> Result<Vec<AuctionHost>> {
+    let client = client_from_opts(cmd.opts).await?;
+    let acks = client
+        .perform_actor_auction(
+            &cmd.actor_ref,
+            labels_vec_to_hashmap(cmd.constraints.unwrap_or_default())?,
+            Duration::from_secs(cmd.timeout),
+        )
+        .await
+        .map_err(convert_network_error)?;
+    Ok(auction_hosts(&client, acks.into_iter().map(|ack| ack.host_id)).await)
+}
+
+/// Auctions a provider in the lattice, returning the hosts that responded as able to run it
+pub(crate) async fn auction_provider(cmd: AuctionProviderCommand) -> Result<Vec<AuctionHost>> {
+    let client = client_from_opts(cmd.opts).await?;
+    let acks = client
+        .perform_provider_auction(
+            &cmd.provider_ref,
+            &cmd.link_name,
+            labels_vec_to_hashmap(cmd.constraints.unwrap_or_default())?,
+            Duration::from_secs(cmd.timeout),
+        )
+        .await
+        .map_err(convert_network_error)?;
+    Ok(auction_hosts(&client, acks.into_iter().map(|ack| ack.host_id)).await)
+}
+
+/// Looks up the labels of each host that responded to an auction. Acks don't carry labels, so
+/// they're read from the host's inventory, and left empty for hosts that fail to report it
+async fn auction_hosts(
+    client: &Client,
+    host_ids: impl Iterator<Item = String>,
+) -> Vec<AuctionHost> {
+    let mut hosts = vec![];
+    for host_id in host_ids {
+        let labels = host_labels(client, &host_id).await;
+        hosts.push(AuctionHost { host_id, labels });
+    }
+    hosts
+}
+
+/// Looks up the labels of a host from its inventory, which are empty if it doesn't report one
+async fn host_labels(client: &Client, host_id: &str) -> BTreeMap<String, String> {
+    match client.get_host_inventory(host_id).await {
+        Ok(inv) => inv.labels.into_iter().collect(),
+        Err(e) => {
+            debug!(
+                target: WASH_CMD_INFO,
+                "Host {} did not report its inventory: {}", host_id, e
+            );
+            BTreeMap::new()
+        }
+    }
+}
+
+pub(crate) async fn start_actor(cmd: StartActorCommand) -> Result<StartActorAck> {
+    let client = client_from_opts(cmd.opts.clone()).await?;
+
+    let host = match cmd.host_id {
+        Some(host) => resolve_host_id(&client, &cmd.opts, &host).await?,
+        None => {
+            let constraints = cmd.constraints.unwrap_or_default();
+            let suitable_hosts = client
                 .perform_actor_auction(
                     &cmd.actor_ref,
-                    labels_vec_to_hashmap(cmd.constraints.unwrap_or_default())?,
+                    labels_vec_to_hashmap(constraints.clone())?,
                     Duration::from_secs(cmd.timeout),
                 )
                 .await
-                .map_err(convert_error)?;
+                .map_err(convert_network_error)?;
             if suitable_hosts.is_empty() {
-                return Err(format!("No suitable hosts found for actor {}", cmd.actor_ref).into());
+                return Err(no_suitable_hosts(
+                    &format!("actor {}", cmd.actor_ref),
+                    &constraints,
+                    cmd.timeout,
+                )
+                .into());
             } else {
                 suitable_hosts[0].host_id.to_string()
             }
@@ -626,28 +1364,32 @@ pub(crate) async fn start_actor(cmd: StartActorCommand) -> Result<StartActorAck>
     client
         .start_actor(&host, &cmd.actor_ref)
         .await
-        .map_err(convert_error)
+        .map_err(convert_network_error)
 }
 
 pub(crate) async fn start_provider(cmd: StartProviderCommand) -> Result<StartProviderAck> {
     let client = client_from_opts(cmd.opts.clone()).await?;
 
     let host = match cmd.host_id {
-        Some(host) => host,
+        Some(host) => resolve_host_id(&client, &cmd.opts, &host).await?,
         None => {
+            let constraints = cmd.constraints.unwrap_or_default();
             let suitable_hosts = client
                 .perform_provider_auction(
                     &cmd.provider_ref,
                     &cmd.link_name,
-                    labels_vec_to_hashmap(cmd.constraints.unwrap_or_default())?,
+                    labels_vec_to_hashmap(constraints.clone())?,
                     Duration::from_secs(cmd.timeout),
                 )
                 .await
-                .map_err(convert_error)?;
+                .map_err(convert_network_error)?;
             if suitable_hosts.is_empty() {
-                return Err(
-                    format!("No suitable hosts found for provider {}", cmd.provider_ref).into(),
-                );
+                return Err(no_suitable_hosts(
+                    &format!("provider {}", cmd.provider_ref),
+                    &constraints,
+                    cmd.timeout,
+                )
+                .into());
             } else {
                 suitable_hosts[0].host_id.to_string()
             }
@@ -657,52 +1399,506 @@ pub(crate) async fn start_provider(cmd: StartProviderCommand) -> Result<StartPro
     client
         .start_provider(&host, &cmd.provider_ref, Some(cmd.link_name))
         .await
-        .map_err(convert_error)
+        .map_err(convert_network_error)
+}
+
+/// Describes a failed auction, including the constraints no host satisfied
+fn no_suitable_hosts(target: &str, constraints: &[String], timeout: u64) -> String {
+    if constraints.is_empty() {
+        format!("No suitable hosts found for {} within {}s", target, timeout)
+    } else {
+        format!(
+            "No suitable hosts found for {} matching constraints {} within {}s",
+            target,
+            constraints.join(", "),
+            timeout
+        )
+    }
 }
 
 pub(crate) async fn stop_provider(cmd: StopProviderCommand) -> Result<StopProviderAck> {
-    let client = client_from_opts(cmd.opts).await?;
+    let client = client_from_opts(cmd.opts.clone()).await?;
+    let host_id = resolve_host_id(&client, &cmd.opts, &cmd.host_id).await?;
     client
-        .stop_provider(
-            &cmd.host_id,
-            &cmd.provider_id,
-            &cmd.link_name,
-            &cmd.contract_id,
-        )
+        .stop_provider(&host_id, &cmd.provider_id, &cmd.link_name, &cmd.contract_id)
         .await
-        .map_err(convert_error)
+        .map_err(convert_network_error)
 }
 
 pub(crate) async fn stop_actor(cmd: StopActorCommand) -> Result<StopActorAck> {
-    let client = client_from_opts(cmd.opts).await?;
+    let client = client_from_opts(cmd.opts.clone()).await?;
+    let host_id = resolve_host_id(&client, &cmd.opts, &cmd.host_id).await?;
     client
-        .stop_actor(&cmd.host_id, &cmd.actor_id)
+        .stop_actor(&host_id, &cmd.actor_id)
         .await
-        .map_err(convert_error)
+        .map_err(convert_network_error)
 }
 
-pub(crate) async fn update_actor(cmd: UpdateActorCommand) -> Result<UpdateActorAck> {
+/// Stops a host through the control interface, first stopping its actors and then its
+/// providers when `--drain` is set. The control interface client has no host stop request,
+/// so it's sent to the host directly
+pub(crate) async fn stop_host(cmd: StopHostCommand) -> Result<StopHostResult> {
+    let client = client_from_opts(cmd.opts.clone()).await?;
+    let host_id = resolve_host_id(&client, &cmd.opts, &cmd.host_id).await?;
+    let mut result = StopHostResult {
+        host_id: host_id.clone(),
+        ..Default::default()
+    };
+    if cmd.drain {
+        let inventory = client
+            .get_host_inventory(&host_id)
+            .await
+            .map_err(convert_network_error)?;
+        for actor in inventory.actors.iter() {
+            let failure = client
+                .stop_actor(&host_id, &actor.id)
+                .await
+                .map_err(convert_network_error)
+                .map(|ack| ack.failure)
+                .unwrap_or_else(|e| Some(e.to_string()));
+            match failure {
+                Some(f) => result
+                    .failures
+                    .push(format!("Failed to stop actor {}: {}", actor.id, f)),
+                None => result.actors_stopped += 1,
+            }
+        }
+        let claims = client.get_claims().await.map_err(convert_network_error)?;
+        for provider in inventory.providers.iter() {
+            let failure = match provider_contract_id(&claims, &provider.id) {
+                Some(contract_id) => client
+                    .stop_provider(&host_id, &provider.id, &provider.link_name, &contract_id)
+                    .await
+                    .map_err(convert_network_error)
+                    .map(|ack| ack.failure)
+                    .unwrap_or_else(|e| Some(e.to_string())),
+                None => Some("no capability contract ID in its claims".to_string()),
+            };
+            match failure {
+                Some(f) => result
+                    .failures
+                    .push(format!("Failed to stop provider {}: {}", provider.id, f)),
+                None => result.providers_stopped += 1,
+            }
+        }
+    }
+
+    let nc = nats_connection(&cmd.opts).await?;
+    let subject = stop_host_subject(&cmd.opts.ns_prefix, &host_id);
+    debug!(target: WASH_CMD_INFO, "Requesting host stop on {}", subject);
+    let request = nc.request(&subject, stop_host_payload(&host_id, cmd.timeout));
+    let reply = actix_rt::time::timeout(cmd.opts.timeout(), request)
+        .await
+        .map_err(|_| {
+            format!(
+                "Host {} did not acknowledge the stop request within {:?}",
+                host_id,
+                cmd.opts.timeout()
+            )
+        })??;
+    let ack: CtlOperationAck = serde_json::from_slice(&reply.data)
+        .map_err(|e| format!("Invalid acknowledgement from host {}: {}", host_id, e))?;
+    if !ack.accepted {
+        result.error = Some(ack.error);
+    }
+    Ok(result)
+}
+
+fn stop_host_subject(ns_prefix: &str, host_id: &str) -> String {
+    format!("{}.{}.cmd.{}.stop", CTL_TOPIC_PREFIX, ns_prefix, host_id)
+}
+
+/// The host stop command, with the graceful shutdown timeout in milliseconds
+fn stop_host_payload(host_id: &str, timeout: Option<u64>) -> Vec<u8> {
+    json!({
+        "host_id": host_id,
+        "timeout": timeout.map(|secs| secs * 1000),
+    })
+    .to_string()
+    .into_bytes()
+}
+
+/// Looks up the capability contract a provider was signed with, which is needed to stop it
+fn provider_contract_id(claims: &ClaimsList, provider_id: &str) -> Option<String> {
+    claims
+        .claims
+        .iter()
+        .find(|c| c.values.get("sub").map(String::as_str) == Some(provider_id))
+        .and_then(|c| c.values.get("capid").cloned())
+        .filter(|capid| !capid.is_empty())
+}
+
+/// Starts or stops instances of an actor, on a single host or spread across the lattice,
+/// until the requested number are running
+pub(crate) async fn scale_actor(cmd: ScaleCommand) -> Result<ScaleResult> {
+    let inventories = match &cmd.host_id {
+        Some(host_id) => vec![get_host_inventory(cmd.opts.clone(), host_id).await?],
+        None => get_inventories(cmd.opts.clone(), cmd.timeout).await?.1,
+    };
+    let previous = inventories
+        .iter()
+        .flat_map(|inv| inv.actors.iter())
+        .filter(|a| actor_matches(a, &cmd.actor))
+        .count();
+    let actions = plan_scale(&cmd.actor, cmd.count, &inventories)?;
+
     let client = client_from_opts(cmd.opts).await?;
+    let mut result = ScaleResult {
+        actor: cmd.actor,
+        previous,
+        requested: cmd.count,
+        started: 0,
+        stopped: 0,
+        failures: vec![],
+    };
+    for action in actions {
+        let failure = match &action {
+            ScaleAction::Start { host_id, actor_ref } => client
+                .start_actor(host_id, actor_ref)
+                .await
+                .map_err(convert_network_error)
+                .map(|ack| ack.failure),
+            ScaleAction::Stop { host_id, actor_id } => client
+                .stop_actor(host_id, actor_id)
+                .await
+                .map_err(convert_network_error)
+                .map(|ack| ack.failure),
+        }
+        .unwrap_or_else(|e| Some(e.to_string()));
+        match (failure, action) {
+            (Some(f), ScaleAction::Start { host_id, .. }) => result
+                .failures
+                .push(format!("Failed to start on {}: {}", host_id, f)),
+            (Some(f), ScaleAction::Stop { host_id, .. }) => result
+                .failures
+                .push(format!("Failed to stop on {}: {}", host_id, f)),
+            (None, ScaleAction::Start { .. }) => result.started += 1,
+            (None, ScaleAction::Stop { .. }) => result.stopped += 1,
+        }
+    }
+    Ok(result)
+}
+
+fn actor_matches(actor: &ActorDescription, id_or_ref: &str) -> bool {
+    actor.id == id_or_ref || actor.image_ref.as_deref() == Some(id_or_ref)
+}
+
+/// Plans the requests that bring the number of instances of an actor across the inventories
+/// to `count`. New instances go to the hosts running the fewest instances, and instances are
+/// stopped on the hosts running the most, so the actor stays spread evenly
+pub(crate) fn plan_scale(
+    actor: &str,
+    count: usize,
+    inventories: &[HostInventory],
+) -> Result<Vec<ScaleAction>> {
+    if inventories.is_empty() {
+        return Err("No hosts reported their inventory, unable to scale actor".into());
+    }
+    // Instances of the actor on each host, in inventory order
+    let mut instances: Vec<(&str, Vec<&ActorDescription>)> = inventories
+        .iter()
+        .map(|inv| {
+            let running = inv
+                .actors
+                .iter()
+                .filter(|a| actor_matches(a, actor))
+                .collect();
+            (inv.host_id.as_str(), running)
+        })
+        .collect();
+    let current: usize = instances.iter().map(|(_, running)| running.len()).sum();
+
+    let mut actions = vec![];
+    if count > current {
+        let known_ref = instances
+            .iter()
+            .flat_map(|(_, running)| running.iter())
+            .find_map(|a| a.image_ref.clone());
+        let actor_ref = match known_ref {
+            Some(actor_ref) => actor_ref,
+            None if current == 0 && !is_actor_id(actor) => actor.to_string(),
+            None => {
+                return Err(format!(
+                    "Unable to start instances of {}, no OCI reference is known for it. Scale it by its OCI reference instead",
+                    actor
+                )
+                .into())
+            }
+        };
+        let mut counts: Vec<usize> = instances.iter().map(|(_, r)| r.len()).collect();
+        for _ in current..count {
+            let (host, _) = counts.iter().enumerate().min_by_key(|(_, n)| **n).unwrap();
+            counts[host] += 1;
+            actions.push(ScaleAction::Start {
+                host_id: instances[host].0.to_string(),
+                actor_ref: actor_ref.clone(),
+            });
+        }
+    }
+    for _ in count..current {
+        // Ties go to the first host, mirroring how instances are started
+        let (host, _) = instances
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|(_, (_, running))| running.len())
+            .unwrap();
+        let stopped = instances[host].1.pop().unwrap();
+        actions.push(ScaleAction::Stop {
+            host_id: instances[host].0.to_string(),
+            actor_id: stopped.id.clone(),
+        });
+    }
+    Ok(actions)
+}
+
+/// Returns true if the input looks like an actor's public key rather than an OCI reference
+fn is_actor_id(input: &str) -> bool {
+    input.len() == 56
+        && input.starts_with('M')
+        && input
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+}
+
+/// Returns true if the input looks like a provider's public key rather than an OCI reference
+fn is_provider_id(input: &str) -> bool {
+    input.len() == 56
+        && input.starts_with('V')
+        && input
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+}
+
+/// Reads a `ctl apply` manifest, which is YAML (or JSON)
+fn load_lattice_manifest(path: &Path) -> Result<LatticeManifest> {
+    let contents = std::fs::read_to_string(path).map_err(|e| {
+        WashError::InvalidInput(format!("Unable to read manifest {}: {}", path.display(), e))
+    })?;
+    serde_yaml::from_str(&contents).map_err(|e| {
+        WashError::InvalidInput(format!("Invalid manifest {}: {}", path.display(), e)).into()
+    })
+}
+
+/// Starts the providers, then scales the actors and finally advertises the links of a
+/// manifest, reporting the outcome of each item. Providers already running with the same
+/// link name are left alone and actors are only scaled when their instance count differs,
+/// so applying a manifest again changes nothing
+pub(crate) async fn apply_manifest(cmd: ApplyCommand) -> Result<Vec<ApplyStep>> {
+    let manifest = load_lattice_manifest(&cmd.manifest)?;
+    let mut steps = vec![];
+
+    let inventories = if manifest.providers.is_empty() {
+        vec![]
+    } else {
+        get_inventories(cmd.opts.clone(), cmd.timeout).await?.1
+    };
+    for provider in manifest.providers.iter() {
+        let outcome = match running_provider(&inventories, provider) {
+            Some(host_id) => Ok(format!("already running on {}", host_id)),
+            None => start_provider(StartProviderCommand {
+                opts: cmd.opts.clone(),
+                output: cmd.output,
+                host_id: provider.host_id.clone(),
+                provider_ref: provider.image.clone(),
+                link_name: provider.link_name.clone(),
+                constraints: Some(
+                    provider
+                        .constraints
+                        .iter()
+                        .map(|(label, value)| format!("{}={}", label, value))
+                        .collect(),
+                ),
+                timeout: cmd.timeout,
+            })
+            .await
+            .and_then(|ack| match ack.failure {
+                Some(f) => Err(f.into()),
+                None => Ok(format!("started on {}", ack.host_id)),
+            }),
+        };
+        let item = format!("{} ({})", provider.image, provider.link_name);
+        steps.push(ApplyStep::new("provider", item, outcome));
+    }
+
+    for actor in manifest.actors.iter() {
+        let outcome = scale_actor(ScaleCommand {
+            opts: cmd.opts.clone(),
+            output: cmd.output,
+            host_id: actor.host_id.clone(),
+            actor: actor.image.clone(),
+            count: actor.instances,
+            timeout: cmd.timeout,
+        })
+        .await
+        .and_then(|result| {
+            if !result.failures.is_empty() {
+                Err(result.failures.join(", ").into())
+            } else if result.started == 0 && result.stopped == 0 {
+                Ok(format!("{} instance(s) already running", result.requested))
+            } else {
+                Ok(format!(
+                    "scaled from {} to {} instance(s)",
+                    result.previous, result.requested
+                ))
+            }
+        });
+        steps.push(ApplyStep::new("actor", actor.image.clone(), outcome));
+    }
+
+    for link in manifest.links.iter() {
+        let item = format!("{} -> {} ({})", link.actor, link.provider, link.link_name);
+        let outcome = apply_link(&cmd, link).await;
+        steps.push(ApplyStep::new("link", item, outcome));
+    }
+    Ok(steps)
+}
+
+/// Returns the host already running a provider with the manifest's image and link name
+fn running_provider(inventories: &[HostInventory], provider: &ManifestProvider) -> Option<String> {
+    inventories
+        .iter()
+        .find(|inv| {
+            inv.providers.iter().any(|p| {
+                p.image_ref.as_deref() == Some(provider.image.as_str())
+                    && p.link_name == provider.link_name
+            })
+        })
+        .map(|inv| inv.host_id.clone())
+}
+
+/// Advertises a link of the manifest. Advertising a link that already exists with the same
+/// values leaves it unchanged
+async fn apply_link(cmd: &ApplyCommand, link: &ManifestLink) -> Result<String> {
+    let actor = resolve_link_target(&link.actor, is_actor_id, cmd.insecure).await?;
+    let provider = resolve_link_target(&link.provider, is_provider_id, cmd.insecure).await?;
+    let contract_id = match link.contract_id.clone().or(provider.contract_id) {
+        Some(contract_id) => contract_id,
+        None => {
+            let client = client_from_opts(cmd.opts.clone()).await?;
+            let claims = client.get_claims().await.map_err(convert_network_error)?;
+            provider_contract_id(&claims, &provider.id).ok_or_else(|| {
+                format!(
+                    "Unable to find the contract ID of provider {}, set contract_id in the manifest",
+                    link.provider
+                )
+            })?
+        }
+    };
+    advertise_link(LinkCommand {
+        opts: cmd.opts.clone(),
+        output: cmd.output,
+        actor_id: actor.id.clone(),
+        provider_id: provider.id.clone(),
+        contract_id: contract_id.clone(),
+        link_name: Some(link.link_name.clone()),
+        values: link
+            .values
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect(),
+    })
+    .await?;
+    Ok(format!(
+        "advertised {} between {} and {}",
+        contract_id, actor.id, provider.id
+    ))
+}
+
+/// Resolves a link target given as a public key or an OCI reference. References are pulled
+/// through the local cache to read the public key from the artifact's claims
+async fn resolve_link_target(
+    target: &str,
+    is_public_key: fn(&str) -> bool,
+    insecure: bool,
+) -> Result<LinkTarget> {
+    if is_public_key(target) {
+        return Ok(LinkTarget {
+            id: target.to_string(),
+            contract_id: None,
+        });
+    }
+    let url = crate::reg::strip_oci_scheme(target).unwrap_or(target);
+    // Hosts start artifacts by their latest tag as well
+    let artifact =
+        crate::reg::pull_artifact(url.to_string(), None, true, None, None, insecure, None).await?;
+    artifact_link_target(&artifact)
+        .map_err(|e| format!("Unable to read the claims of {}: {}", target, e).into())
+}
+
+/// Reads the public key of an actor module or provider archive, and a provider's contract ID
+fn artifact_link_target(artifact: &[u8]) -> Result<LinkTarget> {
+    if crate::par::looks_like_archive(artifact) {
+        let jwt = crate::par::archive_claims_token(artifact)?;
+        let claims = wascap::jwt::Claims::<wascap::jwt::CapabilityProvider>::decode(&jwt)?;
+        Ok(LinkTarget {
+            id: claims.subject,
+            contract_id: claims.metadata.map(|m| m.capid),
+        })
+    } else {
+        let token =
+            wascap::wasm::extract_claims(artifact)?.ok_or("module does not contain claims")?;
+        Ok(LinkTarget {
+            id: token.claims.subject,
+            contract_id: None,
+        })
+    }
+}
+
+pub(crate) async fn update_actor(cmd: UpdateActorCommand) -> Result<UpdateActorAck> {
+    let client = client_from_opts(cmd.opts.clone()).await?;
+    let host_id = resolve_host_id(&client, &cmd.opts, &cmd.host_id).await?;
     client
-        .update_actor(&cmd.host_id, &cmd.actor_id, &cmd.new_actor_ref)
+        .update_actor(&host_id, &cmd.actor_id, &cmd.new_actor_ref)
         .await
-        .map_err(convert_error)
+        .map_err(convert_network_error)
+}
+
+/// Subscribes to the lattice event topic and prints each event, optionally filtered by type,
+/// as it arrives. Returns once the subscription is closed
+pub(crate) async fn watch_events(cmd: WatchCommand) -> Result<()> {
+    let nc = nats_connection(&cmd.opts).await?;
+    let sub = nc
+        .subscribe(&format!("{}.{}", EVENT_TOPIC_PREFIX, cmd.opts.ns_prefix))
+        .await?;
+    while let Some(msg) = sub.next().await {
+        let event: serde_json::Value = match serde_json::from_slice(&msg.data) {
+            Ok(event) => event,
+            Err(e) => {
+                debug!(target: WASH_CMD_INFO, "Skipping malformed lattice event: {}", e);
+                continue;
+            }
+        };
+        if event_matches(&event, &cmd.events) {
+            print_or_log(event_output(&event, &cmd.output.kind));
+        }
+    }
+    Ok(())
+}
+
+/// Returns true if the event's type matches one of the filters. Filters may be the full
+/// CloudEvents type (com.wasmcloud.lattice.actor_started) or just its last segment (actor_started)
+fn event_matches(event: &serde_json::Value, filters: &[String]) -> bool {
+    if filters.is_empty() {
+        return true;
+    }
+    let event_type = event["type"].as_str().unwrap_or_default();
+    let short_type = event_type.rsplit('.').next().unwrap_or_default();
+    filters.iter().any(|f| f == event_type || f == short_type)
 }
 
 /// Handles updating the spinner for text output
 /// JSON output will be corrupted with a spinner
 fn update_spinner_message(
-    spinner: Option<Spinner>,
+    spinner: Option<ProgressBar>,
     msg: String,
     output: &Output,
-) -> Option<Spinner> {
+) -> Option<ProgressBar> {
     if let Some(sp) = spinner {
-        sp.message(msg);
+        sp.set_message(msg);
         Some(sp)
-    } else if matches!(output.kind, OutputKind::Text { .. })
-        && output_destination() == OutputDestination::Cli
-    {
-        Some(Spinner::new(Spinners::Dots12, msg))
+    } else if show_progress(&output.kind) {
+        Some(start_spinner(msg))
     } else {
         None
     }
@@ -711,6 +1907,7 @@ fn update_spinner_message(
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::util::OutputKind;
 
     const RPC_HOST: &str = "0.0.0.0";
     const RPC_PORT: &str = "4222";
@@ -738,6 +1935,8 @@ mod test {
             RPC_PORT,
             "--rpc-timeout",
             "1",
+            "--response-format",
+            "base64",
             ACTOR_ID,
             "HandleOperation",
             "{ \"hello\": \"world\"}",
@@ -749,6 +1948,8 @@ mod test {
                 actor_id,
                 operation,
                 data,
+                data_file,
+                response_format,
             }) => {
                 assert_eq!(opts.rpc_host, RPC_HOST);
                 assert_eq!(opts.rpc_port, RPC_PORT);
@@ -757,7 +1958,9 @@ mod test {
                 assert_eq!(output.kind, OutputKind::Json);
                 assert_eq!(actor_id, ACTOR_ID);
                 assert_eq!(operation, "HandleOperation");
-                assert_eq!(data, vec!["{ \"hello\": \"world\"}".to_string()])
+                assert_eq!(data, vec!["{ \"hello\": \"world\"}".to_string()]);
+                assert!(data_file.is_none());
+                assert_eq!(response_format, CallResponseFormat::Base64);
             }
             cmd => panic!("ctl call constructed incorrect command: {:?}", cmd),
         }
@@ -978,13 +2181,28 @@ mod test {
                 opts,
                 output,
                 host_id,
+                timeout,
             })) => {
                 assert_eq!(opts.rpc_host, RPC_HOST);
                 assert_eq!(opts.rpc_port, RPC_PORT);
                 assert_eq!(opts.ns_prefix, NS_PREFIX);
                 assert_eq!(opts.rpc_timeout, 1);
                 assert_eq!(output.kind, OutputKind::Json);
-                assert_eq!(host_id, HOST_ID.to_string());
+                assert_eq!(host_id.unwrap(), HOST_ID.to_string());
+                assert_eq!(timeout, 1);
+            }
+            cmd => panic!("ctl get inventory constructed incorrect command {:?}", cmd),
+        }
+        let get_inventories =
+            CtlCli::from_iter_safe(&["ctl", "get", "inventory", "--timeout", "5"])?;
+        match get_inventories.command {
+            CtlCliCommand::Get(GetCommand::HostInventory(GetHostInventoryCommand {
+                host_id,
+                timeout,
+                ..
+            })) => {
+                assert!(host_id.is_none());
+                assert_eq!(timeout, 5);
             }
             cmd => panic!("ctl get inventory constructed incorrect command {:?}", cmd),
         }
@@ -1093,7 +2311,770 @@ mod test {
             }
             cmd => panic!("ctl get claims constructed incorrect command {:?}", cmd),
         }
+        let summary_all = CtlCli::from_iter_safe(&[
+            "ctl",
+            "summary",
+            "-o",
+            "json",
+            "--ns-prefix",
+            NS_PREFIX,
+            "--rpc-host",
+            RPC_HOST,
+            "--rpc-port",
+            RPC_PORT,
+            "--rpc-timeout",
+            "1",
+            "--timeout",
+            "3",
+        ])?;
+        match summary_all.command {
+            CtlCliCommand::Summary(super::SummaryCommand {
+                opts,
+                output,
+                timeout,
+            }) => {
+                assert_eq!(opts.rpc_host, RPC_HOST);
+                assert_eq!(opts.rpc_port, RPC_PORT);
+                assert_eq!(opts.ns_prefix, NS_PREFIX);
+                assert_eq!(opts.rpc_timeout, 1);
+                assert_eq!(output.kind, OutputKind::Json);
+                assert_eq!(timeout, 3);
+            }
+            cmd => panic!("ctl summary constructed incorrect command {:?}", cmd),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rpc_timeout() -> Result<()> {
+        let secs = CtlCli::from_iter_safe(&["ctl", "get", "claims", "--rpc-timeout", "3"])?;
+        let ms = CtlCli::from_iter_safe(&[
+            "ctl",
+            "get",
+            "claims",
+            "--rpc-timeout",
+            "3",
+            "--rpc-timeout-ms",
+            "2500",
+        ])?;
+        match (secs.command, ms.command) {
+            (
+                CtlCliCommand::Get(GetCommand::Claims(secs)),
+                CtlCliCommand::Get(GetCommand::Claims(ms)),
+            ) => {
+                assert_eq!(secs.opts.timeout(), Duration::from_secs(3));
+                assert_eq!(ms.opts.timeout(), Duration::from_millis(2500));
+            }
+            cmds => panic!("ctl get claims constructed incorrect commands {:?}", cmds),
+        }
+        assert_eq!(ConnectionOpts::default().timeout(), Duration::from_secs(1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_lattice_prefix_alias() -> Result<()> {
+        let cmd =
+            CtlCli::from_iter_safe(&["ctl", "get", "claims", "--lattice-prefix", "tenant-a"])?;
+        match cmd.command {
+            CtlCliCommand::Get(GetCommand::Claims(cmd)) => {
+                assert_eq!(cmd.opts.ns_prefix, "tenant-a")
+            }
+            cmd => panic!("ctl get claims constructed incorrect command {:?}", cmd),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_rpc_tls_ca() -> Result<()> {
+        let cmd = CtlCli::from_iter_safe(&[
+            "ctl",
+            "get",
+            "claims",
+            "--rpc-credsfile",
+            "./lattice.creds",
+            "--rpc-tls-ca",
+            "./ca.pem",
+        ])?;
+        match cmd.command {
+            CtlCliCommand::Get(GetCommand::Claims(cmd)) => {
+                assert_eq!(cmd.opts.rpc_credsfile.unwrap(), "./lattice.creds");
+                assert_eq!(cmd.opts.rpc_tls_ca.unwrap(), "./ca.pem");
+            }
+            cmd => panic!("ctl get claims constructed incorrect command {:?}", cmd),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_call_response_format() {
+        let msg = b"hello".to_vec();
+        assert_eq!(
+            call_output(
+                None,
+                msg.clone(),
+                CallResponseFormat::Base64,
+                &OutputKind::Json
+            ),
+            "{\"response\":\"aGVsbG8=\"}"
+        );
+        assert_eq!(
+            call_output(None, msg, CallResponseFormat::Raw, &Output::default().kind),
+            "\nCall response (raw): hello"
+        );
+        assert_eq!(
+            call_output(
+                Some("boom".to_string()),
+                vec![],
+                CallResponseFormat::Json,
+                &OutputKind::Json
+            ),
+            "{\"error\":\"boom\"}"
+        );
+        assert!("yaml".parse::<CallResponseFormat>().is_err());
+        assert!(CtlCli::from_iter_safe(&[
+            "ctl",
+            "call",
+            "--data-file",
+            "./payload.json",
+            ACTOR_ID,
+            "HandleOperation",
+        ])
+        .is_ok());
+    }
+
+    #[test]
+    fn test_watch_events() -> Result<()> {
+        let cmd = CtlCli::from_iter_safe(&[
+            "ctl",
+            "watch",
+            "-o",
+            "json",
+            "--event",
+            "actor_started",
+            "-e",
+            "com.wasmcloud.lattice.host_started",
+        ])?;
+        let filters = match cmd.command {
+            CtlCliCommand::Watch(WatchCommand { output, events, .. }) => {
+                assert_eq!(output.kind, OutputKind::Json);
+                events
+            }
+            cmd => panic!("ctl watch constructed incorrect command {:?}", cmd),
+        };
+
+        let actor_started = json!({
+            "type": "com.wasmcloud.lattice.actor_started",
+            "source": HOST_ID,
+            "time": "2021-04-01T12:00:00Z",
+            "data": { "public_key": ACTOR_ID },
+        });
+        let host_started = json!({ "type": "com.wasmcloud.lattice.host_started" });
+        let heartbeat = json!({ "type": "com.wasmcloud.lattice.host_heartbeat" });
+        assert!(event_matches(&actor_started, &filters));
+        assert!(event_matches(&host_started, &filters));
+        assert!(!event_matches(&heartbeat, &filters));
+        assert!(event_matches(&heartbeat, &[]));
+
+        assert_eq!(
+            event_output(&actor_started, &Output::default().kind),
+            format!(
+                "2021-04-01T12:00:00Z actor_started from {}: {{\"public_key\":\"{}\"}}",
+                HOST_ID, ACTOR_ID
+            )
+        );
+        assert_eq!(
+            event_output(&heartbeat, &OutputKind::Json),
+            "{\"type\":\"com.wasmcloud.lattice.host_heartbeat\"}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_match_host_id() {
+        const OTHER_HOST: &str = "NCE7ZZ5ZJVJZ3OZPUAFB4YIXMBSAOS6EFZDGA3GUNIOGJO2MQFOWVTCW";
+        let hosts = || vec![HOST_ID.to_string(), OTHER_HOST.to_string()];
+        assert!(is_host_id(HOST_ID));
+        assert!(!is_host_id("NCE7Y"));
+        assert!(!is_host_id(&HOST_ID.to_lowercase()));
+
+        assert_eq!(match_host_id("NCE7Y", hosts()).unwrap(), HOST_ID);
+        assert_eq!(match_host_id("nce7z", hosts()).unwrap(), OTHER_HOST);
+        let ambiguous = match_host_id("NCE7", hosts()).unwrap_err();
+        assert_eq!(
+            ambiguous.to_string(),
+            format!(
+                "Host ID prefix NCE7 is ambiguous, it matches:\n{}\n{}",
+                HOST_ID, OTHER_HOST
+            )
+        );
+        assert!(matches!(
+            ambiguous.downcast_ref::<WashError>(),
+            Some(WashError::InvalidInput(_))
+        ));
+        let missing = match_host_id("NX", hosts()).unwrap_err();
+        assert_eq!(
+            missing.to_string(),
+            "No host in the lattice has an ID starting with NX"
+        );
+        assert!(matches!(
+            missing.downcast_ref::<WashError>(),
+            Some(WashError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_no_suitable_hosts() {
+        assert_eq!(
+            no_suitable_hosts("actor wasmcloud.azurecr.io/echo:0.2.0", &[], 1),
+            "No suitable hosts found for actor wasmcloud.azurecr.io/echo:0.2.0 within 1s"
+        );
+        assert_eq!(
+            no_suitable_hosts(
+                "provider wasmcloud.azurecr.io/httpserver:0.11.1",
+                &["gpu=true".to_string(), "region=us-east".to_string()],
+                5
+            ),
+            "No suitable hosts found for provider wasmcloud.azurecr.io/httpserver:0.11.1 matching constraints gpu=true, region=us-east within 5s"
+        );
+    }
+
+    #[test]
+    fn test_lattice_summary() {
+        let actor = ActorDescription {
+            id: ACTOR_ID.to_string(),
+            image_ref: None,
+            name: None,
+            revision: 0,
+        };
+        let provider = ProviderDescription {
+            id: PROVIDER_ID.to_string(),
+            link_name: "default".to_string(),
+            image_ref: None,
+            name: None,
+            revision: 0,
+        };
+        let inventory = HostInventory {
+            host_id: HOST_ID.to_string(),
+            labels: std::collections::HashMap::new(),
+            actors: vec![actor.clone(), actor],
+            providers: vec![provider],
+        };
+        let summary = LatticeSummary::new(3, &[inventory.clone(), inventory]);
+        assert_eq!(
+            summary,
+            LatticeSummary {
+                hosts: 3,
+                contributing_hosts: 2,
+                distinct_actors: 1,
+                actor_instances: 4,
+                distinct_providers: 1,
+                provider_instances: 2,
+            }
+        );
+        assert!(summary.is_partial());
+        assert!(!LatticeSummary::new(0, &[]).is_partial());
+    }
+
+    #[test]
+    fn test_stop_host_comprehensive() -> Result<()> {
+        let stop_host_all = CtlCli::from_iter_safe(&[
+            "ctl",
+            "stop",
+            "host",
+            "-o",
+            "json",
+            "--rpc-host",
+            RPC_HOST,
+            "--timeout",
+            "30",
+            "--drain",
+            HOST_ID,
+        ])?;
+        match stop_host_all.command {
+            CtlCliCommand::Stop(StopCommand::Host(StopHostCommand {
+                opts,
+                output,
+                host_id,
+                timeout,
+                drain,
+            })) => {
+                assert_eq!(opts.rpc_host, RPC_HOST);
+                assert_eq!(output.kind, OutputKind::Json);
+                assert_eq!(host_id, HOST_ID);
+                assert_eq!(timeout, Some(30));
+                assert!(drain);
+            }
+            cmd => panic!("ctl stop host constructed incorrect command {:?}", cmd),
+        }
+        let stop_host = CtlCli::from_iter_safe(&["ctl", "stop", "host", HOST_ID])?;
+        match stop_host.command {
+            CtlCliCommand::Stop(StopCommand::Host(StopHostCommand { timeout, drain, .. })) => {
+                assert_eq!(timeout, None);
+                assert!(!drain);
+            }
+            cmd => panic!("ctl stop host constructed incorrect command {:?}", cmd),
+        }
+        assert!(CtlCli::from_iter_safe(&["ctl", "stop", "host"]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stop_host_request() {
+        assert_eq!(
+            stop_host_subject(NS_PREFIX, HOST_ID),
+            format!("wasmbus.ctl.{}.cmd.{}.stop", NS_PREFIX, HOST_ID)
+        );
+        let payload: serde_json::Value =
+            serde_json::from_slice(&stop_host_payload(HOST_ID, Some(30))).unwrap();
+        assert_eq!(payload["host_id"], HOST_ID);
+        assert_eq!(payload["timeout"], 30_000);
+        let payload: serde_json::Value =
+            serde_json::from_slice(&stop_host_payload(HOST_ID, None)).unwrap();
+        assert!(payload["timeout"].is_null());
+
+        let ack: CtlOperationAck = serde_json::from_str(r#"{"accepted": true}"#).unwrap();
+        assert!(ack.accepted);
+        assert!(ack.error.is_empty());
+    }
+
+    #[test]
+    fn test_provider_contract_id() {
+        let claim = |sub: &str, capid: &str| Claims {
+            values: vec![("sub", sub), ("capid", capid)]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        };
+        let claims = ClaimsList {
+            claims: vec![
+                claim(ACTOR_ID, ""),
+                claim(PROVIDER_ID, "wasmcloud:httpserver"),
+            ],
+        };
+        assert_eq!(
+            provider_contract_id(&claims, PROVIDER_ID).unwrap(),
+            "wasmcloud:httpserver"
+        );
+        assert_eq!(provider_contract_id(&claims, ACTOR_ID), None);
+        assert_eq!(provider_contract_id(&claims, "VUNKNOWN"), None);
+    }
+
+    #[test]
+    fn test_stop_host_output() {
+        let mut result = StopHostResult {
+            host_id: HOST_ID.to_string(),
+            actors_stopped: 2,
+            providers_stopped: 1,
+            ..Default::default()
+        };
+        let text = stop_host_output(result.clone(), &OutputKind::Text { max_width: 0 });
+        assert!(text.contains(&format!("Stopping host: {}", HOST_ID)));
+        assert!(text.contains("2 actor(s) and 1 provider(s)"));
+
+        result.error = Some("host is busy".to_string());
+        let json: serde_json::Value =
+            serde_json::from_str(&stop_host_output(result, &OutputKind::Json)).unwrap();
+        assert_eq!(json["error"], "host is busy");
+        assert_eq!(json["stop"]["actors_stopped"], 2);
+    }
+
+    #[test]
+    fn test_scale_comprehensive() -> Result<()> {
+        let scale_all = CtlCli::from_iter_safe(&[
+            "ctl",
+            "scale",
+            "-o",
+            "json",
+            "--rpc-host",
+            RPC_HOST,
+            "--host-id",
+            HOST_ID,
+            "--timeout",
+            "3",
+            ACTOR_ID,
+            "5",
+        ])?;
+        match scale_all.command {
+            CtlCliCommand::Scale(ScaleCommand {
+                opts,
+                output,
+                host_id,
+                actor,
+                count,
+                timeout,
+            }) => {
+                assert_eq!(opts.rpc_host, RPC_HOST);
+                assert_eq!(output.kind, OutputKind::Json);
+                assert_eq!(host_id.unwrap(), HOST_ID);
+                assert_eq!(actor, ACTOR_ID);
+                assert_eq!(count, 5);
+                assert_eq!(timeout, 3);
+            }
+            cmd => panic!("ctl scale constructed incorrect command {:?}", cmd),
+        }
+        assert!(CtlCli::from_iter_safe(&["ctl", "scale", ACTOR_ID, "-1"]).is_err());
+        assert!(CtlCli::from_iter_safe(&["ctl", "scale", ACTOR_ID]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_auction_comprehensive() -> Result<()> {
+        let auction_actor = CtlCli::from_iter_safe(&[
+            "ctl",
+            "auction",
+            "actor",
+            "-o",
+            "json",
+            "--rpc-host",
+            RPC_HOST,
+            "--timeout",
+            "3",
+            "wasmcloud.azurecr.io/echo:0.2.0",
+            "-c",
+            "arch=x86_64",
+            "os=linux",
+        ])?;
+        match auction_actor.command {
+            CtlCliCommand::Auction(AuctionCommand::Actor(AuctionActorCommand {
+                opts,
+                output,
+                actor_ref,
+                constraints,
+                timeout,
+            })) => {
+                assert_eq!(opts.rpc_host, RPC_HOST);
+                assert_eq!(output.kind, OutputKind::Json);
+                assert_eq!(actor_ref, "wasmcloud.azurecr.io/echo:0.2.0");
+                assert_eq!(
+                    constraints.unwrap(),
+                    vec!["arch=x86_64".to_string(), "os=linux".to_string()]
+                );
+                assert_eq!(timeout, 3);
+            }
+            cmd => panic!("ctl auction actor constructed incorrect command {:?}", cmd),
+        }
+        let auction_provider = CtlCli::from_iter_safe(&[
+            "ctl",
+            "auction",
+            "provider",
+            "wasmcloud.azurecr.io/httpserver:0.11.1",
+            "default",
+        ])?;
+        match auction_provider.command {
+            CtlCliCommand::Auction(AuctionCommand::Provider(AuctionProviderCommand {
+                provider_ref,
+                link_name,
+                constraints,
+                timeout,
+                ..
+            })) => {
+                assert_eq!(provider_ref, "wasmcloud.azurecr.io/httpserver:0.11.1");
+                assert_eq!(link_name, "default");
+                assert!(constraints.is_none());
+                assert_eq!(timeout, 1);
+            }
+            cmd => panic!(
+                "ctl auction provider constructed incorrect command {:?}",
+                cmd
+            ),
+        }
+        assert!(CtlCli::from_iter_safe(&[
+            "ctl",
+            "auction",
+            "provider",
+            "wasmcloud.azurecr.io/httpserver:0.11.1"
+        ])
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_auction_output() {
+        assert_eq!(
+            auction_output("actor echo", vec![], &OutputKind::Text { max_width: 0 }),
+            "\nNo hosts responded as able to run actor echo"
+        );
+
+        let mut labels = BTreeMap::new();
+        labels.insert("os".to_string(), "linux".to_string());
+        let hosts = vec![AuctionHost {
+            host_id: HOST_ID.to_string(),
+            labels,
+        }];
+        let json: serde_json::Value =
+            serde_json::from_str(&auction_output("actor echo", hosts, &OutputKind::Json)).unwrap();
+        assert_eq!(json["hosts"][0]["host_id"], HOST_ID);
+        assert_eq!(json["hosts"][0]["labels"]["os"], "linux");
+    }
 
+    #[test]
+    fn test_plan_scale() {
+        const ECHO_REF: &str = "wasmcloud.azurecr.io/echo:0.2.0";
+        let actor = ActorDescription {
+            id: ACTOR_ID.to_string(),
+            image_ref: Some(ECHO_REF.to_string()),
+            name: None,
+            revision: 0,
+        };
+        let inventory = |host_id: &str, instances: usize| HostInventory {
+            host_id: host_id.to_string(),
+            labels: std::collections::HashMap::new(),
+            actors: vec![actor.clone(); instances],
+            providers: vec![],
+        };
+        let start = |host_id: &str| ScaleAction::Start {
+            host_id: host_id.to_string(),
+            actor_ref: ECHO_REF.to_string(),
+        };
+        let stop = |host_id: &str| ScaleAction::Stop {
+            host_id: host_id.to_string(),
+            actor_id: ACTOR_ID.to_string(),
+        };
+
+        let lattice = [inventory("NA", 2), inventory("NB", 0), inventory("NC", 1)];
+        assert_eq!(
+            plan_scale(ACTOR_ID, 6, &lattice).unwrap(),
+            vec![start("NB"), start("NB"), start("NC")]
+        );
+        assert_eq!(
+            plan_scale(ECHO_REF, 1, &lattice).unwrap(),
+            vec![stop("NA"), stop("NA")]
+        );
+        assert!(plan_scale(ACTOR_ID, 3, &lattice).unwrap().is_empty());
+
+        // The first instance can only be started from an OCI reference
+        let empty = [inventory("NA", 0), inventory("NB", 0)];
+        assert_eq!(
+            plan_scale(ECHO_REF, 2, &empty).unwrap(),
+            vec![start("NA"), start("NB")]
+        );
+        assert!(plan_scale(ACTOR_ID, 1, &empty)
+            .unwrap_err()
+            .to_string()
+            .starts_with(&format!("Unable to start instances of {}", ACTOR_ID)));
+        assert!(plan_scale(ECHO_REF, 1, &[]).is_err());
+    }
+
+    #[test]
+    fn test_get_hosts_output() {
+        let mut host: HostSummary = Host {
+            id: HOST_ID.to_string(),
+            uptime_seconds: 42,
+        }
+        .into();
+        assert!(host.labels.is_empty());
+        host.labels
+            .insert("hostcore.os".to_string(), "linux".to_string());
+
+        let json: serde_json::Value =
+            serde_json::from_str(&get_hosts_output(vec![host], &OutputKind::Json)).unwrap();
+        assert_eq!(json["hosts"][0]["id"], HOST_ID);
+        assert_eq!(json["hosts"][0]["uptime_seconds"], 42);
+        assert_eq!(json["hosts"][0]["labels"]["hostcore.os"], "linux");
+    }
+
+    #[test]
+    fn test_inventories_output() {
+        let inventory = |host_id: &str| HostInventory {
+            host_id: host_id.to_string(),
+            labels: std::collections::HashMap::new(),
+            actors: vec![],
+            providers: vec![],
+        };
+        let inventories = vec![inventory(HOST_ID), inventory("NHOST2")];
+
+        assert_eq!(
+            get_inventories_output(vec![], &OutputKind::Text { max_width: 0 }),
+            "No hosts reported their inventory"
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&get_inventories_output(inventories, &OutputKind::Json)).unwrap();
+        assert_eq!(json["inventories"].as_array().unwrap().len(), 2);
+        assert_eq!(json["inventories"][1]["host_id"], "NHOST2");
+    }
+
+    #[test]
+    fn test_apply_comprehensive() -> Result<()> {
+        let apply_all = CtlCli::from_iter_safe(&[
+            "ctl",
+            "apply",
+            "-o",
+            "json",
+            "--rpc-host",
+            RPC_HOST,
+            "--insecure",
+            "--timeout",
+            "3",
+            "./lattice.yaml",
+        ])?;
+        match apply_all.command {
+            CtlCliCommand::Apply(ApplyCommand {
+                opts,
+                output,
+                manifest,
+                insecure,
+                timeout,
+            }) => {
+                assert_eq!(opts.rpc_host, RPC_HOST);
+                assert_eq!(output.kind, OutputKind::Json);
+                assert_eq!(manifest, PathBuf::from("./lattice.yaml"));
+                assert!(insecure);
+                assert_eq!(timeout, 3);
+            }
+            cmd => panic!("ctl apply constructed incorrect command {:?}", cmd),
+        }
         Ok(())
     }
+
+    #[test]
+    fn test_load_lattice_manifest() {
+        let dir = std::env::temp_dir().join(format!("wash-ctl-apply-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("lattice.yaml");
+        std::fs::write(
+            &path,
+            format!(
+                r#"
+actors:
+  - image: wasmcloud.azurecr.io/echo:0.2.0
+    instances: 2
+providers:
+  - image: wasmcloud.azurecr.io/httpserver:0.12.0
+    constraints:
+      region: us-east
+links:
+  - actor: wasmcloud.azurecr.io/echo:0.2.0
+    provider: {}
+    contract_id: wasmcloud:httpserver
+    values:
+      PORT: "8080"
+"#,
+                PROVIDER_ID
+            ),
+        )
+        .unwrap();
+
+        let manifest = load_lattice_manifest(&path).unwrap();
+        assert_eq!(
+            manifest.actors,
+            vec![ManifestActor {
+                image: "wasmcloud.azurecr.io/echo:0.2.0".to_string(),
+                instances: 2,
+                host_id: None,
+            }]
+        );
+        assert_eq!(manifest.providers[0].link_name, "default");
+        assert_eq!(manifest.providers[0].constraints["region"], "us-east");
+        assert_eq!(manifest.links[0].provider, PROVIDER_ID);
+        assert_eq!(manifest.links[0].link_name, "default");
+        assert_eq!(manifest.links[0].values["PORT"], "8080");
+
+        std::fs::write(&path, "{}").unwrap();
+        assert_eq!(
+            load_lattice_manifest(&path).unwrap(),
+            LatticeManifest::default()
+        );
+
+        std::fs::write(&path, "actors:\n  - image: echo\n    count: 2\n").unwrap();
+        let err = load_lattice_manifest(&path).unwrap_err();
+        assert!(err.to_string().contains("unknown field `count`"));
+        assert_eq!(crate::util::exit_code(&*err), 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        let err = load_lattice_manifest(&path).unwrap_err();
+        assert!(err.to_string().starts_with("Unable to read manifest"));
+        assert_eq!(crate::util::exit_code(&*err), 2);
+    }
+
+    #[test]
+    fn test_running_provider() {
+        const HTTPSERVER_REF: &str = "wasmcloud.azurecr.io/httpserver:0.12.0";
+        let provider = |link_name: &str| ManifestProvider {
+            image: HTTPSERVER_REF.to_string(),
+            link_name: link_name.to_string(),
+            host_id: None,
+            constraints: BTreeMap::new(),
+        };
+        let inventory = |host_id: &str, image_ref: Option<&str>| HostInventory {
+            host_id: host_id.to_string(),
+            labels: std::collections::HashMap::new(),
+            actors: vec![],
+            providers: vec![ProviderDescription {
+                id: PROVIDER_ID.to_string(),
+                link_name: "default".to_string(),
+                image_ref: image_ref.map(String::from),
+                name: None,
+                revision: 0,
+            }],
+        };
+        let lattice = [inventory("NA", None), inventory("NB", Some(HTTPSERVER_REF))];
+
+        assert_eq!(
+            running_provider(&lattice, &provider("default")).unwrap(),
+            "NB"
+        );
+        assert!(running_provider(&lattice, &provider("backup")).is_none());
+        assert!(running_provider(&lattice[..1], &provider("default")).is_none());
+        assert!(running_provider(&[], &provider("default")).is_none());
+
+        assert!(is_provider_id(PROVIDER_ID));
+        assert!(!is_provider_id(ACTOR_ID));
+        assert!(!is_provider_id(HTTPSERVER_REF));
+    }
+
+    #[test]
+    fn test_apply_output() {
+        let steps = vec![
+            ApplyStep::new(
+                "provider",
+                "httpserver (default)".to_string(),
+                Ok(format!("started on {}", HOST_ID)),
+            ),
+            ApplyStep::new(
+                "actor",
+                "echo".to_string(),
+                Err("No hosts reported their inventory".into()),
+            ),
+        ];
+        assert_eq!(
+            apply_output(
+                "lattice.yaml",
+                &steps,
+                &OutputKind::Text { max_width: 0 }
+            ),
+            format!(
+                "\nprovider httpserver (default): started on {}\nactor echo: failed: No hosts reported their inventory\n\nApplied manifest lattice.yaml: 1 succeeded, 1 failed",
+                HOST_ID
+            )
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&apply_output("lattice.yaml", &steps, &OutputKind::Json)).unwrap();
+        assert_eq!(json["failed"], 1);
+        assert_eq!(json["steps"][0]["kind"], "provider");
+        assert_eq!(json["steps"][0]["success"], true);
+        assert_eq!(json["steps"][1]["success"], false);
+        assert_eq!(
+            json["steps"][1]["outcome"],
+            "No hosts reported their inventory"
+        );
+
+        let expected = "1 of 2 items of manifest lattice.yaml failed to apply";
+        let err =
+            apply_error("lattice.yaml", &steps, &OutputKind::Text { max_width: 0 }).unwrap_err();
+        assert_eq!(err.to_string(), expected);
+        let json: serde_json::Value = serde_json::from_str(
+            &apply_error("lattice.yaml", &steps, &OutputKind::Json)
+                .unwrap_err()
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(json["result"], "error");
+        assert_eq!(json["error"], expected);
+        assert_eq!(json["failed"], 1);
+        assert_eq!(json["steps"][1]["success"], false);
+        assert!(apply_error("lattice.yaml", &steps[..1], &OutputKind::Json).is_ok());
+    }
 }
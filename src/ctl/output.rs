@@ -1,5 +1,12 @@
 extern crate wasmcloud_control_interface;
-use crate::util::{format_ellipsis, format_optional, format_output, OutputKind, WASH_CMD_INFO};
+use super::{
+    ApplyStep, AuctionHost, CallResponseFormat, HostSummary, LatticeSummary, ScaleResult,
+    StopHostResult,
+};
+use crate::util::{
+    format_ellipsis, format_error_with, format_optional, format_output, OutputKind, Result,
+    WASH_CMD_INFO,
+};
 use log::debug;
 use serde_json::json;
 use term_table::{row::Row, table_cell::*, Table};
@@ -7,25 +14,66 @@ use wasmcloud_control_interface::*;
 
 // Helper output functions, used to ensure consistent output between ctl & standalone commands
 
-pub(crate) fn call_output(error: Option<String>, msg: Vec<u8>, output_kind: &OutputKind) -> String {
-    match error {
-        Some(e) => format_output(
+pub(crate) fn call_output(
+    error: Option<String>,
+    msg: Vec<u8>,
+    format: CallResponseFormat,
+    output_kind: &OutputKind,
+) -> String {
+    let response = error
+        .map(Err)
+        .unwrap_or_else(|| decode_call_response(&msg, format));
+    match response {
+        Err(e) => format_output(
             format!("\nError invoking actor: {}", e),
             json!({ "error": e }),
             &output_kind,
         ),
-        None => {
-            //TODO(issue #32): String::from_utf8_lossy should be decoder only if one is not available
-            let call_response = String::from_utf8_lossy(&msg);
+        Ok(call_response) => {
+            let label = match format {
+                CallResponseFormat::Raw => "raw",
+                CallResponseFormat::Base64 => "base64",
+                CallResponseFormat::Json => "json",
+            };
+            let text = match &call_response {
+                serde_json::Value::String(s) => s.clone(),
+                value => value.to_string(),
+            };
             format_output(
-                format!("\nCall response (raw): {}", call_response),
+                format!("\nCall response ({}): {}", label, text),
                 json!({ "response": call_response }),
                 &output_kind,
             )
         }
     }
 }
-pub(crate) fn get_hosts_output(hosts: Vec<Host>, output_kind: &OutputKind) -> String {
+
+/// Decodes an actor's response into the requested format
+fn decode_call_response(
+    msg: &[u8],
+    format: CallResponseFormat,
+) -> std::result::Result<serde_json::Value, String> {
+    match format {
+        CallResponseFormat::Raw => Ok(String::from_utf8_lossy(msg).into()),
+        CallResponseFormat::Base64 => Ok(base64::encode(msg).into()),
+        CallResponseFormat::Json => serdeconv::from_msgpack_slice(msg)
+            .map_err(|e| format!("Unable to decode response as msgpack: {}", e)),
+    }
+}
+/// Formats a single lattice event. JSON output is the event itself, one per line
+pub(crate) fn event_output(event: &serde_json::Value, output_kind: &OutputKind) -> String {
+    let event_type = event["type"].as_str().unwrap_or("unknown");
+    let text = format!(
+        "{} {} from {}: {}",
+        event["time"].as_str().unwrap_or("-"),
+        event_type.rsplit('.').next().unwrap_or(event_type),
+        event["source"].as_str().unwrap_or("unknown"),
+        event["data"]
+    );
+    format_output(text, event.clone(), output_kind)
+}
+
+pub(crate) fn get_hosts_output(hosts: Vec<HostSummary>, output_kind: &OutputKind) -> String {
     debug!(target: WASH_CMD_INFO, "Hosts:{:?}", hosts);
     match *output_kind {
         OutputKind::Text { max_width } => hosts_table(hosts, max_width),
@@ -39,6 +87,24 @@ pub(crate) fn get_host_inventory_output(inv: HostInventory, output_kind: &Output
         OutputKind::Json => format!("{}", json!({ "inventory": inv })),
     }
 }
+/// Renders the inventory of each host in its own table
+pub(crate) fn get_inventories_output(
+    inventories: Vec<HostInventory>,
+    output_kind: &OutputKind,
+) -> String {
+    debug!(target: WASH_CMD_INFO, "Inventories:{:?}", inventories);
+    match *output_kind {
+        OutputKind::Text { .. } if inventories.is_empty() => {
+            "No hosts reported their inventory".to_string()
+        }
+        OutputKind::Text { max_width } => inventories
+            .into_iter()
+            .map(|inv| host_inventory_table(inv, max_width))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputKind::Json => format!("{}", json!({ "inventories": inventories })),
+    }
+}
 pub(crate) fn get_claims_output(claims: ClaimsList, output_kind: &OutputKind) -> String {
     debug!(target: WASH_CMD_INFO, "Claims:{:?}", claims);
     match *output_kind {
@@ -136,6 +202,119 @@ pub(crate) fn stop_actor_output(
         ),
     }
 }
+pub(crate) fn auction_output(
+    target: &str,
+    hosts: Vec<AuctionHost>,
+    output_kind: &OutputKind,
+) -> String {
+    debug!(target: WASH_CMD_INFO, "Auction hosts:{:?}", hosts);
+    match *output_kind {
+        OutputKind::Text { .. } if hosts.is_empty() => {
+            format!("\nNo hosts responded as able to run {}", target)
+        }
+        OutputKind::Text { max_width } => format!(
+            "\n{} host(s) can run {}\n{}",
+            hosts.len(),
+            target,
+            auction_table(hosts, max_width)
+        ),
+        OutputKind::Json => format!("{}", json!({ "hosts": hosts })),
+    }
+}
+
+/// Helper function to print the hosts that responded to an auction as a table
+pub(crate) fn auction_table(hosts: Vec<AuctionHost>, max_width: usize) -> String {
+    let mut table = Table::new();
+    crate::util::configure_table_style(&mut table, 2, max_width);
+
+    table.add_row(Row::new(vec![
+        TableCell::new_with_alignment("Host ID", 1, Alignment::Left),
+        TableCell::new_with_alignment("Labels", 1, Alignment::Left),
+    ]));
+    hosts.iter().for_each(|h| {
+        let labels = h
+            .labels
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join(", ");
+        table.add_row(Row::new(vec![
+            TableCell::new_with_alignment(h.host_id.clone(), 1, Alignment::Left),
+            TableCell::new_with_alignment(labels, 1, Alignment::Left),
+        ]))
+    });
+
+    table.render()
+}
+
+pub(crate) fn scale_output(result: ScaleResult, output_kind: &OutputKind) -> String {
+    let running = result.previous + result.started - result.stopped;
+    let mut text = format!(
+        "\nScaled actor {} from {} to {} instances (started {}, stopped {})",
+        result.actor, result.previous, running, result.started, result.stopped
+    );
+    for failure in result.failures.iter() {
+        text.push_str(&format!("\n{}", failure));
+    }
+    format_output(
+        text,
+        json!({ "scale": result, "running": running }),
+        output_kind,
+    )
+}
+pub(crate) fn apply_output(
+    manifest: &str,
+    steps: &[ApplyStep],
+    output_kind: &OutputKind,
+) -> String {
+    let failed = steps.iter().filter(|s| !s.success).count();
+    let mut text = String::new();
+    for step in steps.iter() {
+        if step.success {
+            text.push_str(&format!("\n{} {}: {}", step.kind, step.item, step.outcome));
+        } else {
+            text.push_str(&format!(
+                "\n{} {}: failed: {}",
+                step.kind, step.item, step.outcome
+            ));
+        }
+    }
+    text.push_str(&format!(
+        "\n\nApplied manifest {}: {} succeeded, {} failed",
+        manifest,
+        steps.len() - failed,
+        failed
+    ));
+    format_output(
+        text,
+        json!({ "manifest": manifest, "steps": steps, "failed": failed }),
+        output_kind,
+    )
+}
+
+/// Fails `ctl apply` when any item failed to apply. JSON output gets a single error
+/// document carrying every step, as the report isn't printed separately
+pub(crate) fn apply_error(
+    manifest: &str,
+    steps: &[ApplyStep],
+    output_kind: &OutputKind,
+) -> Result<()> {
+    let failed = steps.iter().filter(|s| !s.success).count();
+    if failed == 0 {
+        return Ok(());
+    }
+    Err(format_error_with(
+        format!(
+            "{} of {} items of manifest {} failed to apply",
+            failed,
+            steps.len(),
+            manifest
+        )
+        .into(),
+        output_kind,
+        json!({ "manifest": manifest, "steps": steps, "failed": failed }),
+    ))
+}
 pub(crate) fn stop_provider_output(
     provider_ref: &str,
     failure: Option<String>,
@@ -154,6 +333,30 @@ pub(crate) fn stop_provider_output(
         ),
     }
 }
+pub(crate) fn stop_host_output(result: StopHostResult, output_kind: &OutputKind) -> String {
+    if let Some(e) = &result.error {
+        return format_output(
+            format!("\nError stopping host: {}", e),
+            json!({ "error": e, "stop": result }),
+            output_kind,
+        );
+    }
+    let mut text = format!("\nStopping host: {}", result.host_id);
+    if result.actors_stopped + result.providers_stopped > 0 || !result.failures.is_empty() {
+        text.push_str(&format!(
+            "\nDrained {} actor(s) and {} provider(s)",
+            result.actors_stopped, result.providers_stopped
+        ));
+    }
+    for failure in result.failures.iter() {
+        text.push_str(&format!("\n{}", failure));
+    }
+    format_output(
+        text,
+        json!({ "host_id": result.host_id, "stop": result }),
+        output_kind,
+    )
+}
 pub(crate) fn update_actor_output(
     actor_id: &str,
     new_actor_ref: &str,
@@ -175,19 +378,79 @@ pub(crate) fn update_actor_output(
     }
 }
 
-/// Helper function to print a Host list to stdout as a table
-pub(crate) fn hosts_table(hosts: Vec<Host>, max_width: usize) -> String {
+pub(crate) fn summary_output(summary: LatticeSummary, output_kind: &OutputKind) -> String {
+    debug!(target: WASH_CMD_INFO, "Summary:{:?}", summary);
+    match *output_kind {
+        OutputKind::Text { max_width } => summary_table(summary, max_width),
+        OutputKind::Json => format!(
+            "{}",
+            json!({ "summary": summary, "partial": summary.is_partial() })
+        ),
+    }
+}
+
+/// Helper function to print a LatticeSummary to stdout as a table
+pub(crate) fn summary_table(summary: LatticeSummary, max_width: usize) -> String {
     let mut table = Table::new();
     crate::util::configure_table_style(&mut table, 2, max_width);
 
+    table.add_row(Row::new(vec![TableCell::new_with_alignment(
+        "Lattice Summary",
+        2,
+        Alignment::Center,
+    )]));
+    let hosts = if summary.is_partial() {
+        format!(
+            "{} ({} reported inventory, totals are partial)",
+            summary.hosts, summary.contributing_hosts
+        )
+    } else {
+        format!("{}", summary.hosts)
+    };
+    let rows = vec![
+        ("Hosts", hosts),
+        ("Distinct Actors", format!("{}", summary.distinct_actors)),
+        ("Actor Instances", format!("{}", summary.actor_instances)),
+        (
+            "Distinct Providers",
+            format!("{}", summary.distinct_providers),
+        ),
+        (
+            "Provider Instances",
+            format!("{}", summary.provider_instances),
+        ),
+    ];
+    rows.into_iter().for_each(|(label, value)| {
+        table.add_row(Row::new(vec![
+            TableCell::new_with_alignment(label, 1, Alignment::Left),
+            TableCell::new_with_alignment(value, 1, Alignment::Right),
+        ]))
+    });
+
+    table.render()
+}
+
+/// Helper function to print a Host list to stdout as a table
+pub(crate) fn hosts_table(hosts: Vec<HostSummary>, max_width: usize) -> String {
+    let mut table = Table::new();
+    crate::util::configure_table_style(&mut table, 3, max_width);
+
     table.add_row(Row::new(vec![
         TableCell::new_with_alignment("Host ID", 1, Alignment::Left),
         TableCell::new_with_alignment("Uptime (seconds)", 1, Alignment::Left),
+        TableCell::new_with_alignment("Labels", 1, Alignment::Left),
     ]));
     hosts.iter().for_each(|h| {
+        let labels = h
+            .labels
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join(", ");
         table.add_row(Row::new(vec![
             TableCell::new_with_alignment(h.id.clone(), 1, Alignment::Left),
             TableCell::new_with_alignment(format!("{}", h.uptime_seconds), 1, Alignment::Left),
+            TableCell::new_with_alignment(labels, 1, Alignment::Left),
         ]))
     });
 
@@ -1,8 +1,10 @@
 use crate::util::format_output;
-use crate::util::{Output, OutputKind};
+use crate::util::{parse_duration, Output, OutputKind};
+use indicatif::HumanBytes;
 use serde_json::json;
 use std::env;
 use std::path::Path;
+use std::time::{Duration, SystemTime};
 use std::{fs, path::PathBuf};
 use structopt::StructOpt;
 
@@ -24,25 +26,49 @@ pub(crate) struct DrainCliCommand {
     selection: DrainSelection,
 }
 
-// Propagates output selection from CLI to all commands
+// Propagates options from CLI to all commands
 impl DrainCliCommand {
-    fn output_kind(&self) -> OutputKind {
-        match self.selection {
-            DrainSelection::All(output)
-            | DrainSelection::Lib(output)
-            | DrainSelection::Oci(output) => output.kind,
+    fn opts(&self) -> &DrainOpts {
+        match &self.selection {
+            DrainSelection::All(opts) | DrainSelection::Lib(opts) | DrainSelection::Oci(opts) => {
+                opts
+            }
         }
     }
+
+    fn output_kind(&self) -> OutputKind {
+        self.opts().output.kind
+    }
 }
 
 #[derive(StructOpt, Debug, Clone)]
 pub(crate) enum DrainSelection {
     /// Remove all cached files created by wasmcloud
-    All(Output),
+    All(DrainOpts),
     /// Remove cached files downloaded from OCI registries by wasmcloud
-    Oci(Output),
+    Oci(DrainOpts),
     /// Remove cached binaries extracted from provider archives
-    Lib(Output),
+    Lib(DrainOpts),
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub(crate) struct DrainOpts {
+    /// List the cached entries that would be removed without deleting anything
+    #[structopt(long = "dry-run")]
+    dry_run: bool,
+
+    /// Only remove entries that haven't been modified for this long, e.g. 48h or 7d
+    #[structopt(long = "older-than", parse(try_from_str = parse_duration))]
+    older_than: Option<Duration>,
+
+    /// Only remove entries whose name contains this registry host or repository, e.g.
+    /// localhost:5000 or wasmcloud.azurecr.io/echo. Cache names replace ':', '/' and '.' with '_'
+    /// and the filter is matched the same way
+    #[structopt(long = "filter")]
+    filter: Option<String>,
+
+    #[structopt(flatten)]
+    output: Output,
 }
 
 impl IntoIterator for &DrainSelection {
@@ -62,36 +88,156 @@ impl IntoIterator for &DrainSelection {
     }
 }
 
+/// A file or directory directly inside one of the cache directories
+#[derive(Debug, Clone, PartialEq)]
+struct CacheEntry {
+    path: PathBuf,
+    /// Total size in bytes, including the contents of directories
+    size: u64,
+    /// Number of files, including the contents of directories
+    files: u64,
+    /// Most recent modification time, including the contents of directories
+    modified: SystemTime,
+}
+
 impl DrainCliCommand {
     fn drain(&self) -> Result<String, Box<dyn ::std::error::Error>> {
-        let cleared = self
+        let dirs: Vec<PathBuf> = self
             .selection
             .into_iter()
             .filter(|path| path.exists())
-            .map(remove_dir_contents)
-            .collect::<Result<Vec<String>, Box<dyn ::std::error::Error>>>()?;
+            .collect();
+        let mut entries = vec![];
+        for dir in dirs.iter() {
+            entries.extend(cache_entries(dir)?);
+        }
+        if let Some(filter) = &self.opts().filter {
+            entries.retain(|e| matches_filter(&e.path, filter));
+        }
+        if let Some(age) = self.opts().older_than {
+            let now = SystemTime::now();
+            entries.retain(|e| is_older_than(e, now, age));
+        }
+        if self.opts().dry_run {
+            return Ok(self.dry_run_output(&entries));
+        }
+        for entry in entries.iter() {
+            remove_entry(&entry.path)?;
+        }
+        let cleared: Vec<String> = dirs.iter().map(|d| format!("{}", d.display())).collect();
+        let (bytes, files) = totals(&entries);
+        let opts = self.opts();
+        let summary = if opts.filter.is_some() || opts.older_than.is_some() {
+            format!(
+                "Successfully removed {} selected entries from caches at: {:?}",
+                entries.len(),
+                cleared
+            )
+        } else {
+            format!("Successfully cleared caches at: {:?}", cleared)
+        };
         Ok(format_output(
-            format!("Successfully cleared caches at: {:?}", cleared),
-            json!({ "drained": cleared }),
+            format!(
+                "{}\nFreed {} across {} files",
+                summary,
+                HumanBytes(bytes),
+                files
+            ),
+            json!({ "drained": cleared, "bytes_freed": bytes, "files_removed": files }),
             &self.output_kind(),
         ))
     }
+
+    /// Lists what `drain` would remove without modifying the caches
+    fn dry_run_output(&self, entries: &[CacheEntry]) -> String {
+        let (bytes, files) = totals(entries);
+        let paths: Vec<String> = entries
+            .iter()
+            .map(|e| format!("{}", e.path.display()))
+            .collect();
+        let mut text = format!(
+            "Dry run, nothing was removed. Would free {} across {} files",
+            HumanBytes(bytes),
+            files
+        );
+        for path in paths.iter() {
+            text.push_str(&format!("\n  {}", path));
+        }
+        format_output(
+            text,
+            json!({ "dry_run": true, "entries": paths, "bytes": bytes, "files": files }),
+            &self.output_kind(),
+        )
+    }
 }
 
 pub(crate) fn handle_command(cmd: DrainCliCommand) -> Result<String, Box<dyn ::std::error::Error>> {
     cmd.drain()
 }
 
-fn remove_dir_contents<P: AsRef<Path>>(path: P) -> Result<String, Box<dyn ::std::error::Error>> {
-    for entry in fs::read_dir(&path)? {
-        let path = entry?.path();
-        if path.is_dir() {
-            fs::remove_dir_all(path)?;
-        } else if path.is_file() {
-            fs::remove_file(path)?;
+/// Returns the entries directly inside a cache directory, sorted by path
+fn cache_entries(dir: &Path) -> Result<Vec<CacheEntry>, Box<dyn ::std::error::Error>> {
+    let mut entries = vec![];
+    for entry in fs::read_dir(dir)? {
+        entries.push(entry_usage(&entry?.path())?);
+    }
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(entries)
+}
+
+/// Size, number of files and latest modification time of a file, or of everything inside a directory
+fn entry_usage(path: &Path) -> Result<CacheEntry, Box<dyn ::std::error::Error>> {
+    let metadata = fs::symlink_metadata(path)?;
+    let mut usage = CacheEntry {
+        path: path.to_path_buf(),
+        size: 0,
+        files: 0,
+        modified: metadata.modified()?,
+    };
+    if metadata.is_dir() {
+        for entry in fs::read_dir(path)? {
+            let child = entry_usage(&entry?.path())?;
+            usage.size += child.size;
+            usage.files += child.files;
+            usage.modified = usage.modified.max(child.modified);
         }
+    } else {
+        usage.size = metadata.len();
+        usage.files = 1;
+    }
+    Ok(usage)
+}
+
+/// Returns true if the entry's name contains the filter, normalized the way the OCI cache names
+/// artifacts (e.g. wasmcloud.azurecr.io/echo:0.2.0 is cached as wasmcloud_azurecr_io_echo_0_2_0.bin)
+fn matches_filter(path: &Path, filter: &str) -> bool {
+    let normalize = |s: &str| s.replace(&[':', '/', '.'][..], "_");
+    path.file_name()
+        .map(|name| normalize(&name.to_string_lossy()).contains(&normalize(filter)))
+        .unwrap_or(false)
+}
+
+/// Returns true if the entry was last modified more than `age` before `now`
+fn is_older_than(entry: &CacheEntry, now: SystemTime, age: Duration) -> bool {
+    now.duration_since(entry.modified)
+        .map(|elapsed| elapsed > age)
+        .unwrap_or(false)
+}
+
+/// Total bytes and files across cache entries
+fn totals(entries: &[CacheEntry]) -> (u64, u64) {
+    entries
+        .iter()
+        .fold((0, 0), |(size, files), e| (size + e.size, files + e.files))
+}
+
+fn remove_entry(path: &Path) -> Result<(), Box<dyn ::std::error::Error>> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)?;
+    } else {
+        fs::remove_file(path)?;
     }
-    Ok(format!("{}", path.as_ref().display()))
+    Ok(())
 }
 
 #[cfg(test)]
@@ -103,22 +249,82 @@ mod test {
     fn test_drain_comprehensive() {
         let all = DrainCli::from_iter_safe(&["drain", "all", "-o", "text"]).unwrap();
         match all.command.selection {
-            DrainSelection::All(output) => {
-                assert_eq!(output.kind, OutputKind::Text { max_width: 0 })
+            DrainSelection::All(opts) => {
+                assert_eq!(opts.output.kind, OutputKind::Text { max_width: 0 })
             }
             _ => panic!("drain constructed incorrect command"),
         }
         let lib = DrainCli::from_iter_safe(&["drain", "lib", "-o", "text"]).unwrap();
         match lib.command.selection {
-            DrainSelection::Lib(output) => {
-                assert_eq!(output.kind, OutputKind::Text { max_width: 0 })
+            DrainSelection::Lib(opts) => {
+                assert_eq!(opts.output.kind, OutputKind::Text { max_width: 0 })
             }
             _ => panic!("drain constructed incorrect command"),
         }
         let oci = DrainCli::from_iter_safe(&["drain", "oci", "-o", "json"]).unwrap();
         match oci.command.selection {
-            DrainSelection::Oci(output) => assert_eq!(output.kind, OutputKind::Json),
+            DrainSelection::Oci(opts) => {
+                assert_eq!(opts.output.kind, OutputKind::Json);
+                assert!(!opts.dry_run);
+            }
+            _ => panic!("drain constructed incorrect command"),
+        }
+        let dry_run = DrainCli::from_iter_safe(&[
+            "drain",
+            "all",
+            "--dry-run",
+            "--filter",
+            "localhost:5000",
+            "--older-than",
+            "7d",
+        ])
+        .unwrap();
+        match dry_run.command.selection {
+            DrainSelection::All(opts) => {
+                assert!(opts.dry_run);
+                assert_eq!(opts.filter.unwrap(), "localhost:5000");
+                assert_eq!(opts.older_than.unwrap(), Duration::from_secs(604_800));
+            }
             _ => panic!("drain constructed incorrect command"),
         }
     }
+
+    #[test]
+    fn test_matches_filter() {
+        let cached = Path::new("/tmp/wasmcloud_ocicache/wasmcloud_azurecr_io_echo_0_2_0.bin");
+        assert!(matches_filter(cached, "wasmcloud.azurecr.io"));
+        assert!(matches_filter(cached, "wasmcloud.azurecr.io/echo"));
+        assert!(matches_filter(cached, "echo:0.2.0"));
+        assert!(!matches_filter(cached, "localhost:5000"));
+        assert!(!matches_filter(cached, "wasmcloud.azurecr.io/logging"));
+    }
+
+    #[test]
+    fn test_cache_entries() {
+        let dir = env::temp_dir().join("wash_drain_cache_entries");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("provider/nested")).unwrap();
+        fs::write(dir.join("actor.wasm"), b"0123456789").unwrap();
+        fs::write(dir.join("provider/lib.so"), b"01234").unwrap();
+        fs::write(dir.join("provider/nested/lib.so"), b"012").unwrap();
+
+        let entries = cache_entries(&dir).unwrap();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|e| (e.path.clone(), e.size, e.files))
+                .collect::<Vec<_>>(),
+            vec![
+                (dir.join("actor.wasm"), 10, 1),
+                (dir.join("provider"), 8, 2)
+            ]
+        );
+        assert_eq!(totals(&entries), (18, 3));
+
+        let day = Duration::from_secs(60 * 60 * 24);
+        let now = SystemTime::now();
+        assert!(!is_older_than(&entries[0], now, day));
+        assert!(is_older_than(&entries[0], now + day * 2, day));
+        fs::remove_dir_all(dir).unwrap();
+    }
 }
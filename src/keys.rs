@@ -1,5 +1,13 @@
-use crate::util::{format_output, print_or_log, Output, OutputKind};
+use crate::util::{
+    confirm, format_output, print_or_log, prompt_secret, Output, OutputKind, WashError,
+};
+use chacha20poly1305::aead::{Aead, NewAead};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use log::debug;
 use nkeys::{KeyPair, KeyPairType};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::Serialize;
 use serde_json::json;
 use std::env;
 use std::fs;
@@ -7,7 +15,11 @@ use std::fs::File;
 use std::io::prelude::*;
 use std::io::Error;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use structopt::StructOpt;
+use term_table::row::Row;
+use term_table::table_cell::{Alignment, TableCell};
+use term_table::Table;
 
 #[derive(Debug, Clone, StructOpt)]
 pub(crate) struct KeysCli {
@@ -26,9 +38,35 @@ impl KeysCli {
 pub(crate) enum KeysCliCommand {
     #[structopt(name = "gen", about = "Generates a keypair")]
     GenCommand {
-        /// The type of keypair to generate. May be Account, User, Module (Actor), Service (Capability Provider), Server, Operator, Cluster
+        /// The type of keypair to generate. May be Account, User, Module (Actor), Service (Capability Provider), Server, Operator, Cluster,
+        /// or Project to generate the account and module keys of a new project into the keys directory
         #[structopt(case_insensitive = true)]
-        keytype: KeyPairType,
+        keytype: GenTarget,
+        /// Name of the project, used to name its keys `<name>_account.nk` and `<name>_module.nk` (or `<name>_service.nk`)
+        name: Option<String>,
+        #[structopt(
+            long = "provider",
+            help = "Generate a service key instead of a module key, for a capability provider project"
+        )]
+        provider: bool,
+        #[structopt(
+            short = "d",
+            long = "directory",
+            env = "WASH_KEYS",
+            hide_env_values = true,
+            help = "Absolute path to where project keypairs are stored. Defaults to `$HOME/.wash/keys`"
+        )]
+        directory: Option<String>,
+        #[structopt(
+            long = "force",
+            help = "Overwrite existing project keys with the same names"
+        )]
+        force: bool,
+        #[structopt(
+            long = "encrypt",
+            help = "Encrypt the seed with a passphrase, read from $WASH_KEYS_PASSPHRASE or prompted for"
+        )]
+        encrypt: bool,
         #[structopt(flatten)]
         output: Output,
     },
@@ -47,7 +85,10 @@ pub(crate) enum KeysCliCommand {
         #[structopt(flatten)]
         output: Output,
     },
-    #[structopt(name = "list", about = "Lists all keypairs in a directory")]
+    #[structopt(
+        name = "list",
+        about = "Lists all keypairs in a directory along with their types and public keys"
+    )]
     ListCommand {
         #[structopt(
             short = "d",
@@ -60,19 +101,180 @@ pub(crate) enum KeysCliCommand {
         #[structopt(flatten)]
         output: Output,
     },
+    #[structopt(
+        name = "import",
+        about = "Imports an existing seed into the keys directory"
+    )]
+    ImportCommand {
+        #[structopt(help = "The name to store the key under, e.g. `myuser_account.nk`")]
+        keyname: String,
+        #[structopt(
+            help = "The seed to import, either the seed itself, a path to a file containing it, or - to read it from stdin"
+        )]
+        seed: String,
+        #[structopt(
+            short = "d",
+            long = "directory",
+            env = "WASH_KEYS",
+            hide_env_values = true,
+            help = "Absolute path to where keypairs are stored. Defaults to `$HOME/.wash/keys`"
+        )]
+        directory: Option<String>,
+        #[structopt(long = "force", help = "Overwrite an existing key with the same name")]
+        force: bool,
+        #[structopt(
+            long = "encrypt",
+            help = "Encrypt the stored seed with a passphrase, read from $WASH_KEYS_PASSPHRASE or prompted for"
+        )]
+        encrypt: bool,
+        #[structopt(flatten)]
+        output: Output,
+    },
+    #[structopt(name = "delete", about = "Deletes a keypair from the keys directory")]
+    DeleteCommand {
+        #[structopt(help = "The name of the key to delete, e.g. `myuser_account.nk`")]
+        keyname: String,
+        #[structopt(
+            short = "d",
+            long = "directory",
+            env = "WASH_KEYS",
+            hide_env_values = true,
+            help = "Absolute path to where keypairs are stored. Defaults to `$HOME/.wash/keys`"
+        )]
+        directory: Option<String>,
+        #[structopt(
+            short = "y",
+            long = "yes",
+            help = "Delete the key without asking for confirmation"
+        )]
+        yes: bool,
+        #[structopt(flatten)]
+        output: Output,
+    },
+    #[structopt(
+        name = "convert",
+        about = "Derives the public key and key type from a seed"
+    )]
+    ConvertCommand {
+        #[structopt(
+            help = "The seed to convert, either the seed itself, a path to a file containing it, or - to read it from stdin"
+        )]
+        seed: String,
+        #[structopt(flatten)]
+        output: Output,
+    },
+}
+
+/// What `keys gen` generates, a single keypair of a type or the keys of a project
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum GenTarget {
+    Key(KeyPairType),
+    Project,
+}
+
+impl FromStr for GenTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use KeyPairType::*;
+        match s.to_lowercase().as_str() {
+            "project" => Ok(GenTarget::Project),
+            "account" => Ok(GenTarget::Key(Account)),
+            "user" => Ok(GenTarget::Key(User)),
+            "module" => Ok(GenTarget::Key(Module)),
+            "service" => Ok(GenTarget::Key(Service)),
+            "server" => Ok(GenTarget::Key(Server)),
+            "operator" => Ok(GenTarget::Key(Operator)),
+            "cluster" => Ok(GenTarget::Key(Cluster)),
+            _ => Err(format!("Unknown key type {}", s)),
+        }
+    }
 }
 
 pub(crate) fn handle_command(
     command: KeysCliCommand,
 ) -> Result<String, Box<dyn ::std::error::Error>> {
     match command {
-        KeysCliCommand::GenCommand { keytype, output } => Ok(generate(&keytype, &output.kind)),
+        KeysCliCommand::GenCommand {
+            keytype: GenTarget::Project,
+            name,
+            provider,
+            directory,
+            force,
+            encrypt,
+            output,
+        } => {
+            let name = name.ok_or("A project name is required, e.g. `keys gen project myactor`")?;
+            let passphrase = if encrypt {
+                Some(read_passphrase(true)?)
+            } else {
+                None
+            };
+            generate_project(
+                &name,
+                provider,
+                directory,
+                force,
+                passphrase.as_deref(),
+                &output,
+            )
+        }
+        KeysCliCommand::GenCommand { name: Some(_), .. } => {
+            Err("A name can only be given to `keys gen project`".into())
+        }
+        KeysCliCommand::GenCommand {
+            keytype: GenTarget::Key(keytype),
+            encrypt: false,
+            output,
+            ..
+        } => Ok(generate(&keytype, &output.kind)),
+        KeysCliCommand::GenCommand {
+            keytype: GenTarget::Key(keytype),
+            encrypt: true,
+            output,
+            ..
+        } => generate_encrypted(&keytype, &read_passphrase(true)?, &output.kind),
         KeysCliCommand::GetCommand {
             keyname,
             directory,
             output,
         } => get(&keyname, directory, &output),
         KeysCliCommand::ListCommand { directory, output } => list(directory, &output),
+        KeysCliCommand::ImportCommand {
+            keyname,
+            seed,
+            directory,
+            force,
+            encrypt,
+            output,
+        } => {
+            let passphrase = if encrypt {
+                Some(read_passphrase(true)?)
+            } else {
+                None
+            };
+            import(
+                &keyname,
+                &seed,
+                directory,
+                force,
+                passphrase.as_deref(),
+                &output,
+            )
+        }
+        KeysCliCommand::DeleteCommand {
+            keyname,
+            directory,
+            yes,
+            output,
+        } => delete(
+            &keyname,
+            directory,
+            yes,
+            &output,
+            &mut std::io::stdin().lock(),
+        ),
+        KeysCliCommand::ConvertCommand { seed, output } => convert(&seed, &output),
     }
 }
 
@@ -88,20 +290,128 @@ pub(crate) fn generate(kt: &KeyPairType, output: &OutputKind) -> String {
         json!({
             "public_key": kp.public_key(),
             "seed": kp.seed().unwrap(),
+            "key_type": keypair_type_to_string(kt.clone()),
         }),
         output,
     )
 }
 
+/// Generates a keypair of the specified KeyPairType, outputting its seed encrypted with `passphrase`
+pub(crate) fn generate_encrypted(
+    kt: &KeyPairType,
+    passphrase: &str,
+    output: &OutputKind,
+) -> Result<String, Box<dyn ::std::error::Error>> {
+    let kp = KeyPair::new(kt.clone());
+    let encrypted = encrypt_seed(&kp.seed()?, passphrase, SCRYPT_LOG_N)?;
+    Ok(format_output(
+        format!(
+            "Public Key: {}\nEncrypted Seed: {}\n\nStore the encrypted seed in a .nk file to use it, wash will ask for its passphrase.",
+            kp.public_key(),
+            encrypted
+        ),
+        json!({
+            "public_key": kp.public_key(),
+            "encrypted_seed": encrypted,
+            "key_type": keypair_type_to_string(kt.clone()),
+        }),
+        output,
+    ))
+}
+
+#[derive(Debug, Serialize)]
+struct ProjectKey {
+    key_type: String,
+    public_key: String,
+    path: PathBuf,
+}
+
+/// Generates the keys of a new project into a specified directory, or $WASH_KEYS ($HOME/.wash/keys)
+/// if directory is not specified: an account key and a module key, or a service key for a provider.
+/// Nothing is written if any of the keys already exists, unless `force` is set, in which case the
+/// public keys being replaced are printed so the lost identities can be traced
+pub(crate) fn generate_project(
+    name: &str,
+    provider: bool,
+    directory: Option<String>,
+    force: bool,
+    passphrase: Option<&str>,
+    output: &Output,
+) -> Result<String, Box<dyn ::std::error::Error>> {
+    let dir = resolve_keys_dir(directory)?;
+    let key_types = if provider {
+        vec![KeyPairType::Account, KeyPairType::Service]
+    } else {
+        vec![KeyPairType::Account, KeyPairType::Module]
+    };
+    let paths: Vec<PathBuf> = key_types
+        .iter()
+        .map(|kt| {
+            dir.join(format!(
+                "{}_{}.nk",
+                name,
+                keypair_type_to_string(kt.clone())
+            ))
+        })
+        .collect();
+    for existing in paths.iter().filter(|p| p.exists()) {
+        if !force {
+            return Err(WashError::InvalidInput(format!(
+                "Key {} with {} already exists, use --force to overwrite it",
+                existing.display(),
+                describe_existing_key(existing)
+            ))
+            .into());
+        }
+        eprintln!(
+            "Warning: overwriting key {} with {}",
+            existing.display(),
+            describe_existing_key(existing)
+        );
+    }
+
+    let mut keys = Vec::new();
+    for (kt, path) in key_types.into_iter().zip(paths) {
+        let kp = KeyPair::new(kt.clone());
+        let seed = match passphrase {
+            Some(passphrase) => encrypt_seed(&kp.seed()?, passphrase, SCRYPT_LOG_N)?,
+            None => kp.seed()?,
+        };
+        write_seed(&path, &seed, force)
+            .map_err(|e| format!("Unable to write key {}: {}", path.display(), e))?;
+        keys.push(ProjectKey {
+            key_type: keypair_type_to_string(kt),
+            public_key: kp.public_key(),
+            path,
+        });
+    }
+
+    let summary = keys
+        .iter()
+        .map(|k| format!("{:<8} {} {}", k.key_type, k.public_key, k.path.display()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(format_output(
+        format!("Generated keys for project {}\n{}", name, summary),
+        json!({
+            "project": name,
+            "keys": keys,
+            "encrypted": passphrase.is_some(),
+        }),
+        &output.kind,
+    ))
+}
+
 /// Retrieves a keypair by name in a specified directory, or $WASH_KEYS ($HOME/.wash/keys) if directory is not specified
 pub(crate) fn get(
     keyname: &str,
     directory: Option<String>,
     output: &Output,
 ) -> Result<String, Box<dyn ::std::error::Error>> {
-    let dir = determine_directory(directory)?;
-    let mut f = File::open(format!("{}/{}", dir, keyname))
-        .map_err(|e| format!("{}.\nPlease ensure {}/{} exists.", e, dir, keyname))?;
+    let dir = resolve_keys_dir(directory)?;
+    let path = dir.join(keyname);
+    let mut f = File::open(&path)
+        .map_err(|e| format!("{}.\nPlease ensure {} exists.", e, path.display()))?;
 
     let mut s = String::new();
     let res = match f.read_to_string(&mut s) {
@@ -118,45 +428,444 @@ pub(crate) fn get(
     }
 }
 
+/// A seed file in the keys directory, with the type and public key derived from its seed
+#[derive(Debug, Serialize)]
+struct KeyEntry {
+    name: String,
+    key_type: String,
+    public_key: Option<String>,
+}
+
 /// Lists all keypairs (file extension .nk) in a specified directory or $WASH_KEYS($HOME/.wash/keys) if directory is not specified
 pub(crate) fn list(
     directory: Option<String>,
     output: &Output,
 ) -> Result<String, Box<dyn ::std::error::Error>> {
-    let dir = determine_directory(directory)?;
+    let dir = resolve_keys_dir(directory)?;
+    let entries = key_entries(&dir)?;
+    let keys: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
 
-    let mut keys = vec![];
-    let paths = fs::read_dir(dir.clone())
-        .map_err(|e| format!("Error: {}, please ensure directory {} exists", e, dir))?;
+    let text = match output.kind {
+        OutputKind::Text { max_width } => {
+            let mut table = Table::new();
+            crate::util::configure_table_style(&mut table, 3, max_width);
+            table.add_row(Row::new(vec![
+                TableCell::new_with_alignment("Name", 1, Alignment::Left),
+                TableCell::new_with_alignment("Type", 1, Alignment::Left),
+                TableCell::new_with_alignment("Public Key", 1, Alignment::Left),
+            ]));
+            for entry in entries.iter() {
+                table.add_row(Row::new(vec![
+                    TableCell::new_with_alignment(&entry.name, 1, Alignment::Left),
+                    TableCell::new_with_alignment(&entry.key_type, 1, Alignment::Left),
+                    TableCell::new_with_alignment(
+                        entry.public_key.as_deref().unwrap_or("N/A"),
+                        1,
+                        Alignment::Left,
+                    ),
+                ]));
+            }
+            format!(
+                "====== Keys found in {} ======\n{}",
+                dir.display(),
+                table.render()
+            )
+        }
+        OutputKind::Json => String::new(),
+    };
 
+    Ok(format_output(
+        text,
+        json!({ "keys": keys, "details": entries }),
+        &output.kind,
+    ))
+}
+
+/// Reads every seed file in `dir`, sorted by name. Files that don't hold a valid seed are
+/// still listed, with an `invalid` key type
+fn key_entries(dir: &Path) -> Result<Vec<KeyEntry>, Box<dyn ::std::error::Error>> {
+    let paths = fs::read_dir(dir).map_err(|e| {
+        format!(
+            "Error: {}, please ensure directory {} exists",
+            e,
+            dir.display()
+        )
+    })?;
+
+    let mut entries = vec![];
     for path in paths {
-        let f = String::from(path.unwrap().file_name().to_str().unwrap());
-        if f.ends_with(".nk") {
-            keys.push(f);
+        let path = path?;
+        let name = path.file_name().to_string_lossy().to_string();
+        if !name.ends_with(".nk") {
+            continue;
+        }
+        let seed = fs::read_to_string(path.path()).unwrap_or_default();
+        let kp = KeyPair::from_seed(seed.trim()).ok();
+        let public_key = kp.map(|kp| kp.public_key());
+        // The public key of an encrypted seed isn't known without its passphrase
+        let key_type = match public_key.as_deref().and_then(keypair_type_of) {
+            Some(kt) => keypair_type_to_string(kt),
+            None if is_encrypted_seed(&seed) => "encrypted".to_string(),
+            None => "invalid".to_string(),
+        };
+        entries.push(KeyEntry {
+            name,
+            key_type,
+            public_key,
+        });
+    }
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(entries)
+}
+
+/// Validates a seed and stores it in a specified directory, or $WASH_KEYS ($HOME/.wash/keys) if directory is not specified.
+/// The seed is encrypted with `passphrase` if one is given
+pub(crate) fn import(
+    keyname: &str,
+    seed: &str,
+    directory: Option<String>,
+    force: bool,
+    passphrase: Option<&str>,
+    output: &Output,
+) -> Result<String, Box<dyn ::std::error::Error>> {
+    let seed = read_seed_input(seed, &mut std::io::stdin().lock())?;
+    let kp = KeyPair::from_seed(&seed).map_err(|e| format!("Invalid seed: {}", e))?;
+    let key_type = keypair_type_of(&kp.public_key())
+        .map(keypair_type_to_string)
+        .ok_or("Invalid seed: unrecognized key type")?;
+
+    let dir = resolve_keys_dir(directory)?;
+    let path = dir.join(key_filename(keyname));
+    let stored = match passphrase {
+        Some(passphrase) => encrypt_seed(&seed, passphrase, SCRYPT_LOG_N)?,
+        None => seed,
+    };
+    if force && path.exists() {
+        eprintln!(
+            "Warning: overwriting key {} with {}",
+            path.display(),
+            describe_existing_key(&path)
+        );
+    }
+    write_seed(&path, &stored, force).map_err(|e| -> Box<dyn ::std::error::Error> {
+        match e.kind() {
+            std::io::ErrorKind::AlreadyExists => WashError::InvalidInput(format!(
+                "Key {} with {} already exists, use --force to overwrite it",
+                path.display(),
+                describe_existing_key(&path)
+            ))
+            .into(),
+            _ => format!("Unable to write key {}: {}", path.display(), e).into(),
         }
+    })?;
+
+    Ok(format_output(
+        format!(
+            "Imported {} key {} to {}",
+            key_type,
+            kp.public_key(),
+            path.display()
+        ),
+        json!({
+            "public_key": kp.public_key(),
+            "key_type": key_type,
+            "path": path,
+            "encrypted": passphrase.is_some(),
+        }),
+        &output.kind,
+    ))
+}
+
+/// Deletes a seed file from a specified directory, or $WASH_KEYS ($HOME/.wash/keys) if directory
+/// is not specified, after confirming on `input` unless `yes` is set
+pub(crate) fn delete(
+    keyname: &str,
+    directory: Option<String>,
+    yes: bool,
+    output: &Output,
+    input: &mut impl BufRead,
+) -> Result<String, Box<dyn ::std::error::Error>> {
+    // Only keys directly in the keys directory can be deleted
+    if keyname.contains(std::path::is_separator) || keyname.contains("..") {
+        return Err(WashError::InvalidInput(format!(
+            "Invalid key name {}, it must not contain a path",
+            keyname
+        ))
+        .into());
     }
+    let dir = resolve_keys_dir(directory)?;
+    let path = dir.join(key_filename(keyname));
+    if !path.is_file() {
+        return Err(format!("No such key {} in {}", keyname, dir.display()).into());
+    }
+    let seed = fs::read_to_string(&path).unwrap_or_default();
+    let public_key = KeyPair::from_seed(seed.trim())
+        .ok()
+        .map(|kp| kp.public_key());
+    let description = match public_key.as_deref() {
+        Some(pk) => format!("{} ({})", path.display(), pk),
+        None if is_encrypted_seed(&seed) => format!("{} (encrypted seed)", path.display()),
+        None => format!("{} (invalid seed)", path.display()),
+    };
 
+    if !yes && !confirm(&format!("Delete key {}? [y/N] ", description), input)? {
+        return Ok(format_output(
+            format!("Cancelled deleting key {}", description),
+            json!({"result": "cancelled", "path": path, "public_key": public_key}),
+            &output.kind,
+        ));
+    }
+    fs::remove_file(&path)?;
     Ok(format_output(
-        format!("====== Keys found in {} ======\n{}", dir, keys.join("\n")),
-        json!({ "keys": keys }),
+        format!("Deleted key {}", description),
+        json!({"result": "success", "path": path, "public_key": public_key}),
         &output.kind,
     ))
 }
 
-fn determine_directory(directory: Option<String>) -> Result<String, Error> {
-    if let Some(d) = directory {
-        Ok(d)
-    } else if let Ok(home) = env::var("HOME") {
-        Ok(format!("{}/.wash/keys", home))
+/// Name of the seed file for a key, adding the `.nk` extension if it's missing
+fn key_filename(keyname: &str) -> String {
+    if keyname.ends_with(".nk") {
+        keyname.to_string()
+    } else {
+        format!("{}.nk", keyname)
+    }
+}
+
+/// Derives the public key and key type of a seed
+pub(crate) fn convert(seed: &str, output: &Output) -> Result<String, Box<dyn ::std::error::Error>> {
+    let seed = read_seed_input(seed, &mut std::io::stdin().lock())?;
+    let kp = KeyPair::from_seed(&seed).map_err(|e| format!("Invalid seed: {}", e))?;
+    let key_type = keypair_type_of(&kp.public_key())
+        .map(keypair_type_to_string)
+        .ok_or("Invalid seed: unrecognized key type")?;
+
+    Ok(format_output(
+        format!("Public Key: {}\nKey Type: {}", kp.public_key(), key_type),
+        json!({
+            "public_key": kp.public_key(),
+            "key_type": key_type,
+        }),
+        &output.kind,
+    ))
+}
+
+/// Resolves a seed given on the command line, which may be the seed itself, a path to a
+/// seed file, or - to read the seed from `stdin`
+fn read_seed_input(
+    input: &str,
+    stdin: &mut impl Read,
+) -> Result<String, Box<dyn ::std::error::Error>> {
+    let seed = if input == "-" {
+        let mut s = String::new();
+        stdin.read_to_string(&mut s)?;
+        s
+    } else if Path::new(input).is_file() {
+        fs::read_to_string(input)?
     } else {
-        Err(Error::new(
+        input.to_string()
+    };
+    let seed = seed.trim();
+    if seed.is_empty() {
+        return Err("No seed was provided".into());
+    }
+    Ok(seed.to_string())
+}
+
+/// Writes a seed file readable only by the current user (mode 0600 on Unix), creating its
+/// directory if needed. Fails with `AlreadyExists` if the file exists and `overwrite` isn't set
+fn write_seed(path: &Path, seed: &str, overwrite: bool) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true);
+    if overwrite {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut f = options.open(path)?;
+    // The mode only applies to new files, so tighten the permissions of an overwritten one
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        f.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    f.write_all(seed.as_bytes())
+}
+
+/// Describes the identity held by an existing seed file, which is lost if the file is overwritten
+fn describe_existing_key(path: &Path) -> String {
+    let seed = fs::read_to_string(path).unwrap_or_default();
+    match KeyPair::from_seed(seed.trim()) {
+        Ok(kp) => format!("public key {}", kp.public_key()),
+        Err(_) if is_encrypted_seed(&seed) => "an encrypted seed".to_string(),
+        Err(_) => "an unreadable seed".to_string(),
+    }
+}
+
+/// Environment variable holding the keys directory
+const KEYS_DIR_ENV: &str = "WASH_KEYS";
+/// Keys directory relative to $HOME, used when neither `--directory` nor $WASH_KEYS is set
+const DEFAULT_KEYS_DIR: &str = ".wash/keys";
+
+/// Resolves the directory every key consuming command reads and stores seeds in: the
+/// `--directory` flag, then $WASH_KEYS, then $HOME/.wash/keys. The directory is created if missing
+pub(crate) fn resolve_keys_dir(flag: Option<String>) -> Result<PathBuf, Error> {
+    let dir = keys_dir(
+        flag,
+        crate::config::env_var(KEYS_DIR_ENV),
+        env::var("HOME").ok(),
+    )?;
+    fs::create_dir_all(&dir).map_err(|e| {
+        Error::new(
+            e.kind(),
+            format!("Unable to create keys directory {}: {}", dir.display(), e),
+        )
+    })?;
+    Ok(dir)
+}
+
+/// Applies the keys directory precedence to the flag and environment values, ignoring empty ones
+fn keys_dir(
+    flag: Option<String>,
+    keys_env: Option<String>,
+    home: Option<String>,
+) -> Result<PathBuf, Error> {
+    let non_empty = |value: Option<String>| value.filter(|v| !v.is_empty());
+    match (
+        non_empty(flag).or_else(|| non_empty(keys_env)),
+        non_empty(home),
+    ) {
+        (Some(dir), _) => Ok(PathBuf::from(dir)),
+        (None, Some(home)) => Ok(Path::new(&home).join(DEFAULT_KEYS_DIR)),
+        (None, None) => Err(Error::new(
             std::io::ErrorKind::NotFound,
             "$HOME not found, please set $HOME or $WASH_KEYS for autogenerated keys".to_string(),
-        ))
+        )),
     }
 }
 
-/// Helper function to locate and extract keypair from user input
+/// Environment variable holding the passphrase of encrypted seeds, read instead of prompting
+const PASSPHRASE_ENV: &str = "WASH_KEYS_PASSPHRASE";
+/// Prefix of an encrypted seed, which is followed by the scrypt cost and the base64 encoded
+/// salt, nonce and ciphertext, separated by a colon
+const ENCRYPTED_SEED_PREFIX: &str = "wash-encrypted-seed-v1:";
+/// scrypt cost (log2 of the work factor) of the key encrypting a seed
+const SCRYPT_LOG_N: u8 = 15;
+/// Highest scrypt cost accepted from an encrypted seed, bounding the memory decryption takes
+const MAX_SCRYPT_LOG_N: u8 = 20;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Returns true if the contents of a seed file are an encrypted seed
+pub(crate) fn is_encrypted_seed(seed: &str) -> bool {
+    seed.trim().starts_with(ENCRYPTED_SEED_PREFIX)
+}
+
+/// Derives the cipher for a seed from a passphrase with scrypt
+fn seed_cipher(
+    passphrase: &str,
+    salt: &[u8],
+    log_n: u8,
+) -> Result<ChaCha20Poly1305, Box<dyn ::std::error::Error>> {
+    if log_n > MAX_SCRYPT_LOG_N {
+        return Err(format!("Encrypted seed has an unsupported scrypt cost {}", log_n).into());
+    }
+    let params = scrypt::Params::new(log_n, 8, 1)
+        .map_err(|_| format!("Encrypted seed has an invalid scrypt cost {}", log_n))?;
+    let mut key = [0u8; 32];
+    scrypt::scrypt(passphrase.as_bytes(), salt, &params, &mut key)
+        .map_err(|_| "Unable to derive the seed encryption key")?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+/// Encrypts a seed with ChaCha20-Poly1305, using a key derived from `passphrase`
+fn encrypt_seed(
+    seed: &str,
+    passphrase: &str,
+    log_n: u8,
+) -> Result<String, Box<dyn ::std::error::Error>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = seed_cipher(passphrase, &salt, log_n)?
+        .encrypt(Nonce::from_slice(&nonce), seed.as_bytes())
+        .map_err(|_| "Unable to encrypt the seed")?;
+
+    let mut payload = salt.to_vec();
+    payload.extend_from_slice(&nonce);
+    payload.extend(ciphertext);
+    Ok(format!(
+        "{}{}:{}",
+        ENCRYPTED_SEED_PREFIX,
+        log_n,
+        base64::encode(payload)
+    ))
+}
+
+fn decrypt_seed(encrypted: &str, passphrase: &str) -> Result<String, Box<dyn ::std::error::Error>> {
+    let invalid = || "Invalid encrypted seed".to_string();
+    let mut parts = encrypted
+        .trim()
+        .strip_prefix(ENCRYPTED_SEED_PREFIX)
+        .ok_or_else(invalid)?
+        .splitn(2, ':');
+    let log_n: u8 = parts
+        .next()
+        .and_then(|n| n.parse().ok())
+        .ok_or_else(invalid)?;
+    let payload = parts
+        .next()
+        .and_then(|p| base64::decode(p).ok())
+        .filter(|p| p.len() > SALT_LEN + NONCE_LEN)
+        .ok_or_else(invalid)?;
+    let (salt, rest) = payload.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let seed = seed_cipher(passphrase, salt, log_n)?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Unable to decrypt the seed, the passphrase is incorrect")?;
+    Ok(String::from_utf8(seed)?)
+}
+
+/// Reads the passphrase of encrypted seeds from $WASH_KEYS_PASSPHRASE, or prompts for it.
+/// A prompted passphrase has to be entered twice when `confirm` is set
+fn read_passphrase(confirm: bool) -> Result<String, Box<dyn ::std::error::Error>> {
+    if let Some(passphrase) = env::var(PASSPHRASE_ENV).ok().filter(|p| !p.is_empty()) {
+        debug!("Using the seed passphrase from ${}", PASSPHRASE_ENV);
+        return Ok(passphrase);
+    }
+    let passphrase = prompt_secret("Seed passphrase: ")?;
+    if passphrase.is_empty() {
+        return Err("No passphrase was provided".into());
+    }
+    if confirm && prompt_secret("Confirm passphrase: ")? != passphrase {
+        return Err("Passphrases do not match".into());
+    }
+    Ok(passphrase)
+}
+
+/// Decrypts a seed if it's encrypted, leaving plaintext seeds as they are
+fn decrypt_if_encrypted(seed: String) -> Result<String, Box<dyn ::std::error::Error>> {
+    if is_encrypted_seed(&seed) {
+        decrypt_seed(&seed, &read_passphrase(false)?)
+    } else {
+        Ok(seed)
+    }
+}
+
+/// Prefix of a seed reference that reads the seed from an environment variable, e.g. `env:ISSUER_SEED`
+const ENV_SEED_PREFIX: &str = "env:";
+
+/// Helper function to locate and extract keypair from user input, which may be a seed, a path
+/// to a seed file, or `env:VARNAME` to read the seed from an environment variable
 /// Returns a tuple of the keypair and optional autogenerate message
 pub(crate) fn extract_keypair(
     input: Option<String>,
@@ -166,19 +875,34 @@ pub(crate) fn extract_keypair(
     disable_keygen: bool,
 ) -> Result<KeyPair, Box<dyn std::error::Error>> {
     let seed = if let Some(input_str) = input {
-        match File::open(input_str.clone()) {
-            // User provided file path to seed as argument
-            Ok(mut f) => {
-                let mut s = String::new();
-                f.read_to_string(&mut s)?;
-                s
+        if let Some(var) = input_str.strip_prefix(ENV_SEED_PREFIX) {
+            debug!("Reading seed from environment variable {}", var);
+            env::var(var).map_err(|_| {
+                format!(
+                    "Environment variable {} is not set, it must contain a seed of type {}",
+                    var,
+                    keypair_type_to_string(keygen_type.clone())
+                )
+            })?
+        } else {
+            match File::open(input_str.clone()) {
+                // User provided file path to seed as argument
+                Ok(mut f) => {
+                    debug!("Reading seed from {}", input_str);
+                    let mut s = String::new();
+                    f.read_to_string(&mut s)?;
+                    s
+                }
+                // User provided seed as an argument
+                Err(_e) => {
+                    debug!("Using seed provided as an argument");
+                    input_str
+                }
             }
-            // User provided seed as an argument
-            Err(_e) => input_str,
         }
     } else if let Some(module) = module_path {
         // No seed value provided, attempting to source from provided or default directory
-        let dir = determine_directory(directory)?;
+        let dir = resolve_keys_dir(directory)?;
         // Account key should be re-used, and will attempt to generate based on the terminal USER
         let module_name = match keygen_type {
             KeyPairType::Account => std::env::var("USER").unwrap_or_else(|_| "user".to_string()),
@@ -189,15 +913,18 @@ pub(crate) fn extract_keypair(
                 .unwrap()
                 .to_string(),
         };
-        let path = format!(
-            "{}/{}_{}.nk",
-            dir,
-            module_name,
-            keypair_type_to_string(keygen_type.clone())
-        );
+        let path = dir
+            .join(format!(
+                "{}_{}.nk",
+                module_name,
+                keypair_type_to_string(keygen_type.clone())
+            ))
+            .display()
+            .to_string();
         match File::open(path.clone()) {
             // Default key found
             Ok(mut f) => {
+                debug!("Reading seed from default key {}", path);
                 let mut s = String::new();
                 f.read_to_string(&mut s)?;
                 s
@@ -215,11 +942,11 @@ If you'd like to use alternative keys, you can supply them as a flag.\n",
                     &Output::default().kind,
                 ));
 
-                let kp = KeyPair::new(keygen_type);
+                let kp = KeyPair::new(keygen_type.clone());
                 let seed = kp.seed()?;
-                fs::create_dir_all(Path::new(&path).parent().unwrap())?;
-                let mut f = File::create(path)?;
-                f.write_all(seed.as_bytes())?;
+                // Never replace a key that exists but couldn't be read
+                write_seed(Path::new(&path), &seed, false)
+                    .map_err(|e| format!("Unable to write key {}: {}", path, e))?;
                 seed
             }
             _ => {
@@ -234,7 +961,43 @@ If you'd like to use alternative keys, you can supply them as a flag.\n",
         return Err("Keypair path or string not supplied. Ensure provided keypair is valid".into());
     };
 
-    KeyPair::from_seed(&seed).map_err(|e| format!("{}", e).into())
+    let seed = decrypt_if_encrypted(seed)?;
+    let kp = KeyPair::from_seed(seed.trim()).map_err(|e| format!("{}", e))?;
+    check_keypair_type(&kp, keygen_type)?;
+    Ok(kp)
+}
+
+/// Determines the type of a key from the prefix of its public key
+pub(crate) fn keypair_type_of(public_key: &str) -> Option<KeyPairType> {
+    use KeyPairType::*;
+    match public_key.chars().next()? {
+        'A' => Some(Account),
+        'C' => Some(Cluster),
+        'V' => Some(Service),
+        'M' => Some(Module),
+        'N' => Some(Server),
+        'O' => Some(Operator),
+        'U' => Some(User),
+        _ => None,
+    }
+}
+
+/// Ensures a keypair is of the type required for its role, e.g. an account key as an issuer
+fn check_keypair_type(
+    kp: &KeyPair,
+    expected: KeyPairType,
+) -> Result<(), Box<dyn ::std::error::Error>> {
+    let expected = keypair_type_to_string(expected);
+    match keypair_type_of(&kp.public_key()).map(keypair_type_to_string) {
+        Some(actual) if actual == expected => Ok(()),
+        actual => Err(format!(
+            "Expected a key of type {}, but the provided seed is of type {} ({})",
+            expected,
+            actual.unwrap_or_else(|| "unknown".to_string()),
+            kp.public_key()
+        )
+        .into()),
+    }
 }
 
 fn keypair_type_to_string(keypair_type: KeyPairType) -> String {
@@ -252,9 +1015,14 @@ fn keypair_type_to_string(keypair_type: KeyPairType) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::{generate, KeysCli, KeysCliCommand, OutputKind};
-    use nkeys::KeyPairType;
+    use super::{
+        convert, decrypt_if_encrypted, decrypt_seed, delete, encrypt_seed, extract_keypair,
+        generate, generate_project, import, is_encrypted_seed, key_entries, keys_dir,
+        read_seed_input, resolve_keys_dir, GenTarget, KeysCli, KeysCliCommand, OutputKind,
+    };
+    use nkeys::{KeyPair, KeyPairType};
     use serde::Deserialize;
+    use std::path::PathBuf;
     use structopt::StructOpt;
 
     #[test]
@@ -274,10 +1042,79 @@ mod tests {
         assert_ne!(keypair_json, "");
     }
 
+    #[test]
+    fn test_key_entries() {
+        const ACCOUNT_SEED: &str = "SAAAF62YYA6UCKZNSE7UF7GVWEHHYASDSUSSVCEEHTH3WY57DJKVXKIKOY";
+        let dir = std::env::temp_dir().join(format!("wash-keys-list-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("user_account.nk"), format!("{}\n", ACCOUNT_SEED)).unwrap();
+        std::fs::write(dir.join("broken.nk"), "not a seed").unwrap();
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let entries = key_entries(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "broken.nk");
+        assert_eq!(entries[0].key_type, "invalid");
+        assert!(entries[0].public_key.is_none());
+        assert_eq!(entries[1].name, "user_account.nk");
+        assert_eq!(entries[1].key_type, "account");
+        assert_eq!(
+            entries[1].public_key.as_deref(),
+            Some(
+                KeyPair::from_seed(ACCOUNT_SEED)
+                    .unwrap()
+                    .public_key()
+                    .as_str()
+            )
+        );
+    }
+
+    #[test]
+    fn test_extract_keypair_from_env() {
+        const SEED: &str = "SAAAF62YYA6UCKZNSE7UF7GVWEHHYASDSUSSVCEEHTH3WY57DJKVXKIKOY";
+        std::env::set_var("WASH_TEST_ISSUER_SEED", format!("{}\n", SEED));
+        let kp = extract_keypair(
+            Some("env:WASH_TEST_ISSUER_SEED".to_string()),
+            None,
+            None,
+            KeyPairType::Account,
+            true,
+        )
+        .unwrap();
+        assert_eq!(kp.seed().unwrap(), SEED);
+
+        let err = extract_keypair(
+            Some("env:WASH_TEST_ISSUER_SEED".to_string()),
+            None,
+            None,
+            KeyPairType::Module,
+            true,
+        )
+        .unwrap_err();
+        assert!(err.to_string().starts_with(
+            "Expected a key of type module, but the provided seed is of type account"
+        ));
+
+        let err = extract_keypair(
+            Some("env:WASH_TEST_UNSET_SEED".to_string()),
+            None,
+            None,
+            KeyPairType::Account,
+            true,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Environment variable WASH_TEST_UNSET_SEED is not set, it must contain a seed of type account"
+        );
+    }
+
     #[derive(Debug, Clone, Deserialize)]
     struct KeyPairJson {
         public_key: String,
         seed: String,
+        key_type: String,
     }
 
     #[test]
@@ -294,6 +1131,7 @@ mod tests {
         assert_eq!(keypair.seed.len(), sample_seed.len());
         assert_eq!(keypair.public_key.starts_with('M'), true);
         assert_eq!(keypair.seed.starts_with("SM"), true);
+        assert_eq!(keypair.key_type, "module");
     }
 
     #[test]
@@ -364,7 +1202,11 @@ mod tests {
         key_gen_types.iter().for_each(|cmd| {
             let gen_cmd = KeysCli::from_iter(&["keys", "gen", cmd]);
             match gen_cmd.command {
-                KeysCliCommand::GenCommand { keytype, output } => {
+                KeysCliCommand::GenCommand {
+                    keytype: GenTarget::Key(keytype),
+                    output,
+                    ..
+                } => {
                     use KeyPairType::*;
                     match keytype {
                         Account => assert_eq!(*cmd, "account"),
@@ -384,7 +1226,13 @@ mod tests {
         key_gen_types.iter().for_each(|cmd| {
             let gen_cmd = KeysCli::from_iter(&["keys", "gen", cmd, "-o", "json"]);
             match gen_cmd.command {
-                KeysCliCommand::GenCommand { keytype, output } => {
+                KeysCliCommand::GenCommand {
+                    keytype: GenTarget::Key(keytype),
+                    name,
+                    encrypt,
+                    output,
+                    ..
+                } => {
                     use KeyPairType::*;
                     match keytype {
                         Account => assert_eq!(*cmd, "account"),
@@ -395,11 +1243,132 @@ mod tests {
                         Operator => assert_eq!(*cmd, "operator"),
                         Cluster => assert_eq!(*cmd, "cluster"),
                     }
+                    assert!(name.is_none());
+                    assert!(!encrypt);
                     assert_eq!(output.kind, OutputKind::Json);
                 }
                 _ => panic!("`keys gen` constructed incorrect command"),
             };
         });
+
+        match KeysCli::from_iter(&["keys", "gen", "account", "--encrypt"]).command {
+            KeysCliCommand::GenCommand { encrypt, .. } => assert!(encrypt),
+            _ => panic!("`keys gen` constructed incorrect command"),
+        };
+
+        match KeysCli::from_iter(&[
+            "keys",
+            "gen",
+            "project",
+            "echo",
+            "--provider",
+            "-d",
+            "./tests/fixtures",
+            "--force",
+            "-o",
+            "json",
+        ])
+        .command
+        {
+            KeysCliCommand::GenCommand {
+                keytype,
+                name,
+                provider,
+                directory,
+                force,
+                encrypt,
+                output,
+            } => {
+                assert_eq!(keytype, GenTarget::Project);
+                assert_eq!(name, Some("echo".to_string()));
+                assert!(provider);
+                assert_eq!(directory, Some("./tests/fixtures".to_string()));
+                assert!(force);
+                assert!(!encrypt);
+                assert_eq!(output.kind, OutputKind::Json);
+            }
+            _ => panic!("`keys gen project` constructed incorrect command"),
+        };
+        assert!(KeysCli::from_iter_safe(&["keys", "gen", "notakey"]).is_err());
+    }
+
+    #[test]
+    fn test_generate_project() {
+        let dir = std::env::temp_dir().join(format!("wash-keys-project-{}", std::process::id()));
+        let directory = Some(dir.to_str().unwrap().to_string());
+        let output = crate::util::Output {
+            kind: OutputKind::Json,
+        };
+
+        let res = generate_project("echo", false, directory.clone(), false, None, &output).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&res).unwrap();
+        assert_eq!(json["project"], "echo");
+        assert_eq!(json["keys"][0]["key_type"], "account");
+        assert_eq!(json["keys"][1]["key_type"], "module");
+        assert!(json["keys"][1]["public_key"]
+            .as_str()
+            .unwrap()
+            .starts_with('M'));
+        assert!(dir.join("echo_account.nk").is_file());
+        assert!(dir.join("echo_module.nk").is_file());
+
+        let existing = std::fs::read_to_string(dir.join("echo_account.nk")).unwrap();
+        let existing_public = json["keys"][0]["public_key"].as_str().unwrap().to_string();
+        let err =
+            generate_project("echo", false, directory.clone(), false, None, &output).unwrap_err();
+        assert!(err.to_string().ends_with("use --force to overwrite it"));
+        assert!(err
+            .to_string()
+            .contains(&format!("with public key {}", existing_public)));
+        assert_eq!(crate::util::exit_code(&*err), 2);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("echo_account.nk")).unwrap(),
+            existing
+        );
+        assert!(generate_project("echo", false, directory.clone(), true, None, &output).is_ok());
+        assert_ne!(
+            std::fs::read_to_string(dir.join("echo_account.nk")).unwrap(),
+            existing
+        );
+
+        let res = generate_project("httpserver", true, directory, false, None, &output).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&res).unwrap();
+        assert_eq!(json["keys"][1]["key_type"], "service");
+        assert!(dir.join("httpserver_service.nk").is_file());
+        assert!(!dir.join("httpserver_module.nk").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_keys_dir() {
+        let some = |v: &str| Some(v.to_string());
+        assert_eq!(
+            keys_dir(some("./flag"), some("/env/keys"), some("/home/user")).unwrap(),
+            PathBuf::from("./flag")
+        );
+        assert_eq!(
+            keys_dir(None, some("/env/keys"), some("/home/user")).unwrap(),
+            PathBuf::from("/env/keys")
+        );
+        assert_eq!(
+            keys_dir(None, None, some("/home/user")).unwrap(),
+            PathBuf::from("/home/user/.wash/keys")
+        );
+        // An empty value is treated as unset
+        assert_eq!(
+            keys_dir(some(""), some(""), some("/home/user")).unwrap(),
+            PathBuf::from("/home/user/.wash/keys")
+        );
+        assert!(keys_dir(None, None, None).is_err());
+
+        let dir = std::env::temp_dir().join(format!("wash-keys-dir-{}", std::process::id()));
+        let nested = dir.join("nested");
+        assert_eq!(
+            resolve_keys_dir(Some(nested.to_str().unwrap().to_string())).unwrap(),
+            nested
+        );
+        assert!(nested.is_dir());
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 
     #[test]
@@ -460,4 +1429,329 @@ mod tests {
             other_cmd => panic!("keys get generated other command {:?}", other_cmd),
         }
     }
+
+    #[test]
+    /// Enumerates multiple options of the `import` command to ensure API doesn't
+    /// change between versions. This test will fail if `wash keys import`
+    /// changes syntax, ordering of required elements, or flags.
+    fn test_import_comprehensive() {
+        const KEYPATH: &str = "./";
+        const SEED: &str = "SAAAF62YYA6UCKZNSE7UF7GVWEHHYASDSUSSVCEEHTH3WY57DJKVXKIKOY";
+
+        let import_all_flags = KeysCli::from_iter(&[
+            "keys",
+            "import",
+            "user_account",
+            SEED,
+            "-d",
+            KEYPATH,
+            "--force",
+            "--encrypt",
+            "-o",
+            "json",
+        ]);
+        match import_all_flags.command {
+            KeysCliCommand::ImportCommand {
+                keyname,
+                seed,
+                directory,
+                force,
+                encrypt,
+                output,
+            } => {
+                assert_eq!(keyname, "user_account");
+                assert_eq!(seed, SEED);
+                assert_eq!(directory, Some(KEYPATH.to_string()));
+                assert!(force);
+                assert!(encrypt);
+                assert_eq!(output.kind, OutputKind::Json);
+            }
+            other_cmd => panic!("keys import generated other command {:?}", other_cmd),
+        }
+
+        let import_stdin = KeysCli::from_iter(&["keys", "import", "user_account", "-"]);
+        match import_stdin.command {
+            KeysCliCommand::ImportCommand {
+                seed,
+                force,
+                encrypt,
+                ..
+            } => {
+                assert_eq!(seed, "-");
+                assert!(!force);
+                assert!(!encrypt);
+            }
+            other_cmd => panic!("keys import generated other command {:?}", other_cmd),
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_seed_file_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        const SEED: &str = "SAAAF62YYA6UCKZNSE7UF7GVWEHHYASDSUSSVCEEHTH3WY57DJKVXKIKOY";
+        let dir = std::env::temp_dir().join(format!("wash-keys-perms-{}", std::process::id()));
+        let mode = |name: &str| {
+            std::fs::metadata(dir.join(name))
+                .unwrap()
+                .permissions()
+                .mode()
+                & 0o777
+        };
+
+        extract_keypair(
+            None,
+            Some("./echo.wasm".to_string()),
+            Some(dir.to_str().unwrap().to_string()),
+            KeyPairType::Module,
+            false,
+        )
+        .unwrap();
+        assert_eq!(mode("echo_module.nk"), 0o600);
+
+        std::fs::write(dir.join("user_account.nk"), "").unwrap();
+        std::fs::set_permissions(
+            dir.join("user_account.nk"),
+            std::fs::Permissions::from_mode(0o644),
+        )
+        .unwrap();
+        import(
+            "user_account",
+            SEED,
+            Some(dir.to_str().unwrap().to_string()),
+            true,
+            None,
+            &crate::util::Output::default(),
+        )
+        .unwrap();
+        assert_eq!(mode("user_account.nk"), 0o600);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_convert() {
+        const SEED: &str = "SAAAF62YYA6UCKZNSE7UF7GVWEHHYASDSUSSVCEEHTH3WY57DJKVXKIKOY";
+        let public_key = KeyPair::from_seed(SEED).unwrap().public_key();
+
+        let converted = KeysCli::from_iter(&["keys", "convert", SEED, "-o", "json"]);
+        match converted.command {
+            KeysCliCommand::ConvertCommand { seed, output } => {
+                assert_eq!(seed, SEED);
+                assert_eq!(output.kind, OutputKind::Json);
+                let json: serde_json::Value =
+                    serde_json::from_str(&convert(&seed, &output).unwrap()).unwrap();
+                assert_eq!(json["public_key"], public_key.as_str());
+                assert_eq!(json["key_type"], "account");
+            }
+            other_cmd => panic!("keys convert generated other command {:?}", other_cmd),
+        }
+        assert!(convert("SAnotaseed", &crate::util::Output::default()).is_err());
+    }
+
+    #[test]
+    fn test_import() {
+        const SEED: &str = "SAAAF62YYA6UCKZNSE7UF7GVWEHHYASDSUSSVCEEHTH3WY57DJKVXKIKOY";
+        let dir = std::env::temp_dir().join(format!("wash-keys-import-{}", std::process::id()));
+        let directory = Some(dir.to_str().unwrap().to_string());
+        let output = crate::util::Output::default();
+
+        import(
+            "user_account",
+            SEED,
+            directory.clone(),
+            false,
+            None,
+            &output,
+        )
+        .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.join("user_account.nk")).unwrap(),
+            SEED
+        );
+        let err = import(
+            "user_account.nk",
+            SEED,
+            directory.clone(),
+            false,
+            None,
+            &output,
+        )
+        .unwrap_err();
+        assert!(err.to_string().ends_with("use --force to overwrite it"));
+        assert!(import(
+            "user_account.nk",
+            SEED,
+            directory.clone(),
+            true,
+            None,
+            &output
+        )
+        .is_ok());
+        assert!(import("broken", "SAnotaseed", directory, false, None, &output).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let mut stdin = std::io::Cursor::new(format!("{}\n", SEED));
+        assert_eq!(read_seed_input("-", &mut stdin).unwrap(), SEED);
+        assert!(read_seed_input("-", &mut std::io::Cursor::new("")).is_err());
+    }
+
+    #[test]
+    fn test_encrypted_seeds() {
+        const SEED: &str = "SAAAF62YYA6UCKZNSE7UF7GVWEHHYASDSUSSVCEEHTH3WY57DJKVXKIKOY";
+        // A low scrypt cost keeps the test fast
+        let encrypted = encrypt_seed(SEED, "hunter2", 10).unwrap();
+        assert!(is_encrypted_seed(&encrypted));
+        assert!(!encrypted.contains(SEED));
+        assert_ne!(encrypted, encrypt_seed(SEED, "hunter2", 10).unwrap());
+        assert_eq!(decrypt_seed(&encrypted, "hunter2").unwrap(), SEED);
+        assert_eq!(
+            decrypt_seed(&encrypted, "hunter3").unwrap_err().to_string(),
+            "Unable to decrypt the seed, the passphrase is incorrect"
+        );
+        let tampered = format!("{}AAAA", &encrypted[..encrypted.len() - 4]);
+        assert!(decrypt_seed(&tampered, "hunter2").is_err());
+        assert!(decrypt_seed("wash-encrypted-seed-v1:10:", "hunter2").is_err());
+        assert!(decrypt_seed("wash-encrypted-seed-v1:40:AAAA", "hunter2").is_err());
+        // Plaintext seeds are used as they are, without a passphrase
+        assert_eq!(decrypt_if_encrypted(SEED.to_string()).unwrap(), SEED);
+
+        let dir = std::env::temp_dir().join(format!("wash-keys-encrypted-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("user_account.nk");
+        std::fs::write(&path, format!("{}\n", encrypted)).unwrap();
+        std::env::set_var("WASH_KEYS_PASSPHRASE", "hunter2");
+        let kp = extract_keypair(
+            Some(path.to_str().unwrap().to_string()),
+            None,
+            None,
+            KeyPairType::Account,
+            true,
+        )
+        .unwrap();
+        std::env::remove_var("WASH_KEYS_PASSPHRASE");
+        assert_eq!(kp.seed().unwrap(), SEED);
+
+        let entries = key_entries(&dir).unwrap();
+        assert_eq!(entries[0].key_type, "encrypted");
+        assert!(entries[0].public_key.is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_delete_comprehensive() {
+        let delete_all_flags = KeysCli::from_iter(&[
+            "keys",
+            "delete",
+            "user_account",
+            "-d",
+            "./",
+            "-y",
+            "-o",
+            "json",
+        ]);
+        match delete_all_flags.command {
+            KeysCliCommand::DeleteCommand {
+                keyname,
+                directory,
+                yes,
+                output,
+            } => {
+                assert_eq!(keyname, "user_account");
+                assert_eq!(directory, Some("./".to_string()));
+                assert!(yes);
+                assert_eq!(output.kind, OutputKind::Json);
+            }
+            other_cmd => panic!("keys delete generated other command {:?}", other_cmd),
+        }
+        let delete_long = KeysCli::from_iter(&["keys", "delete", "user_account.nk", "--yes"]);
+        match delete_long.command {
+            KeysCliCommand::DeleteCommand { keyname, yes, .. } => {
+                assert_eq!(keyname, "user_account.nk");
+                assert!(yes);
+            }
+            other_cmd => panic!("keys delete generated other command {:?}", other_cmd),
+        }
+        assert!(KeysCli::from_iter_safe(&["keys", "delete"]).is_err());
+    }
+
+    #[test]
+    fn test_delete() {
+        const SEED: &str = "SAAAF62YYA6UCKZNSE7UF7GVWEHHYASDSUSSVCEEHTH3WY57DJKVXKIKOY";
+        let dir = std::env::temp_dir().join(format!("wash-keys-delete-{}", std::process::id()));
+        let directory = Some(dir.to_str().unwrap().to_string());
+        let output = crate::util::Output {
+            kind: OutputKind::Json,
+        };
+        let public_key = KeyPair::from_seed(SEED).unwrap().public_key();
+        import(
+            "user_account",
+            SEED,
+            directory.clone(),
+            false,
+            None,
+            &output,
+        )
+        .unwrap();
+
+        let cancelled: serde_json::Value = serde_json::from_str(
+            &delete(
+                "user_account",
+                directory.clone(),
+                false,
+                &output,
+                &mut "n\n".as_bytes(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(cancelled["result"], "cancelled");
+        assert!(dir.join("user_account.nk").exists());
+
+        let deleted: serde_json::Value = serde_json::from_str(
+            &delete(
+                "user_account.nk",
+                directory.clone(),
+                false,
+                &output,
+                &mut "y\n".as_bytes(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(deleted["result"], "success");
+        assert_eq!(deleted["public_key"], public_key);
+        assert!(!dir.join("user_account.nk").exists());
+
+        let err = delete(
+            "user_account",
+            directory.clone(),
+            true,
+            &output,
+            &mut "".as_bytes(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().starts_with("No such key user_account in"));
+
+        // Names that would resolve outside of the keys directory are rejected
+        let outside = dir.join("outside.nk");
+        std::fs::write(&outside, SEED).unwrap();
+        for keyname in &[
+            "../outside",
+            "sub/../outside.nk",
+            "..",
+            &outside.display().to_string(),
+        ] {
+            let err = delete(
+                keyname,
+                directory.clone(),
+                true,
+                &output,
+                &mut "".as_bytes(),
+            )
+            .unwrap_err();
+            assert_eq!(crate::util::exit_code(&*err), 2);
+        }
+        assert!(outside.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -1,10 +1,17 @@
+use std::ffi::{OsStr, OsString};
+use std::path::PathBuf;
 use structopt::clap::AppSettings;
 use structopt::StructOpt;
 
+mod complete;
+use complete::CompleteCli;
+mod completions;
+use completions::CompletionsCli;
 mod drain;
 use drain::DrainCli;
 mod claims;
 use claims::ClaimsCli;
+mod config;
 mod ctl;
 use ctl::CtlCli;
 mod keys;
@@ -34,6 +41,31 @@ A single CLI to handle all of your wasmcloud tooling needs
             name = "wash",
             about = ASCII)]
 struct Cli {
+    /// Suppress spinners, progress bars and decorative output, printing only results and errors
+    #[structopt(short = "q", long = "quiet")]
+    quiet: bool,
+
+    /// Print plain ASCII, replacing emoji with text prefixes. Also set by WASH_NO_EMOJI, and
+    /// the default on dumb terminals and non-UTF-8 locales
+    #[structopt(long = "no-emoji")]
+    no_emoji: bool,
+
+    /// Log what wash is doing to stderr, repeated for more detail (-v info, -vv debug,
+    /// -vvv trace). Filters in RUST_LOG are honored as well
+    #[structopt(short = "v", long = "verbose", parse(from_occurrences))]
+    verbose: u8,
+
+    /// Resolve OCI references only from the local cache, never contacting a registry. Also set
+    /// by WASH_OFFLINE
+    #[structopt(long = "offline")]
+    offline: bool,
+
+    /// Config file supplying defaults for flags, read from $HOME/.wash/config.toml if not set
+    #[structopt(long = "config", env = "WASH_CONFIG", parse(from_os_str))]
+    // Read from the raw arguments by `config::load_config_defaults`, ahead of parsing
+    #[allow(dead_code)]
+    config: Option<PathBuf>,
+
     #[structopt(flatten)]
     command: CliCommand,
 }
@@ -61,32 +93,80 @@ enum CliCommand {
     /// Launch wasmcloud REPL environment
     #[structopt(name = "up")]
     Up(UpCli),
+    /// Generate a shell completion script for bash, zsh, fish, powershell or elvish
+    #[structopt(name = "completions")]
+    Completions(CompletionsCli),
 }
 
 #[actix_rt::main]
 async fn main() {
-    let cli = Cli::from_args();
-
-    let res = match cli.command {
-        CliCommand::Drain(draincmd) => drain::handle_command(draincmd.command()),
-        CliCommand::Keys(keyscli) => keys::handle_command(keyscli.command()),
-        CliCommand::Claims(claimscli) => claims::handle_command(claimscli.command()).await,
-        CliCommand::Ctl(ctlcli) => ctl::handle_command(ctlcli.command()).await,
-        CliCommand::Par(parcli) => par::handle_command(parcli.command()).await,
-        CliCommand::Reg(regcli) => reg::handle_command(regcli.command()).await,
-        CliCommand::Up(upcli) => up::handle_command(upcli.command())
-            .await
-            .map(|_s| "Exiting REPL".to_string()),
+    // The hidden `__complete` generators are parsed separately from `Cli` so they don't
+    // show up in the scripts rendered by `wash completions`
+    let args: Vec<OsString> = std::env::args_os().collect();
+    // Config defaults are loaded before the command line is parsed, so they can be injected as
+    // flag defaults, ranking below both explicit flags and environment variables
+    let config = config::load_config_defaults(&args);
+    let res = if args.get(1).map(OsString::as_os_str) == Some(OsStr::new("__complete")) {
+        let completecli = CompleteCli::from_clap(
+            &config::with_config_defaults(CompleteCli::clap())
+                .get_matches_from(args.iter().skip(1)),
+        );
+        complete::handle_command(completecli.command()).await
+    } else if let Err(e) = config {
+        Err(e)
+    } else {
+        let no_color = util::detect_no_color();
+        util::NO_COLOR_MODE.set(no_color).unwrap();
+        let app = config::with_config_defaults(Cli::clap());
+        let app = if no_color {
+            app.global_setting(AppSettings::ColorNever)
+        } else {
+            app
+        };
+        let cli = Cli::from_clap(&app.get_matches());
+        util::QUIET_MODE.set(cli.quiet).unwrap();
+        util::OFFLINE_MODE
+            .set(cli.offline || util::detect_offline())
+            .unwrap();
+        util::NO_EMOJI_MODE
+            .set(cli.no_emoji || util::detect_no_emoji())
+            .unwrap();
+        // The REPL routes log output to its own log pane
+        if !matches!(cli.command, CliCommand::Up(_)) {
+            util::init_cli_logger(cli.verbose);
+        }
+        match cli.command {
+            CliCommand::Drain(draincmd) => drain::handle_command(draincmd.command()),
+            CliCommand::Keys(keyscli) => keys::handle_command(keyscli.command()),
+            CliCommand::Claims(claimscli) => claims::handle_command(claimscli.command()).await,
+            CliCommand::Ctl(ctlcli) => ctl::handle_command(ctlcli.command()).await,
+            CliCommand::Par(parcli) => par::handle_command(parcli.command()).await,
+            CliCommand::Reg(regcli) => reg::handle_command(regcli.command()).await,
+            CliCommand::Up(upcli) => up::handle_command(upcli.command())
+                .await
+                .map(|_s| "Exiting REPL".to_string()),
+            CliCommand::Completions(completionscli) => {
+                completions::handle_command(completionscli.command(), Cli::clap())
+            }
+        }
     };
 
     std::process::exit(match res {
         Ok(out) => {
-            println!("{}", out);
+            // Commands that stream their result to stdout themselves have nothing to add
+            if !out.is_empty() {
+                println!("{}", out);
+            }
             0
         }
+        // The exit code reflects the class of the failure, see `util::WashError`
+        Err(e) if e.is::<util::Aborted>() || e.is::<util::JsonError>() => {
+            eprintln!("{}", e);
+            util::exit_code(&*e)
+        }
         Err(e) => {
             eprintln!("Error: {}", e);
-            1
+            util::exit_code(&*e)
         }
     })
 }
@@ -1,16 +1,76 @@
 extern crate provider_archive;
+use crate::claims::{revoked_issuers, RevocationOpts};
 use crate::keys::extract_keypair;
-use crate::util::{convert_error, format_output, Output, OutputKind, Result};
-use nkeys::KeyPairType;
+use crate::reg::{strip_oci_scheme, AuthOpts};
+use crate::util::{
+    convert_error, format_error, format_output, quiet_mode, show_progress, start_spinner,
+    write_checksum_file, Output, OutputKind, Result, WashError,
+};
+use chrono::{TimeZone, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use indicatif::HumanBytes;
+use log::debug;
+use nkeys::{KeyPair, KeyPairType};
 use provider_archive::*;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
 use std::io::prelude::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use structopt::clap::AppSettings;
 use structopt::StructOpt;
+use wascap::jwt::{validate_token, CapabilityProvider, Claims};
 
 const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// POSIX TAR archives carry this magic in the header of their first entry
+const TAR_MAGIC: &[u8] = b"ustar";
+const TAR_MAGIC_OFFSET: usize = 257;
+const COSIGNATURE_EXTENSION: &str = ".sigs";
+/// Binary path that reads the library from stdin instead of a file
+const STDIN_PATH: &str = "-";
+/// Destination that writes to stdout instead of a file
+const STDOUT_PATH: &str = "-";
+/// Name of the claims token within a provider archive
+const CLAIMS_JWT_FILE: &str = "claims.jwt";
+const ARCH_METADATA_EXTENSION: &str = ".meta.json";
+/// Architecture tokens accepted in `--arch` targets, as reported by `std::env::consts::ARCH`
+const KNOWN_ARCHES: &[&str] = &[
+    "x86",
+    "x86_64",
+    "arm",
+    "aarch64",
+    "mips",
+    "mips64",
+    "powerpc",
+    "powerpc64",
+    "riscv64",
+    "s390x",
+    "sparc64",
+];
+/// OS tokens accepted in `--arch` targets, as reported by `std::env::consts::OS`
+const KNOWN_OSES: &[&str] = &[
+    "linux",
+    "macos",
+    "windows",
+    "ios",
+    "android",
+    "freebsd",
+    "netbsd",
+    "openbsd",
+    "dragonfly",
+    "solaris",
+    "illumos",
+];
+/// Number of hex characters of a library hash shown in `par inspect` text output
+const SHORT_HASH_LEN: usize = 12;
+
+/// Metadata key/value pairs keyed by architecture target
+type ArchMetadata = BTreeMap<String, BTreeMap<String, String>>;
 
 #[derive(Debug, StructOpt, Clone)]
 #[structopt(
@@ -38,17 +98,38 @@ pub(crate) enum ParCliCommand {
     /// Insert a provider into a provider archive file
     #[structopt(name = "insert")]
     Insert(InsertCommand),
+    /// Verify that a provider archive is signed by enough trusted issuers
+    #[structopt(name = "verify")]
+    Verify(VerifyCommand),
+    /// Extract the provider binary for an architecture target from a provider archive
+    #[structopt(name = "extract")]
+    Extract(ExtractCommand),
+    /// Write the signed claims token of a provider archive to a file
+    #[structopt(name = "export-claims")]
+    ExportClaims(ExportClaimsCommand),
+    /// Remove the provider binary for an architecture target from a provider archive
+    #[structopt(name = "remove")]
+    Remove(RemoveCommand),
+    /// Re-sign a provider archive with new signing keys, keeping its targets and metadata
+    #[structopt(name = "sign")]
+    Sign(SignCommand),
+    /// Compare the metadata and target libraries of two provider archives
+    #[structopt(name = "diff")]
+    Diff(DiffCommand),
+    /// Rewrite a provider archive with sorted targets and a chosen compression level, re-signing it
+    #[structopt(name = "repack")]
+    Repack(RepackCommand),
 }
 
 #[derive(StructOpt, Debug, Clone)]
 pub(crate) struct CreateCommand {
     /// Capability contract ID (e.g. wasmcloud:messaging or wasmcloud:keyvalue).
     #[structopt(short = "c", long = "capid")]
-    capid: String,
+    capid: Option<String>,
 
     /// Vendor string to help identify the publisher of the provider (e.g. Redis, Cassandra, wasmcloud, etc). Not unique.
     #[structopt(short = "v", long = "vendor")]
-    vendor: String,
+    vendor: Option<String>,
 
     /// Monotonically increasing revision number
     #[structopt(short = "r", long = "revision")]
@@ -87,17 +168,35 @@ pub(crate) struct CreateCommand {
 
     /// Name of the capability provider
     #[structopt(short = "n", long = "name")]
-    name: String,
+    name: Option<String>,
 
-    /// Architecture of provider binary in format ARCH-OS (e.g. x86_64-linux)
-    #[structopt(short = "a", long = "arch")]
-    arch: String,
+    /// Architecture of provider binary in format ARCH-OS (e.g. x86_64-linux). Detected from
+    /// the binary's header if omitted
+    #[structopt(short = "a", long = "arch", requires = "binary")]
+    arch: Option<String>,
 
-    /// Path to provider binary for populating the archive
+    /// Path to provider binary for populating the archive, or - to read it from stdin (requires --destination and --arch)
     #[structopt(short = "b", long = "binary")]
-    binary: String,
+    binary: Option<String>,
 
-    /// File output destination path
+    /// Path to a TOML or YAML manifest describing the archive. Flags provided on the command line override values in the manifest
+    #[structopt(long = "manifest")]
+    manifest: Option<PathBuf>,
+
+    /// Metadata describing how an architecture target was built, in the format ARCH-OS:KEY=VALUE (e.g. x86_64-linux:libc=glibc)
+    #[structopt(long = "arch-meta", name = "arch-meta")]
+    arch_meta: Vec<String>,
+
+    /// Accept architecture targets with an unrecognized ARCH or OS token
+    #[structopt(long = "allow-unknown-arch")]
+    allow_unknown_arch: bool,
+
+    /// Fail instead of warning when a binary isn't a native library for its architecture target
+    #[structopt(long = "strict")]
+    strict: bool,
+
+    /// File output destination path. A directory (existing, or ending with a path separator)
+    /// gets the default archive name within it
     #[structopt(long = "destination")]
     destination: Option<String>,
 
@@ -105,17 +204,48 @@ pub(crate) struct CreateCommand {
     #[structopt(long = "compress")]
     compress: bool,
 
+    /// Gzip compression level from 0-9, where 0 stores the archive without compressing it
+    /// for fast local iteration. Implies --compress
+    #[structopt(
+        long = "compression",
+        name = "compression",
+        parse(try_from_str = parse_compression_level)
+    )]
+    compression: Option<u32>,
+
+    /// Additional issuer seed keys (account) to co-sign the archive with.
+    /// Can either be seed value or path to seed file
+    #[structopt(long = "cosigner", name = "cosigners")]
+    cosigners: Vec<String>,
+
     /// Disables autogeneration of signing keys
     #[structopt(long = "disable-keygen")]
     disable_keygen: bool,
 
+    /// Only print the success message, omitting the digest of the written archive
+    #[structopt(long = "quiet")]
+    quiet: bool,
+
+    /// Also write a sha256sum compatible checksum of the archive to <archive>.sha256
+    #[structopt(long = "checksum")]
+    checksum: bool,
+
+    /// Rebuild the archive at the destination if it exists, keeping its metadata, its other
+    /// targets and their architecture metadata while swapping in the given binaries
+    #[structopt(long = "replace", conflicts_with = "force")]
+    replace: bool,
+
+    /// Overwrite an existing archive at the destination
+    #[structopt(long = "force")]
+    force: bool,
+
     #[structopt(flatten)]
     pub(crate) output: Output,
 }
 
 #[derive(StructOpt, Debug, Clone)]
 pub(crate) struct InspectCommand {
-    /// Path to provider archive or OCI URL of provider archive
+    /// Path to provider archive or OCI URL of provider archive (e.g. oci://localhost:5000/provider:0.1.0)
     #[structopt(name = "archive")]
     archive: String,
 
@@ -127,45 +257,291 @@ pub(crate) struct InspectCommand {
     #[structopt(long = "allow-latest")]
     allow_latest: bool,
 
-    /// OCI username, if omitted anonymous authentication will be used
+    /// Local provider binary to compare against the archive's library for --arch, failing
+    /// if they differ
+    #[structopt(long = "verify-binary", name = "verify-binary", requires = "arch")]
+    verify_binary: Option<String>,
+
+    /// Architecture target in format ARCH-OS (e.g. x86_64-linux) of the binary given with
+    /// --verify-binary
+    #[structopt(short = "a", long = "arch", name = "arch", requires = "verify-binary")]
+    arch: Option<String>,
+
+    #[structopt(flatten)]
+    opts: AuthOpts,
+
+    #[structopt(flatten)]
+    revocations: RevocationOpts,
+
+    #[structopt(flatten)]
+    pub(crate) output: Output,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub(crate) struct InsertCommand {
+    /// Path to provider archive
+    #[structopt(name = "archive")]
+    archive: String,
+
+    /// Architecture of binary in format ARCH-OS (e.g. x86_64-linux). Can be repeated,
+    /// each architecture is paired with the binary in the same position. Detected from the
+    /// binaries' headers if omitted
+    #[structopt(short = "a", long = "arch", name = "arch", number_of_values = 1)]
+    arch: Vec<String>,
+
+    /// Path to provider binary to insert into archive. Can be repeated, each binary
+    /// is paired with the architecture in the same position
+    #[structopt(
+        short = "b",
+        long = "binary",
+        name = "binary",
+        required = true,
+        number_of_values = 1
+    )]
+    binary: Vec<String>,
+
+    /// Accept architecture targets with an unrecognized ARCH or OS token
+    #[structopt(long = "allow-unknown-arch")]
+    allow_unknown_arch: bool,
+
+    /// Fail instead of warning when a binary isn't a native library for its architecture target
+    #[structopt(long = "strict")]
+    strict: bool,
+
+    /// Location of key files for signing. Defaults to $WASH_KEYS ($HOME/.wash/keys)
+    #[structopt(
+        short = "d",
+        long = "directory",
+        env = "WASH_KEYS",
+        hide_env_values = true
+    )]
+    directory: Option<String>,
+
+    /// Path to issuer seed key (account). If this flag is not provided, the will be sourced from $WASH_KEYS ($HOME/.wash/keys) or generated for you if it cannot be found.
     #[structopt(
-        short = "u",
-        long = "user",
-        env = "WASH_REG_USER",
+        short = "i",
+        long = "issuer",
+        env = "WASH_ISSUER_KEY",
         hide_env_values = true
     )]
-    user: Option<String>,
+    issuer: Option<String>,
 
-    /// OCI password, if omitted anonymous authentication will be used
+    /// Path to subject seed key (service). If this flag is not provided, the will be sourced from $WASH_KEYS ($HOME/.wash/keys) or generated for you if it cannot be found.
     #[structopt(
-        short = "p",
-        long = "password",
-        env = "WASH_REG_PASSWORD",
+        short = "s",
+        long = "subject",
+        env = "WASH_SUBJECT_KEY",
         hide_env_values = true
     )]
-    password: Option<String>,
+    subject: Option<String>,
+
+    /// Additional issuer seed keys (account) to co-sign the archive with.
+    /// Can either be seed value or path to seed file
+    #[structopt(long = "cosigner", name = "cosigners")]
+    cosigners: Vec<String>,
+
+    /// Disables autogeneration of signing keys
+    #[structopt(long = "disable-keygen")]
+    disable_keygen: bool,
+
+    /// Fail instead of replacing the library of an architecture already in the archive
+    #[structopt(long = "no-clobber")]
+    no_clobber: bool,
+
+    /// Gzip compression level from 0-9 used when rewriting a compressed archive, where 0
+    /// stores the archive without compressing it for fast local iteration
+    #[structopt(
+        long = "compression",
+        name = "compression",
+        parse(try_from_str = parse_compression_level)
+    )]
+    compression: Option<u32>,
 
-    /// Allow insecure (HTTP) registry connections
-    #[structopt(long = "insecure")]
-    insecure: bool,
+    /// Only print the success message, omitting the digest of the written archive
+    #[structopt(long = "quiet")]
+    quiet: bool,
 
     #[structopt(flatten)]
     pub(crate) output: Output,
 }
 
 #[derive(StructOpt, Debug, Clone)]
-pub(crate) struct InsertCommand {
+pub(crate) struct VerifyCommand {
+    /// Path to provider archive
+    #[structopt(name = "archive")]
+    archive: String,
+
+    /// Public key of the issuer (account) expected to have signed the archive's claims
+    #[structopt(long = "issuer")]
+    issuer: Option<String>,
+
+    /// Public key of an issuer (account) trusted to sign the archive
+    #[structopt(short = "t", long = "trusted-issuer", name = "trusted-issuers")]
+    trusted_issuers: Vec<String>,
+
+    /// Number of distinct trusted issuers that must have signed the archive, at least 1. Defaults to all trusted issuers
+    #[structopt(long = "require-signers")]
+    require_signers: Option<usize>,
+
+    /// Path to a list of revoked issuer public keys, one per line. Verification fails if a signer is revoked
+    #[structopt(long = "revocations")]
+    revocations: Option<PathBuf>,
+
+    #[structopt(flatten)]
+    pub(crate) output: Output,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub(crate) struct ExtractCommand {
     /// Path to provider archive
     #[structopt(name = "archive")]
     archive: String,
 
-    /// Architecture of binary in format ARCH-OS (e.g. x86_64-linux)
+    /// Architecture target to extract in format ARCH-OS (e.g. x86_64-linux)
     #[structopt(short = "a", long = "arch")]
     arch: String,
 
-    /// Path to provider binary to insert into archive
-    #[structopt(short = "b", long = "binary")]
-    binary: String,
+    /// File output destination path
+    #[structopt(long = "destination")]
+    destination: String,
+
+    #[structopt(flatten)]
+    pub(crate) output: Output,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub(crate) struct ExportClaimsCommand {
+    /// Path to provider archive
+    #[structopt(name = "archive")]
+    archive: String,
+
+    /// File output destination path, or - to write the claims token to stdout
+    #[structopt(long = "destination")]
+    destination: String,
+
+    #[structopt(flatten)]
+    pub(crate) output: Output,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub(crate) struct DiffCommand {
+    /// Path to the original provider archive
+    #[structopt(name = "archive-a")]
+    archive_a: String,
+
+    /// Path to the provider archive to compare against the original
+    #[structopt(name = "archive-b")]
+    archive_b: String,
+
+    #[structopt(flatten)]
+    pub(crate) output: Output,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub(crate) struct RemoveCommand {
+    /// Path to provider archive
+    #[structopt(name = "archive")]
+    archive: String,
+
+    /// Architecture target to remove in format ARCH-OS (e.g. x86_64-linux)
+    #[structopt(short = "a", long = "arch")]
+    arch: String,
+
+    /// Location of key files for signing. Defaults to $WASH_KEYS ($HOME/.wash/keys)
+    #[structopt(
+        short = "d",
+        long = "directory",
+        env = "WASH_KEYS",
+        hide_env_values = true
+    )]
+    directory: Option<String>,
+
+    /// Path to issuer seed key (account). If this flag is not provided, the will be sourced from $WASH_KEYS ($HOME/.wash/keys) or generated for you if it cannot be found.
+    #[structopt(
+        short = "i",
+        long = "issuer",
+        env = "WASH_ISSUER_KEY",
+        hide_env_values = true
+    )]
+    issuer: Option<String>,
+
+    /// Path to subject seed key (service). If this flag is not provided, the will be sourced from $WASH_KEYS ($HOME/.wash/keys) or generated for you if it cannot be found.
+    #[structopt(
+        short = "s",
+        long = "subject",
+        env = "WASH_SUBJECT_KEY",
+        hide_env_values = true
+    )]
+    subject: Option<String>,
+
+    /// Additional issuer seed keys (account) to co-sign the archive with.
+    /// Can either be seed value or path to seed file
+    #[structopt(long = "cosigner", name = "cosigners")]
+    cosigners: Vec<String>,
+
+    /// Disables autogeneration of signing keys
+    #[structopt(long = "disable-keygen")]
+    disable_keygen: bool,
+
+    #[structopt(flatten)]
+    pub(crate) output: Output,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub(crate) struct SignCommand {
+    /// Path to provider archive
+    #[structopt(name = "archive")]
+    archive: String,
+
+    /// Location of key files for signing. Defaults to $WASH_KEYS ($HOME/.wash/keys)
+    #[structopt(
+        short = "d",
+        long = "directory",
+        env = "WASH_KEYS",
+        hide_env_values = true
+    )]
+    directory: Option<String>,
+
+    /// Path to issuer seed key (account). If this flag is not provided, the will be sourced from $WASH_KEYS ($HOME/.wash/keys) or generated for you if it cannot be found.
+    #[structopt(
+        short = "i",
+        long = "issuer",
+        env = "WASH_ISSUER_KEY",
+        hide_env_values = true
+    )]
+    issuer: Option<String>,
+
+    /// Path to subject seed key (service). If this flag is not provided, the will be sourced from $WASH_KEYS ($HOME/.wash/keys) or generated for you if it cannot be found.
+    #[structopt(
+        short = "s",
+        long = "subject",
+        env = "WASH_SUBJECT_KEY",
+        hide_env_values = true
+    )]
+    subject: Option<String>,
+
+    /// Additional issuer seed keys (account) to co-sign the archive with.
+    /// Can either be seed value or path to seed file
+    #[structopt(long = "cosigner", name = "cosigners")]
+    cosigners: Vec<String>,
+
+    /// Disables autogeneration of signing keys
+    #[structopt(long = "disable-keygen")]
+    disable_keygen: bool,
+
+    #[structopt(flatten)]
+    pub(crate) output: Output,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub(crate) struct RepackCommand {
+    /// Path to provider archive
+    #[structopt(name = "archive")]
+    archive: String,
+
+    /// File path to write the repacked archive to. Defaults to rewriting the archive in place
+    #[structopt(long = "destination")]
+    destination: Option<String>,
 
     /// Location of key files for signing. Defaults to $WASH_KEYS ($HOME/.wash/keys)
     #[structopt(
@@ -194,10 +570,28 @@ pub(crate) struct InsertCommand {
     )]
     subject: Option<String>,
 
+    /// Additional issuer seed keys (account) to co-sign the archive with.
+    /// Can either be seed value or path to seed file
+    #[structopt(long = "cosigner", name = "cosigners")]
+    cosigners: Vec<String>,
+
     /// Disables autogeneration of signing keys
     #[structopt(long = "disable-keygen")]
     disable_keygen: bool,
 
+    /// Gzip compression level from 0-9 to write the archive with. Implies a compressed
+    /// archive, otherwise the archive keeps its current compression
+    #[structopt(
+        long = "compression",
+        name = "compression",
+        parse(try_from_str = parse_compression_level)
+    )]
+    compression: Option<u32>,
+
+    /// Write the archive as a plain, uncompressed TAR
+    #[structopt(long = "uncompressed", conflicts_with = "compression")]
+    uncompressed: bool,
+
     #[structopt(flatten)]
     pub(crate) output: Output,
 }
@@ -207,68 +601,208 @@ pub(crate) async fn handle_command(command: ParCliCommand) -> Result<String> {
         ParCliCommand::Create(cmd) => handle_create(cmd),
         ParCliCommand::Inspect(cmd) => handle_inspect(cmd).await,
         ParCliCommand::Insert(cmd) => handle_insert(cmd),
+        ParCliCommand::Verify(cmd) => handle_verify(cmd),
+        ParCliCommand::Extract(cmd) => handle_extract(cmd),
+        ParCliCommand::ExportClaims(cmd) => handle_export_claims(cmd),
+        ParCliCommand::Remove(cmd) => handle_remove(cmd),
+        ParCliCommand::Sign(cmd) => handle_sign(cmd),
+        ParCliCommand::Diff(cmd) => handle_diff(cmd),
+        ParCliCommand::Repack(cmd) => handle_repack(cmd),
     }
 }
 
-/// Creates a provider archive using an initial architecture target, provider, and signing keys
+/// Creates a provider archive using an initial architecture target, provider, and signing keys.
+/// An existing archive at the destination is rebuilt with `--replace` or overwritten with `--force`
 pub(crate) fn handle_create(cmd: CreateCommand) -> Result<String> {
-    let mut par = ProviderArchive::new(
-        &cmd.capid,
-        &cmd.name,
-        &cmd.vendor,
-        cmd.revision,
-        cmd.version,
+    let manifest = match cmd.manifest {
+        Some(ref path) => load_manifest(path)?,
+        None => ParManifest::default(),
+    };
+    let arch = match (cmd.arch, &cmd.binary) {
+        (None, Some(binary)) => Some(detect_binary_arch(binary)?),
+        (arch, _) => arch,
+    };
+    let targets = create_targets(arch, cmd.binary, manifest.targets)?;
+    for target in targets.iter() {
+        validate_arch(&target.arch, cmd.allow_unknown_arch)?;
+    }
+    // Keys are named after the first binary, the same way they are for a single binary
+    let first_binary = key_source(&targets, cmd.destination.as_deref())?;
+
+    let extension = if cmd.compress || cmd.compression.is_some() {
+        ".par.gz"
+    } else {
+        ".par"
+    };
+    let default_name = format!(
+        "{}{}",
+        PathBuf::from(&first_binary)
+            .file_stem()
+            .unwrap()
+            .to_string_lossy(),
+        extension
     );
+    let outfile = archive_destination(cmd.destination, default_name)?;
+    let exists = Path::new(&outfile).exists();
+    if exists && !cmd.replace && !cmd.force {
+        return Err(WashError::InvalidInput(format!(
+            "Archive {} already exists, use --replace to rebuild it with the new binaries or --force to overwrite it",
+            outfile
+        ))
+        .into());
+    }
+    let previous = if exists && cmd.replace {
+        let buf = std::fs::read(&outfile)?;
+        let par = ProviderArchive::try_load(&buf).map_err(convert_error)?;
+        let metadata = par
+            .claims()
+            .and_then(|c| c.metadata)
+            .ok_or("Provider archive does not contain claims")?;
+        Some((par, metadata, is_compressed(&buf)?))
+    } else {
+        None
+    };
+    let previous_metadata = previous.as_ref().map(|(_, metadata, _)| metadata.clone());
+    let capid = required_field(
+        "capid",
+        cmd.capid,
+        manifest
+            .capid
+            .or_else(|| previous_metadata.as_ref().map(|m| m.capid.clone())),
+    )?;
+    let name = required_field(
+        "name",
+        cmd.name,
+        manifest
+            .name
+            .or_else(|| previous_metadata.as_ref().and_then(|m| m.name.clone())),
+    )?;
+    let vendor = required_field(
+        "vendor",
+        cmd.vendor,
+        manifest
+            .vendor
+            .or_else(|| previous_metadata.as_ref().map(|m| m.vendor.clone())),
+    )?;
 
-    let mut f = File::open(cmd.binary.clone())?;
-    let mut lib = Vec::new();
-    f.read_to_end(&mut lib)?;
+    let mut par = ProviderArchive::new(
+        &capid,
+        &name,
+        &vendor,
+        cmd.revision
+            .or(manifest.revision)
+            .or_else(|| previous_metadata.as_ref().and_then(|m| m.rev)),
+        cmd.version
+            .or(manifest.version)
+            .or_else(|| previous_metadata.as_ref().and_then(|m| m.ver.clone())),
+    );
 
     let issuer = extract_keypair(
         cmd.issuer,
-        Some(cmd.binary.clone()),
+        Some(first_binary.clone()),
         cmd.directory.clone(),
         KeyPairType::Account,
         cmd.disable_keygen,
     )?;
     let subject = extract_keypair(
         cmd.subject,
-        Some(cmd.binary.clone()),
-        cmd.directory,
+        Some(first_binary),
+        cmd.directory.clone(),
         KeyPairType::Service,
         cmd.disable_keygen,
     )?;
 
-    par.add_library(&cmd.arch, &lib).map_err(convert_error)?;
+    let binaries: Vec<&str> = targets.iter().map(|t| t.binary.as_str()).collect();
+    for (target, lib) in targets.iter().zip(read_binaries(&binaries)?) {
+        check_library(&target.arch, &target.binary, &lib, cmd.strict)?;
+        par.add_library(&target.arch, &lib).map_err(convert_error)?;
+    }
+    let mut arches: Vec<String> = targets.iter().map(|t| t.arch.clone()).collect();
+    // The targets that aren't swapped out are carried over from the archive being replaced
+    let mut arch_metadata = ArchMetadata::new();
+    if let Some((previous, _, _)) = &previous {
+        let kept: Vec<String> = previous
+            .targets()
+            .into_iter()
+            .filter(|t| !arches.contains(t))
+            .collect();
+        for target in kept.iter() {
+            par.add_library(target, &target_library(previous, target)?)
+                .map_err(convert_error)?;
+        }
+        arches.extend(kept);
+        arch_metadata = read_arch_metadata(&outfile)?;
+        arch_metadata.retain(|target, _| arches.contains(target));
+    }
+    let arch_refs: Vec<&str> = arches.iter().map(|a| a.as_str()).collect();
+    arch_metadata.extend(parse_arch_metadata(&cmd.arch_meta, &arch_refs)?);
 
-    let extension = if cmd.compress { ".par.gz" } else { ".par" };
-    let outfile = match cmd.destination {
-        Some(path) => path,
-        None => format!(
-            "{}{}",
-            PathBuf::from(cmd.binary.clone())
-                .file_stem()
-                .unwrap()
-                .to_str()
-                .unwrap()
-                .to_string(),
-            extension
-        ),
-    };
+    // A rebuilt archive keeps its compression unless a compression flag is given
+    let compress =
+        cmd.compress || cmd.compression.is_some() || matches!(previous, Some((_, _, true)));
+
+    let cosigners = get_cosigners(&cmd.cosigners, cmd.directory)?;
 
     Ok(
-        if par
-            .write(&outfile, &issuer, &subject, cmd.compress)
-            .is_err()
+        if write_archive(
+            &mut par,
+            &outfile,
+            &issuer,
+            &subject,
+            compress,
+            cmd.compression,
+            &cmd.output.kind,
+        )
+        .is_err()
         {
             format!(
                 "Error writing PAR. Please ensure directory {:?} exists",
                 PathBuf::from(outfile).parent().unwrap(),
             )
         } else {
+            if exists {
+                // Co-signatures and metadata of the previous archive don't apply to the new claims
+                replace_cosignatures(&outfile, &cosigners)?;
+                let stale = arch_metadata_path(&outfile);
+                if arch_metadata.is_empty() && Path::new(&stale).exists() {
+                    std::fs::remove_file(&stale)?;
+                }
+            } else {
+                write_cosignatures(&outfile, &cosigners)?;
+            }
+            write_arch_metadata(&outfile, &arch_metadata)?;
+            let digest = file_digest(&outfile)?;
+            let targets = par.targets().len();
+            let size = std::fs::metadata(&outfile)?.len();
+            let checksum_file = if cmd.checksum {
+                Some(write_checksum_file(&outfile)?)
+            } else {
+                None
+            };
+            let quiet = cmd.quiet || quiet_mode();
+            let mut message = if previous.is_some() {
+                format!("Successfully rebuilt archive {}", outfile)
+            } else {
+                format!("Successfully created archive {}", outfile)
+            };
+            if let Some(checksum_file) = &checksum_file {
+                message.push_str(&format!(", checksum in {}", checksum_file));
+            }
+            let mut message = with_digest(message, &digest, quiet);
+            if !quiet {
+                message.push_str(&format!("\n{}", write_summary(&outfile, targets, size)));
+            }
             format_output(
-                format!("Successfully created archive {}", outfile),
-                json!({"result": "success", "file": outfile}),
+                message,
+                json!({
+                    "result": "success",
+                    "file": outfile,
+                    "digest": digest,
+                    "checksum_file": checksum_file,
+                    "targets": targets,
+                    "size": size,
+                    "replaced": previous.is_some(),
+                }),
                 &cmd.output.kind,
             )
         },
@@ -277,27 +811,77 @@ pub(crate) fn handle_create(cmd: CreateCommand) -> Result<String> {
 
 /// Loads a provider archive and outputs the contents of the claims
 pub(crate) async fn handle_inspect(cmd: InspectCommand) -> Result<String> {
-    let archive = match File::open(&cmd.archive) {
-        Ok(mut f) => {
+    // Anything that isn't an existing file is treated as an OCI reference
+    let local = match strip_oci_scheme(&cmd.archive) {
+        Some(_) => None,
+        None => Some(&cmd.archive).filter(|a| Path::new(a).is_file()),
+    };
+    let (archive, arch_metadata, cosignatures) = match local {
+        Some(path) => {
             let mut buf = Vec::new();
-            f.read_to_end(&mut buf)?;
-            ProviderArchive::try_load(&buf).map_err(|e| format!("{}", e))?
+            File::open(path)?.read_to_end(&mut buf)?;
+            (
+                ProviderArchive::try_load(&buf).map_err(|e| format!("{}", e))?,
+                read_arch_metadata(path)?,
+                read_cosignatures(path)?,
+            )
         }
-        Err(_) => {
+        None => {
+            let url = strip_oci_scheme(&cmd.archive).unwrap_or(&cmd.archive);
+            debug!("{} is not a local file, pulling it from a registry", url);
             let artifact = crate::reg::pull_artifact(
-                cmd.archive,
-                cmd.digest,
+                url.to_string(),
+                cmd.digest.clone(),
                 cmd.allow_latest,
-                cmd.user,
-                cmd.password,
-                cmd.insecure,
+                cmd.opts.user.clone(),
+                cmd.opts.password()?,
+                cmd.opts.insecure,
+                cmd.opts.proxy.clone(),
             )
             .await?;
-            ProviderArchive::try_load(&artifact).map_err(|e| format!("{}", e))?
+            (
+                ProviderArchive::try_load(&artifact).map_err(|e| format!("{}", e))?,
+                ArchMetadata::new(),
+                vec![],
+            )
         }
     };
     let claims = archive.claims().unwrap();
+    let libraries = target_libraries(&archive);
+    let verified_binary = match (&cmd.verify_binary, &cmd.arch) {
+        (Some(binary), Some(arch)) => {
+            let bytes = std::fs::read(binary)
+                .map_err(|e| format!("Unable to read binary {}: {}", binary, e))?;
+            let library = verify_binary(&libraries, arch, binary, &bytes)
+                .map_err(|e| format_error(e, &cmd.output.kind))?;
+            Some(json!({"binary": binary, "arch": arch, "sha256": library.sha256}))
+        }
+        _ => None,
+    };
+    let mut inconsistencies = target_hash_mismatches(
+        &claims
+            .metadata
+            .as_ref()
+            .map(|m| m.target_hashes.clone())
+            .unwrap_or_default(),
+        &libraries,
+    );
+    inconsistencies.extend(
+        cosignatures
+            .iter()
+            .filter_map(|jwt| cosignature_inconsistency(jwt, &claims)),
+    );
+    let cosigners: Vec<String> = cosignatures
+        .iter()
+        .filter_map(|jwt| Claims::<CapabilityProvider>::decode(jwt).ok())
+        .map(|cosigned| cosigned.issuer)
+        .collect();
+    let mut signers = vec![claims.issuer.as_str()];
+    signers.extend(cosigners.iter().map(String::as_str));
+    let revoked = cmd.revocations.revoked(&signers)?;
     let metadata = claims.metadata.unwrap();
+    let not_before = claims.not_before.map(format_timestamp);
+    let expires = claims.expires.map(format_timestamp);
 
     let output = match cmd.output.kind {
         OutputKind::Json => {
@@ -316,7 +900,15 @@ pub(crate) async fn handle_inspect(cmd: InspectCommand) -> Result<String> {
                     "vendor": metadata.vendor,
                     "ver": friendly_ver,
                     "rev": friendly_rev,
-                    "targets": archive.targets()})
+                    "not_before": not_before,
+                    "expires": expires,
+                    "targets": archive.targets(),
+                    "libraries": libraries,
+                    "arch_metadata": arch_metadata,
+                    "consistent": inconsistencies.is_empty(),
+                    "inconsistencies": inconsistencies,
+                    "verified_binary": verified_binary,
+                    "revoked": revoked})
             )
         }
         OutputKind::Text { max_width } => {
@@ -364,6 +956,20 @@ pub(crate) async fn handle_inspect(cmd: InspectCommand) -> Result<String> {
                 ]));
             }
 
+            if let Some(not_before) = not_before {
+                table.add_row(Row::new(vec![
+                    TableCell::new("Not Before"),
+                    TableCell::new_with_alignment(not_before, 1, Alignment::Right),
+                ]));
+            }
+
+            if let Some(expires) = expires {
+                table.add_row(Row::new(vec![
+                    TableCell::new("Expires"),
+                    TableCell::new_with_alignment(expires, 1, Alignment::Right),
+                ]));
+            }
+
             table.add_row(Row::new(vec![TableCell::new_with_alignment(
                 "Supported Architecture Targets",
                 2,
@@ -371,18 +977,186 @@ pub(crate) async fn handle_inspect(cmd: InspectCommand) -> Result<String> {
             )]));
 
             table.add_row(Row::new(vec![TableCell::new_with_alignment(
-                archive.targets().join("\n"),
+                archive
+                    .targets()
+                    .iter()
+                    .map(|t| describe_target(t, &arch_metadata))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
                 2,
                 Alignment::Left,
             )]));
 
-            table.render()
-        }
-    };
+            table.add_row(Row::new(vec![TableCell::new_with_alignment(
+                "Target Libraries",
+                2,
+                Alignment::Center,
+            )]));
+            for library in libraries {
+                table.add_row(Row::new(vec![
+                    TableCell::new(library.target),
+                    TableCell::new_with_alignment(
+                        format!(
+                            "{} bytes, sha256:{}",
+                            library.size,
+                            &library.sha256[..SHORT_HASH_LEN]
+                        ),
+                        1,
+                        Alignment::Right,
+                    ),
+                ]));
+            }
+
+            let rendered = match verified_binary {
+                Some(verified) => format!(
+                    "Binary {} matches the {} library of the archive\n{}",
+                    verified["binary"].as_str().unwrap_or_default(),
+                    verified["arch"].as_str().unwrap_or_default(),
+                    table.render()
+                ),
+                None => table.render(),
+            };
+            let rendered = if inconsistencies.is_empty() {
+                rendered
+            } else {
+                format!(
+                    "WARNING: the archive's signatures are inconsistent, it may have been tampered with or assembled incorrectly\n - {}\n{}",
+                    inconsistencies.join("\n - "),
+                    rendered
+                )
+            };
+            if revoked.is_empty() {
+                rendered
+            } else {
+                format!(
+                    "REVOKED: the archive is signed by revoked issuer(s) {}\n{}",
+                    revoked.join(", "),
+                    rendered
+                )
+            }
+        }
+    };
 
     Ok(output)
 }
 
+/// Compares the libraries of an archive against the target hashes signed in its claims,
+/// describing each library that isn't covered by the claims and each claimed target that is
+/// missing from the archive
+fn target_hash_mismatches(
+    hashes: &HashMap<String, String>,
+    libraries: &[TargetLibrary],
+) -> Vec<String> {
+    let mut mismatches = vec![];
+    for library in libraries {
+        match hashes.get(&library.target) {
+            None => mismatches.push(format!(
+                "Target {} is not covered by the archive's claims",
+                library.target
+            )),
+            Some(hash) if !hash.eq_ignore_ascii_case(&library.sha256) => mismatches.push(format!(
+                "Target {} does not match the hash signed in the archive's claims",
+                library.target
+            )),
+            Some(_) => (),
+        }
+    }
+    let mut claimed: Vec<&String> = hashes
+        .keys()
+        .filter(|target| !libraries.iter().any(|l| &l.target == *target))
+        .collect();
+    claimed.sort();
+    for target in claimed {
+        mismatches.push(format!(
+            "Claims cover target {}, which is missing from the archive",
+            target
+        ));
+    }
+    mismatches
+}
+
+/// Describes how a co-signature differs from the archive's claims, if it signs another
+/// subject or other targets
+fn cosignature_inconsistency(jwt: &str, claims: &Claims<CapabilityProvider>) -> Option<String> {
+    let cosigned = match Claims::<CapabilityProvider>::decode(jwt) {
+        Ok(cosigned) => cosigned,
+        Err(e) => return Some(format!("Co-signature could not be decoded: {}", e)),
+    };
+    let target_hashes =
+        |c: &Claims<CapabilityProvider>| c.metadata.clone().map(|m| m.target_hashes);
+    if cosigned.subject != claims.subject {
+        Some(format!(
+            "Co-signature by {} is for subject {}, not {}",
+            cosigned.issuer, cosigned.subject, claims.subject
+        ))
+    } else if target_hashes(&cosigned) != target_hashes(claims) {
+        Some(format!(
+            "Co-signature by {} covers different targets than the archive's claims",
+            cosigned.issuer
+        ))
+    } else {
+        None
+    }
+}
+
+/// Formats a JWT timestamp (seconds since the epoch) as a human readable UTC time
+fn format_timestamp(secs: u64) -> String {
+    match Utc.timestamp_opt(secs as i64, 0).single() {
+        Some(time) => time.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+        None => format!("{} (invalid timestamp)", secs),
+    }
+}
+
+/// Size and hash of the library embedded for a single architecture target
+#[derive(Debug, Serialize)]
+struct TargetLibrary {
+    target: String,
+    size: usize,
+    sha256: String,
+}
+
+fn target_libraries(archive: &ProviderArchive) -> Vec<TargetLibrary> {
+    archive
+        .targets()
+        .into_iter()
+        .map(|target| {
+            let bytes = archive.target_bytes(&target).unwrap_or_default();
+            TargetLibrary {
+                size: bytes.len(),
+                sha256: format!("{:x}", Sha256::digest(&bytes)),
+                target,
+            }
+        })
+        .collect()
+}
+
+/// Checks that a local binary is identical to the library of an architecture target in an
+/// archive, returning that library
+fn verify_binary<'a>(
+    libraries: &'a [TargetLibrary],
+    arch: &str,
+    binary: &str,
+    bytes: &[u8],
+) -> Result<&'a TargetLibrary> {
+    let library = libraries.iter().find(|l| l.target == arch).ok_or_else(|| {
+        let mut targets: Vec<&str> = libraries.iter().map(|l| l.target.as_str()).collect();
+        targets.sort_unstable();
+        WashError::NotFound(format!(
+            "Architecture {} not found in archive, available targets: {}",
+            arch,
+            targets.join(", ")
+        ))
+    })?;
+    let sha256 = format!("{:x}", Sha256::digest(bytes));
+    if sha256 != library.sha256 {
+        return Err(Box::new(WashError::Validation(format!(
+            "Binary {} does not match the {} library of the archive: sha256:{} locally, sha256:{} in the archive",
+            binary, arch, sha256, library.sha256
+        ))));
+    }
+    Ok(library)
+}
+
 /// Loads a provider archive and attempts to insert an additional provider into it
 pub(crate) fn handle_insert(cmd: InsertCommand) -> Result<String> {
     let mut buf = Vec::new();
@@ -390,143 +1164,2500 @@ pub(crate) fn handle_insert(cmd: InsertCommand) -> Result<String> {
     f.read_to_end(&mut buf)?;
 
     let mut par = ProviderArchive::try_load(&buf).map_err(convert_error)?;
+    let compressed = is_compressed(&buf)?;
+    if cmd.compression.is_some() && !compressed {
+        return Err(format!(
+            "Archive {} is not compressed, --compression can only be used with compressed archives",
+            cmd.archive
+        )
+        .into());
+    }
+
+    let arches = if cmd.arch.is_empty() {
+        cmd.binary
+            .iter()
+            .map(|binary| detect_binary_arch(binary))
+            .collect::<Result<Vec<_>>>()?
+    } else {
+        cmd.arch
+    };
+    if arches.len() != cmd.binary.len() {
+        return Err(format!(
+            "Received {} --arch and {} --binary values, each architecture must be paired with a binary",
+            arches.len(),
+            cmd.binary.len()
+        )
+        .into());
+    }
+    for arch in arches.iter() {
+        validate_arch(arch, cmd.allow_unknown_arch)?;
+    }
+    let existing = par.targets();
+    let clobbered = clobbered_targets(&existing, &arches);
+    if !clobbered.is_empty() {
+        let message = format!(
+            "archive {} already contains {} (existing targets: {})",
+            cmd.archive,
+            clobbered.join(", "),
+            existing.join(", ")
+        );
+        if cmd.no_clobber {
+            return Err(format!("Refusing to replace libraries, {}", message).into());
+        }
+        eprintln!("Warning: replacing existing libraries, {}", message);
+    }
 
     let issuer = extract_keypair(
         cmd.issuer,
-        Some(cmd.binary.clone()),
+        Some(cmd.binary[0].clone()),
         cmd.directory.clone(),
         KeyPairType::Account,
         cmd.disable_keygen,
     )?;
     let subject = extract_keypair(
         cmd.subject,
-        Some(cmd.binary.clone()),
-        cmd.directory,
+        Some(cmd.binary[0].clone()),
+        cmd.directory.clone(),
         KeyPairType::Service,
         cmd.disable_keygen,
     )?;
 
-    let mut f = File::open(cmd.binary.clone())?;
-    let mut lib = Vec::new();
-    f.read_to_end(&mut lib)?;
+    let binaries: Vec<&str> = cmd.binary.iter().map(|b| b.as_str()).collect();
+    for ((arch, binary), lib) in arches
+        .iter()
+        .zip(cmd.binary.iter())
+        .zip(read_binaries(&binaries)?)
+    {
+        check_library(arch, binary, &lib, cmd.strict)?;
+        par.add_library(arch, &lib).map_err(convert_error)?;
+    }
+
+    let cosigners = get_cosigners(&cmd.cosigners, cmd.directory)?;
+
+    write_archive(
+        &mut par,
+        &cmd.archive,
+        &issuer,
+        &subject,
+        compressed,
+        cmd.compression,
+        &cmd.output.kind,
+    )?;
+    write_cosignatures(&cmd.archive, &cosigners)?;
+    let digest = file_digest(&cmd.archive)?;
+    let targets = par.targets().len();
+    let size = std::fs::metadata(&cmd.archive)?.len();
+
+    let quiet = cmd.quiet || quiet_mode();
+    let message = format!(
+        "Successfully inserted {} into archive {}",
+        cmd.binary.join(", "),
+        cmd.archive
+    );
+    let mut message = with_digest(message, &digest, quiet);
+    if !quiet {
+        message.push_str(&format!("\n{}", write_summary(&cmd.archive, targets, size)));
+    }
+    Ok(format_output(
+        message,
+        json!({
+            "result": "success",
+            "file": cmd.archive,
+            "digest": digest,
+            "targets": targets,
+            "size": size,
+        }),
+        &cmd.output.kind,
+    ))
+}
+
+/// Verifies the signatures of a provider archive and its co-signatures against a set of trusted issuers
+pub(crate) fn handle_verify(cmd: VerifyCommand) -> Result<String> {
+    if cmd.issuer.is_none() && cmd.trusted_issuers.is_empty() {
+        return Err("An expected issuer or at least one trusted issuer must be provided".into());
+    }
+    // Requiring no signers would accept an archive that no trusted issuer signed
+    if cmd.require_signers == Some(0) {
+        return Err(
+            WashError::InvalidInput("--require-signers must be at least 1".to_string()).into(),
+        );
+    }
+    let mut buf = Vec::new();
+    let mut f = File::open(cmd.archive.clone())?;
+    f.read_to_end(&mut buf)?;
+    let par = ProviderArchive::try_load(&buf).map_err(convert_error)?;
+    let claims = par
+        .claims()
+        .ok_or("Provider archive does not contain claims")?;
+
+    validate_archive_signature(&archive_claims_token(&buf)?).map_err(verification_failed)?;
+    if let Some(ref expected) = cmd.issuer {
+        check_issuer(&claims.issuer, expected).map_err(verification_failed)?;
+    }
+    revoked_issuers(cmd.revocations.as_deref(), &[&claims.issuer], true)?;
+    if cmd.trusted_issuers.is_empty() {
+        return Ok(format_output(
+            format!(
+                "Archive {} has a valid signature from issuer {}",
+                cmd.archive, claims.issuer
+            ),
+            json!({"result": "success", "file": cmd.archive, "issuer": claims.issuer}),
+            &cmd.output.kind,
+        ));
+    }
 
-    par.add_library(&cmd.arch, &lib).map_err(convert_error)?;
+    let mut signers = vec![claims.issuer.clone()];
+    for jwt in read_cosignatures(&cmd.archive)? {
+        signers.push(validate_cosignature(&jwt, &claims).map_err(verification_failed)?);
+    }
+    revoked_issuers(
+        cmd.revocations.as_deref(),
+        &signers.iter().map(String::as_str).collect::<Vec<_>>(),
+        true,
+    )?;
 
-    par.write(&cmd.archive, &issuer, &subject, is_compressed(&buf)?)
-        .map_err(convert_error)?;
+    let required = cmd.require_signers.unwrap_or(cmd.trusted_issuers.len());
+    let trusted =
+        trusted_signers(&signers, &cmd.trusted_issuers, required).map_err(verification_failed)?;
 
     Ok(format_output(
         format!(
-            "Successfully inserted {} into archive {}",
-            cmd.binary, cmd.archive
+            "Archive {} is signed by {} trusted issuer(s), {} required",
+            cmd.archive,
+            trusted.len(),
+            required
         ),
-        json!({"result": "success", "file": cmd.archive}),
+        json!({"result": "success", "file": cmd.archive, "signers": trusted, "required": required}),
         &cmd.output.kind,
     ))
 }
 
-/// Inspects the byte slice for a GZIP header, and returns true if the file is compressed
-fn is_compressed(input: &[u8]) -> Result<bool> {
-    if input.len() < 2 {
-        return Err("Not enough bytes to be a valid PAR file".into());
+/// Marks an error of `par verify` as a failed verification, for its exit code
+fn verification_failed(e: Box<dyn std::error::Error>) -> Box<dyn std::error::Error> {
+    Box::new(WashError::Validation(e.to_string()))
+}
+
+/// Writes the provider binary for a single architecture target of an archive to a file
+pub(crate) fn handle_extract(cmd: ExtractCommand) -> Result<String> {
+    let mut buf = Vec::new();
+    let mut f = File::open(cmd.archive.clone())?;
+    f.read_to_end(&mut buf)?;
+    let par = ProviderArchive::try_load(&buf).map_err(convert_error)?;
+
+    let lib = target_library(&par, &cmd.arch)?;
+    let mut f = File::create(&cmd.destination)?;
+    f.write_all(&lib)?;
+
+    Ok(format_output(
+        format!(
+            "Successfully extracted {} from archive {} to {}",
+            cmd.arch, cmd.archive, cmd.destination
+        ),
+        json!({"result": "success", "file": cmd.destination, "arch": cmd.arch, "size": lib.len()}),
+        &cmd.output.kind,
+    ))
+}
+
+/// Writes the claims token of an archive, without its binaries, to a file or to stdout
+pub(crate) fn handle_export_claims(cmd: ExportClaimsCommand) -> Result<String> {
+    let buf = std::fs::read(&cmd.archive)?;
+    let jwt = archive_claims_token(&buf)?;
+
+    if cmd.destination == STDOUT_PATH {
+        return Ok(format_output(
+            jwt.clone(),
+            json!({"result": "success", "jwt": jwt}),
+            &cmd.output.kind,
+        ));
     }
-    Ok(input[0..2] == GZIP_MAGIC)
+    std::fs::write(&cmd.destination, &jwt)?;
+    Ok(format_output(
+        format!(
+            "Successfully exported claims from archive {} to {}",
+            cmd.archive, cmd.destination
+        ),
+        json!({"result": "success", "file": cmd.destination}),
+        &cmd.output.kind,
+    ))
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
+/// Returns the library for an architecture target, listing the available targets if it's missing
+/// Returns the architectures being inserted that already have a library in the archive
+fn clobbered_targets(existing: &[String], arches: &[String]) -> Vec<String> {
+    let mut clobbered: Vec<String> = arches
+        .iter()
+        .filter(|a| existing.contains(a))
+        .cloned()
+        .collect();
+    clobbered.dedup();
+    clobbered
+}
 
-    // Uses all flags and options of the `par create` command
-    // to ensure API does not change between versions
-    #[test]
-    fn test_par_create_comprehensive() {
-        const ISSUER: &str = "SAAJLQZDZO57THPTIIEELEY7FJYOJZQWQD7FF4J67TUYTSCOXTF7R4Y3VY";
-        const SUBJECT: &str = "SVAH7IN6QE6XODCGIIWZQDZ5LNSSS4FNEO6SNHZSSASW4BBBKSZ6KWTKWY";
-        let create_long = ParCli::from_iter_safe(&[
-            "par",
-            "create",
-            "--arch",
-            "x86_64-testrunner",
-            "--binary",
-            "./testrunner.so",
-            "--capid",
-            "wasmcloud:test",
-            "--name",
-            "CreateTest",
-            "--vendor",
-            "TestRunner",
-            "--destination",
-            "./test.par.gz",
-            "--revision",
-            "1",
-            "--version",
-            "1.11.111",
-            "--directory",
-            "./tests/fixtures",
-            "--issuer",
-            ISSUER,
-            "--subject",
-            SUBJECT,
-            "--output",
-            "text",
-            "--disable-keygen",
-            "--compress",
+/// Rebuilds a provider archive without the library for one architecture target, signing
+/// it again since the claims hold a hash of every target
+pub(crate) fn handle_remove(cmd: RemoveCommand) -> Result<String> {
+    let mut buf = Vec::new();
+    let mut f = File::open(cmd.archive.clone())?;
+    f.read_to_end(&mut buf)?;
+    let par = ProviderArchive::try_load(&buf).map_err(convert_error)?;
+
+    target_library(&par, &cmd.arch)?;
+    let remaining = remaining_targets(par.targets(), &cmd.arch)?;
+    let mut rebuilt = rebuild_archive(&par, &remaining)?;
+
+    let issuer = extract_keypair(
+        cmd.issuer,
+        Some(cmd.archive.clone()),
+        cmd.directory.clone(),
+        KeyPairType::Account,
+        cmd.disable_keygen,
+    )?;
+    let subject = extract_keypair(
+        cmd.subject,
+        Some(cmd.archive.clone()),
+        cmd.directory.clone(),
+        KeyPairType::Service,
+        cmd.disable_keygen,
+    )?;
+    let cosigners = get_cosigners(&cmd.cosigners, cmd.directory)?;
+
+    write_archive(
+        &mut rebuilt,
+        &cmd.archive,
+        &issuer,
+        &subject,
+        is_compressed(&buf)?,
+        None,
+        &cmd.output.kind,
+    )?;
+    write_cosignatures(&cmd.archive, &cosigners)?;
+
+    let mut arch_metadata = read_arch_metadata(&cmd.archive)?;
+    if arch_metadata.remove(&cmd.arch).is_some() {
+        if arch_metadata.is_empty() {
+            std::fs::remove_file(arch_metadata_path(&cmd.archive))?;
+        } else {
+            write_arch_metadata(&cmd.archive, &arch_metadata)?;
+        }
+    }
+
+    Ok(format_output(
+        format!(
+            "Successfully removed {} from archive {}, remaining targets: {}",
+            cmd.arch,
+            cmd.archive,
+            remaining.join(", ")
+        ),
+        json!({"result": "success", "file": cmd.archive, "arch": cmd.arch, "targets": remaining}),
+        &cmd.output.kind,
+    ))
+}
+
+/// Re-signs every target of an archive with the provided keys, leaving its metadata and
+/// libraries unchanged. Cosignatures of the previous claims are replaced by new ones from
+/// the provided cosigners, or removed if there are none
+pub(crate) fn handle_sign(cmd: SignCommand) -> Result<String> {
+    let buf = std::fs::read(&cmd.archive)?;
+    let par = ProviderArchive::try_load(&buf).map_err(convert_error)?;
+    let mut targets = par.targets();
+    targets.sort();
+    let mut rebuilt = rebuild_archive(&par, &targets)?;
+
+    let issuer = extract_keypair(
+        cmd.issuer,
+        Some(cmd.archive.clone()),
+        cmd.directory.clone(),
+        KeyPairType::Account,
+        cmd.disable_keygen,
+    )?;
+    let subject = extract_keypair(
+        cmd.subject,
+        Some(cmd.archive.clone()),
+        cmd.directory.clone(),
+        KeyPairType::Service,
+        cmd.disable_keygen,
+    )?;
+    let cosigners = get_cosigners(&cmd.cosigners, cmd.directory)?;
+
+    write_archive(
+        &mut rebuilt,
+        &cmd.archive,
+        &issuer,
+        &subject,
+        is_compressed(&buf)?,
+        None,
+        &cmd.output.kind,
+    )?;
+    replace_cosignatures(&cmd.archive, &cosigners)?;
+
+    Ok(format_output(
+        format!(
+            "Successfully re-signed archive {} with issuer {} and subject {}",
+            cmd.archive,
+            issuer.public_key(),
+            subject.public_key()
+        ),
+        json!({
+            "result": "success",
+            "file": cmd.archive,
+            "issuer": issuer.public_key(),
+            "subject": subject.public_key(),
+            "targets": targets,
+        }),
+        &cmd.output.kind,
+    ))
+}
+
+/// Rewrites an archive with the selected compression. The rewritten archive is signed with the
+/// provided keys, since its claims are issued anew, so repacking the same contents twice doesn't
+/// produce identical bytes
+pub(crate) fn handle_repack(cmd: RepackCommand) -> Result<String> {
+    let destination = cmd
+        .destination
+        .clone()
+        .unwrap_or_else(|| cmd.archive.clone());
+    let buf = std::fs::read(&cmd.archive)?;
+    let par = ProviderArchive::try_load(&buf).map_err(convert_error)?;
+    let mut targets = par.targets();
+    targets.sort();
+    let mut rebuilt = rebuild_archive(&par, &targets)?;
+
+    let issuer = extract_keypair(
+        cmd.issuer,
+        Some(cmd.archive.clone()),
+        cmd.directory.clone(),
+        KeyPairType::Account,
+        cmd.disable_keygen,
+    )?;
+    let subject = extract_keypair(
+        cmd.subject,
+        Some(cmd.archive.clone()),
+        cmd.directory.clone(),
+        KeyPairType::Service,
+        cmd.disable_keygen,
+    )?;
+    let cosigners = get_cosigners(&cmd.cosigners, cmd.directory)?;
+
+    let compress = !cmd.uncompressed && (cmd.compression.is_some() || is_compressed(&buf)?);
+    write_archive(
+        &mut rebuilt,
+        &destination,
+        &issuer,
+        &subject,
+        compress,
+        cmd.compression,
+        &cmd.output.kind,
+    )?;
+    replace_cosignatures(&destination, &cosigners)?;
+
+    let size_before = buf.len() as u64;
+    let size_after = std::fs::metadata(&destination)?.len();
+    let digest = file_digest(&destination)?;
+    Ok(format_output(
+        with_digest(
+            format!(
+                "Successfully repacked archive {} to {} ({})",
+                cmd.archive,
+                destination,
+                size_change(size_before, size_after)
+            ),
+            &digest,
+            quiet_mode(),
+        ),
+        json!({
+            "result": "success",
+            "file": destination,
+            "source": cmd.archive,
+            "compressed": compress,
+            "size_before": size_before,
+            "size_after": size_after,
+            "digest": digest,
+            "issuer": issuer.public_key(),
+            "subject": subject.public_key(),
+            "targets": targets,
+        }),
+        &cmd.output.kind,
+    ))
+}
+
+/// Describes a change in file size, e.g. `2048 -> 1024 bytes (-50.0%)`
+fn size_change(before: u64, after: u64) -> String {
+    if before == 0 {
+        return format!("{} -> {} bytes", before, after);
+    }
+    let percent = (after as f64 - before as f64) / before as f64 * 100.0;
+    format!("{} -> {} bytes ({:+.1}%)", before, after, percent)
+}
+
+/// Metadata and library hashes of an archive, the parts of it compared by `par diff`
+#[derive(Debug, Default)]
+struct ArchiveSummary {
+    fields: Vec<(&'static str, Option<String>)>,
+    /// SHA256 of each target's library, by target
+    libraries: BTreeMap<String, String>,
+}
+
+impl ArchiveSummary {
+    fn new(par: &ProviderArchive) -> Result<ArchiveSummary> {
+        let metadata = par
+            .claims()
+            .and_then(|c| c.metadata)
+            .ok_or("Provider archive does not contain claims")?;
+        Ok(ArchiveSummary {
+            fields: vec![
+                ("name", metadata.name),
+                ("capability_contract_id", Some(metadata.capid)),
+                ("vendor", Some(metadata.vendor)),
+                ("rev", metadata.rev.map(|r| r.to_string())),
+                ("ver", metadata.ver),
+            ],
+            libraries: target_libraries(par)
+                .into_iter()
+                .map(|l| (l.target, l.sha256))
+                .collect(),
+        })
+    }
+}
+
+/// A value that differs between two archives. `None` if it's unset in that archive
+#[derive(Debug, PartialEq, Serialize)]
+struct Change {
+    a: Option<String>,
+    b: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+struct ArchiveDiff {
+    /// Changed metadata fields, by field name
+    fields: BTreeMap<String, Change>,
+    /// Targets only in the second archive
+    added: Vec<String>,
+    /// Targets only in the first archive
+    removed: Vec<String>,
+    /// Library hashes of targets present in both archives with different libraries
+    changed: BTreeMap<String, Change>,
+}
+
+impl ArchiveDiff {
+    fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+    }
+}
+
+fn diff_archives(a: &ArchiveSummary, b: &ArchiveSummary) -> ArchiveDiff {
+    let mut diff = ArchiveDiff::default();
+    for ((field, value_a), (_, value_b)) in a.fields.iter().zip(b.fields.iter()) {
+        if value_a != value_b {
+            diff.fields.insert(
+                field.to_string(),
+                Change {
+                    a: value_a.clone(),
+                    b: value_b.clone(),
+                },
+            );
+        }
+    }
+    for (target, hash_a) in a.libraries.iter() {
+        match b.libraries.get(target) {
+            None => diff.removed.push(target.clone()),
+            Some(hash_b) if hash_b != hash_a => {
+                diff.changed.insert(
+                    target.clone(),
+                    Change {
+                        a: Some(hash_a.clone()),
+                        b: Some(hash_b.clone()),
+                    },
+                );
+            }
+            Some(_) => (),
+        }
+    }
+    diff.added = b
+        .libraries
+        .keys()
+        .filter(|target| !a.libraries.contains_key(*target))
+        .cloned()
+        .collect();
+    diff
+}
+
+/// Lists the differences between two archives, one per line
+fn describe_diff(diff: &ArchiveDiff) -> Vec<String> {
+    let value = |v: &Option<String>| v.clone().unwrap_or_else(|| "None".to_string());
+    let short = |h: &Option<String>| {
+        let hash = h.as_deref().unwrap_or_default();
+        format!("sha256:{}", &hash[..SHORT_HASH_LEN.min(hash.len())])
+    };
+    let mut lines: Vec<String> = diff
+        .fields
+        .iter()
+        .map(|(field, change)| format!("{}: {} -> {}", field, value(&change.a), value(&change.b)))
+        .collect();
+    lines.extend(diff.added.iter().map(|t| format!("+ target {}", t)));
+    lines.extend(diff.removed.iter().map(|t| format!("- target {}", t)));
+    lines.extend(diff.changed.iter().map(|(target, change)| {
+        format!(
+            "~ target {}: {} -> {}",
+            target,
+            short(&change.a),
+            short(&change.b)
+        )
+    }));
+    lines
+}
+
+/// Compares the metadata, targets and library hashes of two archives
+pub(crate) fn handle_diff(cmd: DiffCommand) -> Result<String> {
+    let load = |path: &str| -> Result<ArchiveSummary> {
+        let buf =
+            std::fs::read(path).map_err(|e| format!("Unable to read archive {}: {}", path, e))?;
+        ArchiveSummary::new(&ProviderArchive::try_load(&buf).map_err(convert_error)?)
+    };
+    let diff = diff_archives(&load(&cmd.archive_a)?, &load(&cmd.archive_b)?);
+    let text = if diff.is_empty() {
+        format!(
+            "No differences between {} and {}",
+            cmd.archive_a, cmd.archive_b
+        )
+    } else {
+        format!(
+            "Differences between {} and {}:\n{}",
+            cmd.archive_a,
+            cmd.archive_b,
+            describe_diff(&diff).join("\n")
+        )
+    };
+    Ok(format_output(
+        text,
+        json!({
+            "a": cmd.archive_a,
+            "b": cmd.archive_b,
+            "identical": diff.is_empty(),
+            "diff": diff,
+        }),
+        &cmd.output.kind,
+    ))
+}
+
+/// Builds an unsigned copy of an archive's metadata holding the libraries of `targets`
+fn rebuild_archive(par: &ProviderArchive, targets: &[String]) -> Result<ProviderArchive> {
+    let metadata = par
+        .claims()
+        .and_then(|c| c.metadata)
+        .ok_or("Provider archive does not contain claims")?;
+    let mut rebuilt = ProviderArchive::new(
+        &metadata.capid,
+        &metadata.name.unwrap_or_default(),
+        &metadata.vendor,
+        metadata.rev,
+        metadata.ver,
+    );
+    for target in targets.iter() {
+        rebuilt
+            .add_library(target, &target_library(par, target)?)
+            .map_err(convert_error)?;
+    }
+    Ok(rebuilt)
+}
+
+/// Returns the targets left after removing `arch`, refusing to leave an archive without any
+fn remaining_targets(mut targets: Vec<String>, arch: &str) -> Result<Vec<String>> {
+    targets.retain(|t| t != arch);
+    targets.sort();
+    if targets.is_empty() {
+        return Err(format!(
+            "Refusing to remove {}, it is the only target in the archive",
+            arch
+        )
+        .into());
+    }
+    Ok(targets)
+}
+
+fn target_library(par: &ProviderArchive, arch: &str) -> Result<Vec<u8>> {
+    par.target_bytes(arch).ok_or_else(|| {
+        let mut targets = par.targets();
+        targets.sort();
+        format!(
+            "Architecture {} not found in archive, available targets: {}",
+            arch,
+            targets.join(", ")
+        )
+        .into()
+    })
+}
+
+fn get_cosigners(keys: &[String], directory: Option<String>) -> Result<Vec<KeyPair>> {
+    keys.iter()
+        .map(|k| {
+            extract_keypair(
+                Some(k.to_string()),
+                None,
+                directory.clone(),
+                KeyPairType::Account,
+                true,
+            )
+        })
+        .collect()
+}
+
+/// Path of the file holding the co-signatures of an archive, one JWT per line
+fn cosignature_path(archive: &str) -> String {
+    format!("{}{}", archive, COSIGNATURE_EXTENSION)
+}
+
+/// Replaces the cosignatures of a rewritten archive, whose previous cosignatures no longer
+/// match its new claims
+fn replace_cosignatures(archive: &str, cosigners: &[KeyPair]) -> Result<()> {
+    let stale = cosignature_path(archive);
+    if cosigners.is_empty() && Path::new(&stale).exists() {
+        std::fs::remove_file(&stale)?;
+        eprintln!(
+            "Warning: removed cosignatures of the previous claims, use --cosigner to co-sign {} again",
+            archive
+        );
+    }
+    write_cosignatures(archive, cosigners)
+}
+
+/// Signs the claims of a written archive with each co-signer, storing the resulting
+/// tokens alongside the archive
+fn write_cosignatures(archive: &str, cosigners: &[KeyPair]) -> Result<()> {
+    if cosigners.is_empty() {
+        return Ok(());
+    }
+    let mut buf = Vec::new();
+    let mut f = File::open(archive)?;
+    f.read_to_end(&mut buf)?;
+    let claims = ProviderArchive::try_load(&buf)
+        .map_err(convert_error)?
+        .claims()
+        .ok_or("Provider archive does not contain claims")?;
+
+    let jwts = cosigners
+        .iter()
+        .map(|kp| {
+            let mut cosigned = claims.clone();
+            cosigned.issuer = kp.public_key();
+            cosigned.encode(kp).map_err(|e| e.into())
+        })
+        .collect::<Result<Vec<String>>>()?;
+    let mut f = File::create(cosignature_path(archive))?;
+    f.write_all(jwts.join("\n").as_bytes())?;
+    Ok(())
+}
+
+fn read_cosignatures(archive: &str) -> Result<Vec<String>> {
+    match std::fs::read_to_string(cosignature_path(archive)) {
+        Ok(contents) => Ok(contents
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| l.trim().to_string())
+            .collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Reads the raw claims token embedded in a provider archive, which `ProviderArchive`
+/// only exposes after decoding
+pub(crate) fn archive_claims_token(buf: &[u8]) -> Result<String> {
+    let reader: Box<dyn Read> = if is_compressed(buf)? {
+        Box::new(GzDecoder::new(buf))
+    } else {
+        Box::new(buf)
+    };
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.path()?.as_os_str() == CLAIMS_JWT_FILE {
+            let mut jwt = String::new();
+            entry.read_to_string(&mut jwt)?;
+            return Ok(jwt.trim().to_string());
+        }
+    }
+    Err("Provider archive does not contain claims".into())
+}
+
+/// Builds a compressed provider archive from its claims token and the native library of
+/// each target, in the layout `ProviderArchive` writes, so a provider archive pushed as
+/// separate layers can be reassembled
+pub(crate) fn assemble_archive(jwt: &str, targets: &[(&str, &[u8])]) -> Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    let files = std::iter::once((CLAIMS_JWT_FILE.to_string(), jwt.as_bytes()))
+        .chain(targets.iter().map(|(t, lib)| (format!("{}.bin", t), *lib)));
+    for (name, contents) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, name, contents)?;
+    }
+    Ok(builder.into_inner()?.finish()?)
+}
+
+/// Validates the signature and validity period of the archive's own claims token
+pub(crate) fn validate_archive_signature(jwt: &str) -> Result<()> {
+    let validation = validate_token::<CapabilityProvider>(jwt)?;
+    if !validation.signature_valid {
+        Err("Provider archive claims signature is invalid".into())
+    } else if validation.expired || validation.cannot_use_yet {
+        Err("Provider archive claims are expired or not yet valid".into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Ensures the archive was issued by the expected account
+fn check_issuer(issuer: &str, expected: &str) -> Result<()> {
+    if issuer == expected {
+        Ok(())
+    } else {
+        Err(format!(
+            "Provider archive was issued by {}, expected {}",
+            issuer, expected
+        )
+        .into())
+    }
+}
+
+/// Validates a co-signature token against the archive's claims, returning the co-signer's public key
+fn validate_cosignature(jwt: &str, claims: &Claims<CapabilityProvider>) -> Result<String> {
+    let validation = validate_token::<CapabilityProvider>(jwt)?;
+    if !validation.signature_valid || validation.expired || validation.cannot_use_yet {
+        return Err("Provider archive co-signature is invalid or expired".into());
+    }
+    let cosigned = Claims::<CapabilityProvider>::decode(jwt)?;
+    let target_hashes =
+        |c: &Claims<CapabilityProvider>| c.metadata.clone().map(|m| m.target_hashes);
+    if cosigned.subject != claims.subject || target_hashes(&cosigned) != target_hashes(claims) {
+        return Err(format!(
+            "Co-signature by {} does not match the archive contents",
+            cosigned.issuer
+        )
+        .into());
+    }
+    Ok(cosigned.issuer)
+}
+
+/// Archive properties read from a `par create --manifest` file
+#[derive(Debug, Default, Deserialize)]
+struct ParManifest {
+    capid: Option<String>,
+    name: Option<String>,
+    vendor: Option<String>,
+    revision: Option<i32>,
+    version: Option<String>,
+    #[serde(default)]
+    targets: Vec<ManifestTarget>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct ManifestTarget {
+    arch: String,
+    binary: String,
+}
+
+/// Reads a TOML or YAML manifest, chosen by file extension. Relative binary paths
+/// are resolved against the directory containing the manifest
+fn load_manifest(path: &Path) -> Result<ParManifest> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Unable to read manifest {}: {}", path.display(), e))?;
+    let mut manifest = parse_manifest(path, &contents)?;
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+    for target in manifest.targets.iter_mut() {
+        if target.binary != STDIN_PATH && Path::new(&target.binary).is_relative() {
+            target.binary = base.join(&target.binary).to_string_lossy().to_string();
+        }
+    }
+    Ok(manifest)
+}
+
+fn parse_manifest(path: &Path, contents: &str) -> Result<ParManifest> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    match extension.as_str() {
+        "toml" => serdeconv::from_toml_str(contents)
+            .map_err(|e| format!("Invalid manifest {}: {}", path.display(), e).into()),
+        "yaml" | "yml" => serde_yaml::from_str(contents)
+            .map_err(|e| format!("Invalid manifest {}: {}", path.display(), e).into()),
+        _ => Err(format!(
+            "Unsupported manifest {}, expected a .toml, .yaml or .yml file",
+            path.display()
+        )
+        .into()),
+    }
+}
+
+/// Returns the path that keys and the default archive name are derived from: the first
+/// binary, or the destination when a binary is read from stdin
+fn key_source(targets: &[ManifestTarget], destination: Option<&str>) -> Result<String> {
+    let from_stdin = targets.iter().filter(|t| t.binary == STDIN_PATH).count();
+    if from_stdin > 1 {
+        return Err("Only one binary can be read from stdin".into());
+    }
+    match destination {
+        Some(destination) if from_stdin == 1 => Ok(destination.to_string()),
+        None if from_stdin == 1 => {
+            Err("--destination must be provided when reading the binary from stdin".into())
+        }
+        _ => Ok(targets[0].binary.clone()),
+    }
+}
+
+/// Resolves the file `par create` writes to. A destination that is a directory, either existing
+/// or ending with a path separator, is created if needed and gets the default name within it
+fn archive_destination(destination: Option<String>, default_name: String) -> Result<String> {
+    match destination {
+        Some(dir) if Path::new(&dir).is_dir() || dir.ends_with(std::path::is_separator) => {
+            std::fs::create_dir_all(&dir)?;
+            Ok(Path::new(&dir)
+                .join(default_name)
+                .to_string_lossy()
+                .to_string())
+        }
+        Some(path) => Ok(path),
+        None => Ok(default_name),
+    }
+}
+
+/// Reads a provider binary from a file, or from `stdin` when the path is -
+fn read_binary(path: &str, stdin: &mut impl Read) -> Result<Vec<u8>> {
+    let mut lib = Vec::new();
+    if path == STDIN_PATH {
+        stdin.read_to_end(&mut lib)?;
+        if lib.is_empty() {
+            return Err("No provider binary was provided on stdin".into());
+        }
+    } else {
+        File::open(path)?.read_to_end(&mut lib)?;
+    }
+    Ok(lib)
+}
+
+/// Reads several provider binaries concurrently. Libraries are returned in the order of
+/// `binaries` regardless of which read finishes first, so archives are built the same way
+fn read_binaries(binaries: &[&str]) -> Result<Vec<Vec<u8>>> {
+    // Errors are carried as strings, as the boxed errors can't be sent between threads
+    binaries
+        .par_iter()
+        .map(|binary| read_binary(binary, &mut std::io::stdin().lock()).map_err(|e| e.to_string()))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| e.into())
+}
+
+/// Picks the command line value over the manifest value, failing if neither was provided
+fn required_field(key: &str, flag: Option<String>, manifest: Option<String>) -> Result<String> {
+    flag.or(manifest).ok_or_else(|| {
+        format!(
+            "Missing required field '{}', provide --{} or set {} in the manifest",
+            key, key, key
+        )
+        .into()
+    })
+}
+
+/// Combines manifest targets with the `--arch`/`--binary` pair, which replaces
+/// a manifest target for the same architecture
+fn create_targets(
+    arch: Option<String>,
+    binary: Option<String>,
+    mut targets: Vec<ManifestTarget>,
+) -> Result<Vec<ManifestTarget>> {
+    if let (Some(arch), Some(binary)) = (arch, binary) {
+        targets.retain(|t| t.arch != arch);
+        targets.push(ManifestTarget { arch, binary });
+    }
+    if targets.is_empty() {
+        return Err(
+            "Missing required field 'targets', provide --arch and --binary or list targets in the manifest"
+                .into(),
+        );
+    }
+    Ok(targets)
+}
+
+/// Ensures an architecture target is in ARCH-OS form, and unless `allow_unknown` is set,
+/// that both tokens are ones a host could report
+fn validate_arch(target: &str, allow_unknown: bool) -> Result<()> {
+    let mut parts = target.splitn(2, '-');
+    match (parts.next(), parts.next()) {
+        (Some(arch), Some(os)) if !arch.is_empty() && !os.is_empty() => {
+            if allow_unknown || (KNOWN_ARCHES.contains(&arch) && KNOWN_OSES.contains(&os)) {
+                return Ok(());
+            }
+        }
+        _ => {
+            return Err(format!(
+                "Invalid architecture target '{}', expected ARCH-OS (e.g. x86_64-linux)",
+                target
+            )
+            .into())
+        }
+    }
+    Err(format!(
+        "Unknown architecture target '{}', expected ARCH-OS where ARCH is one of {} and OS is one of {}. Use --allow-unknown-arch to accept it anyway",
+        target,
+        KNOWN_ARCHES.join(", "),
+        KNOWN_OSES.join(", ")
+    )
+    .into())
+}
+
+/// Native library formats a provider binary can be built as
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LibraryFormat {
+    Elf,
+    MachO,
+    Pe,
+}
+
+impl std::fmt::Display for LibraryFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            LibraryFormat::Elf => "ELF",
+            LibraryFormat::MachO => "Mach-O",
+            LibraryFormat::Pe => "PE",
+        })
+    }
+}
+
+/// Returns the library format hosts on an OS load, if known
+fn os_library_format(os: &str) -> Option<LibraryFormat> {
+    match os {
+        "macos" | "ios" => Some(LibraryFormat::MachO),
+        "windows" => Some(LibraryFormat::Pe),
+        os if KNOWN_OSES.contains(&os) => Some(LibraryFormat::Elf),
+        _ => None,
+    }
+}
+
+/// Identifies the format of a library from its magic bytes, along with the machine type
+/// recorded in its header when it's specific to one architecture
+fn library_format(lib: &[u8]) -> Option<(LibraryFormat, Option<u32>)> {
+    let u16_at = |offset: usize, big_endian: bool| {
+        lib.get(offset..offset + 2).map(|b| {
+            let bytes = [b[0], b[1]];
+            u32::from(if big_endian {
+                u16::from_be_bytes(bytes)
+            } else {
+                u16::from_le_bytes(bytes)
+            })
+        })
+    };
+    let u32_at = |offset: usize, big_endian: bool| {
+        lib.get(offset..offset + 4).map(|b| {
+            let bytes = [b[0], b[1], b[2], b[3]];
+            if big_endian {
+                u32::from_be_bytes(bytes)
+            } else {
+                u32::from_le_bytes(bytes)
+            }
+        })
+    };
+    match lib.get(0..4)? {
+        // EI_DATA is 2 for big endian objects, e_machine follows the 16 byte ident and e_type
+        [0x7f, b'E', b'L', b'F'] => Some((LibraryFormat::Elf, u16_at(18, lib.get(5) == Some(&2)))),
+        [0xfe, 0xed, 0xfa, 0xce] | [0xfe, 0xed, 0xfa, 0xcf] => {
+            Some((LibraryFormat::MachO, u32_at(4, true)))
+        }
+        [0xce, 0xfa, 0xed, 0xfe] | [0xcf, 0xfa, 0xed, 0xfe] => {
+            Some((LibraryFormat::MachO, u32_at(4, false)))
+        }
+        // Universal binaries contain a library for several architectures
+        [0xca, 0xfe, 0xba, 0xbe] => Some((LibraryFormat::MachO, None)),
+        [b'M', b'Z', ..] => {
+            // The DOS header points to the PE signature, which is followed by the machine type
+            let machine = u32_at(0x3c, false).and_then(|pe| {
+                let pe = pe as usize;
+                match lib.get(pe..pe + 4) {
+                    Some(b"PE\0\0") => u16_at(pe + 4, false),
+                    _ => None,
+                }
+            });
+            Some((LibraryFormat::Pe, machine))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the machine types a library format records for an architecture, if known
+fn arch_machines(format: LibraryFormat, arch: &str) -> Option<&'static [u32]> {
+    let machines: &[u32] = match (format, arch) {
+        (LibraryFormat::Elf, "x86") => &[3],
+        (LibraryFormat::Elf, "x86_64") => &[62],
+        (LibraryFormat::Elf, "arm") => &[40],
+        (LibraryFormat::Elf, "aarch64") => &[183],
+        (LibraryFormat::Elf, "mips") | (LibraryFormat::Elf, "mips64") => &[8],
+        (LibraryFormat::Elf, "powerpc") => &[20],
+        (LibraryFormat::Elf, "powerpc64") => &[21],
+        (LibraryFormat::Elf, "riscv64") => &[243],
+        (LibraryFormat::Elf, "s390x") => &[22],
+        (LibraryFormat::Elf, "sparc64") => &[43],
+        (LibraryFormat::MachO, "x86") => &[0x7],
+        (LibraryFormat::MachO, "x86_64") => &[0x0100_0007],
+        (LibraryFormat::MachO, "arm") => &[0xc],
+        (LibraryFormat::MachO, "aarch64") => &[0x0100_000c],
+        (LibraryFormat::Pe, "x86") => &[0x14c],
+        (LibraryFormat::Pe, "x86_64") => &[0x8664],
+        (LibraryFormat::Pe, "arm") => &[0x1c0, 0x1c4],
+        (LibraryFormat::Pe, "aarch64") => &[0xaa64],
+        _ => return None,
+    };
+    Some(machines)
+}
+
+/// Derives the architecture target a library was built for from its header. ELF libraries
+/// are taken to be for Linux unless their OS ABI names another OS, and libraries whose
+/// machine type matches several architectures aren't detected
+fn detect_arch(lib: &[u8]) -> Option<String> {
+    let (format, machine) = library_format(lib)?;
+    let machine = machine?;
+    let os = match format {
+        // EI_OSABI follows the ELF class, data encoding and version bytes
+        LibraryFormat::Elf => match lib.get(7)? {
+            0 | 3 => "linux",
+            2 => "netbsd",
+            6 => "solaris",
+            9 => "freebsd",
+            12 => "openbsd",
+            _ => return None,
+        },
+        LibraryFormat::MachO => "macos",
+        LibraryFormat::Pe => "windows",
+    };
+    let mut arches = KNOWN_ARCHES.iter().filter(|arch| {
+        arch_machines(format, arch)
+            .unwrap_or(&[])
+            .contains(&machine)
+    });
+    match (arches.next(), arches.next()) {
+        (Some(arch), None) => Some(format!("{}-{}", arch, os)),
+        _ => None,
+    }
+}
+
+/// Detects the architecture target of a binary given without `--arch`, printing it so it
+/// can be confirmed
+fn detect_binary_arch(binary: &str) -> Result<String> {
+    if binary == STDIN_PATH {
+        return Err("--arch must be provided when reading the binary from stdin".into());
+    }
+    let lib = std::fs::read(binary)?;
+    let arch = detect_arch(&lib).ok_or_else(|| {
+        format!(
+            "Unable to detect the architecture target of binary {}, provide it with --arch",
+            binary
+        )
+    })?;
+    eprintln!(
+        "Detected architecture target {} for binary {}",
+        arch, binary
+    );
+    Ok(arch)
+}
+
+/// Describes why a library can't be loaded by hosts of an architecture target, if it can't.
+/// Targets with an unrecognized OS, and architectures whose machine type isn't known for
+/// the library format, are only checked as far as possible
+fn library_mismatch(target: &str, lib: &[u8]) -> Option<String> {
+    let mut parts = target.splitn(2, '-');
+    let (arch, os) = (parts.next()?, parts.next()?);
+    let expected = os_library_format(os)?;
+    let (format, machine) = match library_format(lib) {
+        Some(found) => found,
+        None => return Some(format!("is not a native library, expected {}", expected)),
+    };
+    if format != expected {
+        return Some(format!("is in {} format, expected {}", format, expected));
+    }
+    match (machine, arch_machines(format, arch)) {
+        (Some(machine), Some(machines)) if !machines.contains(&machine) => Some(format!(
+            "is built for machine type {:#x}, not {}",
+            machine, arch
+        )),
+        _ => None,
+    }
+}
+
+/// Warns when a binary doesn't look like a library hosts of its architecture target can
+/// load, or fails under `strict`
+fn check_library(target: &str, binary: &str, lib: &[u8], strict: bool) -> Result<()> {
+    match library_mismatch(target, lib) {
+        Some(mismatch) if strict => {
+            Err(format!("Binary {} for {} {}", binary, target, mismatch).into())
+        }
+        Some(mismatch) => {
+            eprintln!("Warning: binary {} for {} {}", binary, target, mismatch);
+            Ok(())
+        }
+        None => {
+            debug!("Binary {} is a native library for {}", binary, target);
+            Ok(())
+        }
+    }
+}
+
+/// Parses `ARCH-OS:KEY=VALUE` metadata flags, ensuring each refers to one of the
+/// architecture targets being added
+fn parse_arch_metadata(values: &[String], arches: &[&str]) -> Result<ArchMetadata> {
+    let mut metadata = ArchMetadata::new();
+    for value in values {
+        let mut parts = value.splitn(2, ':');
+        let arch = parts.next().unwrap_or_default();
+        let mut pair = parts.next().unwrap_or_default().splitn(2, '=');
+        let (key, val) = match (pair.next(), pair.next()) {
+            (Some(key), Some(val)) if !arch.is_empty() && !key.is_empty() => (key, val),
+            _ => {
+                return Err(format!(
+                    "Invalid architecture metadata '{}', expected ARCH-OS:KEY=VALUE",
+                    value
+                )
+                .into())
+            }
+        };
+        if !arches.contains(&arch) {
+            return Err(format!(
+                "Architecture metadata '{}' refers to {}, which is not being added to the archive",
+                value, arch
+            )
+            .into());
+        }
+        metadata
+            .entry(arch.to_string())
+            .or_default()
+            .insert(key.to_string(), val.to_string());
+    }
+    Ok(metadata)
+}
+
+/// Path of the file holding the per-architecture metadata of an archive
+fn arch_metadata_path(archive: &str) -> String {
+    format!("{}{}", archive, ARCH_METADATA_EXTENSION)
+}
+
+/// Stores per-architecture metadata alongside the archive
+fn write_arch_metadata(archive: &str, metadata: &ArchMetadata) -> Result<()> {
+    if metadata.is_empty() {
+        return Ok(());
+    }
+    let f = File::create(arch_metadata_path(archive))?;
+    serde_json::to_writer_pretty(f, metadata)?;
+    Ok(())
+}
+
+fn read_arch_metadata(archive: &str) -> Result<ArchMetadata> {
+    match File::open(arch_metadata_path(archive)) {
+        Ok(f) => Ok(serde_json::from_reader(f)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(ArchMetadata::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Describes an architecture target along with its metadata, e.g. `x86_64-linux (libc=glibc)`
+fn describe_target(target: &str, metadata: &ArchMetadata) -> String {
+    match metadata.get(target) {
+        Some(meta) if !meta.is_empty() => format!(
+            "{} ({})",
+            target,
+            meta.iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        _ => target.to_string(),
+    }
+}
+
+/// Returns the distinct trusted issuers among the signers, failing if fewer than required signed
+fn trusted_signers(signers: &[String], trusted: &[String], required: usize) -> Result<Vec<String>> {
+    let mut matched: Vec<String> = signers
+        .iter()
+        .filter(|s| trusted.contains(s))
+        .cloned()
+        .collect();
+    matched.sort();
+    matched.dedup();
+    if matched.len() < required {
+        Err(format!(
+            "Provider archive is signed by {} trusted issuer(s), but {} are required",
+            matched.len(),
+            required
+        )
+        .into())
+    } else {
+        Ok(matched)
+    }
+}
+
+/// Parses a gzip compression level, which must be between 0 and 9
+fn parse_compression_level(value: &str) -> Result<u32> {
+    match value.parse::<u32>() {
+        Ok(level) if level <= 9 => Ok(level),
+        _ => Err(format!(
+            "Invalid compression level '{}', expected a number from 0-9",
+            value
+        )
+        .into()),
+    }
+}
+
+/// Writes the provider archive to `destination`. When a compression level is provided the
+/// archive is written uncompressed and then gzipped at that level, since provider_archive
+/// always uses its own level. Large archives take a while, so a spinner shows the current phase
+fn write_archive(
+    par: &mut ProviderArchive,
+    destination: &str,
+    issuer: &KeyPair,
+    subject: &KeyPair,
+    compress: bool,
+    level: Option<u32>,
+    kind: &OutputKind,
+) -> Result<()> {
+    // provider_archive hashes, signs, compresses and writes in a single call
+    let phases = if compress && level.is_none() {
+        "Hashing, signing, compressing and writing"
+    } else {
+        "Hashing, signing and writing"
+    };
+    let spinner = if show_progress(kind) {
+        Some(start_spinner(format!("{} {} ...", phases, destination)))
+    } else {
+        None
+    };
+    let res = match level {
+        Some(level) if compress => {
+            par.write(destination, issuer, subject, false)
+                .map_err(convert_error)?;
+            if let Some(spinner) = &spinner {
+                spinner.set_message(format!(
+                    "Compressing {} at level {} ...",
+                    destination, level
+                ));
+            }
+            let tarball = std::fs::read(destination)?;
+            gzip_to_file(&tarball, destination, level)
+        }
+        _ => par
+            .write(destination, issuer, subject, compress)
+            .map_err(convert_error),
+    };
+    if let Some(spinner) = spinner {
+        spinner.finish_and_clear();
+    }
+    res
+}
+
+/// Summarizes a written archive by its path, number of targets and size
+fn write_summary(path: &str, targets: usize, size: u64) -> String {
+    format!(
+        "Wrote {} with {} target{} ({})",
+        path,
+        targets,
+        if targets == 1 { "" } else { "s" },
+        HumanBytes(size)
+    )
+}
+
+fn gzip_to_file(input: &[u8], destination: &str, level: u32) -> Result<()> {
+    let mut encoder = GzEncoder::new(File::create(destination)?, Compression::new(level));
+    encoder.write_all(input)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+/// Returns the sha256 digest of a written archive, in the same form `wash reg push` reports
+fn file_digest(path: &str) -> Result<String> {
+    let bytes = std::fs::read(path)?;
+    Ok(format!("sha256:{:x}", Sha256::digest(&bytes)))
+}
+
+fn with_digest(message: String, digest: &str, quiet: bool) -> String {
+    if quiet {
+        message
+    } else {
+        format!("{}\nDigest: {}", message, digest)
+    }
+}
+
+/// Returns true if the bytes look like a provider archive, either a GZIP compressed file
+/// or an uncompressed TAR
+pub(crate) fn looks_like_archive(input: &[u8]) -> bool {
+    input.starts_with(&GZIP_MAGIC)
+        || matches!(input.get(TAR_MAGIC_OFFSET..), Some(rest) if rest.starts_with(TAR_MAGIC))
+}
+
+/// Inspects the byte slice for a GZIP header, and returns true if the file is compressed
+fn is_compressed(input: &[u8]) -> Result<bool> {
+    if input.len() < 2 {
+        return Err("Not enough bytes to be a valid PAR file".into());
+    }
+    Ok(input[0..2] == GZIP_MAGIC)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Uses all flags and options of the `par create` command
+    // to ensure API does not change between versions
+    #[test]
+    fn test_par_create_comprehensive() {
+        const ISSUER: &str = "SAAJLQZDZO57THPTIIEELEY7FJYOJZQWQD7FF4J67TUYTSCOXTF7R4Y3VY";
+        const SUBJECT: &str = "SVAH7IN6QE6XODCGIIWZQDZ5LNSSS4FNEO6SNHZSSASW4BBBKSZ6KWTKWY";
+        let create_long = ParCli::from_iter_safe(&[
+            "par",
+            "create",
+            "--arch",
+            "x86_64-testrunner",
+            "--binary",
+            "./testrunner.so",
+            "--capid",
+            "wasmcloud:test",
+            "--name",
+            "CreateTest",
+            "--vendor",
+            "TestRunner",
+            "--destination",
+            "./test.par.gz",
+            "--revision",
+            "1",
+            "--version",
+            "1.11.111",
+            "--directory",
+            "./tests/fixtures",
+            "--issuer",
+            ISSUER,
+            "--subject",
+            SUBJECT,
+            "--output",
+            "text",
+            "--cosigner",
+            ISSUER,
+            "--arch-meta",
+            "x86_64-testrunner:libc=glibc",
+            "--manifest",
+            "./provider.toml",
+            "--allow-unknown-arch",
+            "--strict",
+            "--quiet",
+            "--disable-keygen",
+            "--compress",
+            "--compression",
+            "0",
+            "--checksum",
+            "--replace",
+        ])
+        .unwrap();
+        match create_long.command {
+            ParCliCommand::Create(CreateCommand {
+                capid,
+                vendor,
+                revision,
+                version,
+                directory,
+                issuer,
+                subject,
+                name,
+                arch,
+                binary,
+                manifest,
+                arch_meta,
+                allow_unknown_arch,
+                strict,
+                destination,
+                compress,
+                compression,
+                cosigners,
+                disable_keygen,
+                quiet,
+                checksum,
+                replace,
+                force,
+                output,
+            }) => {
+                assert_eq!(capid.unwrap(), "wasmcloud:test");
+                assert_eq!(arch.unwrap(), "x86_64-testrunner");
+                assert_eq!(binary.unwrap(), "./testrunner.so");
+                assert_eq!(directory.unwrap(), "./tests/fixtures");
+                assert_eq!(issuer.unwrap(), ISSUER);
+                assert_eq!(subject.unwrap(), SUBJECT);
+                assert_eq!(output.kind, OutputKind::Text { max_width: 0 });
+                assert_eq!(name.unwrap(), "CreateTest");
+                assert_eq!(vendor.unwrap(), "TestRunner");
+                assert_eq!(destination.unwrap(), "./test.par.gz");
+                assert_eq!(revision.unwrap(), 1);
+                assert_eq!(version.unwrap(), "1.11.111");
+                assert_eq!(cosigners, vec![ISSUER.to_string()]);
+                assert_eq!(arch_meta, vec!["x86_64-testrunner:libc=glibc"]);
+                assert_eq!(manifest.unwrap(), PathBuf::from("./provider.toml"));
+                assert!(allow_unknown_arch);
+                assert!(strict);
+                assert!(disable_keygen);
+                assert!(compress);
+                assert_eq!(compression.unwrap(), 0);
+                assert!(quiet);
+                assert!(checksum);
+                assert!(replace);
+                assert!(!force);
+            }
+            cmd => panic!("par insert constructed incorrect command {:?}", cmd),
+        }
+        let create_short = ParCli::from_iter_safe(&[
+            "par",
+            "create",
+            "-a",
+            "x86_64-testrunner",
+            "-b",
+            "./testrunner.so",
+            "-c",
+            "wasmcloud:test",
+            "-n",
+            "CreateTest",
+            "-v",
+            "TestRunner",
+            "--destination",
+            "./test.par.gz",
+            "-r",
+            "1",
+            "--version",
+            "1.11.111",
+            "-d",
+            "./tests/fixtures",
+            "-i",
+            ISSUER,
+            "-s",
+            SUBJECT,
+            "-o",
+            "json",
+        ])
+        .unwrap();
+        match create_short.command {
+            ParCliCommand::Create(CreateCommand {
+                capid,
+                vendor,
+                revision,
+                version,
+                directory,
+                issuer,
+                subject,
+                name,
+                arch,
+                binary,
+                manifest,
+                arch_meta,
+                allow_unknown_arch,
+                strict,
+                destination,
+                compress,
+                compression,
+                cosigners,
+                disable_keygen,
+                quiet,
+                checksum,
+                replace,
+                force,
+                output,
+            }) => {
+                assert_eq!(capid.unwrap(), "wasmcloud:test");
+                assert_eq!(arch.unwrap(), "x86_64-testrunner");
+                assert_eq!(binary.unwrap(), "./testrunner.so");
+                assert_eq!(directory.unwrap(), "./tests/fixtures");
+                assert_eq!(issuer.unwrap(), ISSUER);
+                assert_eq!(subject.unwrap(), SUBJECT);
+                assert_eq!(output.kind, OutputKind::Json);
+                assert_eq!(name.unwrap(), "CreateTest");
+                assert_eq!(vendor.unwrap(), "TestRunner");
+                assert_eq!(destination.unwrap(), "./test.par.gz");
+                assert_eq!(revision.unwrap(), 1);
+                assert_eq!(version.unwrap(), "1.11.111");
+                assert!(cosigners.is_empty());
+                assert!(arch_meta.is_empty());
+                assert!(manifest.is_none());
+                assert!(!allow_unknown_arch);
+                assert!(!strict);
+                assert!(!disable_keygen);
+                assert!(!compress);
+                assert!(compression.is_none());
+                assert!(!quiet);
+                assert!(!checksum);
+                assert!(!replace);
+                assert!(!force);
+            }
+            cmd => panic!("par insert constructed incorrect command {:?}", cmd),
+        }
+        assert!(ParCli::from_iter_safe(&[
+            "par",
+            "create",
+            "-a",
+            "x86_64-linux",
+            "-b",
+            "./testrunner.so",
+            "--replace",
+            "--force",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_create_existing_archive() {
+        let dir = std::env::temp_dir().join(format!("wash-par-existing-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("existing.par.gz");
+        std::fs::write(&archive, b"previous archive").unwrap();
+        let create = ParCli::from_iter(&[
+            "par",
+            "create",
+            "-a",
+            "x86_64-linux",
+            "-b",
+            "./testrunner.so",
+            "-c",
+            "wasmcloud:test",
+            "-n",
+            "CreateTest",
+            "-v",
+            "TestRunner",
+            "--destination",
+            archive.to_str().unwrap(),
+        ]);
+        let err = match create.command {
+            ParCliCommand::Create(cmd) => handle_create(cmd).unwrap_err(),
+            cmd => panic!("par create constructed incorrect command {:?}", cmd),
+        };
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Archive {} already exists, use --replace to rebuild it with the new binaries or --force to overwrite it",
+                archive.display()
+            )
+        );
+        assert_eq!(crate::util::exit_code(&*err), 2);
+        assert_eq!(std::fs::read(&archive).unwrap(), b"previous archive");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // Uses all flags and options of the `par insert` command
+    // to ensure API does not change between versions
+    #[test]
+    fn test_par_insert_comprehensive() {
+        const ISSUER: &str = "SAAJLQZDZO57THPTQLEELEY7FJYOJZQWQD7FF4J67TUYTSCOXTF7R4Y3VY";
+        const SUBJECT: &str = "SVAH7IN6QE6XODCGQAWZQDZ5LNSSS4FNEO6SNHZSSASW4BBBKSZ6KWTKWY";
+        let insert_short = ParCli::from_iter_safe(&[
+            "par",
+            "insert",
+            "libtest.par.gz",
+            "-a",
+            "x86_64-testrunner",
+            "-b",
+            "./testrunner.so",
+            "-a",
+            "aarch64-testrunner",
+            "-b",
+            "./testrunner_arm.so",
+            "-d",
+            "./tests/fixtures",
+            "-i",
+            ISSUER,
+            "-s",
+            SUBJECT,
+            "-o",
+            "text",
+            "--disable-keygen",
+        ])
+        .unwrap();
+        match insert_short.command {
+            ParCliCommand::Insert(InsertCommand {
+                archive,
+                arch,
+                binary,
+                allow_unknown_arch,
+                strict,
+                directory,
+                issuer,
+                subject,
+                output,
+                cosigners,
+                disable_keygen,
+                no_clobber,
+                compression,
+                quiet,
+            }) => {
+                assert_eq!(archive, "libtest.par.gz");
+                assert_eq!(arch, vec!["x86_64-testrunner", "aarch64-testrunner"]);
+                assert_eq!(binary, vec!["./testrunner.so", "./testrunner_arm.so"]);
+                assert_eq!(directory.unwrap(), "./tests/fixtures");
+                assert_eq!(issuer.unwrap(), ISSUER);
+                assert_eq!(subject.unwrap(), SUBJECT);
+                assert_eq!(output.kind, OutputKind::Text { max_width: 0 });
+                assert!(cosigners.is_empty());
+                assert!(disable_keygen);
+                assert!(compression.is_none());
+                assert!(!allow_unknown_arch);
+                assert!(!strict);
+                assert!(!quiet);
+                assert!(!no_clobber);
+            }
+            cmd => panic!("par insert constructed incorrect command {:?}", cmd),
+        }
+        let insert_long = ParCli::from_iter_safe(&[
+            "par",
+            "insert",
+            "libtest.par.gz",
+            "--arch",
+            "x86_64-testrunner",
+            "--binary",
+            "./testrunner.so",
+            "--directory",
+            "./tests/fixtures",
+            "--issuer",
+            ISSUER,
+            "--subject",
+            SUBJECT,
+            "--output",
+            "text",
+            "--cosigner",
+            ISSUER,
+            "--compression",
+            "9",
+            "--allow-unknown-arch",
+            "--strict",
+            "--quiet",
+            "--no-clobber",
+        ])
+        .unwrap();
+        match insert_long.command {
+            ParCliCommand::Insert(InsertCommand {
+                archive,
+                arch,
+                binary,
+                allow_unknown_arch,
+                strict,
+                directory,
+                issuer,
+                subject,
+                output,
+                cosigners,
+                disable_keygen,
+                no_clobber,
+                compression,
+                quiet,
+            }) => {
+                assert_eq!(archive, "libtest.par.gz");
+                assert_eq!(arch, vec!["x86_64-testrunner"]);
+                assert_eq!(binary, vec!["./testrunner.so"]);
+                assert_eq!(directory.unwrap(), "./tests/fixtures");
+                assert_eq!(issuer.unwrap(), ISSUER);
+                assert_eq!(subject.unwrap(), SUBJECT);
+                assert_eq!(output.kind, OutputKind::Text { max_width: 0 });
+                assert_eq!(cosigners, vec![ISSUER.to_string()]);
+                assert!(!disable_keygen);
+                assert_eq!(compression.unwrap(), 9);
+                assert!(allow_unknown_arch);
+                assert!(strict);
+                assert!(quiet);
+                assert!(no_clobber);
+            }
+            cmd => panic!("par insert constructed incorrect command {:?}", cmd),
+        }
+    }
+
+    // Uses all flags and options of the `par inspect` command
+    // to ensure API does not change between versions
+    #[test]
+    fn test_par_inspect_comprehensive() {
+        const LOCAL: &str = "./coolthing.par.gz";
+        const REMOTE: &str = "oci://wasmcloud.azurecr.io/coolthing.par.gz";
+
+        let inspect_long = ParCli::from_iter_safe(&[
+            "par",
+            "inspect",
+            LOCAL,
+            "--digest",
+            "sha256:blah",
+            "--output",
+            "json",
+            "--password",
+            "secret",
+            "--user",
+            "name",
+            "--revocations",
+            "./revoked.txt",
+            "--strict",
+            "--verify-binary",
+            "./target/release/libcoolthing.so",
+            "--arch",
+            "x86_64-linux",
+        ])
+        .unwrap();
+        match inspect_long.command {
+            ParCliCommand::Inspect(InspectCommand {
+                archive,
+                digest,
+                allow_latest,
+                verify_binary,
+                arch,
+                opts,
+                revocations,
+                output,
+            }) => {
+                assert_eq!(verify_binary.unwrap(), "./target/release/libcoolthing.so");
+                assert_eq!(arch.unwrap(), "x86_64-linux");
+                assert_eq!(
+                    revocations.revocations,
+                    Some(PathBuf::from("./revoked.txt"))
+                );
+                assert!(revocations.strict);
+                assert_eq!(archive, LOCAL);
+                assert_eq!(digest.unwrap(), "sha256:blah");
+                assert!(!allow_latest);
+                assert!(!opts.insecure);
+                assert_eq!(opts.user.unwrap(), "name");
+                assert_eq!(opts.password.unwrap(), "secret");
+                assert_eq!(output.kind, OutputKind::Json);
+            }
+            cmd => panic!("par inspect constructed incorrect command {:?}", cmd),
+        }
+        let inspect_short = ParCli::from_iter_safe(&[
+            "par",
+            "inspect",
+            REMOTE,
+            "-d",
+            "sha256:blah",
+            "-o",
+            "json",
+            "-p",
+            "secret",
+            "-u",
+            "name",
+            "--allow-latest",
+            "--insecure",
+        ])
+        .unwrap();
+        match inspect_short.command {
+            ParCliCommand::Inspect(InspectCommand {
+                archive,
+                digest,
+                allow_latest,
+                verify_binary,
+                arch,
+                opts,
+                revocations,
+                output,
+            }) => {
+                assert!(verify_binary.is_none());
+                assert!(arch.is_none());
+                assert!(revocations.revocations.is_none());
+                assert!(!revocations.strict);
+                assert_eq!(archive, REMOTE);
+                assert_eq!(digest.unwrap(), "sha256:blah");
+                assert!(allow_latest);
+                assert!(opts.insecure);
+                assert_eq!(opts.user.unwrap(), "name");
+                assert_eq!(opts.password.unwrap(), "secret");
+                assert_eq!(output.kind, OutputKind::Json);
+            }
+            cmd => panic!("par inspect constructed incorrect command {:?}", cmd),
+        }
+        // A binary can only be verified against the library of a given architecture
+        assert!(
+            ParCli::from_iter_safe(&["par", "inspect", LOCAL, "--verify-binary", "lib.so"])
+                .is_err()
+        );
+        assert!(ParCli::from_iter_safe(&["par", "inspect", LOCAL, "-a", "x86_64-linux"]).is_err());
+    }
+
+    #[test]
+    fn test_verify_binary() {
+        let libraries = vec![TargetLibrary {
+            target: "x86_64-linux".to_string(),
+            size: 6,
+            sha256: format!("{:x}", Sha256::digest(b"linux1")),
+        }];
+        assert_eq!(
+            verify_binary(&libraries, "x86_64-linux", "lib.so", b"linux1")
+                .unwrap()
+                .target,
+            "x86_64-linux"
+        );
+        let stale = verify_binary(&libraries, "x86_64-linux", "lib.so", b"linux0").unwrap_err();
+        assert!(matches!(
+            stale.downcast_ref::<WashError>(),
+            Some(WashError::Validation(_))
+        ));
+        assert!(stale
+            .to_string()
+            .starts_with("Binary lib.so does not match the x86_64-linux library of the archive"));
+        let missing = verify_binary(&libraries, "aarch64-linux", "lib.so", b"linux1").unwrap_err();
+        assert_eq!(
+            missing.to_string(),
+            "Architecture aarch64-linux not found in archive, available targets: x86_64-linux"
+        );
+    }
+
+    // Uses all flags and options of the `par verify` command
+    // to ensure API does not change between versions
+    #[test]
+    fn test_par_verify_comprehensive() {
+        const BUILD_KEY: &str = "ACOJJN6WUP4ODD75XEBKKTCCUJJCY5ZKQ56XVKYK4BEJWGVAOOQHZMCW";
+        const RELEASE_KEY: &str = "AAJE5CFZJDBPKGGXSMVTGBRO3TXRU5N6L4B4ZX44HG2RTMJQQSFBYBBQ";
+        let verify = ParCli::from_iter_safe(&[
+            "par",
+            "verify",
+            "libtest.par.gz",
+            "--issuer",
+            RELEASE_KEY,
+            "--trusted-issuer",
+            BUILD_KEY,
+            "-t",
+            RELEASE_KEY,
+            "--require-signers",
+            "2",
+            "--revocations",
+            "./revoked.txt",
+            "-o",
+            "json",
+        ])
+        .unwrap();
+        match verify.command {
+            ParCliCommand::Verify(VerifyCommand {
+                archive,
+                issuer,
+                trusted_issuers,
+                require_signers,
+                revocations,
+                output,
+            }) => {
+                assert_eq!(revocations, Some(PathBuf::from("./revoked.txt")));
+                assert_eq!(archive, "libtest.par.gz");
+                assert_eq!(issuer.unwrap(), RELEASE_KEY);
+                assert_eq!(trusted_issuers, vec![BUILD_KEY, RELEASE_KEY]);
+                assert_eq!(require_signers.unwrap(), 2);
+                assert_eq!(output.kind, OutputKind::Json);
+            }
+            cmd => panic!("par verify constructed incorrect command {:?}", cmd),
+        }
+
+        let require_none = ParCli::from_iter_safe(&[
+            "par",
+            "verify",
+            "libtest.par.gz",
+            "-t",
+            BUILD_KEY,
+            "--require-signers",
+            "0",
+        ])
+        .unwrap();
+        match require_none.command {
+            ParCliCommand::Verify(cmd) => {
+                let err = handle_verify(cmd).unwrap_err();
+                assert_eq!(crate::util::exit_code(&*err), 2);
+            }
+            cmd => panic!("par verify constructed incorrect command {:?}", cmd),
+        }
+    }
+
+    #[test]
+    fn test_archive_claims_token() {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (name, contents) in &[
+            (CLAIMS_JWT_FILE, &b"header.claims.signature\n"[..]),
+            ("x86_64-linux.bin", &b"library"[..]),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_cksum();
+            builder.append_data(&mut header, name, *contents).unwrap();
+        }
+        let tarball = builder.into_inner().unwrap();
+        assert_eq!(
+            archive_claims_token(&tarball).unwrap(),
+            "header.claims.signature"
+        );
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&tarball).unwrap();
+        let compressed = encoder.finish().unwrap();
+        assert_eq!(
+            archive_claims_token(&compressed).unwrap(),
+            "header.claims.signature"
+        );
+
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(0);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "x86_64-linux.bin", &b""[..])
+            .unwrap();
+        assert!(archive_claims_token(&builder.into_inner().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_assemble_archive() {
+        let archive = assemble_archive(
+            "header.claims.signature",
+            &[
+                ("aarch64-linux", &b"arm"[..]),
+                ("x86_64-linux", &b"library"[..]),
+            ],
+        )
+        .unwrap();
+        assert!(is_compressed(&archive).unwrap());
+        assert_eq!(
+            archive_claims_token(&archive).unwrap(),
+            "header.claims.signature"
+        );
+
+        let mut tar = tar::Archive::new(GzDecoder::new(&archive[..]));
+        let entries = tar
+            .entries()
+            .unwrap()
+            .map(|e| {
+                let mut e = e.unwrap();
+                let mut contents = Vec::new();
+                e.read_to_end(&mut contents).unwrap();
+                (e.path().unwrap().to_string_lossy().to_string(), contents)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            vec![
+                (
+                    CLAIMS_JWT_FILE.to_string(),
+                    b"header.claims.signature".to_vec()
+                ),
+                ("aarch64-linux.bin".to_string(), b"arm".to_vec()),
+                ("x86_64-linux.bin".to_string(), b"library".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_timestamp(1618000000), "2021-04-09 20:26:40 UTC");
+    }
+
+    #[test]
+    fn test_check_issuer() {
+        assert!(check_issuer("ABUILD", "ABUILD").is_ok());
+        assert_eq!(
+            check_issuer("AUNTRUSTED", "ABUILD")
+                .unwrap_err()
+                .to_string(),
+            "Provider archive was issued by AUNTRUSTED, expected ABUILD"
+        );
+    }
+
+    #[test]
+    fn test_par_trusted_signers() {
+        let trusted = vec!["ABUILD".to_string(), "ARELEASE".to_string()];
+        let signers = vec![
+            "ABUILD".to_string(),
+            "AUNTRUSTED".to_string(),
+            "ABUILD".to_string(),
+        ];
+        assert_eq!(
+            trusted_signers(&signers, &trusted, 1).unwrap(),
+            vec!["ABUILD".to_string()]
+        );
+        assert!(trusted_signers(&signers, &trusted, 2).is_err());
+
+        let signers = vec!["ARELEASE".to_string(), "ABUILD".to_string()];
+        assert_eq!(trusted_signers(&signers, &trusted, 2).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_parse_arch_metadata() {
+        let values = vec![
+            "x86_64-linux:libc=glibc".to_string(),
+            "x86_64-linux:toolchain=1.51.0".to_string(),
+        ];
+        let metadata = parse_arch_metadata(&values, &["x86_64-linux"]).unwrap();
+        assert_eq!(metadata["x86_64-linux"]["libc"], "glibc");
+        assert_eq!(metadata["x86_64-linux"]["toolchain"], "1.51.0");
+        assert_eq!(
+            describe_target("x86_64-linux", &metadata),
+            "x86_64-linux (libc=glibc, toolchain=1.51.0)"
+        );
+        assert_eq!(describe_target("aarch64-linux", &metadata), "aarch64-linux");
+
+        assert!(parse_arch_metadata(&["x86_64-linux".to_string()], &["x86_64-linux"]).is_err());
+        assert!(
+            parse_arch_metadata(&["x86_64-linux:libc".to_string()], &["x86_64-linux"]).is_err()
+        );
+        assert!(
+            parse_arch_metadata(&["x86_64-linux:=glibc".to_string()], &["x86_64-linux"]).is_err()
+        );
+        let err = parse_arch_metadata(&["aarch64-linux:libc=musl".to_string()], &["x86_64-linux"])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Architecture metadata 'aarch64-linux:libc=musl' refers to aarch64-linux, which is not being added to the archive"
+        );
+    }
+
+    #[test]
+    fn test_validate_arch() {
+        assert!(validate_arch("x86_64-linux", false).is_ok());
+        assert!(validate_arch("aarch64-macos", false).is_ok());
+        assert!(validate_arch("mips64-android", false).is_ok());
+        let err = validate_arch("x86-64_linux", false)
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("Unknown architecture target 'x86-64_linux'"));
+        assert!(err.contains("x86_64, arm, aarch64"));
+        assert!(validate_arch("x86_64-testrunner", false).is_err());
+        assert!(validate_arch("x86_64-testrunner", true).is_ok());
+        assert!(validate_arch("x86_64", true).is_err());
+        assert!(validate_arch("-linux", true).is_err());
+    }
+
+    /// Returns a minimal 64-bit little endian ELF header for the provided machine type
+    fn elf_header(machine: u16) -> Vec<u8> {
+        let mut header = vec![0x7f, b'E', b'L', b'F', 2, 1, 1];
+        header.resize(18, 0);
+        header.extend_from_slice(&machine.to_le_bytes());
+        header
+    }
+
+    #[test]
+    fn test_detect_arch() {
+        assert_eq!(detect_arch(&elf_header(62)).unwrap(), "x86_64-linux");
+        assert_eq!(detect_arch(&elf_header(183)).unwrap(), "aarch64-linux");
+        let mut freebsd = elf_header(62);
+        freebsd[7] = 9;
+        assert_eq!(detect_arch(&freebsd).unwrap(), "x86_64-freebsd");
+        // mips and mips64 share a machine type
+        assert_eq!(detect_arch(&elf_header(8)), None);
+        assert_eq!(detect_arch(&elf_header(0x1234)), None);
+
+        let mut macho = vec![0xcf, 0xfa, 0xed, 0xfe];
+        macho.extend_from_slice(&0x0100_000c_u32.to_le_bytes());
+        assert_eq!(detect_arch(&macho).unwrap(), "aarch64-macos");
+        assert_eq!(detect_arch(&[0xca, 0xfe, 0xba, 0xbe]), None);
+
+        let mut pe = b"MZ".to_vec();
+        pe.resize(0x3c, 0);
+        pe.extend_from_slice(&0x40_u32.to_le_bytes());
+        pe.extend_from_slice(b"PE\0\0");
+        pe.extend_from_slice(&0x8664_u16.to_le_bytes());
+        assert_eq!(detect_arch(&pe).unwrap(), "x86_64-windows");
+        assert_eq!(detect_arch(b"not a library"), None);
+
+        let binary =
+            std::env::temp_dir().join(format!("wash-detect-arch-{}.so", std::process::id()));
+        std::fs::write(&binary, elf_header(62)).unwrap();
+        assert_eq!(
+            detect_binary_arch(binary.to_str().unwrap()).unwrap(),
+            "x86_64-linux"
+        );
+        std::fs::write(&binary, b"not a library").unwrap();
+        assert!(detect_binary_arch(binary.to_str().unwrap())
+            .unwrap_err()
+            .to_string()
+            .starts_with("Unable to detect the architecture target"));
+        std::fs::remove_file(&binary).unwrap();
+        assert!(detect_binary_arch(STDIN_PATH).is_err());
+
+        match ParCli::from_iter_safe(&["par", "insert", "libtest.par.gz", "-b", "./libtest.so"])
+            .unwrap()
+            .command
+        {
+            ParCliCommand::Insert(InsertCommand { arch, binary, .. }) => {
+                assert!(arch.is_empty());
+                assert_eq!(binary, vec!["./libtest.so"]);
+            }
+            cmd => panic!("par insert constructed incorrect command {:?}", cmd),
+        }
+        assert!(ParCli::from_iter_safe(&["par", "create", "-a", "x86_64-linux"]).is_err());
+    }
+
+    #[test]
+    fn test_library_mismatch() {
+        assert_eq!(library_mismatch("x86_64-linux", &elf_header(62)), None);
+        assert_eq!(library_mismatch("aarch64-android", &elf_header(183)), None);
+        assert_eq!(
+            library_mismatch("aarch64-linux", &elf_header(62)).unwrap(),
+            "is built for machine type 0x3e, not aarch64"
+        );
+        assert_eq!(
+            library_mismatch("x86_64-macos", &elf_header(62)).unwrap(),
+            "is in ELF format, expected Mach-O"
+        );
+        assert_eq!(
+            library_mismatch("x86_64-linux", b"01100010 01110100").unwrap(),
+            "is not a native library, expected ELF"
+        );
+
+        let mut macho = vec![0xcf, 0xfa, 0xed, 0xfe];
+        macho.extend_from_slice(&0x0100_000c_u32.to_le_bytes());
+        assert_eq!(library_mismatch("aarch64-macos", &macho), None);
+        assert!(library_mismatch("x86_64-macos", &macho).is_some());
+        assert_eq!(
+            library_mismatch("x86_64-macos", &[0xca, 0xfe, 0xba, 0xbe]),
+            None
+        );
+
+        let mut pe = b"MZ".to_vec();
+        pe.resize(0x3c, 0);
+        pe.extend_from_slice(&0x40_u32.to_le_bytes());
+        pe.extend_from_slice(b"PE\0\0");
+        pe.extend_from_slice(&0x8664_u16.to_le_bytes());
+        assert_eq!(library_mismatch("x86_64-windows", &pe), None);
+        assert!(library_mismatch("aarch64-windows", &pe).is_some());
+        assert!(library_mismatch("x86_64-linux", &pe).is_some());
+
+        // Unknown OS tokens can't be checked
+        assert_eq!(
+            library_mismatch("x86_64-testrunner", b"not a library"),
+            None
+        );
+
+        assert!(check_library("x86_64-linux", "./linux.so", b"text", false).is_ok());
+        assert_eq!(
+            check_library("x86_64-linux", "./linux.so", b"text", true)
+                .unwrap_err()
+                .to_string(),
+            "Binary ./linux.so for x86_64-linux is not a native library, expected ELF"
+        );
+    }
+
+    #[test]
+    fn test_binary_from_stdin() {
+        let target = |binary: &str| ManifestTarget {
+            arch: "x86_64-linux".to_string(),
+            binary: binary.to_string(),
+        };
+        assert_eq!(
+            key_source(&[target("./libtest.so")], None).unwrap(),
+            "./libtest.so"
+        );
+        assert_eq!(
+            key_source(&[target("-")], Some("./provider.par")).unwrap(),
+            "./provider.par"
+        );
+        assert_eq!(
+            key_source(&[target("-")], None).unwrap_err().to_string(),
+            "--destination must be provided when reading the binary from stdin"
+        );
+        assert!(key_source(&[target("-"), target("-")], Some("./provider.par")).is_err());
+        match ParCli::from_iter_safe(&["par", "create", "-a", "x86_64-linux", "-b", "-"])
+            .unwrap()
+            .command
+        {
+            ParCliCommand::Create(CreateCommand { binary, .. }) => {
+                assert_eq!(binary.unwrap(), STDIN_PATH)
+            }
+            cmd => panic!("par create constructed incorrect command {:?}", cmd),
+        }
+
+        let mut stdin = std::io::Cursor::new(b"library".to_vec());
+        assert_eq!(read_binary("-", &mut stdin).unwrap(), b"library".to_vec());
+        assert!(read_binary("-", &mut std::io::Cursor::new(vec![])).is_err());
+    }
+
+    #[test]
+    fn test_read_binaries() {
+        let dir = std::env::temp_dir().join(format!("wash-read-binaries-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let paths: Vec<String> = (0..5)
+            .map(|i| {
+                let path = dir.join(format!("lib{}.so", i));
+                std::fs::write(&path, vec![i as u8; 1024 * (5 - i)]).unwrap();
+                path.to_string_lossy().to_string()
+            })
+            .collect();
+        let binaries: Vec<&str> = paths.iter().map(|p| p.as_str()).collect();
+        let libs = read_binaries(&binaries).unwrap();
+        for (i, lib) in libs.iter().enumerate() {
+            assert_eq!(lib, &vec![i as u8; 1024 * (5 - i)]);
+        }
+        let missing = dir.join("missing.so").to_string_lossy().to_string();
+        assert!(read_binaries(&[binaries[0], &missing]).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_compression_level() {
+        assert_eq!(parse_compression_level("0").unwrap(), 0);
+        assert_eq!(parse_compression_level("9").unwrap(), 9);
+        assert!(parse_compression_level("10").is_err());
+        assert!(parse_compression_level("fast").is_err());
+        assert!(ParCli::from_iter_safe(&[
+            "par",
+            "insert",
+            "libtest.par.gz",
+            "-a",
+            "x86_64-linux",
+            "-b",
+            "./libtest.so",
+            "--compression",
+            "11",
+        ])
+        .is_err());
+
+        let destination = std::env::temp_dir().join(format!("wash-gzip-{}", std::process::id()));
+        let destination = destination.to_str().unwrap();
+        let input = b"a tarball, a tarball, a tarball".repeat(64);
+        gzip_to_file(&input, destination, 0).unwrap();
+        let stored = std::fs::read(destination).unwrap();
+        gzip_to_file(&input, destination, 9).unwrap();
+        let compressed = std::fs::read(destination).unwrap();
+        std::fs::remove_file(destination).unwrap();
+        assert!(is_compressed(&stored).unwrap());
+        assert!(stored.len() > input.len());
+        assert!(compressed.len() < input.len());
+    }
+
+    #[test]
+    fn test_archive_destination() {
+        let root =
+            std::env::temp_dir().join(format!("wash-par-destination-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let existing = root.to_str().unwrap().to_string();
+
+        assert_eq!(
+            archive_destination(None, "libtest.par".to_string()).unwrap(),
+            "libtest.par"
+        );
+        assert_eq!(
+            archive_destination(Some(existing.clone()), "libtest.par".to_string()).unwrap(),
+            root.join("libtest.par").to_str().unwrap()
+        );
+        // A path that isn't a directory is used as the file name
+        let explicit = root.join("custom.par.gz").to_str().unwrap().to_string();
+        assert_eq!(
+            archive_destination(Some(explicit.clone()), "libtest.par".to_string()).unwrap(),
+            explicit
+        );
+        let nested = format!("{}/build/artifacts/", existing);
+        assert_eq!(
+            archive_destination(Some(nested), "libtest.par.gz".to_string()).unwrap(),
+            root.join("build/artifacts/libtest.par.gz")
+                .to_str()
+                .unwrap()
+        );
+        assert!(root.join("build").join("artifacts").is_dir());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_create_manifest() {
+        let toml = r#"
+capid = "wasmcloud:test"
+name = "ManifestTest"
+vendor = "TestRunner"
+revision = 2
+
+[[targets]]
+arch = "x86_64-linux"
+binary = "./libtest.so"
+
+[[targets]]
+arch = "aarch64-linux"
+binary = "./libtest_arm.so"
+"#;
+        let yaml = r#"
+capid: wasmcloud:test
+name: ManifestTest
+vendor: TestRunner
+revision: 2
+targets:
+  - arch: x86_64-linux
+    binary: ./libtest.so
+  - arch: aarch64-linux
+    binary: ./libtest_arm.so
+"#;
+        for (file, contents) in &[("provider.toml", toml), ("provider.yaml", yaml)] {
+            let manifest = parse_manifest(Path::new(file), contents).unwrap();
+            assert_eq!(manifest.capid.unwrap(), "wasmcloud:test");
+            assert_eq!(manifest.name.unwrap(), "ManifestTest");
+            assert_eq!(manifest.vendor.unwrap(), "TestRunner");
+            assert_eq!(manifest.revision.unwrap(), 2);
+            assert!(manifest.version.is_none());
+            assert_eq!(manifest.targets.len(), 2);
+            assert_eq!(manifest.targets[1].arch, "aarch64-linux");
+        }
+        assert!(parse_manifest(Path::new("provider.json"), "{}").is_err());
+
+        assert_eq!(
+            required_field(
+                "name",
+                Some("Flag".to_string()),
+                Some("Manifest".to_string())
+            )
+            .unwrap(),
+            "Flag"
+        );
+        assert_eq!(
+            required_field("vendor", None, None)
+                .unwrap_err()
+                .to_string(),
+            "Missing required field 'vendor', provide --vendor or set vendor in the manifest"
+        );
+
+        let manifest = parse_manifest(Path::new("provider.toml"), toml).unwrap();
+        let targets = create_targets(
+            Some("x86_64-linux".to_string()),
+            Some("./override.so".to_string()),
+            manifest.targets,
+        )
+        .unwrap();
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].arch, "aarch64-linux");
+        assert_eq!(targets[1].binary, "./override.so");
+        assert!(create_targets(None, None, vec![]).is_err());
+    }
+
+    // Uses all flags and options of the `par extract` command
+    // to ensure API does not change between versions
+    #[test]
+    fn test_par_extract_comprehensive() {
+        let extract_short = ParCli::from_iter_safe(&[
+            "par",
+            "extract",
+            "libtest.par.gz",
+            "-a",
+            "x86_64-linux",
+            "--destination",
+            "./libtest.so",
+            "-o",
+            "json",
+        ])
+        .unwrap();
+        match extract_short.command {
+            ParCliCommand::Extract(ExtractCommand {
+                archive,
+                arch,
+                destination,
+                output,
+            }) => {
+                assert_eq!(archive, "libtest.par.gz");
+                assert_eq!(arch, "x86_64-linux");
+                assert_eq!(destination, "./libtest.so");
+                assert_eq!(output.kind, OutputKind::Json);
+            }
+            cmd => panic!("par extract constructed incorrect command {:?}", cmd),
+        }
+        let extract_long = ParCli::from_iter_safe(&[
+            "par",
+            "extract",
+            "libtest.par.gz",
+            "--arch",
+            "x86_64-linux",
+            "--destination",
+            "./libtest.so",
+        ])
+        .unwrap();
+        match extract_long.command {
+            ParCliCommand::Extract(ExtractCommand { arch, output, .. }) => {
+                assert_eq!(arch, "x86_64-linux");
+                assert_eq!(output.kind, OutputKind::Text { max_width: 0 });
+            }
+            cmd => panic!("par extract constructed incorrect command {:?}", cmd),
+        }
+    }
+
+    #[test]
+    fn test_par_export_claims() {
+        let export = ParCli::from_iter_safe(&[
+            "par",
+            "export-claims",
+            "libtest.par.gz",
+            "--destination",
+            "./libtest.jwt",
+            "-o",
+            "json",
         ])
         .unwrap();
-        match create_long.command {
-            ParCliCommand::Create(CreateCommand {
-                capid,
-                vendor,
-                revision,
-                version,
-                directory,
-                issuer,
-                subject,
-                name,
-                arch,
-                binary,
+        match export.command {
+            ParCliCommand::ExportClaims(ExportClaimsCommand {
+                archive,
                 destination,
-                compress,
-                disable_keygen,
                 output,
             }) => {
-                assert_eq!(capid, "wasmcloud:test");
-                assert_eq!(arch, "x86_64-testrunner");
-                assert_eq!(binary, "./testrunner.so");
-                assert_eq!(directory.unwrap(), "./tests/fixtures");
-                assert_eq!(issuer.unwrap(), ISSUER);
-                assert_eq!(subject.unwrap(), SUBJECT);
-                assert_eq!(output.kind, OutputKind::Text { max_width: 0 });
-                assert_eq!(name, "CreateTest");
-                assert_eq!(vendor, "TestRunner");
-                assert_eq!(destination.unwrap(), "./test.par.gz");
-                assert_eq!(revision.unwrap(), 1);
-                assert_eq!(version.unwrap(), "1.11.111");
-                assert!(disable_keygen);
-                assert!(compress);
+                assert_eq!(archive, "libtest.par.gz");
+                assert_eq!(destination, "./libtest.jwt");
+                assert_eq!(output.kind, OutputKind::Json);
             }
-            cmd => panic!("par insert constructed incorrect command {:?}", cmd),
+            cmd => panic!("par export-claims constructed incorrect command {:?}", cmd),
         }
-        let create_short = ParCli::from_iter_safe(&[
+        assert!(ParCli::from_iter_safe(&["par", "export-claims", "libtest.par.gz"]).is_err());
+
+        let dir = std::env::temp_dir().join(format!("wash-export-claims-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("libtest.par");
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(24);
+        header.set_cksum();
+        builder
+            .append_data(
+                &mut header,
+                CLAIMS_JWT_FILE,
+                &b"header.claims.signature\n"[..],
+            )
+            .unwrap();
+        std::fs::write(&archive, builder.into_inner().unwrap()).unwrap();
+
+        let export = |destination: &str| {
+            let archive = archive.to_str().unwrap();
+            let cmd = ParCli::from_iter_safe(&[
+                "par",
+                "export-claims",
+                archive,
+                "--destination",
+                destination,
+            ])
+            .unwrap();
+            match cmd.command {
+                ParCliCommand::ExportClaims(cmd) => handle_export_claims(cmd),
+                cmd => panic!("par export-claims constructed incorrect command {:?}", cmd),
+            }
+        };
+        assert_eq!(export(STDOUT_PATH).unwrap(), "header.claims.signature");
+        let jwt = dir.join("libtest.jwt");
+        export(jwt.to_str().unwrap()).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&jwt).unwrap(),
+            "header.claims.signature"
+        );
+
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(0);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "x86_64-linux.bin", &b""[..])
+            .unwrap();
+        std::fs::write(&archive, builder.into_inner().unwrap()).unwrap();
+        assert_eq!(
+            export(STDOUT_PATH).unwrap_err().to_string(),
+            "Provider archive does not contain claims"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // Uses all flags and options of the `par remove` command
+    // to ensure API does not change between versions
+    #[test]
+    fn test_par_remove_comprehensive() {
+        const ISSUER: &str = "SAAJLQZDZO57THPTQLEELEY7FJYOJZQWQD7FF4J67TUYTSCOXTF7R4Y3VY";
+        const SUBJECT: &str = "SVAH7IN6QE6XODCGQAWZQDZ5LNSSS4FNEO6SNHZSSASW4BBBKSZ6KWTKWY";
+        let remove_short = ParCli::from_iter_safe(&[
             "par",
-            "create",
+            "remove",
+            "libtest.par.gz",
             "-a",
-            "x86_64-testrunner",
-            "-b",
-            "./testrunner.so",
-            "-c",
-            "wasmcloud:test",
-            "-n",
-            "CreateTest",
-            "-v",
-            "TestRunner",
-            "--destination",
-            "./test.par.gz",
-            "-r",
-            "1",
-            "--version",
-            "1.11.111",
+            "x86_64-linux",
             "-d",
             "./tests/fixtures",
             "-i",
@@ -537,56 +3668,68 @@ mod test {
             "json",
         ])
         .unwrap();
-        match create_short.command {
-            ParCliCommand::Create(CreateCommand {
-                capid,
-                vendor,
-                revision,
-                version,
+        match remove_short.command {
+            ParCliCommand::Remove(RemoveCommand {
+                archive,
+                arch,
                 directory,
                 issuer,
                 subject,
-                name,
-                arch,
-                binary,
-                destination,
-                compress,
+                cosigners,
                 disable_keygen,
                 output,
             }) => {
-                assert_eq!(capid, "wasmcloud:test");
-                assert_eq!(arch, "x86_64-testrunner");
-                assert_eq!(binary, "./testrunner.so");
+                assert_eq!(archive, "libtest.par.gz");
+                assert_eq!(arch, "x86_64-linux");
                 assert_eq!(directory.unwrap(), "./tests/fixtures");
                 assert_eq!(issuer.unwrap(), ISSUER);
                 assert_eq!(subject.unwrap(), SUBJECT);
-                assert_eq!(output.kind, OutputKind::Json);
-                assert_eq!(name, "CreateTest");
-                assert_eq!(vendor, "TestRunner");
-                assert_eq!(destination.unwrap(), "./test.par.gz");
-                assert_eq!(revision.unwrap(), 1);
-                assert_eq!(version.unwrap(), "1.11.111");
+                assert!(cosigners.is_empty());
                 assert!(!disable_keygen);
-                assert!(!compress);
+                assert_eq!(output.kind, OutputKind::Json);
             }
-            cmd => panic!("par insert constructed incorrect command {:?}", cmd),
+            cmd => panic!("par remove constructed incorrect command {:?}", cmd),
+        }
+        let remove_long = ParCli::from_iter_safe(&[
+            "par",
+            "remove",
+            "libtest.par.gz",
+            "--arch",
+            "x86_64-linux",
+            "--directory",
+            "./tests/fixtures",
+            "--issuer",
+            ISSUER,
+            "--subject",
+            SUBJECT,
+            "--cosigner",
+            ISSUER,
+            "--disable-keygen",
+        ])
+        .unwrap();
+        match remove_long.command {
+            ParCliCommand::Remove(RemoveCommand {
+                cosigners,
+                disable_keygen,
+                output,
+                ..
+            }) => {
+                assert_eq!(cosigners, vec![ISSUER.to_string()]);
+                assert!(disable_keygen);
+                assert_eq!(output.kind, OutputKind::Text { max_width: 0 });
+            }
+            cmd => panic!("par remove constructed incorrect command {:?}", cmd),
         }
     }
 
-    // Uses all flags and options of the `par insert` command
-    // to ensure API does not change between versions
     #[test]
-    fn test_par_insert_comprehensive() {
+    fn test_par_sign_comprehensive() {
         const ISSUER: &str = "SAAJLQZDZO57THPTQLEELEY7FJYOJZQWQD7FF4J67TUYTSCOXTF7R4Y3VY";
         const SUBJECT: &str = "SVAH7IN6QE6XODCGQAWZQDZ5LNSSS4FNEO6SNHZSSASW4BBBKSZ6KWTKWY";
-        let insert_short = ParCli::from_iter_safe(&[
+        let sign_short = ParCli::from_iter_safe(&[
             "par",
-            "insert",
+            "sign",
             "libtest.par.gz",
-            "-a",
-            "x86_64-testrunner",
-            "-b",
-            "./testrunner.so",
             "-d",
             "./tests/fixtures",
             "-i",
@@ -594,150 +3737,300 @@ mod test {
             "-s",
             SUBJECT,
             "-o",
-            "text",
-            "--disable-keygen",
+            "json",
         ])
         .unwrap();
-        match insert_short.command {
-            ParCliCommand::Insert(InsertCommand {
+        match sign_short.command {
+            ParCliCommand::Sign(SignCommand {
                 archive,
-                arch,
-                binary,
                 directory,
                 issuer,
                 subject,
-                output,
+                cosigners,
                 disable_keygen,
+                output,
             }) => {
                 assert_eq!(archive, "libtest.par.gz");
-                assert_eq!(arch, "x86_64-testrunner");
-                assert_eq!(binary, "./testrunner.so");
                 assert_eq!(directory.unwrap(), "./tests/fixtures");
                 assert_eq!(issuer.unwrap(), ISSUER);
                 assert_eq!(subject.unwrap(), SUBJECT);
-                assert_eq!(output.kind, OutputKind::Text { max_width: 0 });
-                assert!(disable_keygen);
+                assert!(cosigners.is_empty());
+                assert!(!disable_keygen);
+                assert_eq!(output.kind, OutputKind::Json);
             }
-            cmd => panic!("par insert constructed incorrect command {:?}", cmd),
+            cmd => panic!("par sign constructed incorrect command {:?}", cmd),
         }
-        let insert_long = ParCli::from_iter_safe(&[
+        let sign_long = ParCli::from_iter_safe(&[
             "par",
-            "insert",
+            "sign",
             "libtest.par.gz",
-            "--arch",
-            "x86_64-testrunner",
-            "--binary",
-            "./testrunner.so",
             "--directory",
             "./tests/fixtures",
             "--issuer",
             ISSUER,
             "--subject",
             SUBJECT,
-            "--output",
-            "text",
+            "--cosigner",
+            ISSUER,
+            "--disable-keygen",
         ])
         .unwrap();
-        match insert_long.command {
-            ParCliCommand::Insert(InsertCommand {
-                archive,
-                arch,
-                binary,
-                directory,
-                issuer,
-                subject,
-                output,
+        match sign_long.command {
+            ParCliCommand::Sign(SignCommand {
+                cosigners,
                 disable_keygen,
+                output,
+                ..
             }) => {
-                assert_eq!(archive, "libtest.par.gz");
-                assert_eq!(arch, "x86_64-testrunner");
-                assert_eq!(binary, "./testrunner.so");
-                assert_eq!(directory.unwrap(), "./tests/fixtures");
-                assert_eq!(issuer.unwrap(), ISSUER);
-                assert_eq!(subject.unwrap(), SUBJECT);
+                assert_eq!(cosigners, vec![ISSUER.to_string()]);
+                assert!(disable_keygen);
                 assert_eq!(output.kind, OutputKind::Text { max_width: 0 });
-                assert!(!disable_keygen);
             }
-            cmd => panic!("par insert constructed incorrect command {:?}", cmd),
+            cmd => panic!("par sign constructed incorrect command {:?}", cmd),
         }
+        assert!(ParCli::from_iter_safe(&["par", "sign"]).is_err());
     }
 
-    // Uses all flags and options of the `par inspect` command
-    // to ensure API does not change between versions
     #[test]
-    fn test_par_inspect_comprehensive() {
-        const LOCAL: &str = "./coolthing.par.gz";
-        const REMOTE: &str = "wasmcloud.azurecr.io/coolthing.par.gz";
-
-        let inspect_long = ParCli::from_iter_safe(&[
+    fn test_par_repack_comprehensive() {
+        const ISSUER: &str = "SAAJLQZDZO57THPTQLEELEY7FJYOJZQWQD7FF4J67TUYTSCOXTF7R4Y3VY";
+        const SUBJECT: &str = "SVAH7IN6QE6XODCGQAWZQDZ5LNSSS4FNEO6SNHZSSASW4BBBKSZ6KWTKWY";
+        let repack = ParCli::from_iter_safe(&[
             "par",
-            "inspect",
-            LOCAL,
-            "--digest",
-            "sha256:blah",
-            "--output",
+            "repack",
+            "libtest.par.gz",
+            "--destination",
+            "libtest-small.par.gz",
+            "-d",
+            "./tests/fixtures",
+            "-i",
+            ISSUER,
+            "-s",
+            SUBJECT,
+            "--cosigner",
+            ISSUER,
+            "--disable-keygen",
+            "--compression",
+            "9",
+            "-o",
             "json",
-            "--password",
-            "secret",
-            "--user",
-            "name",
         ])
         .unwrap();
-        match inspect_long.command {
-            ParCliCommand::Inspect(InspectCommand {
+        match repack.command {
+            ParCliCommand::Repack(RepackCommand {
                 archive,
-                digest,
-                allow_latest,
-                user,
-                password,
-                insecure,
+                destination,
+                directory,
+                issuer,
+                subject,
+                cosigners,
+                disable_keygen,
+                compression,
+                uncompressed,
                 output,
             }) => {
-                assert_eq!(archive, LOCAL);
-                assert_eq!(digest.unwrap(), "sha256:blah");
-                assert!(!allow_latest);
-                assert!(!insecure);
-                assert_eq!(user.unwrap(), "name");
-                assert_eq!(password.unwrap(), "secret");
+                assert_eq!(archive, "libtest.par.gz");
+                assert_eq!(destination.unwrap(), "libtest-small.par.gz");
+                assert_eq!(directory.unwrap(), "./tests/fixtures");
+                assert_eq!(issuer.unwrap(), ISSUER);
+                assert_eq!(subject.unwrap(), SUBJECT);
+                assert_eq!(cosigners, vec![ISSUER.to_string()]);
+                assert!(disable_keygen);
+                assert_eq!(compression, Some(9));
+                assert!(!uncompressed);
                 assert_eq!(output.kind, OutputKind::Json);
             }
-            cmd => panic!("par inspect constructed incorrect command {:?}", cmd),
+            cmd => panic!("par repack constructed incorrect command {:?}", cmd),
         }
-        let inspect_short = ParCli::from_iter_safe(&[
+        let uncompressed =
+            ParCli::from_iter_safe(&["par", "repack", "libtest.par.gz", "--uncompressed"]).unwrap();
+        match uncompressed.command {
+            ParCliCommand::Repack(RepackCommand {
+                destination,
+                compression,
+                uncompressed,
+                ..
+            }) => {
+                assert!(destination.is_none());
+                assert!(compression.is_none());
+                assert!(uncompressed);
+            }
+            cmd => panic!("par repack constructed incorrect command {:?}", cmd),
+        }
+        assert!(ParCli::from_iter_safe(&[
             "par",
-            "inspect",
-            REMOTE,
-            "-d",
-            "sha256:blah",
+            "repack",
+            "libtest.par.gz",
+            "--uncompressed",
+            "--compression",
+            "6"
+        ])
+        .is_err());
+        assert!(ParCli::from_iter_safe(&[
+            "par",
+            "repack",
+            "libtest.par.gz",
+            "--compression",
+            "10"
+        ])
+        .is_err());
+        assert!(ParCli::from_iter_safe(&["par", "repack"]).is_err());
+    }
+
+    #[test]
+    fn test_write_summary() {
+        assert_eq!(
+            write_summary("echo.par.gz", 1, 512),
+            "Wrote echo.par.gz with 1 target (512B)"
+        );
+        assert_eq!(
+            write_summary("fat.par.gz", 3, 300 * 1024 * 1024),
+            "Wrote fat.par.gz with 3 targets (300.00MiB)"
+        );
+    }
+
+    #[test]
+    fn test_size_change() {
+        assert_eq!(size_change(2048, 1024), "2048 -> 1024 bytes (-50.0%)");
+        assert_eq!(size_change(1000, 1100), "1000 -> 1100 bytes (+10.0%)");
+        assert_eq!(size_change(0, 10), "0 -> 10 bytes");
+    }
+
+    #[test]
+    fn test_clobbered_targets() {
+        let existing = vec!["x86_64-linux".to_string(), "aarch64-linux".to_string()];
+        assert!(clobbered_targets(&existing, &["x86_64-macos".to_string()]).is_empty());
+        assert_eq!(
+            clobbered_targets(
+                &existing,
+                &["x86_64-macos".to_string(), "aarch64-linux".to_string()]
+            ),
+            vec!["aarch64-linux"]
+        );
+    }
+
+    #[test]
+    fn test_par_diff_comprehensive() {
+        let diff = ParCli::from_iter_safe(&[
+            "par",
+            "diff",
+            "libtest-0.1.par.gz",
+            "libtest-0.2.par.gz",
             "-o",
             "json",
-            "-p",
-            "secret",
-            "-u",
-            "name",
-            "--allow-latest",
-            "--insecure",
         ])
         .unwrap();
-        match inspect_short.command {
-            ParCliCommand::Inspect(InspectCommand {
-                archive,
-                digest,
-                allow_latest,
-                user,
-                password,
-                insecure,
+        match diff.command {
+            ParCliCommand::Diff(DiffCommand {
+                archive_a,
+                archive_b,
                 output,
             }) => {
-                assert_eq!(archive, REMOTE);
-                assert_eq!(digest.unwrap(), "sha256:blah");
-                assert!(allow_latest);
-                assert!(insecure);
-                assert_eq!(user.unwrap(), "name");
-                assert_eq!(password.unwrap(), "secret");
+                assert_eq!(archive_a, "libtest-0.1.par.gz");
+                assert_eq!(archive_b, "libtest-0.2.par.gz");
                 assert_eq!(output.kind, OutputKind::Json);
             }
-            cmd => panic!("par inspect constructed incorrect command {:?}", cmd),
+            cmd => panic!("par diff constructed incorrect command {:?}", cmd),
         }
+        assert!(ParCli::from_iter_safe(&["par", "diff", "libtest-0.1.par.gz"]).is_err());
+    }
+
+    #[test]
+    fn test_diff_archives() {
+        let summary = |ver: &str, libraries: &[(&str, &str)]| ArchiveSummary {
+            fields: vec![
+                ("capability_contract_id", Some("wasmcloud:test".to_string())),
+                ("ver", Some(ver.to_string())),
+                ("rev", None),
+            ],
+            libraries: libraries
+                .iter()
+                .map(|(t, h)| (t.to_string(), h.to_string()))
+                .collect(),
+        };
+        let a = summary(
+            "0.1.0",
+            &[
+                ("x86_64-linux", "aaaaaaaaaaaaaaaa"),
+                ("x86_64-macos", "bbbb"),
+            ],
+        );
+        assert!(diff_archives(&a, &a).is_empty());
+
+        let b = summary(
+            "0.2.0",
+            &[
+                ("x86_64-linux", "cccccccccccccccc"),
+                ("aarch64-linux", "dddd"),
+            ],
+        );
+        let diff = diff_archives(&a, &b);
+        assert_eq!(diff.added, vec!["aarch64-linux"]);
+        assert_eq!(diff.removed, vec!["x86_64-macos"]);
+        assert_eq!(
+            describe_diff(&diff),
+            vec![
+                "ver: 0.1.0 -> 0.2.0",
+                "+ target aarch64-linux",
+                "- target x86_64-macos",
+                "~ target x86_64-linux: sha256:aaaaaaaaaaaa -> sha256:cccccccccccc",
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&diff).unwrap()["fields"]["ver"],
+            json!({"a": "0.1.0", "b": "0.2.0"})
+        );
+    }
+
+    #[test]
+    fn test_target_hash_mismatches() {
+        let library = |target: &str, bytes: &[u8]| TargetLibrary {
+            target: target.to_string(),
+            size: bytes.len(),
+            sha256: format!("{:x}", Sha256::digest(bytes)),
+        };
+        let libraries = vec![
+            library("x86_64-linux", b"linux"),
+            library("x86_64-macos", b"macos"),
+        ];
+        let mut hashes: HashMap<String, String> = libraries
+            .iter()
+            .map(|l| (l.target.clone(), l.sha256.to_uppercase()))
+            .collect();
+        assert!(target_hash_mismatches(&hashes, &libraries).is_empty());
+
+        hashes.insert(
+            "x86_64-macos".to_string(),
+            format!("{:x}", Sha256::digest(b"tampered")),
+        );
+        hashes.insert("aarch64-linux".to_string(), "00".to_string());
+        hashes.remove("x86_64-linux");
+        assert_eq!(
+            target_hash_mismatches(&hashes, &libraries),
+            vec![
+                "Target x86_64-linux is not covered by the archive's claims",
+                "Target x86_64-macos does not match the hash signed in the archive's claims",
+                "Claims cover target aarch64-linux, which is missing from the archive",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_remaining_targets() {
+        let targets = vec![
+            "x86_64-linux".to_string(),
+            "aarch64-linux".to_string(),
+            "x86_64-macos".to_string(),
+        ];
+        assert_eq!(
+            remaining_targets(targets, "x86_64-linux").unwrap(),
+            vec!["aarch64-linux", "x86_64-macos"]
+        );
+        assert_eq!(
+            remaining_targets(vec!["x86_64-linux".to_string()], "x86_64-linux")
+                .unwrap_err()
+                .to_string(),
+            "Refusing to remove x86_64-linux, it is the only target in the archive"
+        );
     }
 }
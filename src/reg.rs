@@ -1,31 +1,86 @@
 extern crate oci_distribution;
-use crate::util::{format_output, output_destination, Output, OutputDestination, OutputKind};
-use log::{debug, info};
+use crate::util::{
+    confirm, format_output, no_emoji_mode, offline_mode, output_destination, prompt_secret,
+    quiet_mode, show_progress, start_spinner, with_context, write_checksum_file, Aborted, Output,
+    OutputDestination, OutputKind, WashError,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use futures_util::future::{select, Either};
+use indicatif::{ProgressBar, ProgressFinish, ProgressStyle};
+use log::{debug, info, trace};
 use oci_distribution::client::*;
+use oci_distribution::manifest::{
+    OciDescriptor, OciManifest, IMAGE_MANIFEST_MEDIA_TYPE, OCI_IMAGE_MEDIA_TYPE,
+};
 use oci_distribution::secrets::RegistryAuth;
 use oci_distribution::Reference;
 use provider_archive::ProviderArchive;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
-use spinners::{Spinner, Spinners};
-use std::fs::File;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::env;
+use std::fs::{copy, create_dir_all, remove_file, rename, File, OpenOptions};
+use std::future::Future;
 use std::io::prelude::*;
+use std::io::{BufReader, BufWriter, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use structopt::clap::AppSettings;
 use structopt::StructOpt;
+use wascap::jwt::{validate_token, Actor};
 
 const PROVIDER_ARCHIVE_MEDIA_TYPE: &str = "application/vnd.wasmcloud.provider.archive.layer.v1+par";
 const PROVIDER_ARCHIVE_CONFIG_MEDIA_TYPE: &str =
     "application/vnd.wasmcloud.provider.archive.config";
 const PROVIDER_ARCHIVE_FILE_EXTENSION: &str = ".par.gz";
+const PROVIDER_TARGET_MEDIA_TYPE: &str = "application/vnd.wasmcloud.provider.archive.target.v1";
+const PROVIDER_TARGET_ANNOTATION: &str = "com.wasmcloud.provider.target";
+/// Extension of a single architecture target pulled from a provider archive pushed with
+/// `--split-targets`, which is a native library
+const PROVIDER_TARGET_FILE_EXTENSION: &str = ".bin";
+/// Layer holding the claims token of a provider archive pushed with `--split-targets`
+const PROVIDER_CLAIMS_MEDIA_TYPE: &str = "application/vnd.wasmcloud.provider.archive.claims.v1+jwt";
+const PROVIDER_CLAIMS_FILE: &str = "claims.jwt";
 const WASM_MEDIA_TYPE: &str = "application/vnd.module.wasm.content.layer.v1+wasm";
 const WASM_CONFIG_MEDIA_TYPE: &str = "application/vnd.wasmcloud.actor.archive.config";
 const OCI_MEDIA_TYPE: &str = "application/vnd.oci.image.layer.v1.tar";
 const WASM_FILE_EXTENSION: &str = ".wasm";
+pub(crate) const WASM_MAGIC: &[u8] = b"\0asm";
+const OCI_CREATED_ANNOTATION: &str = "org.opencontainers.image.created";
+const OCI_AUTHORS_ANNOTATION: &str = "org.opencontainers.image.authors";
+const OCI_TITLE_ANNOTATION: &str = "org.opencontainers.image.title";
+const OCI_EMPTY_MEDIA_TYPE: &str = "application/vnd.oci.empty.v1+json";
+const OCI_INDEX_MEDIA_TYPE: &str = "application/vnd.oci.image.index.v1+json";
+const DOCKER_MANIFEST_LIST_MEDIA_TYPE: &str =
+    "application/vnd.docker.distribution.manifest.list.v2+json";
+const SPDX_MEDIA_TYPE: &str = "application/spdx+json";
+const CYCLONEDX_MEDIA_TYPE: &str = "application/vnd.cyclonedx+json";
+const DOCKER_CONFIG_FILE: &str = "config.json";
+const WASH_CREDENTIALS_FILE: &str = "credentials.json";
+const REGISTRY_CONFIG_FILE: &str = "registries.toml";
+const OCI_SCHEME: &str = "oci://";
+const STDOUT_DESTINATION: &str = "-";
+const STDIN_ARTIFACT: &str = "-";
+const REG_USER_ENV: &str = "WASH_REG_USER";
+const REG_PASSWORD_ENV: &str = "WASH_REG_PASSWORD";
+const REGISTRY_CONFIG_ENV: &str = "WASH_REGISTRY_CONFIG";
+/// Prefix of credentials scoped to a registry host, e.g. WASH_REG__GHCR_IO_USER
+const HOST_CREDENTIALS_ENV_PREFIX: &str = "WASH_REG__";
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
 
 pub(crate) const SHOWER_EMOJI: &str = "\u{1F6BF}";
+/// Replaces `SHOWER_EMOJI` when output is restricted to ASCII
+const ASCII_SUCCESS_PREFIX: &str = "OK:";
 
+#[derive(Debug, PartialEq)]
 pub(crate) enum SupportedArtifacts {
     Par,
     Wasm,
+    /// Native library for a single target of a provider archive pushed with `--split-targets`
+    Target,
 }
 
 #[derive(Debug, StructOpt, Clone)]
@@ -51,6 +106,30 @@ pub(crate) enum RegCliCommand {
     /// Push an artifact to an OCI compliant registry
     #[structopt(name = "push")]
     Push(PushCommand),
+    /// List the tagged artifacts of a repository with their digests and sizes
+    #[structopt(name = "list")]
+    List(ListCommand),
+    /// List the tags of a repository
+    #[structopt(name = "tags")]
+    Tags(TagsCommand),
+    /// Delete an artifact from an OCI compliant registry, if the registry allows it
+    #[structopt(name = "delete")]
+    Delete(DeleteCommand),
+    /// Print the manifest of an artifact as served by an OCI compliant registry
+    #[structopt(name = "manifest")]
+    Manifest(ManifestCommand),
+    /// Print the config object pushed alongside an artifact
+    #[structopt(name = "inspect-config")]
+    InspectConfig(InspectConfigCommand),
+    /// Copy an artifact between registries without writing it to disk
+    #[structopt(name = "copy")]
+    Copy(CopyCommand),
+    /// Log in to an OCI compliant registry, storing the credentials for later commands
+    #[structopt(name = "login")]
+    Login(LoginCommand),
+    /// Remove the credentials stored by `reg login` for a registry
+    #[structopt(name = "logout")]
+    Logout(LogoutCommand),
 }
 
 #[derive(StructOpt, Debug, Clone)]
@@ -59,7 +138,7 @@ pub(crate) struct PullCommand {
     #[structopt(name = "url")]
     pub(crate) url: String,
 
-    /// File destination of artifact
+    /// File destination of artifact, or - to write the artifact to stdout
     #[structopt(long = "destination")]
     pub(crate) destination: Option<String>,
 
@@ -71,6 +150,48 @@ pub(crate) struct PullCommand {
     #[structopt(long = "allow-latest")]
     pub(crate) allow_latest: bool,
 
+    /// Number of times to retry after transient registry errors
+    #[structopt(long = "retries", default_value = "3")]
+    pub(crate) retries: u32,
+
+    /// Additional layer media type to accept, for artifacts pushed by other tools
+    #[structopt(long = "media-type")]
+    pub(crate) media_type: Option<String>,
+
+    /// Always download the artifact, bypassing the local OCI cache
+    #[structopt(long = "no-cache")]
+    pub(crate) no_cache: bool,
+
+    /// Maximum number of seconds the pull may take, including retries
+    #[structopt(long = "timeout", default_value = "300")]
+    pub(crate) timeout: u64,
+
+    /// Only download the layer of this architecture target in the format ARCH-OS (e.g.
+    /// x86_64-linux), from a provider archive pushed with --split-targets or an image index
+    #[structopt(long = "platform")]
+    pub(crate) platform: Option<String>,
+
+    /// Also write a sha256sum compatible checksum of the artifact to <destination>.sha256
+    #[structopt(long = "checksum")]
+    pub(crate) checksum: bool,
+
+    /// Write each layer to a separate file in the destination directory, named after its
+    /// target or title annotation. Artifacts with more than one layer are always written so
+    #[structopt(long = "all-layers", conflicts_with = "platform")]
+    pub(crate) all_layers: bool,
+
+    /// Fail unless the artifact is an actor or provider archive with claims whose signature is
+    /// valid and that are neither expired nor not yet valid. Nothing is written if it isn't
+    #[structopt(
+        long = "verify-claims",
+        conflicts_with_all = &["platform", "all-layers"]
+    )]
+    pub(crate) verify_claims: bool,
+
+    /// TOML or JSON file of per registry settings, defaults to $HOME/.wash/registries.toml
+    #[structopt(long = "registry-config", env = "WASH_REGISTRY_CONFIG")]
+    pub(crate) registry_config: Option<String>,
+
     #[structopt(flatten)]
     pub(crate) output: Output,
 
@@ -84,11 +205,12 @@ pub(crate) struct PushCommand {
     #[structopt(name = "url")]
     pub(crate) url: String,
 
-    /// Path to artifact to push
+    /// Path to artifact to push, or - to read it from stdin
     #[structopt(name = "artifact")]
     pub(crate) artifact: String,
 
-    /// Path to config file, if omitted will default to a blank configuration
+    /// Path to config file, if omitted will default to the capability contract ID of a provider
+    /// archive, or a blank configuration for actors
     #[structopt(short = "c", long = "config")]
     pub(crate) config: Option<String>,
 
@@ -96,6 +218,249 @@ pub(crate) struct PushCommand {
     #[structopt(long = "allow-latest")]
     pub(crate) allow_latest: bool,
 
+    /// Author(s) of the artifact, added to the `org.opencontainers.image.authors` annotation
+    #[structopt(long = "author")]
+    pub(crate) author: Option<String>,
+
+    /// RFC3339 creation timestamp of the artifact, defaults to the current time
+    #[structopt(long = "created")]
+    pub(crate) created: Option<String>,
+
+    /// Annotation to add to the artifact manifest in the format KEY=VALUE
+    /// (e.g. org.opencontainers.image.source=https://github.com/wasmcloud/wash)
+    #[structopt(long = "annotation", name = "annotations")]
+    pub(crate) annotations: Vec<String>,
+
+    /// Push each architecture target of a provider archive as a separate layer
+    #[structopt(long = "split-targets")]
+    pub(crate) split_targets: bool,
+
+    /// Additional tag to push the artifact under, reusing the uploaded layers (e.g. --tag 1.2
+    /// --tag latest). Can be repeated
+    #[structopt(long = "tag", name = "tags")]
+    pub(crate) tags: Vec<String>,
+
+    /// Media type of the artifact layer, overriding the type detected from the artifact
+    #[structopt(long = "media-type")]
+    pub(crate) media_type: Option<String>,
+
+    /// Number of times to retry after transient registry errors
+    #[structopt(long = "retries", default_value = "3")]
+    pub(crate) retries: u32,
+
+    /// SBOM (SPDX or CycloneDX JSON) to attach to the pushed artifact as an OCI referrer
+    #[structopt(long = "sbom", parse(from_os_str))]
+    pub(crate) sbom: Option<PathBuf>,
+
+    /// Maximum number of seconds the push may take, including retries
+    #[structopt(long = "timeout", default_value = "300")]
+    pub(crate) timeout: u64,
+
+    /// TOML or JSON file of per registry settings, defaults to $HOME/.wash/registries.toml
+    #[structopt(long = "registry-config", env = "WASH_REGISTRY_CONFIG")]
+    pub(crate) registry_config: Option<String>,
+
+    #[structopt(flatten)]
+    pub(crate) output: Output,
+
+    #[structopt(flatten)]
+    pub(crate) opts: AuthOpts,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub(crate) struct ListCommand {
+    /// Repository to list artifacts of (e.g. localhost:5000/echo)
+    #[structopt(name = "repository")]
+    pub(crate) repository: String,
+
+    /// TOML or JSON file of per registry settings, defaults to $HOME/.wash/registries.toml
+    #[structopt(long = "registry-config", env = "WASH_REGISTRY_CONFIG")]
+    pub(crate) registry_config: Option<String>,
+
+    #[structopt(flatten)]
+    pub(crate) output: Output,
+
+    #[structopt(flatten)]
+    pub(crate) opts: AuthOpts,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub(crate) struct TagsCommand {
+    /// Repository to list tags of (e.g. localhost:5000/echo)
+    #[structopt(name = "repository")]
+    pub(crate) repository: String,
+
+    /// Maximum number of tags to request from the registry
+    #[structopt(long = "limit")]
+    pub(crate) limit: Option<usize>,
+
+    /// List the tags following this one, as printed when more tags remain
+    #[structopt(long = "last")]
+    pub(crate) last: Option<String>,
+
+    /// Only print tags matching this glob, where `*` matches any run of characters and `?` a
+    /// single character (e.g. "0.2.*")
+    #[structopt(long = "filter")]
+    pub(crate) filter: Option<String>,
+
+    /// TOML or JSON file of per registry settings, defaults to $HOME/.wash/registries.toml
+    #[structopt(long = "registry-config", env = "WASH_REGISTRY_CONFIG")]
+    pub(crate) registry_config: Option<String>,
+
+    #[structopt(flatten)]
+    pub(crate) output: Output,
+
+    #[structopt(flatten)]
+    pub(crate) opts: AuthOpts,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub(crate) struct DeleteCommand {
+    /// URL of artifact to delete
+    #[structopt(name = "url")]
+    pub(crate) url: String,
+
+    /// Delete the artifact without asking for confirmation
+    #[structopt(short = "y", long = "force", alias = "yes")]
+    pub(crate) force: bool,
+
+    /// TOML or JSON file of per registry settings, defaults to $HOME/.wash/registries.toml
+    #[structopt(long = "registry-config", env = "WASH_REGISTRY_CONFIG")]
+    pub(crate) registry_config: Option<String>,
+
+    #[structopt(flatten)]
+    pub(crate) output: Output,
+
+    #[structopt(flatten)]
+    pub(crate) opts: AuthOpts,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub(crate) struct ManifestCommand {
+    /// URL of artifact to fetch the manifest of
+    #[structopt(name = "url")]
+    pub(crate) url: String,
+
+    /// Print the manifest exactly as the registry returned it instead of pretty-printing it
+    #[structopt(long = "raw")]
+    pub(crate) raw: bool,
+
+    /// TOML or JSON file of per registry settings, defaults to $HOME/.wash/registries.toml
+    #[structopt(long = "registry-config", env = "WASH_REGISTRY_CONFIG")]
+    pub(crate) registry_config: Option<String>,
+
+    #[structopt(flatten)]
+    pub(crate) output: Output,
+
+    #[structopt(flatten)]
+    pub(crate) opts: AuthOpts,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub(crate) struct InspectConfigCommand {
+    /// URL of artifact to fetch the config of
+    #[structopt(name = "url")]
+    pub(crate) url: String,
+
+    /// Print the config exactly as it was pushed instead of pretty-printing it
+    #[structopt(long = "raw")]
+    pub(crate) raw: bool,
+
+    /// TOML or JSON file of per registry settings, defaults to $HOME/.wash/registries.toml
+    #[structopt(long = "registry-config", env = "WASH_REGISTRY_CONFIG")]
+    pub(crate) registry_config: Option<String>,
+
+    #[structopt(flatten)]
+    pub(crate) output: Output,
+
+    #[structopt(flatten)]
+    pub(crate) opts: AuthOpts,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub(crate) struct CopyCommand {
+    /// URL of the artifact to copy (e.g. staging.azurecr.io/echo:0.2.0)
+    #[structopt(name = "source")]
+    pub(crate) source: String,
+
+    /// URL to copy the artifact to (e.g. prod.azurecr.io/echo:0.2.0)
+    #[structopt(name = "destination")]
+    pub(crate) destination: String,
+
+    /// OCI username for the source registry, if omitted stored credentials or anonymous authentication will be used
+    #[structopt(long = "src-user")]
+    pub(crate) src_user: Option<String>,
+
+    /// OCI password for the source registry
+    #[structopt(long = "src-password")]
+    pub(crate) src_password: Option<String>,
+
+    /// Allow insecure (HTTP) connections to the source registry
+    #[structopt(long = "src-insecure")]
+    pub(crate) src_insecure: bool,
+
+    /// OCI username for the destination registry, if omitted stored credentials or anonymous authentication will be used
+    #[structopt(long = "dst-user")]
+    pub(crate) dst_user: Option<String>,
+
+    /// OCI password for the destination registry
+    #[structopt(long = "dst-password")]
+    pub(crate) dst_password: Option<String>,
+
+    /// Allow insecure (HTTP) connections to the destination registry
+    #[structopt(long = "dst-insecure")]
+    pub(crate) dst_insecure: bool,
+
+    /// Proxy URL for registry connections, overriding HTTP_PROXY and HTTPS_PROXY
+    #[structopt(long = "proxy")]
+    pub(crate) proxy: Option<String>,
+
+    /// TOML or JSON file of per registry settings, defaults to $HOME/.wash/registries.toml
+    #[structopt(long = "registry-config", env = "WASH_REGISTRY_CONFIG")]
+    pub(crate) registry_config: Option<String>,
+
+    /// Maximum number of seconds the copy may take
+    #[structopt(long = "timeout", default_value = "300")]
+    pub(crate) timeout: u64,
+
+    #[structopt(flatten)]
+    pub(crate) output: Output,
+}
+
+impl CopyCommand {
+    /// Connection options for the source registry
+    fn source_opts(&self) -> AuthOpts {
+        AuthOpts {
+            user: self.src_user.clone(),
+            password: self.src_password.clone(),
+            insecure: self.src_insecure,
+            proxy: self.proxy.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Connection options for the destination registry
+    fn destination_opts(&self) -> AuthOpts {
+        AuthOpts {
+            user: self.dst_user.clone(),
+            password: self.dst_password.clone(),
+            insecure: self.dst_insecure,
+            proxy: self.proxy.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub(crate) struct LoginCommand {
+    /// Registry to log in to (e.g. localhost:5000)
+    #[structopt(name = "registry")]
+    pub(crate) registry: String,
+
+    /// TOML or JSON file of per registry settings, defaults to $HOME/.wash/registries.toml
+    #[structopt(long = "registry-config", env = "WASH_REGISTRY_CONFIG")]
+    pub(crate) registry_config: Option<String>,
+
     #[structopt(flatten)]
     pub(crate) output: Output,
 
@@ -104,28 +469,147 @@ pub(crate) struct PushCommand {
 }
 
 #[derive(StructOpt, Debug, Clone)]
+pub(crate) struct LogoutCommand {
+    /// Registry to remove the stored credentials of (e.g. localhost:5000)
+    #[structopt(name = "registry", required_unless = "all")]
+    pub(crate) registry: Option<String>,
+
+    /// Remove the stored credentials of every registry
+    #[structopt(long = "all", conflicts_with = "registry")]
+    pub(crate) all: bool,
+
+    #[structopt(flatten)]
+    pub(crate) output: Output,
+}
+
+#[derive(StructOpt, Debug, Clone, Default)]
 pub(crate) struct AuthOpts {
-    /// OCI username, if omitted anonymous authentication will be used
-    #[structopt(
-        short = "u",
-        long = "user",
-        env = "WASH_REG_USER",
-        hide_env_values = true
-    )]
+    /// OCI username. If omitted, $WASH_REG_USER is used, or anonymous authentication if
+    /// that isn't set either
+    #[structopt(short = "u", long = "user")]
     pub(crate) user: Option<String>,
 
-    /// OCI password, if omitted anonymous authentication will be used
-    #[structopt(
-        short = "p",
-        long = "password",
-        env = "WASH_REG_PASSWORD",
-        hide_env_values = true
-    )]
+    /// OCI password. If omitted, $WASH_REG_PASSWORD is used, or anonymous authentication
+    /// if that isn't set either
+    #[structopt(short = "p", long = "password")]
     pub(crate) password: Option<String>,
 
+    /// Read the OCI password from stdin
+    #[structopt(long = "password-stdin", conflicts_with = "password")]
+    pub(crate) password_stdin: bool,
+
     /// Allow insecure (HTTP) registry connections
     #[structopt(long = "insecure")]
     pub(crate) insecure: bool,
+
+    /// Proxy URL for registry connections, overriding HTTP_PROXY and HTTPS_PROXY. Hosts
+    /// listed in NO_PROXY are still connected to directly
+    #[structopt(long = "proxy")]
+    pub(crate) proxy: Option<String>,
+
+    /// PEM encoded CA certificate to trust for the registry, set from the registry config
+    #[structopt(skip)]
+    pub(crate) ca_cert: Option<String>,
+}
+
+impl AuthOpts {
+    /// Returns the password given with `--password`, or reads it from stdin
+    /// when `--password-stdin` is set
+    pub(crate) fn password(&self) -> Result<Option<String>, Box<dyn ::std::error::Error>> {
+        if self.password_stdin {
+            read_password(&mut std::io::stdin().lock()).map(Some)
+        } else {
+            Ok(self.password.clone())
+        }
+    }
+
+    /// Fills in the settings of `registry` from the registry config file given with
+    /// `--registry-config`, or the default one if it exists. Flags take precedence over the
+    /// file, and so do credentials scoped to the host in the environment
+    pub(crate) fn for_registry(
+        &self,
+        registry: &str,
+        config: Option<&str>,
+    ) -> Result<AuthOpts, Box<dyn ::std::error::Error>> {
+        let path = match config {
+            Some(path) => PathBuf::from(path),
+            None => match registry_config_path() {
+                Some(path) if path.is_file() => path,
+                _ => return Ok(self.clone()),
+            },
+        };
+        let settings = load_registry_config(&path)?
+            .remove(registry)
+            .unwrap_or_default();
+        if host_env_credentials(registry, |name| env::var(name).ok()).is_some() {
+            return Ok(self.with_settings(RegistrySettings {
+                user: None,
+                password: None,
+                ..settings
+            }));
+        }
+        Ok(self.with_settings(settings))
+    }
+
+    fn with_settings(&self, settings: RegistrySettings) -> AuthOpts {
+        let mut opts = self.clone();
+        opts.insecure |= settings.insecure;
+        opts.ca_cert = settings.ca_cert;
+        // Credentials from the file are only used as a pair, and never combined with a
+        // user or password given on the command line
+        if opts.user.is_none() && opts.password.is_none() && !opts.password_stdin {
+            opts.user = settings.user;
+            opts.password = settings.password;
+        }
+        opts
+    }
+}
+
+/// Connection settings of a registry host in the registry config file
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct RegistrySettings {
+    /// Connect to the registry over HTTP
+    #[serde(default)]
+    insecure: bool,
+    /// Path to a PEM encoded CA certificate to trust for the registry
+    ca_cert: Option<String>,
+    user: Option<String>,
+    password: Option<String>,
+}
+
+/// Loads a registry config, mapping registry hosts such as `localhost:5000` to their
+/// settings. Files with a .json extension are read as JSON, any other file as TOML
+fn load_registry_config(
+    path: &Path,
+) -> Result<HashMap<String, RegistrySettings>, Box<dyn ::std::error::Error>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Unable to read registry config {}: {}", path.display(), e))?;
+    let is_json = matches!(path.extension().and_then(|e| e.to_str()), Some(e) if e.eq_ignore_ascii_case("json"));
+    let config = if is_json {
+        serde_json::from_str(&contents).map_err(|e| e.to_string())
+    } else {
+        serdeconv::from_toml_str(&contents).map_err(|e| e.to_string())
+    };
+    config.map_err(|e| format!("Invalid registry config {}: {}", path.display(), e).into())
+}
+
+/// Location of the default registry config, `$HOME/.wash/registries.toml`
+fn registry_config_path() -> Option<PathBuf> {
+    env::var("HOME")
+        .ok()
+        .map(|home| PathBuf::from(home).join(".wash").join(REGISTRY_CONFIG_FILE))
+}
+
+/// Reads a password from the input, trimming the trailing newline
+fn read_password(input: &mut impl Read) -> Result<String, Box<dyn ::std::error::Error>> {
+    let mut password = String::new();
+    input.read_to_string(&mut password)?;
+    let password = password.trim_end_matches(&['\n', '\r'][..]);
+    if password.is_empty() {
+        return Err("No password was provided on stdin".into());
+    }
+    Ok(password.to_string())
 }
 
 pub(crate) async fn handle_command(
@@ -134,47 +618,273 @@ pub(crate) async fn handle_command(
     match command {
         RegCliCommand::Pull(cmd) => handle_pull(cmd).await,
         RegCliCommand::Push(cmd) => handle_push(cmd).await,
+        RegCliCommand::List(cmd) => handle_list(cmd).await,
+        RegCliCommand::Tags(cmd) => handle_tags(cmd).await,
+        RegCliCommand::Delete(cmd) => handle_delete(cmd).await,
+        RegCliCommand::Manifest(cmd) => handle_manifest(cmd).await,
+        RegCliCommand::InspectConfig(cmd) => handle_inspect_config(cmd).await,
+        RegCliCommand::Copy(cmd) => handle_copy(cmd).await,
+        RegCliCommand::Login(cmd) => handle_login(cmd).await,
+        RegCliCommand::Logout(cmd) => handle_logout(cmd),
+    }
+}
+
+/// Creates an empty scratch file with a random name in the temp dir. The file is created
+/// exclusively, so a file or symlink planted under the same name is never written through
+fn create_scratch_file() -> std::io::Result<PathBuf> {
+    loop {
+        let path = env::temp_dir().join(format!("wash-pull-{:016x}.part", OsRng.next_u64()));
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(_) => return Ok(path),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
     }
 }
 
 pub(crate) async fn handle_pull(cmd: PullCommand) -> Result<String, Box<dyn ::std::error::Error>> {
-    let image: Reference = cmd.url.parse().unwrap();
-    let spinner = match cmd.output.kind {
-        OutputKind::Text { .. } if output_destination() == OutputDestination::Cli => {
-            Some(Spinner::new(
-                Spinners::Dots12,
-                format!(" Downloading {} ...", image.whole()),
-            ))
+    let image = parse_reference(&cmd.url)?;
+    check_latest_tag(&image, cmd.allow_latest)?;
+    let to_stdout = cmd.destination.as_deref() == Some(STDOUT_DESTINATION);
+    if to_stdout && output_destination() == OutputDestination::Repl {
+        return Err("Artifacts can't be written to stdout in the REPL".into());
+    }
+    if to_stdout && cmd.checksum {
+        return Err("--checksum can't be used when writing the artifact to stdout".into());
+    }
+    if to_stdout && cmd.all_layers {
+        return Err("--all-layers can't be used when writing the artifact to stdout".into());
+    }
+    let offline = offline_mode();
+    if offline && (cmd.no_cache || cmd.platform.is_some()) {
+        return Err(WashError::InvalidInput(
+            "--no-cache and --platform can't be used offline, as they need the registry".into(),
+        )
+        .into());
+    }
+    let show_progress = show_progress(&cmd.output.kind);
+    info!("Downloading {}", image.whole());
+    let opts = cmd
+        .opts
+        .for_registry(image.registry(), cmd.registry_config.as_deref())?;
+    let auth = registry_auth(&image, opts.user.clone(), opts.password()?);
+    // Layers are streamed to a scratch file so a failed pull never leaves a partial
+    // artifact at the destination
+    let partial = create_scratch_file()?;
+    // Target layers are accepted on their own with --platform, or with the claims layer of
+    // a provider archive pushed with --split-targets
+    let mut media_types = vec![
+        PROVIDER_ARCHIVE_MEDIA_TYPE,
+        WASM_MEDIA_TYPE,
+        OCI_MEDIA_TYPE,
+        PROVIDER_TARGET_MEDIA_TYPE,
+        PROVIDER_CLAIMS_MEDIA_TYPE,
+    ];
+    if let Some(media_type) = &cmd.media_type {
+        media_types.push(media_type);
+    }
+    // The cache holds whole artifacts by manifest digest, so a single platform isn't cached
+    let cache = if cmd.no_cache || cmd.platform.is_some() {
+        None
+    } else {
+        Some(oci_cache_dir())
+    };
+    let (image, auth, partial, opts) = (&image, &auth, &partial, &opts);
+    let (media_types, cache, platform) = (&media_types, cache.as_deref(), cmd.platform.as_deref());
+    let downloaded = if offline {
+        let cache_dir = oci_cache_dir();
+        cached_artifact(&cache_dir, image, cmd.digest.clone()).and_then(|pulled| {
+            copy(cached_artifact_path(&cache_dir, &pulled.digest), partial)?;
+            Ok(pulled)
+        })
+    } else {
+        let retried = with_retries(cmd.retries, RETRY_BASE_DELAY, || {
+            let digest = cmd.digest.clone();
+            async move {
+                let registry = RegistryApi::connect(image, auth, opts, "pull").await?;
+                let mut writer = BufWriter::new(
+                    OpenOptions::new()
+                        .write(true)
+                        .truncate(true)
+                        .open(partial)?,
+                );
+                let pulled = download_artifact(
+                    &registry,
+                    digest,
+                    media_types,
+                    platform,
+                    show_progress,
+                    cache,
+                    &mut writer,
+                )
+                .await?;
+                writer.flush()?;
+                Ok(pulled)
+            }
+        });
+        until_interrupted(with_timeout(cmd.timeout, retried)).await
+    };
+    let pulled = match downloaded {
+        Ok(pulled) => pulled,
+        Err(e) => {
+            let _ = remove_file(partial);
+            if e.is::<Aborted>() {
+                return Err(e);
+            }
+            return Err(pull_stage_error(PullStage::Download, image, e));
         }
-        _ => None,
     };
-    info!("Downloading {}", image.whole());
-    let artifact = pull_artifact(
-        cmd.url,
-        cmd.digest,
-        cmd.allow_latest,
-        cmd.opts.user,
-        cmd.opts.password,
-        cmd.opts.insecure,
-    )
-    .await?;
+    if let (Some(cache), false) = (cache, offline) {
+        // A failure to populate the cache shouldn't fail the pull itself
+        let stored = if pulled.cached {
+            Ok(())
+        } else {
+            File::open(partial).map_err(|e| e.into()).and_then(|mut f| {
+                store_cached_artifact(cache, &pulled.digest, &pulled.layers, &mut f)
+            })
+        };
+        if let Err(e) = stored.and_then(|_| store_cached_reference(cache, image, &pulled)) {
+            debug!("Failed to cache {}: {}", image.whole(), e);
+        }
+    }
+
+    // A provider archive pushed with --split-targets is reassembled from its claims and
+    // target layers, unless its layers were asked for on their own
+    let split_archive = !cmd.all_layers && is_split_archive(&pulled.layers);
+    if split_archive {
+        if let Err(e) = assemble_split_archive(partial, &pulled.layers) {
+            let _ = remove_file(partial);
+            return Err(pull_stage_error(PullStage::Validate, image, e));
+        }
+    }
+    let multiple_layers = pulled.layers.len() > 1 && !split_archive;
+    let media_type = if split_archive {
+        PROVIDER_ARCHIVE_MEDIA_TYPE
+    } else {
+        pulled.media_type.as_str()
+    };
 
-    let outfile = write_artifact(&artifact, &image, cmd.destination)?;
+    if cmd.verify_claims {
+        let verified = if multiple_layers {
+            Err(WashError::Validation(format!(
+                "claims of an artifact with {} layers can't be verified",
+                pulled.layers.len()
+            ))
+            .into())
+        } else {
+            std::fs::read(partial)
+                .map_err(Into::into)
+                .and_then(|artifact| verify_claims(&artifact))
+        };
+        if let Err(e) = verified {
+            let _ = remove_file(partial);
+            return Err(pull_stage_error(PullStage::Validate, image, e));
+        }
+    }
 
-    if spinner.is_some() {
-        spinner.unwrap().stop();
+    if cmd.all_layers || multiple_layers {
+        let files = if to_stdout {
+            Err(WashError::InvalidInput(format!(
+                "{} has {} layers, which can't be written to stdout",
+                image.whole(),
+                pulled.layers.len()
+            ))
+            .into())
+        } else {
+            write_layers(partial, image, &pulled.layers, cmd.destination)
+        };
+        let _ = remove_file(partial);
+        return layers_output(image, &pulled, files?, cmd.checksum, &cmd.output.kind);
     }
 
+    let outfile = write_artifact(partial, image, media_type, cmd.destination);
+    let _ = remove_file(partial);
+    let outfile = outfile?;
+    let checksum_file = if cmd.checksum {
+        Some(write_checksum_file(&outfile)?)
+    } else {
+        None
+    };
+
+    let result = json!({
+        "result": "success",
+        "file": outfile,
+        "digest": pulled.digest,
+        "media_type": media_type,
+        "size": pulled.size,
+        "cached": pulled.cached,
+        "checksum_file": checksum_file,
+    });
+    if to_stdout {
+        // Stdout holds the artifact, so the result is reported on stderr
+        match cmd.output.kind {
+            OutputKind::Json => eprintln!("{}", result),
+            OutputKind::Text { .. } if !quiet_mode() => eprintln!(
+                "Successfully pulled and validated {} to stdout",
+                image.whole()
+            ),
+            OutputKind::Text { .. } => (),
+        }
+        return Ok(String::new());
+    }
     Ok(format_output(
-        format!(
-            "\n{} Successfully pulled and validated {}",
-            SHOWER_EMOJI, outfile
+        success_output(
+            match &checksum_file {
+                Some(checksum_file) => format!(
+                    "Successfully pulled and validated {}, checksum in {}",
+                    outfile, checksum_file
+                ),
+                None => format!("Successfully pulled and validated {}", outfile),
+            },
+            outfile.clone(),
+            &cmd.output.kind,
         ),
-        json!({"result": "success", "file": outfile}),
+        result,
         &cmd.output.kind,
     ))
 }
 
+/// Reports the files the layers of an artifact were written to by `write_layers`
+fn layers_output(
+    image: &Reference,
+    pulled: &PulledArtifact,
+    (directory, files): (String, Vec<String>),
+    checksum: bool,
+    output_kind: &OutputKind,
+) -> Result<String, Box<dyn ::std::error::Error>> {
+    let checksum_files = if checksum {
+        files
+            .iter()
+            .map(|f| write_checksum_file(f))
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        vec![]
+    };
+    Ok(format_output(
+        success_output(
+            format!(
+                "Successfully pulled and validated {} layer(s) of {} to {}\n{}",
+                files.len(),
+                image.whole(),
+                directory,
+                files.join("\n")
+            ),
+            directory.clone(),
+            output_kind,
+        ),
+        json!({
+            "result": "success",
+            "directory": directory,
+            "files": files,
+            "digest": pulled.digest,
+            "size": pulled.size,
+            "cached": pulled.cached,
+            "checksum_files": checksum_files,
+        }),
+        output_kind,
+    ))
+}
+
 pub(crate) async fn pull_artifact(
     url: String,
     digest: Option<String>,
@@ -182,102 +892,850 @@ pub(crate) async fn pull_artifact(
     user: Option<String>,
     password: Option<String>,
     insecure: bool,
+    proxy: Option<String>,
 ) -> Result<Vec<u8>, Box<dyn ::std::error::Error>> {
-    let image: Reference = url.parse()?;
+    let image = parse_reference(&url)?;
+    check_latest_tag(&image, allow_latest)?;
+    let cache = oci_cache_dir();
+    if offline_mode() {
+        let pulled = cached_artifact(&cache, &image, digest)?;
+        return Ok(std::fs::read(cached_artifact_path(&cache, &pulled.digest))?);
+    }
+    let opts = AuthOpts {
+        user,
+        password,
+        insecure,
+        proxy,
+        ..Default::default()
+    }
+    .for_registry(
+        image.registry(),
+        crate::config::env_var(REGISTRY_CONFIG_ENV).as_deref(),
+    )?;
+    let auth = registry_auth(&image, opts.user.clone(), opts.password.clone());
+    let registry = RegistryApi::connect(&image, &auth, &opts, "pull").await?;
+    let mut artifact = vec![];
+    let pulled = download_artifact(
+        &registry,
+        digest,
+        &[PROVIDER_ARCHIVE_MEDIA_TYPE, WASM_MEDIA_TYPE, OCI_MEDIA_TYPE],
+        None,
+        false,
+        Some(&cache),
+        &mut artifact,
+    )
+    .await?;
+    // Caching what was inspected lets it be inspected again offline
+    let stored = if pulled.cached {
+        Ok(())
+    } else {
+        store_cached_artifact(
+            &cache,
+            &pulled.digest,
+            &pulled.layers,
+            &mut artifact.as_slice(),
+        )
+    };
+    if let Err(e) = stored.and_then(|_| store_cached_reference(&cache, &image, &pulled)) {
+        debug!("Failed to cache {}: {}", image.whole(), e);
+    }
+    Ok(artifact)
+}
 
+fn digest_mismatch(expected: &str, actual: &str) -> Box<dyn ::std::error::Error> {
+    Box::new(WashError::Validation(format!(
+        "Digest mismatch: expected {} but got {}, aborting",
+        expected, actual
+    )))
+}
+
+fn check_latest_tag(
+    image: &Reference,
+    allow_latest: bool,
+) -> Result<(), Box<dyn ::std::error::Error>> {
     if image.tag().unwrap_or("latest") == "latest" && !allow_latest {
-        return Err(
+        return Err(Box::new(WashError::InvalidInput(
             "Pulling artifacts with tag 'latest' is prohibited. This can be overriden with a flag"
-                .into(),
-        );
+                .to_string(),
+        )));
     };
+    Ok(())
+}
 
-    let mut client = Client::new(ClientConfig {
-        protocol: if insecure {
-            ClientProtocol::Http
-        } else {
-            ClientProtocol::Https
-        },
-    });
+/// Reformats a digest in case the sha256: prefix is left off
+fn normalize_digest(digest: String) -> String {
+    if digest.starts_with("sha256:") {
+        digest
+    } else {
+        format!("sha256:{}", digest)
+    }
+}
 
-    let auth = match (user, password) {
-        (Some(user), Some(password)) => RegistryAuth::Basic(user, password),
-        _ => RegistryAuth::Anonymous,
-    };
+/// Manifest digest, media type of the first layer and total layer size of a
+/// downloaded artifact, and whether it was served from the local cache. The layers are
+/// written one after the other, in the order of their descriptors
+#[derive(Debug)]
+struct PulledArtifact {
+    digest: String,
+    media_type: String,
+    size: u64,
+    cached: bool,
+    layers: Vec<OciDescriptor>,
+}
 
-    let image_data = client
-        .pull(
-            &image,
-            &auth,
-            vec![PROVIDER_ARCHIVE_MEDIA_TYPE, WASM_MEDIA_TYPE, OCI_MEDIA_TYPE],
-        )
-        .await?;
+/// Directory of the OCI cache shared with the wasmcloud host, see `wash drain`
+fn oci_cache_dir() -> PathBuf {
+    env::temp_dir().join("wasmcloud_ocicache")
+}
 
-    // Reformatting digest in case the sha256: prefix is left off
-    let digest = match digest {
-        Some(d) if d.starts_with("sha256:") => Some(d),
-        Some(d) => Some(format!("sha256:{}", d)),
-        None => None,
-    };
+/// Path of the cache entry for an artifact, keyed by its manifest digest
+fn cached_artifact_path(cache: &Path, digest: &str) -> PathBuf {
+    cache.join(format!("{}.bin", digest.replace(':', "_")))
+}
 
-    match (digest, image_data.digest) {
-        (Some(digest), Some(image_digest)) if digest != image_digest => {
-            Err("Image digest did not match provided digest, aborting")
+/// Copies a downloaded artifact into the cache, under the digest of the manifest listing its
+/// `layers`. The entry is written under a scratch name and checked against the layer digests
+/// first, so a concurrent pull never reads a partially written or mismatched entry
+fn store_cached_artifact(
+    cache: &Path,
+    digest: &str,
+    layers: &[OciDescriptor],
+    downloaded: &mut impl Read,
+) -> Result<(), Box<dyn ::std::error::Error>> {
+    create_dir_all(cache)?;
+    let entry = cached_artifact_path(cache, digest);
+    let scratch = entry.with_extension(format!("{}.part", std::process::id()));
+    let stored = std::io::copy(downloaded, &mut File::create(&scratch)?)
+        .map_err(Into::into)
+        .and_then(|_| verify_layers(&mut File::open(&scratch)?, layers))
+        .and_then(|_| rename(&scratch, &entry).map_err(Into::into));
+    if stored.is_err() {
+        let _ = remove_file(&scratch);
+    }
+    stored
+}
+
+/// Opens the cache entry of an artifact after checking it still holds its layers. An entry
+/// that doesn't is removed, so the artifact is downloaded again
+fn open_cached_artifact(cache: &Path, digest: &str, layers: &[OciDescriptor]) -> Option<File> {
+    let entry = cached_artifact_path(cache, digest);
+    let mut cached = File::open(&entry).ok()?;
+    match verify_layers(&mut cached, layers) {
+        Ok(()) => {
+            cached.seek(std::io::SeekFrom::Start(0)).ok()?;
+            Some(cached)
         }
-        _ => {
-            debug!("Image digest validated against provided digest");
-            Ok(())
+        Err(e) => {
+            debug!("Discarding cache entry {}: {}", entry.display(), e);
+            let _ = remove_file(&entry);
+            None
         }
-    }?;
+    }
+}
 
-    Ok(image_data
-        .layers
-        .iter()
-        .map(|l| l.data.clone())
-        .flatten()
-        .collect::<Vec<_>>())
+/// Checks that `data` holds the layers one after the other, each matching its digest
+fn verify_layers(
+    data: &mut impl Read,
+    layers: &[OciDescriptor],
+) -> Result<(), Box<dyn ::std::error::Error>> {
+    for layer in layers {
+        let mut hasher = Sha256::new();
+        std::io::copy(
+            &mut data.by_ref().take(layer.size.max(0) as u64),
+            &mut hasher,
+        )?;
+        let digest = format!("sha256:{:x}", hasher.finalize());
+        if digest != layer.digest {
+            return Err(digest_mismatch(&layer.digest, &digest));
+        }
+    }
+    if data.read(&mut [0])? != 0 {
+        return Err(Box::new(WashError::Validation(
+            "Artifact is larger than its layers".to_string(),
+        )));
+    }
+    Ok(())
 }
 
-pub(crate) fn write_artifact(
-    artifact: &[u8],
+/// What is recorded about a cached artifact so it can be written out again offline
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedManifest {
+    media_type: String,
+    layers: Vec<OciDescriptor>,
+}
+
+/// Path of the record of a cached artifact's layers, next to the artifact itself
+fn cached_manifest_path(cache: &Path, digest: &str) -> PathBuf {
+    cache.join(format!("{}.json", digest.replace(':', "_")))
+}
+
+/// Path of the file holding the digest a reference resolved to when it was last pulled,
+/// followed by the reference itself. It's named after the hash of the reference so any
+/// registry, repository and tag can be stored
+fn cached_reference_path(cache: &Path, image: &Reference) -> PathBuf {
+    cache
+        .join("refs")
+        .join(format!("{:x}", Sha256::digest(image.whole().as_bytes())))
+}
+
+/// Records the layers of a cached artifact and the digest its reference resolved to, which
+/// is what offline pulls look up instead of asking the registry
+fn store_cached_reference(
+    cache: &Path,
+    image: &Reference,
+    pulled: &PulledArtifact,
+) -> Result<(), Box<dyn ::std::error::Error>> {
+    let manifest = CachedManifest {
+        media_type: pulled.media_type.clone(),
+        layers: pulled.layers.clone(),
+    };
+    std::fs::write(
+        cached_manifest_path(cache, &pulled.digest),
+        serde_json::to_vec(&manifest)?,
+    )?;
+    let reference = cached_reference_path(cache, image);
+    create_dir_all(reference.parent().unwrap_or(cache))?;
+    std::fs::write(reference, format!("{}\n{}\n", pulled.digest, image.whole()))?;
+    Ok(())
+}
+
+/// Lists the references of the artifacts in the local cache, for shell completions
+pub(crate) fn cached_references() -> Vec<String> {
+    cached_references_in(&oci_cache_dir())
+}
+
+fn cached_references_in(cache: &Path) -> Vec<String> {
+    let entries = match std::fs::read_dir(cache.join("refs")) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+    let mut references = entries
+        .filter_map(|e| std::fs::read_to_string(e.ok()?.path()).ok())
+        .filter_map(|record| Some(record.lines().nth(1)?.trim().to_string()))
+        .filter(|reference| !reference.is_empty())
+        .collect::<Vec<_>>();
+    references.sort();
+    references
+}
+
+/// Resolves an artifact strictly from the cache for `--offline`, by the digest of the
+/// reference or the one its tag resolved to when it was last pulled
+fn cached_artifact(
+    cache: &Path,
+    image: &Reference,
+    digest: Option<String>,
+) -> Result<PulledArtifact, Box<dyn ::std::error::Error>> {
+    let not_cached = || -> Box<dyn ::std::error::Error> {
+        Box::new(WashError::NotFound(format!(
+            "{} is not in the local cache and wash is offline",
+            image.whole()
+        )))
+    };
+    let resolved = match image.digest() {
+        Some(digest) => digest.to_string(),
+        None => std::fs::read_to_string(cached_reference_path(cache, image))
+            .map_err(|_| not_cached())?
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_string(),
+    };
+    if let Some(digest) = digest.map(normalize_digest) {
+        if digest != resolved {
+            return Err(digest_mismatch(&digest, &resolved));
+        }
+    }
+    let size = std::fs::metadata(cached_artifact_path(cache, &resolved))
+        .map_err(|_| not_cached())?
+        .len();
+    let manifest: CachedManifest = std::fs::read(cached_manifest_path(cache, &resolved))
+        .ok()
+        .and_then(|m| serde_json::from_slice(&m).ok())
+        .ok_or_else(not_cached)?;
+    if open_cached_artifact(cache, &resolved, &manifest.layers).is_none() {
+        return Err(not_cached());
+    }
+    info!("Using cached {} ({})", image.whole(), resolved);
+    Ok(PulledArtifact {
+        digest: resolved,
+        media_type: manifest.media_type,
+        size,
+        cached: true,
+        layers: manifest.layers,
+    })
+}
+
+/// Streams the layers of an artifact straight from the registry API into `out`, verifying
+/// the manifest digest if one was provided, the digest of each layer and that each layer
+/// has one of the accepted media types. When `show_progress` is set, a progress bar driven
+/// by the layer sizes is rendered to stderr. If `cache` holds an entry for the manifest
+/// digest, it's copied into `out` instead of downloading the layers
+async fn download_artifact(
+    registry: &RegistryApi<'_>,
+    digest: Option<String>,
+    accepted_media_types: &[&str],
+    platform: Option<&str>,
+    show_progress: bool,
+    cache: Option<&Path>,
+    out: &mut impl Write,
+) -> Result<PulledArtifact, Box<dyn ::std::error::Error>> {
+    let image = registry.image;
+    let reference = image.digest().or_else(|| image.tag()).unwrap_or("latest");
+    let (manifest, manifest_digest, from_index) = match platform {
+        Some(platform) => resolve_platform_manifest(registry, reference, platform).await?,
+        None => {
+            let (manifest, digest) = registry.manifest(reference).await?;
+            (manifest, digest, false)
+        }
+    };
+    if let Some(digest) = digest.map(normalize_digest) {
+        if digest != manifest_digest {
+            return Err(digest_mismatch(&digest, &manifest_digest));
+        }
+        debug!("Image digest validated against provided digest");
+    }
+    let layers = match platform {
+        Some(platform) if !from_index => platform_layers(&manifest.layers, platform)?,
+        _ => manifest.layers,
+    };
+    if let Some(layer) = layers
+        .iter()
+        .find(|l| !accepted_media_types.contains(&l.media_type.as_str()))
+    {
+        return Err(format!("Incompatible layer media type {}", layer.media_type).into());
+    }
+    let media_type = layers
+        .first()
+        .map(|l| l.media_type.clone())
+        .unwrap_or_default();
+
+    if let Some(cache) = cache {
+        if let Some(mut cached) = open_cached_artifact(cache, &manifest_digest, &layers) {
+            info!("Using cached {} ({})", image.whole(), manifest_digest);
+            let size = std::io::copy(&mut cached, out)?;
+            return Ok(PulledArtifact {
+                digest: manifest_digest,
+                media_type,
+                size,
+                cached: true,
+                layers,
+            });
+        }
+    }
+
+    let total: u64 = layers.iter().map(|l| l.size.max(0) as u64).sum();
+    let progress = if !show_progress {
+        ProgressBar::hidden()
+    } else if total > 0 {
+        let bar = ProgressBar::new(total);
+        bar.set_style(
+            ProgressStyle::default_bar()
+                .template(" Downloading {msg} [{bar:30}] {bytes}/{total_bytes} ({eta})")
+                .progress_chars("=> ")
+                .on_finish(ProgressFinish::AndClear),
+        );
+        bar
+    } else {
+        let spinner = ProgressBar::new_spinner();
+        spinner.set_style(
+            ProgressStyle::default_spinner()
+                .template(" {spinner} Downloading {msg} ...")
+                .on_finish(ProgressFinish::AndClear),
+        );
+        spinner.enable_steady_tick(80);
+        spinner
+    };
+    progress.set_message(image.whole());
+
+    let copied: Result<u64, Box<dyn ::std::error::Error>> = async {
+        let mut size = 0;
+        for layer in &layers {
+            let mut res = registry
+                .request(reqwest::Method::GET, &format!("blobs/{}", layer.digest))
+                .send()
+                .await?;
+            if !res.status().is_success() {
+                return Err(status_error(
+                    format!("Failed to fetch layer {}", layer.digest),
+                    res.status(),
+                ));
+            }
+            let mut hasher = Sha256::new();
+            while let Some(chunk) = res.chunk().await? {
+                hasher.update(&chunk);
+                out.write_all(&chunk)?;
+                size += chunk.len() as u64;
+                progress.inc(chunk.len() as u64);
+            }
+            let layer_digest = format!("sha256:{:x}", hasher.finalize());
+            if layer_digest != layer.digest {
+                return Err(digest_mismatch(&layer.digest, &layer_digest));
+            }
+        }
+        Ok(size)
+    }
+    .await;
+    progress.finish_and_clear();
+
+    Ok(PulledArtifact {
+        digest: manifest_digest,
+        size: copied?,
+        media_type,
+        cached: false,
+        layers,
+    })
+}
+
+/// Resolves the manifest to pull a platform from. When the reference points at an image index,
+/// the manifest for the platform is followed and the returned flag is set. The returned
+/// digest is always the one of the referenced manifest or index
+async fn resolve_platform_manifest(
+    registry: &RegistryApi<'_>,
+    reference: &str,
+    platform: &str,
+) -> Result<(OciManifest, String, bool), Box<dyn ::std::error::Error>> {
+    let raw = registry
+        .raw_manifest(
+            reference,
+            &[
+                OCI_IMAGE_MEDIA_TYPE,
+                IMAGE_MANIFEST_MEDIA_TYPE,
+                OCI_INDEX_MEDIA_TYPE,
+                DOCKER_MANIFEST_LIST_MEDIA_TYPE,
+            ],
+        )
+        .await?;
+    let value: serde_json::Value = serde_json::from_slice(&raw.body)?;
+    if value.get("manifests").is_none() {
+        return Ok((serde_json::from_value(value)?, raw.digest, false));
+    }
+    let index: ImageIndex = serde_json::from_value(value)?;
+    let platform_digest = index_platform_manifest(&index, platform)?;
+    debug!(
+        "Using manifest {} for platform {}",
+        platform_digest, platform
+    );
+    let (manifest, _) = registry.manifest(&platform_digest).await?;
+    Ok((manifest, raw.digest, true))
+}
+
+/// An OCI image index (or Docker manifest list), referring to a manifest per platform
+#[derive(Debug, Deserialize)]
+struct ImageIndex {
+    manifests: Vec<IndexEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IndexEntry {
+    digest: String,
+    platform: Option<IndexPlatform>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IndexPlatform {
+    architecture: String,
+    os: String,
+}
+
+impl IndexPlatform {
+    /// The platform in the ARCH-OS format of provider archive targets, translating the Go
+    /// architecture and OS names image indexes use
+    fn target(&self) -> String {
+        let arch = match self.architecture.as_str() {
+            "amd64" => "x86_64",
+            "arm64" => "aarch64",
+            "386" => "x86",
+            "ppc64le" => "powerpc64",
+            arch => arch,
+        };
+        let os = match self.os.as_str() {
+            "darwin" => "macos",
+            os => os,
+        };
+        format!("{}-{}", arch, os)
+    }
+}
+
+/// Finds the digest of the manifest for a platform in an image index
+fn index_platform_manifest(
+    index: &ImageIndex,
+    platform: &str,
+) -> Result<String, Box<dyn ::std::error::Error>> {
+    let entries: Vec<(String, &str)> = index
+        .manifests
+        .iter()
+        .filter_map(|m| Some((m.platform.as_ref()?.target(), m.digest.as_str())))
+        .collect();
+    match entries.iter().find(|(target, _)| target == platform) {
+        Some((_, digest)) => Ok(digest.to_string()),
+        None => Err(no_platform_error(
+            platform,
+            entries.into_iter().map(|(target, _)| target).collect(),
+        )),
+    }
+}
+
+/// Selects the layer of a platform from the layers of a provider archive pushed with
+/// `--split-targets`, which are annotated with their architecture target
+fn platform_layers(
+    layers: &[OciDescriptor],
+    platform: &str,
+) -> Result<Vec<OciDescriptor>, Box<dyn ::std::error::Error>> {
+    match layers.iter().find(|l| layer_target(l) == Some(platform)) {
+        Some(layer) => Ok(vec![layer.clone()]),
+        None => Err(no_platform_error(
+            platform,
+            layers
+                .iter()
+                .filter_map(layer_target)
+                .map(String::from)
+                .collect(),
+        )),
+    }
+}
+
+fn no_platform_error(platform: &str, available: Vec<String>) -> Box<dyn ::std::error::Error> {
+    let message = if available.is_empty() {
+        format!(
+            "No layer for platform {}, the artifact has no platform specific layers",
+            platform
+        )
+    } else {
+        format!(
+            "No layer for platform {}, available platforms: {}",
+            platform,
+            available.join(", ")
+        )
+    };
+    Box::new(WashError::NotFound(message))
+}
+
+/// Returns the reference without its `oci://` scheme, or `None` if the input doesn't
+/// explicitly refer to an OCI artifact
+pub(crate) fn strip_oci_scheme(input: &str) -> Option<&str> {
+    input.strip_prefix(OCI_SCHEME)
+}
+
+/// Parses an OCI reference, ignoring an `oci://` scheme and describing the offending
+/// reference on failure
+pub(crate) fn parse_reference(url: &str) -> Result<Reference, Box<dyn ::std::error::Error>> {
+    strip_oci_scheme(url).unwrap_or(url).parse().map_err(|e| {
+        WashError::InvalidInput(format!("Invalid OCI reference '{}': {}", url, e)).into()
+    })
+}
+
+/// Validates a downloaded artifact and moves it to the output destination. The type of
+/// the artifact, and so its file extension, is taken from the layer media type, falling
+/// back to inspecting its contents when the media type isn't specific to wasmcloud. The
+/// artifact is only read into memory when its contents have to be validated
+pub(crate) fn write_artifact(
+    downloaded: &Path,
     image: &Reference,
+    media_type: &str,
     output: Option<String>,
 ) -> Result<String, Box<dyn ::std::error::Error>> {
-    let file_extension = match validate_artifact(&artifact, image.repository())? {
-        SupportedArtifacts::Par => PROVIDER_ARCHIVE_FILE_EXTENSION,
-        SupportedArtifacts::Wasm => WASM_FILE_EXTENSION,
+    let read = || {
+        std::fs::read(downloaded)
+            .map_err(|e| pull_stage_error(PullStage::Validate, image, e.into()))
     };
+    let name = image.repository();
+    let validated = match media_type_artifact(media_type) {
+        Some(SupportedArtifacts::Wasm) => {
+            validate_actor_module(&read()?, name).map(|_| SupportedArtifacts::Wasm)
+        }
+        Some(SupportedArtifacts::Par) => {
+            validate_provider_archive(&read()?, name).map(|_| SupportedArtifacts::Par)
+        }
+        // A native library was verified by its digest
+        Some(SupportedArtifacts::Target) => Ok(SupportedArtifacts::Target),
+        None => validate_artifact(&read()?, name),
+    };
+    let file_extension =
+        match validated.map_err(|e| pull_stage_error(PullStage::Validate, image, e))? {
+            SupportedArtifacts::Par => PROVIDER_ARCHIVE_FILE_EXTENSION,
+            SupportedArtifacts::Wasm => WASM_FILE_EXTENSION,
+            SupportedArtifacts::Target => PROVIDER_TARGET_FILE_EXTENSION,
+        };
+    if output.as_deref() == Some(STDOUT_DESTINATION) {
+        let stdout = std::io::stdout();
+        let mut stdout = stdout.lock();
+        return File::open(downloaded)
+            .and_then(|mut f| std::io::copy(&mut f, &mut stdout))
+            .and_then(|_| stdout.flush())
+            .map(|_| STDOUT_DESTINATION.to_string())
+            .map_err(|e| pull_stage_error(PullStage::Write, image, e.into()));
+    }
     // Output to provided file, or use artifact_name.file_extension
-    let outfile = output.unwrap_or(format!(
-        "{}{}",
-        image
-            .repository()
-            .to_string()
-            .split('/')
-            .collect::<Vec<_>>()
-            .pop()
-            .unwrap()
-            .to_string(),
-        file_extension
-    ));
-    let mut f = File::create(outfile.clone())?;
-    f.write_all(&artifact)?;
+    let outfile = output.unwrap_or(format!("{}{}", repository_name(image), file_extension));
+    create_parent_dir(&outfile).map_err(|e| pull_stage_error(PullStage::Write, image, e))?;
+    // Renaming fails across filesystems, in which case the artifact is copied instead
+    if rename(downloaded, &outfile).is_err() {
+        if let Err(e) = copy(downloaded, &outfile) {
+            // Don't leave a partially written artifact behind
+            let _ = remove_file(&outfile);
+            return Err(pull_stage_error(PullStage::Write, image, e.into()));
+        }
+    }
     Ok(outfile)
 }
 
-/// Helper function to determine artifact type and validate that it is
-/// a valid artifact of that type
+/// Splits a downloaded artifact into its layers, validating each one by its media type and
+/// writing it to its own file in `directory`, which defaults to the repository name.
+/// Every layer is validated before any is written, holding only a layer whose contents are
+/// validated in memory at a time. Returns the directory and the written files
+fn write_layers(
+    downloaded: &Path,
+    image: &Reference,
+    layers: &[OciDescriptor],
+    directory: Option<String>,
+) -> Result<(String, Vec<String>), Box<dyn ::std::error::Error>> {
+    let validate_error =
+        |e: Box<dyn ::std::error::Error>| pull_stage_error(PullStage::Validate, image, e);
+    let write_error = |e: std::io::Error| pull_stage_error(PullStage::Write, image, e.into());
+    let open = || {
+        File::open(downloaded)
+            .map(BufReader::new)
+            .map_err(|e| validate_error(e.into()))
+    };
+    let size = |layer: &OciDescriptor| layer.size.max(0) as u64;
+
+    let mut reader = open()?;
+    let mut remaining = std::fs::metadata(downloaded)
+        .map_err(|e| validate_error(e.into()))?
+        .len();
+    let mut names: Vec<String> = vec![];
+    for (index, layer) in layers.iter().enumerate() {
+        if size(layer) > remaining {
+            return Err(validate_error(
+                format!("Layer {} is truncated", layer.digest).into(),
+            ));
+        }
+        remaining -= size(layer);
+        match media_type_artifact(&layer.media_type) {
+            Some(SupportedArtifacts::Wasm) => read_layer(&mut reader, size(layer))
+                .and_then(|data| validate_actor_module(&data, image.repository())),
+            Some(SupportedArtifacts::Par) => read_layer(&mut reader, size(layer))
+                .and_then(|data| validate_provider_archive(&data, image.repository())),
+            // Native libraries and other layers were verified by their digest
+            Some(SupportedArtifacts::Target) | None => reader
+                .seek(SeekFrom::Current(size(layer) as i64))
+                .map(|_| ())
+                .map_err(Into::into),
+        }
+        .map_err(validate_error)?;
+        let mut name = layer_file_name(layer, index);
+        if names.contains(&name) {
+            name = format!("{}-{}", index, name);
+        }
+        names.push(name);
+    }
+
+    let directory = directory.unwrap_or_else(|| repository_name(image));
+    create_dir_all(&directory).map_err(write_error)?;
+    let mut reader = open()?;
+    let mut files = vec![];
+    for (layer, name) in layers.iter().zip(names) {
+        let path = Path::new(&directory).join(name);
+        File::create(&path)
+            .map(BufWriter::new)
+            .and_then(|mut file| {
+                std::io::copy(&mut (&mut reader).take(size(layer)), &mut file)?;
+                file.flush()
+            })
+            .map_err(write_error)?;
+        files.push(path.to_string_lossy().to_string());
+    }
+    Ok((directory, files))
+}
+
+/// Returns true if the layers are those of a provider archive pushed with `--split-targets`,
+/// a claims layer alongside one layer per architecture target
+fn is_split_archive(layers: &[OciDescriptor]) -> bool {
+    let (claims, targets): (Vec<_>, Vec<_>) = layers
+        .iter()
+        .partition(|l| l.media_type == PROVIDER_CLAIMS_MEDIA_TYPE);
+    claims.len() == 1
+        && !targets.is_empty()
+        && targets
+            .iter()
+            .all(|l| l.media_type == PROVIDER_TARGET_MEDIA_TYPE && layer_target(l).is_some())
+}
+
+fn layer_target(layer: &OciDescriptor) -> Option<&str> {
+    layer
+        .annotations
+        .as_ref()
+        .and_then(|a| a.get(PROVIDER_TARGET_ANNOTATION))
+        .map(String::as_str)
+}
+
+/// Replaces the downloaded layers of a provider archive pushed with `--split-targets` with
+/// the provider archive they were split from
+fn assemble_split_archive(
+    downloaded: &Path,
+    layers: &[OciDescriptor],
+) -> Result<(), Box<dyn ::std::error::Error>> {
+    let artifact = std::fs::read(downloaded)?;
+    let mut offset = 0;
+    let mut jwt = None;
+    let mut targets = vec![];
+    for layer in layers {
+        let size = layer.size.max(0) as usize;
+        let data = artifact
+            .get(offset..offset + size)
+            .ok_or_else(|| format!("Layer {} is truncated", layer.digest))?;
+        offset += size;
+        match layer_target(layer) {
+            Some(target) => targets.push((target, data)),
+            None => jwt = Some(String::from_utf8(data.to_vec())?),
+        }
+    }
+    let jwt = jwt.ok_or("Provider archive does not contain claims")?;
+    let archive = crate::par::assemble_archive(&jwt, &targets)?;
+    std::fs::write(downloaded, archive)?;
+    Ok(())
+}
+
+/// Names the file of a layer after its architecture target or title annotation, falling
+/// back to its position in the manifest. Only the last component of an annotation is used,
+/// so a layer can't be written outside of the destination directory
+fn layer_file_name(layer: &OciDescriptor, index: usize) -> String {
+    let annotation = |key| {
+        layer
+            .annotations
+            .as_ref()
+            .and_then(|a| a.get(key))
+            .and_then(|value| Path::new(value).file_name())
+            .map(|name| name.to_string_lossy().to_string())
+    };
+    if let Some(target) = annotation(PROVIDER_TARGET_ANNOTATION) {
+        return format!("{}{}", target, PROVIDER_TARGET_FILE_EXTENSION);
+    }
+    if let Some(title) = annotation(OCI_TITLE_ANNOTATION) {
+        return title;
+    }
+    let extension = match media_type_artifact(&layer.media_type) {
+        Some(SupportedArtifacts::Wasm) => WASM_FILE_EXTENSION,
+        Some(SupportedArtifacts::Par) => PROVIDER_ARCHIVE_FILE_EXTENSION,
+        Some(SupportedArtifacts::Target) => PROVIDER_TARGET_FILE_EXTENSION,
+        None => "",
+    };
+    format!("layer-{}{}", index, extension)
+}
+
+/// Last component of an image's repository, e.g. `echo` for `localhost:5000/wasmcloud/echo`
+fn repository_name(image: &Reference) -> String {
+    image
+        .repository()
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Reads the next `size` bytes of a downloaded artifact, which hold a single layer
+fn read_layer(reader: &mut impl Read, size: u64) -> Result<Vec<u8>, Box<dyn ::std::error::Error>> {
+    let mut data = Vec::with_capacity(size as usize);
+    reader.take(size).read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Returns the type of artifact a layer media type identifies, if any
+fn media_type_artifact(media_type: &str) -> Option<SupportedArtifacts> {
+    match media_type {
+        WASM_MEDIA_TYPE => Some(SupportedArtifacts::Wasm),
+        PROVIDER_ARCHIVE_MEDIA_TYPE => Some(SupportedArtifacts::Par),
+        PROVIDER_TARGET_MEDIA_TYPE => Some(SupportedArtifacts::Target),
+        _ => None,
+    }
+}
+
+/// Stages of `reg pull`, in the order they're completed
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PullStage {
+    Download,
+    Validate,
+    Write,
+}
+
+/// Describes which stage of a pull failed, and which stages had already succeeded
+fn pull_stage_error(
+    stage: PullStage,
+    image: &Reference,
+    e: Box<dyn ::std::error::Error>,
+) -> Box<dyn ::std::error::Error> {
+    let (action, completed) = match stage {
+        PullStage::Download => ("download", "nothing was written"),
+        PullStage::Validate => ("validate", "artifact was downloaded"),
+        PullStage::Write => ("write", "artifact was downloaded and validated"),
+    };
+    let message = format!(
+        "Failed to {} {} ({}): {}",
+        action,
+        image.whole(),
+        completed,
+        e
+    );
+    with_context(e, message)
+}
+
+/// Creates any missing parent directories of the provided output file, failing
+/// early if a parent path exists but is not a directory
+fn create_parent_dir(outfile: &str) -> Result<(), Box<dyn ::std::error::Error>> {
+    match Path::new(outfile).parent() {
+        Some(parent) if parent.as_os_str().is_empty() => Ok(()),
+        Some(parent) if parent.exists() && !parent.is_dir() => Err(format!(
+            "Unable to write artifact to {}, {} is not a directory",
+            outfile,
+            parent.display()
+        )
+        .into()),
+        Some(parent) => Ok(create_dir_all(parent)?),
+        None => Ok(()),
+    }
+}
+
+/// Determines the type of an artifact. WebAssembly modules must be signed actors,
+/// anything else must be a loadable provider archive
 pub(crate) fn validate_artifact(
     artifact: &[u8],
     name: &str,
 ) -> Result<SupportedArtifacts, Box<dyn ::std::error::Error>> {
-    match validate_actor_module(artifact, name) {
-        Ok(_) => Ok(SupportedArtifacts::Wasm),
-        Err(_) => match validate_provider_archive(artifact, name) {
-            Ok(_) => Ok(SupportedArtifacts::Par),
-            Err(_) => Err("Unsupported artifact type".into()),
-        },
+    if artifact.starts_with(WASM_MAGIC) {
+        return validate_actor_module(artifact, name).map(|_| SupportedArtifacts::Wasm);
     }
+    match validate_provider_archive(artifact, name) {
+        Ok(_) => Ok(SupportedArtifacts::Par),
+        Err(_) => Err(Box::new(WashError::Validation(format!(
+            "Unsupported artifact type: {}",
+            name
+        )))),
+    }
+}
+
+/// Requires the claims of an actor module or provider archive to be present, validly signed
+/// and within their validity period, enforcing a signing policy for `reg pull --verify-claims`
+fn verify_claims(artifact: &[u8]) -> Result<(), Box<dyn ::std::error::Error>> {
+    let verified = if artifact.starts_with(WASM_MAGIC) {
+        wascap::wasm::extract_claims(artifact)
+            .map_err(|e| e.into())
+            .and_then(|token| token.ok_or_else(|| "actor module is not signed".into()))
+            .and_then(|token| {
+                let validation = validate_token::<Actor>(&token.jwt)?;
+                if !validation.signature_valid {
+                    Err("actor claims signature is invalid".into())
+                } else if validation.expired || validation.cannot_use_yet {
+                    Err("actor claims are expired or not yet valid".into())
+                } else {
+                    Ok(())
+                }
+            })
+    } else {
+        crate::par::archive_claims_token(artifact)
+            .and_then(|jwt| crate::par::validate_archive_signature(&jwt))
+    };
+    verified.map_err(|e| WashError::Validation(format!("Claims verification failed: {}", e)).into())
 }
 
 /// Attempts to inspect the claims of an actor module
@@ -286,10 +1744,19 @@ fn validate_actor_module(
     artifact: &[u8],
     module: &str,
 ) -> Result<(), Box<dyn ::std::error::Error>> {
+    if crate::claims::is_provider_module(artifact) {
+        return Err(Box::new(WashError::Validation(format!(
+            "{} is a wasm-based capability provider, only actor modules and provider archives are supported",
+            module
+        ))));
+    }
     match wascap::wasm::extract_claims(&artifact) {
         Ok(Some(_token)) => Ok(()),
-        Ok(None) => Err(format!("No capabilities discovered in actor module : {}", &module).into()),
-        Err(e) => Err(Box::new(e)),
+        Ok(None) => Err(Box::new(WashError::Validation(format!(
+            "No capabilities found in actor module {}",
+            &module
+        )))),
+        Err(e) => Err(Box::new(WashError::Validation(e.to_string()))),
     }
 }
 
@@ -301,297 +1768,4347 @@ fn validate_provider_archive(
 ) -> Result<(), Box<dyn ::std::error::Error>> {
     match ProviderArchive::try_load(artifact) {
         Ok(_par) => Ok(()),
-        Err(_e) => Err(format!("Invalid provider archive : {}", archive).into()),
+        Err(_e) => Err(Box::new(WashError::Validation(format!(
+            "Invalid provider archive : {}",
+            archive
+        )))),
     }
 }
 
 pub(crate) async fn handle_push(cmd: PushCommand) -> Result<String, Box<dyn ::std::error::Error>> {
-    let spinner = match cmd.output.kind {
-        OutputKind::Text { .. } if output_destination() == OutputDestination::Cli => {
-            Some(Spinner::new(
-                Spinners::Dots12,
-                format!(" Pushing {} to {} ...", cmd.artifact, cmd.url),
-            ))
-        }
-        _ => None,
+    let spinner = if show_progress(&cmd.output.kind) {
+        Some(start_spinner(format!(
+            "Pushing {} to {} ...",
+            cmd.artifact, cmd.url
+        )))
+    } else {
+        None
     };
     info!(" Pushing {} to {} ...", cmd.artifact, cmd.url);
 
-    push_artifact(
-        cmd.url.clone(),
-        cmd.artifact,
-        cmd.config,
-        cmd.allow_latest,
-        cmd.opts.user,
-        cmd.opts.password,
-        cmd.opts.insecure,
-    )
-    .await?;
+    let pushed = until_interrupted(with_timeout(cmd.timeout, push_artifact(&cmd))).await?;
+    let sbom = match cmd.sbom {
+        Some(ref sbom) => match push_sbom(&cmd, &pushed, sbom).await {
+            Ok(digest) => Some(digest),
+            Err(e) => {
+                eprintln!("Warning: SBOM was not attached, {}", e);
+                None
+            }
+        },
+        None => None,
+    };
 
-    if spinner.is_some() {
-        spinner.unwrap().stop();
+    if let Some(spinner) = spinner {
+        spinner.finish_and_clear();
     }
+    let sbom_line = sbom
+        .as_ref()
+        .map(|digest| format!("\nSBOM: {}", digest))
+        .unwrap_or_default();
+    let tags_line = if pushed.tags.len() > 1 {
+        format!("\nTags: {}", pushed.tags.join(", "))
+    } else {
+        String::new()
+    };
     Ok(format_output(
-        format!(
-            "\n{} Successfully validated and pushed to {}",
-            SHOWER_EMOJI, cmd.url
+        success_output(
+            format!(
+                "Successfully validated and pushed to {}\nDigest: {}\nSize: {} bytes\nPinned reference: {}{}{}",
+                cmd.url, pushed.digest, pushed.size, pushed.reference, tags_line, sbom_line
+            ),
+            pushed.digest.clone(),
+            &cmd.output.kind,
         ),
-        json!({"result": "success", "url": cmd.url}),
+        json!({
+            "result": "success",
+            "url": cmd.url,
+            "digest": pushed.digest,
+            "size": pushed.size,
+            "reference": pushed.reference,
+            "tags": pushed.tags,
+            "sbom": sbom,
+        }),
         &cmd.output.kind,
     ))
 }
 
+/// Reads the artifact to push from a file, or buffers all of `stdin` when the path is -
+fn read_artifact(
+    path: &str,
+    stdin: &mut impl Read,
+) -> Result<Vec<u8>, Box<dyn ::std::error::Error>> {
+    let mut artifact = Vec::new();
+    if path == STDIN_ARTIFACT {
+        stdin.read_to_end(&mut artifact)?;
+        if artifact.is_empty() {
+            return Err("No artifact was provided on stdin".into());
+        }
+    } else {
+        File::open(path)?.read_to_end(&mut artifact)?;
+    }
+    Ok(artifact)
+}
+
+/// Reads the config file to push with an artifact, ensuring it's valid JSON
+fn read_config(path: &str) -> Result<Vec<u8>, Box<dyn ::std::error::Error>> {
+    let config =
+        std::fs::read(path).map_err(|e| format!("Unable to read config file {}: {}", path, e))?;
+    if let Err(e) = serde_json::from_slice::<serde_json::Value>(&config) {
+        return Err(format!("Config file {} is not valid JSON: {}", path, e).into());
+    }
+    Ok(config)
+}
+
+/// Builds the config pushed with an artifact when no `--config` is given. Provider archives
+/// record their capability contract ID so registries indexing on the config can tell them
+/// apart, anything else gets a blank config
+fn default_config(artifact: &[u8], artifact_type: &SupportedArtifacts) -> Vec<u8> {
+    let capid = match artifact_type {
+        SupportedArtifacts::Par => ProviderArchive::try_load(artifact)
+            .ok()
+            .and_then(|par| par.claims())
+            .and_then(|claims| claims.metadata)
+            .map(|metadata| metadata.capid),
+        SupportedArtifacts::Wasm | SupportedArtifacts::Target => None,
+    };
+    match capid {
+        Some(capid) => json!({ "capid": capid }).to_string().into_bytes(),
+        None => b"{}".to_vec(),
+    }
+}
+
+/// Digest of the manifest pushed for an artifact and the total size of its layers
+pub(crate) struct PushedArtifact {
+    pub(crate) digest: String,
+    pub(crate) size: usize,
+    /// Size of the manifest, needed to refer to the artifact from another manifest
+    pub(crate) manifest_size: usize,
+    /// Reference pinning the pushed artifact by digest
+    pub(crate) reference: String,
+    /// Every tag the artifact was pushed under, starting with the tag of its reference
+    pub(crate) tags: Vec<String>,
+}
+
 pub(crate) async fn push_artifact(
-    url: String,
-    artifact: String,
-    config: Option<String>,
-    allow_latest: bool,
-    user: Option<String>,
-    password: Option<String>,
-    insecure: bool,
-) -> Result<(), Box<dyn ::std::error::Error>> {
-    let image: Reference = url.parse().unwrap();
+    cmd: &PushCommand,
+) -> Result<PushedArtifact, Box<dyn ::std::error::Error>> {
+    let image = parse_reference(&cmd.url)?;
 
-    if image.tag().unwrap() == "latest" && !allow_latest {
-        return Err(
-            "Pushing artifacts with tag 'latest' is prohibited. This can be overriden with a flag"
-                .into(),
-        );
+    let tag = image
+        .tag()
+        .ok_or_else(|| format!("Invalid OCI reference '{}': missing tag", cmd.url))?;
+    let tags = push_tags(tag, &cmd.tags, cmd.allow_latest)?;
+
+    let annotations =
+        artifact_annotations(cmd.author.clone(), cmd.created.clone(), &cmd.annotations)?;
+
+    if cmd.artifact == STDIN_ARTIFACT && cmd.opts.password_stdin {
+        return Err("--password-stdin can't be used when reading the artifact from stdin".into());
+    }
+    let artifact_buf = read_artifact(&cmd.artifact, &mut std::io::stdin().lock())?;
+    let artifact_type = validate_artifact(&artifact_buf, &cmd.artifact)?;
+
+    let config_buf = match &cmd.config {
+        Some(config_file) => read_config(config_file)?,
+        None => default_config(&artifact_buf, &artifact_type),
+    };
+    let config_buf = annotate_config(config_buf, &annotations);
+
+    let (artifact_media_type, config_media_type) = match artifact_type {
+        SupportedArtifacts::Wasm => (WASM_MEDIA_TYPE, WASM_CONFIG_MEDIA_TYPE),
+        SupportedArtifacts::Par => (
+            PROVIDER_ARCHIVE_MEDIA_TYPE,
+            PROVIDER_ARCHIVE_CONFIG_MEDIA_TYPE,
+        ),
+        SupportedArtifacts::Target => (
+            PROVIDER_TARGET_MEDIA_TYPE,
+            PROVIDER_ARCHIVE_CONFIG_MEDIA_TYPE,
+        ),
     };
 
-    let mut config_buf = vec![];
-    match config {
-        Some(config_file) => {
-            let mut f = File::open(config_file)?;
-            f.read_to_end(&mut config_buf)?;
+    let artifact_media_type = cmd.media_type.as_deref().unwrap_or(artifact_media_type);
+
+    let layers = if cmd.split_targets {
+        if cmd.media_type.is_some() {
+            return Err("--media-type can't be used with --split-targets".into());
         }
-        None => {
-            // If no config provided, send blank config
-            config_buf = b"{}".to_vec();
+        if artifact_media_type != PROVIDER_ARCHIVE_MEDIA_TYPE {
+            return Err("--split-targets can only be used to push provider archives".into());
         }
+        target_layers(&artifact_buf)?
+    } else {
+        vec![(
+            descriptor(&artifact_buf, artifact_media_type),
+            ImageLayer {
+                data: artifact_buf,
+                media_type: artifact_media_type.to_string(),
+            },
+        )]
     };
 
-    let mut artifact_buf = vec![];
-    let mut f = File::open(artifact.clone())?;
-    f.read_to_end(&mut artifact_buf)?;
+    let manifest = OciManifest {
+        schema_version: 2,
+        media_type: Some(OCI_IMAGE_MEDIA_TYPE.to_string()),
+        config: descriptor(&config_buf, config_media_type),
+        layers: layers.iter().map(|(d, _)| d.clone()).collect(),
+        annotations: Some(annotations),
+    };
+    let manifest_bytes = serde_json::to_vec(&manifest)?;
+    let digest = format!("sha256:{:x}", Sha256::digest(&manifest_bytes));
+    let pushed = PushedArtifact {
+        reference: pinned_reference(&image, &digest),
+        digest,
+        size: layers.iter().map(|(_, l)| l.data.len()).sum(),
+        manifest_size: manifest_bytes.len(),
+        tags: tags.clone(),
+    };
 
-    let (artifact_media_type, config_media_type) =
-        match validate_artifact(&artifact_buf, &artifact)? {
-            SupportedArtifacts::Wasm => (WASM_MEDIA_TYPE, WASM_CONFIG_MEDIA_TYPE),
-            SupportedArtifacts::Par => (
-                PROVIDER_ARCHIVE_MEDIA_TYPE,
-                PROVIDER_ARCHIVE_CONFIG_MEDIA_TYPE,
-            ),
-        };
+    let opts = cmd
+        .opts
+        .for_registry(image.registry(), cmd.registry_config.as_deref())?;
+    let auth = registry_auth(&image, opts.user.clone(), opts.password()?);
+    let (image, layers, config_buf, auth, opts) = (&image, &layers, &config_buf, &auth, &opts);
+    let (manifest_bytes, tags) = (&manifest_bytes, &tags);
+    with_retries(cmd.retries, RETRY_BASE_DELAY, || async move {
+        let registry = RegistryApi::connect(image, auth, opts, "pull,push").await?;
+        registry.upload_blob(config_buf).await?;
+        for (_, layer) in layers {
+            registry.upload_blob(&layer.data).await?;
+        }
+        // Additional tags only need the manifest, as the registry already holds its blobs
+        for tag in tags {
+            registry.put_manifest(tag, manifest_bytes).await?;
+        }
+        Ok(())
+    })
+    .await?;
+    Ok(pushed)
+}
 
-    let image_data = ImageData {
-        layers: vec![ImageLayer {
-            data: artifact_buf,
-            media_type: artifact_media_type.to_string(),
-        }],
-        digest: None,
-    };
+/// Collects the tag of the pushed reference followed by the additional tags, without
+/// duplicates. Each must be a valid OCI tag, and `latest` is only allowed with `--allow-latest`
+fn push_tags(
+    tag: &str,
+    additional: &[String],
+    allow_latest: bool,
+) -> Result<Vec<String>, Box<dyn ::std::error::Error>> {
+    let mut tags = vec![tag.to_string()];
+    for tag in additional {
+        if !is_valid_tag(tag) {
+            return Err(format!(
+                "Invalid tag '{}', tags may contain up to 128 letters, digits, '_', '.' and '-' and can't start with '.' or '-'",
+                tag
+            )
+            .into());
+        }
+        if !tags.contains(tag) {
+            tags.push(tag.clone());
+        }
+    }
+    if tags.iter().any(|t| t == "latest") && !allow_latest {
+        return Err(
+            "Pushing artifacts with tag 'latest' is prohibited. This can be overriden with a flag"
+                .into(),
+        );
+    }
+    Ok(tags)
+}
 
-    let mut client = Client::new(ClientConfig {
-        protocol: if insecure {
-            ClientProtocol::Http
-        } else {
-            ClientProtocol::Https
-        },
-    });
+/// Checks a tag against the OCI distribution spec, `[a-zA-Z0-9_][a-zA-Z0-9._-]{0,127}`
+fn is_valid_tag(tag: &str) -> bool {
+    let valid_char = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '-';
+    tag.len() <= 128
+        && tag.chars().all(valid_char)
+        && tag
+            .chars()
+            .next()
+            .map(|c| c.is_ascii_alphanumeric() || c == '_')
+            .unwrap_or(false)
+}
 
-    let auth = match (user, password) {
-        (Some(user), Some(password)) => RegistryAuth::Basic(user, password),
-        _ => RegistryAuth::Anonymous,
-    };
+/// Attaches an SBOM to a pushed artifact with the OCI 1.1 referrers API, returning the digest
+/// of the referrer manifest
+async fn push_sbom(
+    cmd: &PushCommand,
+    pushed: &PushedArtifact,
+    sbom: &Path,
+) -> Result<String, Box<dyn ::std::error::Error>> {
+    let data = std::fs::read(sbom)
+        .map_err(|e| format!("unable to read SBOM {}: {}", sbom.display(), e))?;
+    let media_type = sbom_media_type(&data)?;
+    let title = sbom
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let manifest = referrer_manifest(
+        &data,
+        media_type,
+        &title,
+        (&pushed.digest, pushed.manifest_size),
+    );
 
-    client
-        .push(
-            &image,
-            &image_data,
-            &config_buf,
-            config_media_type,
-            &auth,
-            None,
-        )
-        .await?;
-    Ok(())
+    let image = parse_reference(&cmd.url)?;
+    let opts = cmd
+        .opts
+        .for_registry(image.registry(), cmd.registry_config.as_deref())?;
+    let auth = registry_auth(&image, opts.user.clone(), opts.password()?);
+    let registry = RegistryApi::connect(&image, &auth, &opts, "pull,push").await?;
+    registry.upload_blob(b"{}").await?;
+    registry.upload_blob(&data).await?;
+    registry.put_referrer(&serde_json::to_vec(&manifest)?).await
 }
 
-#[cfg(test)]
-mod tests {
-    use super::{PullCommand, PushCommand, RegCli, RegCliCommand};
-    use crate::util::OutputKind;
-    use structopt::StructOpt;
+/// Detects the media type of an SPDX or CycloneDX JSON document
+fn sbom_media_type(data: &[u8]) -> Result<&'static str, Box<dyn ::std::error::Error>> {
+    let doc: serde_json::Value =
+        serde_json::from_slice(data).map_err(|e| format!("SBOM is not valid JSON: {}", e))?;
+    if doc.get("spdxVersion").is_some() {
+        Ok(SPDX_MEDIA_TYPE)
+    } else if doc.get("bomFormat").and_then(|f| f.as_str()) == Some("CycloneDX") {
+        Ok(CYCLONEDX_MEDIA_TYPE)
+    } else {
+        Err("SBOM must be an SPDX or CycloneDX JSON document".into())
+    }
+}
 
-    const ECHO_WASM: &str = "wasmcloud.azurecr.io/echo:0.2.0";
-    const LOCAL_REGISTRY: &str = "localhost:5000";
+/// Builds an artifact manifest for `data` whose `subject` is the manifest with the given digest
+/// and size, so registries list it among that manifest's referrers
+fn referrer_manifest(
+    data: &[u8],
+    media_type: &str,
+    title: &str,
+    (subject_digest, subject_size): (&str, usize),
+) -> serde_json::Value {
+    let layer = descriptor(data, media_type);
+    let config = descriptor(b"{}", OCI_EMPTY_MEDIA_TYPE);
+    json!({
+        "schemaVersion": 2,
+        "mediaType": OCI_IMAGE_MEDIA_TYPE,
+        "artifactType": media_type,
+        "config": {
+            "mediaType": config.media_type,
+            "digest": config.digest,
+            "size": config.size,
+        },
+        "layers": [{
+            "mediaType": layer.media_type,
+            "digest": layer.digest,
+            "size": layer.size,
+            "annotations": { OCI_TITLE_ANNOTATION: title },
+        }],
+        "subject": {
+            "mediaType": OCI_IMAGE_MEDIA_TYPE,
+            "digest": subject_digest,
+            "size": subject_size,
+        },
+        "annotations": { OCI_CREATED_ANNOTATION: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true) },
+    })
+}
 
-    #[test]
-    /// Enumerates multiple options of the `pull` command to ensure API doesn't
-    /// change between versions. This test will fail if `wash reg pull`
-    /// changes syntax, ordering of required elements, or flags.
-    fn test_pull_comprehensive() {
-        // Not explicitly used, just a placeholder for a directory
-        const TESTDIR: &str = "./tests/fixtures";
+/// A tagged artifact in a repository
+#[derive(Debug, Serialize)]
+struct ListedArtifact {
+    tag: String,
+    digest: String,
+    size: u64,
+}
 
-        let pull_basic = RegCli::from_iter(&["reg", "pull", ECHO_WASM]);
-        let pull_all_flags =
-            RegCli::from_iter(&["reg", "pull", ECHO_WASM, "--allow-latest", "--insecure"]);
-        let pull_all_options = RegCli::from_iter(&[
-            "reg",
-            "pull",
-            ECHO_WASM,
-            "--destination",
-            TESTDIR,
-            "--digest",
-            "sha256:a17a163afa8447622055deb049587641a9e23243a6cc4411eb33bd4267214cf3",
+pub(crate) async fn handle_list(cmd: ListCommand) -> Result<String, Box<dyn ::std::error::Error>> {
+    let image = parse_reference(&cmd.repository)?;
+    let opts = cmd
+        .opts
+        .for_registry(image.registry(), cmd.registry_config.as_deref())?;
+    let auth = registry_auth(&image, opts.user.clone(), opts.password()?);
+    let registry = RegistryApi::connect(&image, &auth, &opts, "pull").await?;
+    let artifacts = list_artifacts(&registry).await?;
+
+    let output = match cmd.output.kind {
+        OutputKind::Json => json!({
+            "repository": image.repository(),
+            "artifacts": artifacts,
+        })
+        .to_string(),
+        OutputKind::Text { max_width } => {
+            use term_table::row::Row;
+            use term_table::table_cell::*;
+            use term_table::Table;
+
+            let mut table = Table::new();
+            crate::util::configure_table_style(&mut table, 3, max_width);
+            table.add_row(Row::new(vec![
+                TableCell::new_with_alignment("Tag", 1, Alignment::Left),
+                TableCell::new_with_alignment("Digest", 1, Alignment::Left),
+                TableCell::new_with_alignment("Size (bytes)", 1, Alignment::Right),
+            ]));
+            artifacts.iter().for_each(|a| {
+                table.add_row(Row::new(vec![
+                    TableCell::new_with_alignment(&a.tag, 1, Alignment::Left),
+                    TableCell::new_with_alignment(&a.digest, 1, Alignment::Left),
+                    TableCell::new_with_alignment(a.size, 1, Alignment::Right),
+                ]))
+            });
+            table.render()
+        }
+    };
+    Ok(output)
+}
+
+pub(crate) async fn handle_tags(cmd: TagsCommand) -> Result<String, Box<dyn ::std::error::Error>> {
+    let image = parse_reference(&cmd.repository)?;
+    let opts = cmd
+        .opts
+        .for_registry(image.registry(), cmd.registry_config.as_deref())?;
+    let auth = registry_auth(&image, opts.user.clone(), opts.password()?);
+    if cmd.limit == Some(0) {
+        return Err(WashError::InvalidInput("--limit must be at least 1".to_string()).into());
+    }
+    let registry = RegistryApi::connect(&image, &auth, &opts, "pull").await?;
+    let page = registry.tags_page(cmd.limit, cmd.last.as_deref()).await?;
+    // Continuation is over the registry's listing, so it ignores the filter
+    let next = if page.more {
+        page.tags.last().cloned()
+    } else {
+        None
+    };
+    let tags: Vec<String> = match &cmd.filter {
+        Some(pattern) => page
+            .tags
+            .into_iter()
+            .filter(|tag| glob_matches(pattern, tag))
+            .collect(),
+        None => page.tags,
+    };
+    Ok(match cmd.output.kind {
+        OutputKind::Json => json!({
+            "repository": image.repository(),
+            "tags": tags,
+            "more": page.more,
+            "next": next,
+        })
+        .to_string(),
+        OutputKind::Text { .. } => {
+            if page.more && !quiet_mode() {
+                match &next {
+                    Some(next) => eprintln!("More tags remain, continue with --last {}", next),
+                    None => eprintln!("More tags remain"),
+                }
+            }
+            tags.join("\n")
+        }
+    })
+}
+
+/// Matches a tag against a glob, where `*` matches any run of characters and `?` any single one
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text position it was tried at, for backtracking
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Resolves the digest and total layer size of every tag in a repository
+async fn list_artifacts(
+    registry: &RegistryApi<'_>,
+) -> Result<Vec<ListedArtifact>, Box<dyn ::std::error::Error>> {
+    let mut artifacts = vec![];
+    for tag in registry.tags().await? {
+        let (manifest, digest) = registry.manifest(&tag).await?;
+        artifacts.push(ListedArtifact {
+            tag,
+            digest,
+            size: manifest.layers.iter().map(|l| l.size.max(0) as u64).sum(),
+        });
+    }
+    Ok(artifacts)
+}
+
+pub(crate) async fn handle_delete(
+    cmd: DeleteCommand,
+) -> Result<String, Box<dyn ::std::error::Error>> {
+    let image = parse_reference(&cmd.url)?;
+    let opts = cmd
+        .opts
+        .for_registry(image.registry(), cmd.registry_config.as_deref())?;
+    let auth = registry_auth(&image, opts.user.clone(), opts.password()?);
+    let registry = RegistryApi::connect(&image, &auth, &opts, "pull,delete").await?;
+    // Registries only delete manifests by digest, so tags are resolved first
+    let digest = match image.digest() {
+        Some(digest) => digest.to_string(),
+        None => registry.manifest(image.tag().unwrap_or("latest")).await?.1,
+    };
+
+    if !cmd.force
+        && !confirm(
+            &format!("Delete {} ({})? [y/N] ", image.whole(), digest),
+            &mut std::io::stdin().lock(),
+        )?
+    {
+        return Ok(format_output(
+            format!("Cancelled deleting {}", image.whole()),
+            json!({"result": "cancelled", "url": cmd.url, "digest": digest}),
+            &cmd.output.kind,
+        ));
+    }
+
+    registry.delete_manifest(&digest).await?;
+    Ok(format_output(
+        success_output(
+            format!("Successfully deleted {}", image.whole()),
+            digest.clone(),
+            &cmd.output.kind,
+        ),
+        json!({"result": "success", "url": cmd.url, "digest": digest}),
+        &cmd.output.kind,
+    ))
+}
+
+pub(crate) async fn handle_manifest(
+    cmd: ManifestCommand,
+) -> Result<String, Box<dyn ::std::error::Error>> {
+    let image = parse_reference(&cmd.url)?;
+    let opts = cmd
+        .opts
+        .for_registry(image.registry(), cmd.registry_config.as_deref())?;
+    let auth = registry_auth(&image, opts.user.clone(), opts.password()?);
+    let registry = RegistryApi::connect(&image, &auth, &opts, "pull").await?;
+    let reference = image.digest().or_else(|| image.tag()).unwrap_or("latest");
+    // Indexes and Docker manifests are accepted too, so unexpected media types can be seen
+    let manifest = registry
+        .raw_manifest(
+            reference,
+            &[
+                OCI_IMAGE_MEDIA_TYPE,
+                IMAGE_MANIFEST_MEDIA_TYPE,
+                OCI_INDEX_MEDIA_TYPE,
+                DOCKER_MANIFEST_LIST_MEDIA_TYPE,
+            ],
+        )
+        .await?;
+    manifest_output(&image, manifest, cmd.raw, &cmd.output.kind)
+}
+
+/// Renders a fetched manifest, pretty-printed or as served when `raw` is set. JSON output
+/// wraps the manifest with its digest and media type
+fn manifest_output(
+    image: &Reference,
+    manifest: RawManifest,
+    raw: bool,
+    output_kind: &OutputKind,
+) -> Result<String, Box<dyn ::std::error::Error>> {
+    if raw {
+        return Ok(String::from_utf8_lossy(&manifest.body).into_owned());
+    }
+    let value: serde_json::Value = serde_json::from_slice(&manifest.body)
+        .map_err(|e| format!("Registry returned an invalid manifest: {}", e))?;
+    // Registries don't always report the content type, which manifests also declare
+    let media_type = manifest
+        .media_type
+        .or_else(|| value["mediaType"].as_str().map(|m| m.to_string()));
+    Ok(match output_kind {
+        OutputKind::Json => json!({
+            "url": image.whole(),
+            "digest": manifest.digest,
+            "media_type": media_type,
+            "manifest": value,
+        })
+        .to_string(),
+        OutputKind::Text { .. } => serde_json::to_string_pretty(&value)?,
+    })
+}
+
+pub(crate) async fn handle_inspect_config(
+    cmd: InspectConfigCommand,
+) -> Result<String, Box<dyn ::std::error::Error>> {
+    let image = parse_reference(&cmd.url)?;
+    let opts = cmd
+        .opts
+        .for_registry(image.registry(), cmd.registry_config.as_deref())?;
+    let auth = registry_auth(&image, opts.user.clone(), opts.password()?);
+    let registry = RegistryApi::connect(&image, &auth, &opts, "pull").await?;
+    let reference = image.digest().or_else(|| image.tag()).unwrap_or("latest");
+    let (manifest, _) = registry.manifest(reference).await?;
+    let config = registry.blob(&manifest.config.digest).await?;
+    config_output(&image, &manifest.config, config, cmd.raw, &cmd.output.kind)
+}
+
+/// Renders the config blob of an artifact, pretty-printed or as pushed when `raw` is set.
+/// JSON output wraps the config with its digest and media type
+fn config_output(
+    image: &Reference,
+    descriptor: &OciDescriptor,
+    config: Vec<u8>,
+    raw: bool,
+    output_kind: &OutputKind,
+) -> Result<String, Box<dyn ::std::error::Error>> {
+    if raw {
+        return Ok(String::from_utf8_lossy(&config).into_owned());
+    }
+    let value: serde_json::Value = serde_json::from_slice(&config).map_err(|e| {
+        format!(
+            "Config {} is not valid JSON ({}), use --raw to print it as pushed",
+            descriptor.digest, e
+        )
+    })?;
+    Ok(match output_kind {
+        OutputKind::Json => json!({
+            "url": image.whole(),
+            "digest": descriptor.digest,
+            "media_type": descriptor.media_type,
+            "config": value,
+        })
+        .to_string(),
+        OutputKind::Text { .. } => serde_json::to_string_pretty(&value)?,
+    })
+}
+
+pub(crate) async fn handle_copy(cmd: CopyCommand) -> Result<String, Box<dyn ::std::error::Error>> {
+    let source = parse_reference(&cmd.source)?;
+    let destination = parse_reference(&cmd.destination)?;
+    let config = cmd.registry_config.as_deref();
+    let source_opts = cmd.source_opts().for_registry(source.registry(), config)?;
+    let destination_opts = cmd
+        .destination_opts()
+        .for_registry(destination.registry(), config)?;
+    let source_auth = registry_auth(&source, source_opts.user.clone(), source_opts.password()?);
+    let destination_auth = registry_auth(
+        &destination,
+        destination_opts.user.clone(),
+        destination_opts.password()?,
+    );
+
+    let copied = until_interrupted(with_timeout(cmd.timeout, async {
+        let source_api = RegistryApi::connect(&source, &source_auth, &source_opts, "pull").await?;
+        let destination_api = RegistryApi::connect(
+            &destination,
+            &destination_auth,
+            &destination_opts,
+            "pull,push",
+        )
+        .await?;
+        copy_artifact(&source_api, &destination_api).await
+    }))
+    .await?;
+
+    Ok(format_output(
+        success_output(
+            format!(
+                "Successfully copied {} to {}",
+                source.whole(),
+                destination.whole()
+            ),
+            copied.digest.clone(),
+            &cmd.output.kind,
+        ),
+        json!({
+            "result": "success",
+            "source": source.whole(),
+            "destination": destination.whole(),
+            "digest": copied.digest,
+            "blobs_copied": copied.blobs_copied,
+            "blobs_existing": copied.blobs_existing,
+        }),
+        &cmd.output.kind,
+    ))
+}
+
+struct CopiedArtifact {
+    digest: String,
+    blobs_copied: usize,
+    /// Blobs the destination repository already had, which weren't transferred
+    blobs_existing: usize,
+}
+
+/// Copies the manifest of the source artifact and the blobs it refers to into the destination
+/// repository, holding one blob in memory at a time. The manifest is pushed unchanged, so the
+/// destination digest must match the source digest
+async fn copy_artifact(
+    source: &RegistryApi<'_>,
+    destination: &RegistryApi<'_>,
+) -> Result<CopiedArtifact, Box<dyn ::std::error::Error>> {
+    let reference = |image: &Reference| {
+        image
+            .digest()
+            .or_else(|| image.tag())
+            .unwrap_or("latest")
+            .to_string()
+    };
+    let manifest = source
+        .raw_manifest(
+            &reference(source.image),
+            &[OCI_IMAGE_MEDIA_TYPE, IMAGE_MANIFEST_MEDIA_TYPE],
+        )
+        .await?;
+    let parsed: OciManifest = serde_json::from_slice(&manifest.body)
+        .map_err(|e| format!("Registry returned an invalid manifest: {}", e))?;
+    let digest = format!("sha256:{:x}", Sha256::digest(&manifest.body));
+
+    let mut copied = CopiedArtifact {
+        digest,
+        blobs_copied: 0,
+        blobs_existing: 0,
+    };
+    let blobs = std::iter::once(&parsed.config).chain(parsed.layers.iter());
+    for blob in blobs {
+        if destination.has_blob(&blob.digest).await? {
+            debug!("Destination already has blob {}", blob.digest);
+            copied.blobs_existing += 1;
+            continue;
+        }
+        destination
+            .upload_blob(&source.blob(&blob.digest).await?)
+            .await?;
+        copied.blobs_copied += 1;
+    }
+
+    let media_type = parsed.media_type.as_deref().unwrap_or(OCI_IMAGE_MEDIA_TYPE);
+    let destination_reference = reference(destination.image);
+    let pushed = match destination
+        .put_manifest_as(&destination_reference, &manifest.body, media_type)
+        .await?
+    {
+        Some(pushed) => pushed,
+        None => {
+            destination
+                .raw_manifest(&destination_reference, &[media_type])
+                .await?
+                .digest
+        }
+    };
+    if pushed != copied.digest {
+        return Err(digest_mismatch(&copied.digest, &pushed));
+    }
+    Ok(copied)
+}
+
+pub(crate) async fn handle_login(
+    cmd: LoginCommand,
+) -> Result<String, Box<dyn ::std::error::Error>> {
+    let registry = normalize_registry(&cmd.registry);
+    if registry.is_empty() {
+        return Err("A registry is required to log in".into());
+    }
+    let user = match cmd
+        .opts
+        .user
+        .clone()
+        .or_else(|| crate::config::env_var(REG_USER_ENV))
+    {
+        Some(user) => user,
+        None if cmd.opts.password_stdin => {
+            return Err("--user is required when reading the password from stdin".into())
+        }
+        None => prompt_user(&mut std::io::stdin().lock())?,
+    };
+    let password = match cmd
+        .opts
+        .password()?
+        .or_else(|| crate::config::env_var(REG_PASSWORD_ENV))
+    {
+        Some(password) => password,
+        None => prompt_password()?,
+    };
+    let auth = RegistryAuth::Basic(user.clone(), password.clone());
+    // Only the connection settings of the registry config apply, the credentials being logged
+    // in with are the ones given or prompted for
+    let opts = cmd
+        .opts
+        .for_registry(registry, cmd.registry_config.as_deref())?;
+    let client = registry_client(opts.proxy.as_deref(), opts.ca_cert.as_deref())?;
+    validate_login(&client, registry, &auth, opts.insecure).await?;
+
+    let path = wash_credentials_path().ok_or("Unable to determine the home directory")?;
+    store_credentials(&path, registry, &user, &password)?;
+    Ok(match cmd.output.kind {
+        OutputKind::Json => json!({
+            "result": "success",
+            "registry": registry,
+            "user": user,
+            "file": path.to_string_lossy(),
+        })
+        .to_string(),
+        _ => success_output(
+            format!("Logged in to {} as {}", registry, user),
+            registry.to_string(),
+            &cmd.output.kind,
+        ),
+    })
+}
+
+pub(crate) fn handle_logout(cmd: LogoutCommand) -> Result<String, Box<dyn ::std::error::Error>> {
+    let path = wash_credentials_path().ok_or("Unable to determine the home directory")?;
+    let registry = if cmd.all {
+        None
+    } else {
+        cmd.registry.as_deref().map(normalize_registry)
+    };
+    let removed = remove_credentials(&path, registry)?;
+    let text = match (registry, removed.is_empty()) {
+        (Some(registry), false) => format!("Removed stored credentials for {}", registry),
+        (Some(registry), true) => format!("No credentials were stored for {}", registry),
+        (None, false) => format!("Removed stored credentials for {}", removed.join(", ")),
+        (None, true) => "No credentials were stored".to_string(),
+    };
+    Ok(format_output(
+        text,
+        json!({
+            "result": "success",
+            "removed": !removed.is_empty(),
+            "registries": removed,
+        }),
+        &cmd.output.kind,
+    ))
+}
+
+/// Prompts on stderr for a registry username
+fn prompt_user(input: &mut impl BufRead) -> Result<String, Box<dyn ::std::error::Error>> {
+    eprint!("Username: ");
+    std::io::stderr().flush()?;
+    let mut user = String::new();
+    input.read_line(&mut user)?;
+    let user = user.trim();
+    if user.is_empty() {
+        return Err("No username was provided".into());
+    }
+    Ok(user.to_string())
+}
+
+/// Prompts on stderr for a registry password without echoing it to the terminal
+fn prompt_password() -> Result<String, Box<dyn ::std::error::Error>> {
+    match prompt_secret("Password: ")? {
+        password if !password.is_empty() => Ok(password),
+        _ => Err("No password was provided".into()),
+    }
+}
+
+/// Checks credentials against a registry. Registries issuing a bearer challenge must grant a
+/// token for the credentials, others must accept them on the base API endpoint
+async fn validate_login(
+    client: &reqwest::Client,
+    registry: &str,
+    auth: &RegistryAuth,
+    insecure: bool,
+) -> Result<(), Box<dyn ::std::error::Error>> {
+    if request_token(client, registry, insecure, auth, None)
+        .await?
+        .is_some()
+    {
+        return Ok(());
+    }
+    let mut req = client.get(format!("{}/v2/", registry_url(registry, insecure)));
+    if let RegistryAuth::Basic(user, password) = auth {
+        req = req.basic_auth(user, Some(password));
+    }
+    let res = req.send().await?;
+    match res.status() {
+        s if s.is_success() => Ok(()),
+        s if s == reqwest::StatusCode::UNAUTHORIZED || s == reqwest::StatusCode::FORBIDDEN => {
+            Err(Box::new(WashError::Auth(format!(
+                "Registry {} rejected the provided credentials",
+                registry
+            ))))
+        }
+        s => Err(status_error(format!("Failed to log in to {}", registry), s)),
+    }
+}
+
+/// Reports a successful operation. On the CLI the decorated message is printed to stderr,
+/// unless `--quiet` is set, and only the essential `result` such as a file name or digest
+/// is returned for stdout so it can be piped into other tools
+fn success_output(message: String, result: String, output_kind: &OutputKind) -> String {
+    let prefix = if no_emoji_mode() {
+        ASCII_SUCCESS_PREFIX
+    } else {
+        SHOWER_EMOJI
+    };
+    let decorated = format!("\n{} {}", prefix, message);
+    match (output_kind, output_destination()) {
+        (OutputKind::Text { .. }, OutputDestination::Cli) => {
+            if !quiet_mode() {
+                eprintln!("{}", decorated);
+            }
+            result
+        }
+        _ => decorated,
+    }
+}
+
+/// Describes an unexpected registry response, classifying it by status. Server errors are
+/// network errors, which are retried as they may not occur again
+fn status_error(context: String, status: reqwest::StatusCode) -> Box<dyn ::std::error::Error> {
+    let message = format!("{}: {}", context, status);
+    match status.as_u16() {
+        401 | 403 => Box::new(WashError::Auth(message)),
+        404 => Box::new(WashError::NotFound(message)),
+        _ if status.is_server_error() => Box::new(WashError::Network(message)),
+        _ => message.into(),
+    }
+}
+
+/// Returns true for network failures and registry server errors, which are worth retrying
+fn is_transient(e: &(dyn ::std::error::Error + 'static)) -> bool {
+    let mut current = Some(e);
+    while let Some(e) = current {
+        if let Some(e) = e.downcast_ref::<WashError>() {
+            return matches!(e, WashError::Network(_));
+        }
+        if let Some(e) = e.downcast_ref::<reqwest::Error>() {
+            return e.is_connect()
+                || e.is_timeout()
+                || e.is_request()
+                || e.is_body()
+                || matches!(e.status(), Some(s) if s.is_server_error());
+        }
+        current = e.source();
+    }
+    false
+}
+
+/// Runs a registry operation, retrying transient failures up to `retries` times with an
+/// exponential backoff starting at `base_delay`
+async fn with_retries<T, F, Fut>(
+    retries: u32,
+    base_delay: Duration,
+    mut operation: F,
+) -> Result<T, Box<dyn ::std::error::Error>>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Box<dyn ::std::error::Error>>>,
+{
+    let mut attempt = 0;
+    loop {
+        match operation().await {
+            Err(e) if attempt < retries && is_transient(e.as_ref()) => {
+                attempt += 1;
+                debug!(
+                    "Transient registry error on attempt {}, backing off for {:?}",
+                    attempt,
+                    base_delay * 2u32.pow(attempt - 1)
+                );
+                eprintln!("{}, retrying ({}/{})...", e, attempt, retries);
+                actix_rt::time::sleep(base_delay * 2u32.pow(attempt - 1)).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Bounds the time a registry operation may take, failing once `seconds` have elapsed
+async fn with_timeout<T>(
+    seconds: u64,
+    operation: impl Future<Output = Result<T, Box<dyn ::std::error::Error>>>,
+) -> Result<T, Box<dyn ::std::error::Error>> {
+    actix_rt::time::timeout(Duration::from_secs(seconds), operation)
+        .await
+        .map_err(|_| WashError::Network(format!("Operation timed out after {}s", seconds)))?
+}
+
+/// Runs a registry operation until it completes or the user hits Ctrl-C. An interrupt drops
+/// the operation, cancelling the transfer in flight and its progress output, and fails
+/// with `Aborted`
+async fn until_interrupted<T>(
+    operation: impl Future<Output = Result<T, Box<dyn ::std::error::Error>>>,
+) -> Result<T, Box<dyn ::std::error::Error>> {
+    until_signal(operation, actix_rt::signal::ctrl_c()).await
+}
+
+async fn until_signal<T>(
+    operation: impl Future<Output = Result<T, Box<dyn ::std::error::Error>>>,
+    signal: impl Future<Output = std::io::Result<()>>,
+) -> Result<T, Box<dyn ::std::error::Error>> {
+    match select(Box::pin(operation), Box::pin(signal)).await {
+        Either::Left((result, _)) => result,
+        Either::Right((Ok(()), _)) => Err(Aborted.into()),
+        // Without a signal handler the operation can't be interrupted, so it just completes
+        Either::Right((Err(e), operation)) => {
+            debug!("Unable to listen for Ctrl-C: {}", e);
+            operation.await
+        }
+    }
+}
+
+/// Determines registry credentials for the image's registry from the environment and the
+/// credentials stored by `wash reg login` and `docker login`
+fn registry_auth(
+    image: &Reference,
+    user: Option<String>,
+    password: Option<String>,
+) -> RegistryAuth {
+    credentials_for(
+        image.registry(),
+        user,
+        password,
+        crate::config::env_var,
+        &[wash_credentials_path(), docker_config_path()],
+    )
+}
+
+/// Picks the credentials for `registry`, in order of precedence:
+/// 1. the user and password given on the command line
+/// 2. credentials scoped to the registry host in the environment, unless a user was given
+/// 3. credentials stored for the registry in the first of the `stored` files that has them
+/// 4. the global WASH_REG_USER and WASH_REG_PASSWORD, each only used if its flag wasn't given
+///
+/// Without any of these the registry is connected to anonymously
+fn credentials_for(
+    registry: &str,
+    user: Option<String>,
+    password: Option<String>,
+    var: impl Fn(&str) -> Option<String>,
+    stored: &[Option<PathBuf>],
+) -> RegistryAuth {
+    if let (Some(user), Some(password)) = (&user, &password) {
+        debug!(
+            "Using the provided credentials of {} for {}",
+            user, registry
+        );
+        return RegistryAuth::Basic(user.clone(), password.clone());
+    }
+    if user.is_none() {
+        if let Some((user, password)) = host_env_credentials(registry, &var) {
+            debug!("Using credentials for {} from the environment", registry);
+            return RegistryAuth::Basic(user, password);
+        }
+    }
+    for path in stored.iter().flatten() {
+        if let Some(auth) = docker_config_auth(path, registry) {
+            debug!("Using credentials for {} from {}", registry, path.display());
+            return auth;
+        }
+    }
+    match (
+        user.or_else(|| var(REG_USER_ENV)),
+        password.or_else(|| var(REG_PASSWORD_ENV)),
+    ) {
+        (Some(user), Some(password)) => {
+            debug!(
+                "Using the credentials of {} for {} from the environment",
+                user, registry
+            );
+            RegistryAuth::Basic(user, password)
+        }
+        _ => {
+            debug!(
+                "No credentials found for {}, connecting anonymously",
+                registry
+            );
+            RegistryAuth::Anonymous
+        }
+    }
+}
+
+/// Name of the environment variable holding a credential scoped to a registry host. The host
+/// is upper-cased and every character other than an ASCII letter or digit is replaced with
+/// an underscore, so the user for ghcr.io is read from WASH_REG__GHCR_IO_USER and the one
+/// for localhost:5000 from WASH_REG__LOCALHOST_5000_USER
+fn host_credential_var(registry: &str, suffix: &str) -> String {
+    let host: String = registry
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}{}_{}", HOST_CREDENTIALS_ENV_PREFIX, host, suffix)
+}
+
+/// Looks up the user and password scoped to a registry host, both of which must be set
+fn host_env_credentials(
+    registry: &str,
+    var: impl Fn(&str) -> Option<String>,
+) -> Option<(String, String)> {
+    let user = var(&host_credential_var(registry, "USER"))?;
+    let password = var(&host_credential_var(registry, "PASSWORD"))?;
+    Some((user, password))
+}
+
+/// Checks the registry's authentication challenge ahead of a push or pull. When the registry
+/// issues a bearer challenge, the credentials are exchanged for a token so that rejected
+/// credentials are reported clearly instead of as an opaque failure of the operation.
+/// Returns the token, or `None` if the registry didn't issue a bearer challenge
+async fn registry_token(
+    client: &reqwest::Client,
+    image: &Reference,
+    auth: &RegistryAuth,
+    insecure: bool,
+    actions: &str,
+) -> Result<Option<String>, Box<dyn ::std::error::Error>> {
+    request_token(
+        client,
+        image.registry(),
+        insecure,
+        auth,
+        Some(format!("repository:{}:{}", image.repository(), actions)),
+    )
+    .await
+}
+
+/// Exchanges credentials for a bearer token with the `scope`, when the registry issues a
+/// bearer challenge. Returns `None` if it didn't
+async fn request_token(
+    client: &reqwest::Client,
+    registry: &str,
+    insecure: bool,
+    auth: &RegistryAuth,
+    scope: Option<String>,
+) -> Result<Option<String>, Box<dyn ::std::error::Error>> {
+    let res = client
+        .get(format!("{}/v2/", registry_url(registry, insecure)))
+        .send()
+        .await?;
+    if res.status() != reqwest::StatusCode::UNAUTHORIZED {
+        return Ok(None);
+    }
+    let challenge = match res
+        .headers()
+        .get(reqwest::header::WWW_AUTHENTICATE)
+        .and_then(|h| h.to_str().ok())
+        .and_then(parse_bearer_challenge)
+    {
+        Some(challenge) => challenge,
+        // Other authentication schemes are handled by the registry client
+        None => return Ok(None),
+    };
+    let realm = challenge
+        .get("realm")
+        .ok_or("Registry bearer challenge did not include a token realm")?;
+
+    let mut query = vec![];
+    if let Some(scope) = scope {
+        query.push(("scope", scope));
+    }
+    if let Some(service) = challenge.get("service") {
+        query.push(("service", service.to_string()));
+    }
+    let mut token_req = client.get(realm).query(&query);
+    if let RegistryAuth::Basic(user, password) = auth {
+        token_req = token_req.basic_auth(user, Some(password));
+    }
+    let token_res = token_req.send().await?;
+    match token_res.status() {
+        s if s.is_success() => {
+            debug!("Retrieved bearer token for {} from {}", registry, realm);
+            let body: TokenResponse = token_res.json().await?;
+            Ok(body.token.or(body.access_token))
+        }
+        s if s == reqwest::StatusCode::UNAUTHORIZED || s == reqwest::StatusCode::FORBIDDEN => {
+            Err(Box::new(WashError::Auth(format!(
+                "Registry {} rejected the provided credentials: {}",
+                registry,
+                token_res.text().await.unwrap_or_default()
+            ))))
+        }
+        s => Err(status_error(
+            format!("Failed to retrieve bearer token from {}", realm),
+            s,
+        )),
+    }
+}
+
+/// Client for the registry API of an image's repository, authorized for a set of actions
+struct RegistryApi<'a> {
+    client: reqwest::Client,
+    base_url: String,
+    image: &'a Reference,
+    auth: &'a RegistryAuth,
+    token: Option<String>,
+}
+
+impl<'a> RegistryApi<'a> {
+    /// Connects to the registry over HTTP when `opts.insecure` is set and through the proxy
+    /// `opts.proxy` or the environment configures
+    async fn connect(
+        image: &'a Reference,
+        auth: &'a RegistryAuth,
+        opts: &AuthOpts,
+        actions: &str,
+    ) -> Result<RegistryApi<'a>, Box<dyn ::std::error::Error>> {
+        let client = registry_client(opts.proxy.as_deref(), opts.ca_cert.as_deref())?;
+        let base_url = registry_base_url(image, opts.insecure);
+        debug!(
+            "Connecting to {} for {} access to {}",
+            base_url,
+            actions,
+            image.repository()
+        );
+        Ok(RegistryApi {
+            base_url,
+            token: registry_token(&client, image, auth, opts.insecure, actions).await?,
+            client,
+            image,
+            auth,
+        })
+    }
+
+    /// Builds an authorized request for a path relative to the repository, e.g. `tags/list`
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let url = format!("{}/v2/{}/{}", self.base_url, self.image.repository(), path);
+        trace!("{} {}", method, url);
+        self.authorize(self.client.request(method, url))
+    }
+
+    fn authorize(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match (&self.token, self.auth) {
+            (Some(token), _) => req.bearer_auth(token),
+            (None, RegistryAuth::Basic(user, password)) => req.basic_auth(user, Some(password)),
+            (None, RegistryAuth::Anonymous) => req,
+        }
+    }
+
+    /// Fetches the manifest for a tag or digest, along with the manifest digest
+    async fn manifest(
+        &self,
+        reference: &str,
+    ) -> Result<(OciManifest, String), Box<dyn ::std::error::Error>> {
+        let manifest = self
+            .raw_manifest(
+                reference,
+                &[OCI_IMAGE_MEDIA_TYPE, IMAGE_MANIFEST_MEDIA_TYPE],
+            )
+            .await?;
+        Ok((serde_json::from_slice(&manifest.body)?, manifest.digest))
+    }
+
+    /// Fetches the manifest for a tag or digest in one of the `accepted` media types, as
+    /// served by the registry
+    async fn raw_manifest(
+        &self,
+        reference: &str,
+        accepted: &[&str],
+    ) -> Result<RawManifest, Box<dyn ::std::error::Error>> {
+        let res = self
+            .request(reqwest::Method::GET, &format!("manifests/{}", reference))
+            .header(reqwest::header::ACCEPT, accepted.join(", "))
+            .send()
+            .await?;
+        if !res.status().is_success() {
+            return Err(status_error(
+                "Failed to fetch manifest".to_string(),
+                res.status(),
+            ));
+        }
+        let header = |name| {
+            res.headers()
+                .get(name)
+                .and_then(|h| h.to_str().ok())
+                .map(|h| h.to_string())
+        };
+        let header_digest = header("Docker-Content-Digest");
+        let media_type = header(reqwest::header::CONTENT_TYPE.as_str());
+        let body = res.bytes().await?.to_vec();
+        // The digest is always computed from the body, so a registry can't vouch for content
+        // it didn't serve
+        let digest = format!("sha256:{:x}", Sha256::digest(&body));
+        if let Some(header_digest) = header_digest.filter(|d| d.starts_with("sha256:")) {
+            if header_digest != digest {
+                return Err(Box::new(WashError::Validation(format!(
+                    "Registry reported manifest digest {} but served a manifest with digest {}, aborting",
+                    header_digest, digest
+                ))));
+            }
+        }
+        if reference.starts_with("sha256:") && reference != digest {
+            return Err(digest_mismatch(reference, &digest));
+        }
+        Ok(RawManifest {
+            body,
+            digest,
+            media_type,
+        })
+    }
+
+    /// Returns true if the repository has the blob with the digest
+    async fn has_blob(&self, digest: &str) -> Result<bool, Box<dyn ::std::error::Error>> {
+        let res = self
+            .request(reqwest::Method::HEAD, &format!("blobs/{}", digest))
+            .send()
+            .await?;
+        Ok(res.status().is_success())
+    }
+
+    /// Fetches a blob, verifying its contents against the digest
+    async fn blob(&self, digest: &str) -> Result<Vec<u8>, Box<dyn ::std::error::Error>> {
+        let res = self
+            .request(reqwest::Method::GET, &format!("blobs/{}", digest))
+            .send()
+            .await?;
+        if !res.status().is_success() {
+            return Err(status_error(
+                format!("Failed to fetch blob {}", digest),
+                res.status(),
+            ));
+        }
+        let data = res.bytes().await?.to_vec();
+        let actual = format!("sha256:{:x}", Sha256::digest(&data));
+        if actual != digest {
+            return Err(digest_mismatch(digest, &actual));
+        }
+        Ok(data)
+    }
+
+    /// Uploads a blob in a single request, unless the repository already has it
+    async fn upload_blob(&self, data: &[u8]) -> Result<(), Box<dyn ::std::error::Error>> {
+        let digest = format!("sha256:{:x}", Sha256::digest(data));
+        if self.has_blob(&digest).await? {
+            return Ok(());
+        }
+
+        let res = self
+            .request(reqwest::Method::POST, "blobs/uploads/")
+            .send()
+            .await?;
+        let location = match res.headers().get(reqwest::header::LOCATION) {
+            Some(location) if res.status().is_success() => location.to_str()?.to_string(),
+            _ => {
+                return Err(status_error(
+                    "Failed to start blob upload".to_string(),
+                    res.status(),
+                ))
+            }
+        };
+        // Upload locations may be relative to the registry
+        let location = if location.starts_with('/') {
+            format!("{}{}", self.base_url, location)
+        } else {
+            location
+        };
+        let separator = if location.contains('?') { '&' } else { '?' };
+        let res = self
+            .authorize(
+                self.client
+                    .put(format!("{}{}digest={}", location, separator, digest)),
+            )
+            .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+            .body(data.to_vec())
+            .send()
+            .await?;
+        if !res.status().is_success() {
+            return Err(status_error(
+                format!("Failed to upload blob {}", digest),
+                res.status(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Pushes a manifest under a tag
+    async fn put_manifest(
+        &self,
+        tag: &str,
+        manifest: &[u8],
+    ) -> Result<(), Box<dyn ::std::error::Error>> {
+        self.put_manifest_as(tag, manifest, OCI_IMAGE_MEDIA_TYPE)
+            .await
+            .map(|_| ())
+    }
+
+    /// Pushes a manifest of a media type under a tag or digest, returning the digest the
+    /// registry reports for it, if any
+    async fn put_manifest_as(
+        &self,
+        reference: &str,
+        manifest: &[u8],
+        media_type: &str,
+    ) -> Result<Option<String>, Box<dyn ::std::error::Error>> {
+        let res = self
+            .request(reqwest::Method::PUT, &format!("manifests/{}", reference))
+            .header(reqwest::header::CONTENT_TYPE, media_type)
+            .body(manifest.to_vec())
+            .send()
+            .await?;
+        if !res.status().is_success() {
+            return Err(status_error(
+                format!("Failed to push manifest {}", reference),
+                res.status(),
+            ));
+        }
+        Ok(res
+            .headers()
+            .get("Docker-Content-Digest")
+            .and_then(|h| h.to_str().ok())
+            .map(|h| h.to_string()))
+    }
+
+    /// Pushes a manifest that refers to another manifest by digest, failing if the registry
+    /// doesn't support the OCI referrers API
+    async fn put_referrer(&self, manifest: &[u8]) -> Result<String, Box<dyn ::std::error::Error>> {
+        let digest = format!("sha256:{:x}", Sha256::digest(manifest));
+        let res = self
+            .request(reqwest::Method::PUT, &format!("manifests/{}", digest))
+            .header(reqwest::header::CONTENT_TYPE, OCI_IMAGE_MEDIA_TYPE)
+            .body(manifest.to_vec())
+            .send()
+            .await?;
+        // Registries that process the `subject` of a manifest confirm it with this header
+        match res.status() {
+            s if s.is_success() && res.headers().contains_key("OCI-Subject") => Ok(digest),
+            s if s.is_success() => Err(format!(
+                "registry {} stored it as {} but does not support the OCI referrers API",
+                self.image.registry(),
+                digest
+            )
+            .into()),
+            s if s == reqwest::StatusCode::BAD_REQUEST => Err(format!(
+                "registry {} does not support the OCI referrers API",
+                self.image.registry()
+            )
+            .into()),
+            s => Err(status_error("Failed to push SBOM manifest".to_string(), s)),
+        }
+    }
+
+    async fn tags(&self) -> Result<Vec<String>, Box<dyn ::std::error::Error>> {
+        Ok(self.tags_page(None, None).await?.tags)
+    }
+
+    /// Lists at most `limit` tags following `last`, using the registry's `n` and `last`
+    /// pagination parameters
+    async fn tags_page(
+        &self,
+        limit: Option<usize>,
+        last: Option<&str>,
+    ) -> Result<TagPage, Box<dyn ::std::error::Error>> {
+        let mut query = vec![];
+        if let Some(limit) = limit {
+            query.push(("n", limit.to_string()));
+        }
+        if let Some(last) = last {
+            query.push(("last", last.to_string()));
+        }
+        let res = self
+            .request(reqwest::Method::GET, "tags/list")
+            .query(&query)
+            .send()
+            .await?;
+        if !res.status().is_success() {
+            return Err(status_error(
+                format!("Failed to list tags of {}", self.image.repository()),
+                res.status(),
+            ));
+        }
+        let next_link = has_next_link(res.headers());
+        let list: TagList = res.json().await?;
+        Ok(tag_page(list.tags.unwrap_or_default(), limit, next_link))
+    }
+
+    async fn delete_manifest(&self, digest: &str) -> Result<(), Box<dyn ::std::error::Error>> {
+        let res = self
+            .request(reqwest::Method::DELETE, &format!("manifests/{}", digest))
+            .send()
+            .await?;
+        match res.status() {
+            s if s.is_success() => Ok(()),
+            s if s == reqwest::StatusCode::METHOD_NOT_ALLOWED
+                || s == reqwest::StatusCode::FORBIDDEN
+                || s == reqwest::StatusCode::UNAUTHORIZED =>
+            {
+                Err(format!(
+                    "Registry {} does not allow deleting artifacts ({})",
+                    self.image.registry(),
+                    s
+                )
+                .into())
+            }
+            s => Err(status_error(format!("Failed to delete {}", digest), s)),
+        }
+    }
+}
+
+/// A manifest as served by the registry
+struct RawManifest {
+    body: Vec<u8>,
+    digest: String,
+    /// Content type the registry reported for the manifest, if any
+    media_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TagList {
+    tags: Option<Vec<String>>,
+}
+
+/// One page of a repository's tags
+#[derive(Debug, PartialEq)]
+struct TagPage {
+    tags: Vec<String>,
+    /// Whether the registry has more tags after this page
+    more: bool,
+}
+
+/// Trims a page of tags to `limit`, since registries may ignore `n`, noting whether more
+/// remain either beyond the limit or behind a `Link: <...>; rel="next"` header
+fn tag_page(mut tags: Vec<String>, limit: Option<usize>, next_link: bool) -> TagPage {
+    let truncated = matches!(limit, Some(n) if tags.len() > n);
+    if let Some(n) = limit {
+        tags.truncate(n);
+    }
+    TagPage {
+        tags,
+        more: next_link || truncated,
+    }
+}
+
+/// Whether a response links to a next page of results
+fn has_next_link(headers: &reqwest::header::HeaderMap) -> bool {
+    headers
+        .get_all(reqwest::header::LINK)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|link| {
+            link.split(';')
+                .skip(1)
+                .any(|param| matches!(param.trim(), "rel=\"next\"" | "rel=next"))
+        })
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    token: Option<String>,
+    access_token: Option<String>,
+}
+
+/// Builds the HTTP client for registry connections. Requests go through the explicit
+/// `proxy` if provided, or the proxy HTTPS_PROXY or HTTP_PROXY configures for the URL
+/// scheme, unless the host is excluded by NO_PROXY. The PEM certificate at `ca_cert` is
+/// trusted in addition to the system roots
+fn registry_client(
+    proxy: Option<&str>,
+    ca_cert: Option<&str>,
+) -> Result<reqwest::Client, Box<dyn ::std::error::Error>> {
+    if offline_mode() {
+        return Err(Box::new(WashError::InvalidInput(
+            "Registries can't be contacted while wash is offline, only cached artifacts can be pulled or inspected".to_string(),
+        )));
+    }
+    let explicit = proxy
+        .map(|p| reqwest::Url::parse(p).map_err(|e| format!("Invalid proxy URL '{}': {}", p, e)))
+        .transpose()?;
+    let no_proxy = proxy_env("NO_PROXY").unwrap_or_default();
+    if let Some(ref proxy) = explicit {
+        debug!("Connecting to registries through proxy {}", proxy);
+    }
+    // Adding a custom proxy disables the client's own handling of the proxy variables
+    let proxy =
+        reqwest::Proxy::custom(move |url| proxy_for(url, explicit.as_ref(), &no_proxy, proxy_env));
+    let mut builder = reqwest::Client::builder().proxy(proxy);
+    if let Some(path) = ca_cert {
+        let pem = std::fs::read(path)
+            .map_err(|e| format!("Unable to read CA certificate {}: {}", path, e))?;
+        let cert = reqwest::Certificate::from_pem(&pem)
+            .map_err(|e| format!("Invalid CA certificate {}: {}", path, e))?;
+        builder = builder.add_root_certificate(cert);
+    }
+    Ok(builder.build()?)
+}
+
+/// Reads a proxy environment variable, accepting the lowercase form used by curl
+fn proxy_env(name: &str) -> Option<String> {
+    env::var(name)
+        .or_else(|_| env::var(name.to_ascii_lowercase()))
+        .ok()
+        .filter(|v| !v.is_empty())
+}
+
+/// Determines the proxy to connect to a URL through, if any
+fn proxy_for(
+    url: &reqwest::Url,
+    explicit: Option<&reqwest::Url>,
+    no_proxy: &str,
+    env: impl Fn(&str) -> Option<String>,
+) -> Option<reqwest::Url> {
+    let host = url.host_str().unwrap_or_default();
+    if no_proxy_matches(host, no_proxy) {
+        return None;
+    }
+    if let Some(explicit) = explicit {
+        return Some(explicit.clone());
+    }
+    let var = if url.scheme() == "https" {
+        "HTTPS_PROXY"
+    } else {
+        "HTTP_PROXY"
+    };
+    env(var).and_then(|p| reqwest::Url::parse(&p).ok())
+}
+
+/// Checks a host against a comma separated NO_PROXY list, where `*` matches every host and
+/// an entry matches the host itself along with its subdomains
+fn no_proxy_matches(host: &str, no_proxy: &str) -> bool {
+    no_proxy
+        .split(',')
+        .map(|entry| entry.trim().trim_start_matches('.'))
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| {
+            // Ports aren't considered, as the host alone identifies the registry
+            let entry = entry.split(':').next().unwrap_or(entry);
+            entry == "*"
+                || host.eq_ignore_ascii_case(entry)
+                || host
+                    .to_ascii_lowercase()
+                    .ends_with(&format!(".{}", entry.to_ascii_lowercase()))
+        })
+}
+
+/// Base URL of an image's registry
+fn registry_base_url(image: &Reference, insecure: bool) -> String {
+    registry_url(image.registry(), insecure)
+}
+
+/// Base URL of a registry, mapping Docker Hub to its registry host
+fn registry_url(registry: &str, insecure: bool) -> String {
+    let scheme = if insecure { "http" } else { "https" };
+    let registry = match registry {
+        "docker.io" => "registry-1.docker.io",
+        registry => registry,
+    };
+    format!("{}://{}", scheme, registry)
+}
+
+/// Parses the parameters of a `WWW-Authenticate: Bearer` challenge
+fn parse_bearer_challenge(header: &str) -> Option<HashMap<String, String>> {
+    let mut rest = header.strip_prefix("Bearer ")?.trim();
+    let mut params = HashMap::new();
+    while !rest.is_empty() {
+        let eq = rest.find('=')?;
+        let key = rest[..eq].trim().to_ascii_lowercase();
+        rest = &rest[eq + 1..];
+        let value = if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"')?;
+            rest = &quoted[end + 1..];
+            &quoted[..end]
+        } else {
+            let end = rest.find(',').unwrap_or(rest.len());
+            let value = rest[..end].trim();
+            rest = &rest[end..];
+            value
+        };
+        params.insert(key, value.to_string());
+        rest = rest.trim_start_matches(',').trim_start();
+    }
+    Some(params)
+}
+
+/// Location of the docker CLI config, `$DOCKER_CONFIG/config.json` or `$HOME/.docker/config.json`
+fn docker_config_path() -> Option<PathBuf> {
+    match env::var("DOCKER_CONFIG") {
+        Ok(dir) => Some(PathBuf::from(dir).join(DOCKER_CONFIG_FILE)),
+        Err(_) => env::var("HOME")
+            .ok()
+            .map(|home| PathBuf::from(home).join(".docker").join(DOCKER_CONFIG_FILE)),
+    }
+}
+
+/// Location of the credentials stored by `wash reg login`, `$WASH_REG_CREDENTIALS` or
+/// `$HOME/.wash/credentials.json`
+fn wash_credentials_path() -> Option<PathBuf> {
+    match crate::config::env_var("WASH_REG_CREDENTIALS") {
+        Some(path) => Some(PathBuf::from(path)),
+        None => env::var("HOME").ok().map(|home| {
+            PathBuf::from(home)
+                .join(".wash")
+                .join(WASH_CREDENTIALS_FILE)
+        }),
+    }
+}
+
+/// Stores basic credentials for a registry in a credentials file using the docker CLI config
+/// format, replacing any previously stored for the registry. The file is only readable by
+/// the current user, as the credentials are merely base64 encoded
+fn store_credentials(
+    path: &Path,
+    registry: &str,
+    user: &str,
+    password: &str,
+) -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut config = read_credentials(path)?;
+    let registry = normalize_registry(registry);
+    config
+        .auths
+        .retain(|host, _| normalize_registry(host) != registry);
+    config.auths.insert(
+        registry.to_string(),
+        DockerAuth {
+            auth: Some(base64::encode(format!("{}:{}", user, password))),
+        },
+    );
+    write_credentials(path, &config)
+}
+
+/// Removes the stored credentials for a registry, or for every registry when `None`, in
+/// which case the credentials file is deleted. Returns the registries that were removed
+fn remove_credentials(
+    path: &Path,
+    registry: Option<&str>,
+) -> Result<Vec<String>, Box<dyn ::std::error::Error>> {
+    let mut config = read_credentials(path)?;
+    let mut removed: Vec<String> = config
+        .auths
+        .keys()
+        .filter(|host| registry.is_none() || registry == Some(normalize_registry(host)))
+        .cloned()
+        .collect();
+    removed.sort();
+    match registry {
+        None if path.exists() => remove_file(path)?,
+        Some(_) if !removed.is_empty() => {
+            config.auths.retain(|host, _| !removed.contains(host));
+            write_credentials(path, &config)?;
+        }
+        _ => (),
+    }
+    Ok(removed)
+}
+
+/// Reads a credentials file, treating a missing file as empty
+fn read_credentials(path: &Path) -> Result<DockerConfig, Box<dyn ::std::error::Error>> {
+    match File::open(path) {
+        Ok(file) => serde_json::from_reader(file).map_err(|e| {
+            format!(
+                "Failed to parse registry credentials in {}: {}",
+                path.display(),
+                e
+            )
+            .into()
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(DockerConfig::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn write_credentials(
+    path: &Path,
+    config: &DockerConfig,
+) -> Result<(), Box<dyn ::std::error::Error>> {
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        // The mode only applies to new files, so existing ones are restricted as well
+        if path.exists() {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        }
+    }
+    let file = options.open(path)?;
+    serde_json::to_writer_pretty(file, config)?;
+    Ok(())
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct DockerConfig {
+    #[serde(default)]
+    auths: HashMap<String, DockerAuth>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct DockerAuth {
+    auth: Option<String>,
+}
+
+/// Reads basic credentials for a registry from a docker CLI config file. Returns `None`
+/// if the config can't be read or doesn't contain credentials for the registry
+fn docker_config_auth(path: &Path, registry: &str) -> Option<RegistryAuth> {
+    let config: DockerConfig = serde_json::from_reader(File::open(path).ok()?).ok()?;
+    let registry = normalize_registry(registry);
+    let auth = config
+        .auths
+        .iter()
+        .find(|(host, _)| normalize_registry(host) == registry)
+        .and_then(|(_, auth)| auth.auth.clone())?;
+    let decoded = String::from_utf8(base64::decode(auth).ok()?).ok()?;
+    let mut parts = decoded.splitn(2, ':');
+    match (parts.next(), parts.next()) {
+        (Some(user), Some(password)) => {
+            Some(RegistryAuth::Basic(user.to_string(), password.to_string()))
+        }
+        _ => None,
+    }
+}
+
+/// Strips the scheme and path from a registry host as stored by `docker login`, mapping
+/// the Docker Hub index server to its registry host
+fn normalize_registry(registry: &str) -> &str {
+    let host = registry
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .split('/')
+        .next()
+        .unwrap_or_default();
+    match host {
+        "index.docker.io" | "registry-1.docker.io" => "docker.io",
+        host => host,
+    }
+}
+
+/// Builds the standard OCI `created` and `authors` annotations for an artifact, along with
+/// any custom `KEY=VALUE` annotations. The creation time defaults to the current time, and
+/// must be a RFC3339 timestamp when provided. Custom annotations take precedence, and the
+/// last value wins when a key is repeated
+fn artifact_annotations(
+    author: Option<String>,
+    created: Option<String>,
+    custom: &[String],
+) -> Result<HashMap<String, String>, Box<dyn ::std::error::Error>> {
+    let created = match created {
+        Some(c) => DateTime::parse_from_rfc3339(&c)
+            .map_err(|e| format!("Invalid created timestamp '{}', expected RFC3339: {}", c, e))?
+            .to_rfc3339(),
+        None => Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+    };
+    let mut annotations = HashMap::new();
+    annotations.insert(OCI_CREATED_ANNOTATION.to_string(), created);
+    if let Some(author) = author {
+        annotations.insert(OCI_AUTHORS_ANNOTATION.to_string(), author);
+    }
+    for annotation in custom {
+        let mut parts = annotation.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some(key), Some(value)) if !key.is_empty() => {
+                annotations.insert(key.to_string(), value.to_string());
+            }
+            _ => {
+                return Err(
+                    format!("Invalid annotation '{}', expected KEY=VALUE", annotation).into(),
+                )
+            }
+        }
+    }
+    Ok(annotations)
+}
+
+/// Adds `created` and `author` fields to a JSON object config blob, leaving any
+/// values already present in the config untouched. Configs that aren't JSON
+/// objects are pushed as-is
+fn annotate_config(config: Vec<u8>, annotations: &HashMap<String, String>) -> Vec<u8> {
+    let mut value = match serde_json::from_slice::<serde_json::Value>(&config) {
+        Ok(serde_json::Value::Object(map)) => map,
+        _ => return config,
+    };
+    let fields = [
+        ("created", OCI_CREATED_ANNOTATION),
+        ("author", OCI_AUTHORS_ANNOTATION),
+    ];
+    for (field, annotation) in fields.iter() {
+        if let Some(v) = annotations.get(*annotation) {
+            value.entry(field.to_string()).or_insert_with(|| json!(v));
+        }
+    }
+    serde_json::to_vec(&value).unwrap_or(config)
+}
+
+/// Refers to an image's repository by manifest digest, e.g. `localhost:5000/echo@sha256:...`
+fn pinned_reference(image: &Reference, digest: &str) -> String {
+    format!("{}/{}@{}", image.registry(), image.repository(), digest)
+}
+
+/// Builds one layer per architecture target of a provider archive, recording the
+/// target in the layer's annotations, followed by a layer holding the archive's claims
+fn target_layers(
+    archive: &[u8],
+) -> Result<Vec<(OciDescriptor, ImageLayer)>, Box<dyn ::std::error::Error>> {
+    let par = ProviderArchive::try_load(archive).map_err(|e| format!("{}", e))?;
+    let mut targets = par.targets();
+    targets.sort();
+    let target_layers = targets
+        .into_iter()
+        .map(|target| {
+            let data = par
+                .target_bytes(&target)
+                .ok_or_else(|| format!("Provider archive is missing target {}", target))?;
+            let mut layer_annotations = HashMap::new();
+            layer_annotations.insert(PROVIDER_TARGET_ANNOTATION.to_string(), target);
+            let layer_descriptor = OciDescriptor {
+                annotations: Some(layer_annotations),
+                ..descriptor(&data, PROVIDER_TARGET_MEDIA_TYPE)
+            };
+            Ok((
+                layer_descriptor,
+                ImageLayer {
+                    data,
+                    media_type: PROVIDER_TARGET_MEDIA_TYPE.to_string(),
+                },
+            ))
+        })
+        .collect::<Result<Vec<_>, Box<dyn ::std::error::Error>>>()?;
+    // The claims are pushed as a layer of their own, so the signed archive can be
+    // reassembled when it's pulled
+    let claims = crate::par::archive_claims_token(archive)?.into_bytes();
+    let mut claims_annotations = HashMap::new();
+    claims_annotations.insert(
+        OCI_TITLE_ANNOTATION.to_string(),
+        PROVIDER_CLAIMS_FILE.to_string(),
+    );
+    let claims_layer = (
+        OciDescriptor {
+            annotations: Some(claims_annotations),
+            ..descriptor(&claims, PROVIDER_CLAIMS_MEDIA_TYPE)
+        },
+        ImageLayer {
+            data: claims,
+            media_type: PROVIDER_CLAIMS_MEDIA_TYPE.to_string(),
+        },
+    );
+    Ok(target_layers
+        .into_iter()
+        .chain(std::iter::once(claims_layer))
+        .collect())
+}
+
+fn descriptor(data: &[u8], media_type: &str) -> OciDescriptor {
+    OciDescriptor {
+        media_type: media_type.to_string(),
+        digest: format!("sha256:{:x}", Sha256::digest(data)),
+        size: data.len() as i64,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        annotate_config, artifact_annotations, assemble_split_archive, cached_artifact,
+        cached_artifact_path, cached_references_in, config_output, create_parent_dir,
+        create_scratch_file, credentials_for, default_config, docker_config_auth,
+        download_artifact, glob_matches, handle_copy, handle_inspect_config, handle_manifest,
+        has_next_link, host_credential_var, host_env_credentials, index_platform_manifest,
+        is_split_archive, layer_file_name, list_artifacts, load_registry_config,
+        media_type_artifact, no_proxy_matches, normalize_registry, parse_bearer_challenge,
+        parse_reference, pinned_reference, platform_layers, prompt_user, proxy_for, push_tags,
+        read_artifact, read_config, read_password, referrer_manifest, registry_auth,
+        registry_client, registry_token, remove_credentials, sbom_media_type, status_error,
+        store_cached_artifact, store_cached_reference, store_credentials, strip_oci_scheme,
+        tag_page, until_signal, validate_login, verify_claims, with_retries, with_timeout,
+        write_artifact, write_layers, AuthOpts, CopyCommand, DeleteCommand, ImageIndex,
+        ListCommand, LoginCommand, LogoutCommand, ManifestCommand, PullCommand, PushCommand,
+        RegCli, RegCliCommand, RegistryApi, RegistrySettings, SupportedArtifacts, TagPage,
+        TagsCommand, CYCLONEDX_MEDIA_TYPE, OCI_AUTHORS_ANNOTATION, OCI_CREATED_ANNOTATION,
+        OCI_EMPTY_MEDIA_TYPE, OCI_INDEX_MEDIA_TYPE, OCI_MEDIA_TYPE, OCI_TITLE_ANNOTATION,
+        PROVIDER_ARCHIVE_MEDIA_TYPE, PROVIDER_CLAIMS_MEDIA_TYPE, PROVIDER_TARGET_ANNOTATION,
+        PROVIDER_TARGET_MEDIA_TYPE, SPDX_MEDIA_TYPE, STDOUT_DESTINATION, WASM_CONFIG_MEDIA_TYPE,
+        WASM_MEDIA_TYPE,
+    };
+    use crate::util::{Aborted, OutputKind, WashError};
+    use oci_distribution::manifest::{OciDescriptor, OCI_IMAGE_MEDIA_TYPE};
+    use oci_distribution::secrets::RegistryAuth;
+    use reqwest::StatusCode;
+    use serde_json::json;
+    use sha2::{Digest, Sha256};
+    use std::cell::Cell;
+    use std::collections::HashMap;
+    use std::env::temp_dir;
+    use std::fs::{remove_dir_all, write, File};
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::path::PathBuf;
+    use std::thread;
+    use std::time::Duration;
+    use structopt::StructOpt;
+
+    const ECHO_WASM: &str = "wasmcloud.azurecr.io/echo:0.2.0";
+    const LOCAL_REGISTRY: &str = "localhost:5000";
+
+    #[test]
+    /// Enumerates multiple options of the `pull` command to ensure API doesn't
+    /// change between versions. This test will fail if `wash reg pull`
+    /// changes syntax, ordering of required elements, or flags.
+    fn test_pull_comprehensive() {
+        // Not explicitly used, just a placeholder for a directory
+        const TESTDIR: &str = "./tests/fixtures";
+
+        let pull_basic = RegCli::from_iter(&["reg", "pull", ECHO_WASM]);
+        let pull_all_flags = RegCli::from_iter(&[
+            "reg",
+            "pull",
+            ECHO_WASM,
+            "--allow-latest",
+            "--insecure",
+            "--password-stdin",
+            "--no-cache",
+            "--checksum",
+            "--all-layers",
+        ]);
+        let pull_all_options = RegCli::from_iter(&[
+            "reg",
+            "pull",
+            ECHO_WASM,
+            "--destination",
+            TESTDIR,
+            "--digest",
+            "sha256:a17a163afa8447622055deb049587641a9e23243a6cc4411eb33bd4267214cf3",
+            "--output",
+            "text",
+            "--password",
+            "password",
+            "--user",
+            "user",
+            "--retries",
+            "5",
+            "--media-type",
+            "application/vnd.example.wasm",
+            "--timeout",
+            "30",
+            "--platform",
+            "x86_64-linux",
+        ]);
+        match pull_basic.command {
+            RegCliCommand::Pull(PullCommand {
+                url,
+                retries,
+                no_cache,
+                timeout,
+                platform,
+                checksum,
+                ..
+            }) => {
+                assert_eq!(url, ECHO_WASM);
+                assert_eq!(retries, 3);
+                assert_eq!(timeout, 300);
+                assert!(!no_cache);
+                assert!(!checksum);
+                assert!(platform.is_none());
+            }
+            _ => panic!("`reg pull` constructed incorrect command"),
+        };
+
+        let pull_stdout = RegCli::from_iter(&["reg", "pull", ECHO_WASM, "--destination", "-"]);
+        match pull_stdout.command {
+            RegCliCommand::Pull(PullCommand { destination, .. }) => {
+                assert_eq!(destination.as_deref(), Some(STDOUT_DESTINATION));
+            }
+            _ => panic!("`reg pull` constructed incorrect command"),
+        };
+
+        let pull_verified = RegCli::from_iter(&["reg", "pull", ECHO_WASM, "--verify-claims"]);
+        match pull_verified.command {
+            RegCliCommand::Pull(PullCommand { verify_claims, .. }) => assert!(verify_claims),
+            _ => panic!("`reg pull` constructed incorrect command"),
+        };
+        assert!(RegCli::from_iter_safe(&[
+            "reg",
+            "pull",
+            ECHO_WASM,
+            "--verify-claims",
+            "--all-layers"
+        ])
+        .is_err());
+
+        match pull_all_flags.command {
+            RegCliCommand::Pull(PullCommand {
+                url,
+                allow_latest,
+                opts,
+                no_cache,
+                checksum,
+                all_layers,
+                ..
+            }) => {
+                assert_eq!(url, ECHO_WASM);
+                assert!(allow_latest);
+                assert!(no_cache);
+                assert!(checksum);
+                assert!(all_layers);
+                assert!(opts.insecure);
+                assert!(opts.password_stdin);
+            }
+            _ => panic!("`reg pull` constructed incorrect command"),
+        };
+
+        match pull_all_options.command {
+            RegCliCommand::Pull(PullCommand {
+                url,
+                destination,
+                digest,
+                output,
+                opts,
+                retries,
+                media_type,
+                timeout,
+                platform,
+                ..
+            }) => {
+                assert_eq!(url, ECHO_WASM);
+                assert_eq!(destination.unwrap(), TESTDIR);
+                assert_eq!(platform.unwrap(), "x86_64-linux");
+                assert_eq!(retries, 5);
+                assert_eq!(timeout, 30);
+                assert_eq!(media_type.unwrap(), "application/vnd.example.wasm");
+                assert_eq!(
+                    digest.unwrap(),
+                    "sha256:a17a163afa8447622055deb049587641a9e23243a6cc4411eb33bd4267214cf3"
+                );
+                assert_eq!(output.kind, OutputKind::Text { max_width: 0 });
+                assert_eq!(opts.user.unwrap(), "user");
+                assert_eq!(opts.password.unwrap(), "password");
+            }
+            _ => panic!("`reg pull` constructed incorrect command"),
+        };
+    }
+
+    #[test]
+    /// Enumerates multiple options of the `push` command to ensure API doesn't
+    /// change between versions. This test will fail if `wash reg push`
+    /// changes syntax, ordering of required elements, or flags.
+    fn test_push_comprehensive() {
+        // Not explicitly used, just a placeholder for a directory
+        const TESTDIR: &str = "./tests/fixtures";
+
+        // Push echo.wasm and pull from local registry
+        let echo_push_basic = &format!("{}/echo:pushbasic", LOCAL_REGISTRY);
+        let push_basic = RegCli::from_iter(&[
+            "reg",
+            "push",
+            echo_push_basic,
+            &format!("{}/echopush.wasm", TESTDIR),
+            "--insecure",
+        ]);
+        match push_basic.command {
+            RegCliCommand::Push(PushCommand {
+                url,
+                artifact,
+                opts,
+                ..
+            }) => {
+                assert_eq!(&url, echo_push_basic);
+                assert_eq!(artifact, format!("{}/echopush.wasm", TESTDIR));
+                assert!(opts.insecure);
+            }
+            _ => panic!("`reg push` constructed incorrect command"),
+        };
+
+        // Push logging.par.gz and pull from local registry
+        let logging_push_all_flags = &format!("{}/logging:allflags", LOCAL_REGISTRY);
+        let push_all_flags = RegCli::from_iter(&[
+            "reg",
+            "push",
+            logging_push_all_flags,
+            &format!("{}/logging.par.gz", TESTDIR),
+            "--insecure",
+            "--allow-latest",
+        ]);
+        match push_all_flags.command {
+            RegCliCommand::Push(PushCommand {
+                url,
+                artifact,
+                opts,
+                allow_latest,
+                ..
+            }) => {
+                assert_eq!(&url, logging_push_all_flags);
+                assert_eq!(artifact, format!("{}/logging.par.gz", TESTDIR));
+                assert!(opts.insecure);
+                assert!(allow_latest);
+            }
+            _ => panic!("`reg push` constructed incorrect command"),
+        };
+
+        // Push logging.par.gz to different tag and pull to confirm successful push
+        let logging_push_all_options = &format!("{}/logging:alloptions", LOCAL_REGISTRY);
+        let push_all_options = RegCli::from_iter(&[
+            "reg",
+            "push",
+            logging_push_all_options,
+            &format!("{}/logging.par.gz", TESTDIR),
+            "--allow-latest",
+            "--insecure",
+            "--config",
+            &format!("{}/config.json", TESTDIR),
             "--output",
-            "text",
+            "json",
+            "--password",
+            "supers3cr3t",
+            "--user",
+            "localuser",
+            "--author",
+            "wasmCloud Team",
+            "--created",
+            "2021-04-01T12:00:00Z",
+            "--annotation",
+            "org.opencontainers.image.revision=0.1.0",
+            "--annotation",
+            "org.opencontainers.image.source=https://github.com/wasmcloud/wash",
+            "--split-targets",
+            "--tag",
+            "0.1",
+            "--tag",
+            "latest",
+            "--retries",
+            "0",
+            "--media-type",
+            "application/vnd.example.par",
+            "--sbom",
+            "sbom.spdx.json",
+            "--timeout",
+            "60",
+        ]);
+        match push_all_options.command {
+            RegCliCommand::Push(PushCommand {
+                url,
+                artifact,
+                opts,
+                allow_latest,
+                config,
+                output,
+                author,
+                created,
+                annotations,
+                split_targets,
+                tags,
+                retries,
+                media_type,
+                sbom,
+                timeout,
+                ..
+            }) => {
+                assert_eq!(&url, logging_push_all_options);
+                assert_eq!(tags, vec!["0.1", "latest"]);
+                assert_eq!(timeout, 60);
+                assert_eq!(sbom.unwrap(), PathBuf::from("sbom.spdx.json"));
+                assert!(split_targets);
+                assert_eq!(retries, 0);
+                assert_eq!(media_type.unwrap(), "application/vnd.example.par");
+                assert_eq!(author.unwrap(), "wasmCloud Team");
+                assert_eq!(created.unwrap(), "2021-04-01T12:00:00Z");
+                assert_eq!(
+                    annotations,
+                    vec![
+                        "org.opencontainers.image.revision=0.1.0",
+                        "org.opencontainers.image.source=https://github.com/wasmcloud/wash"
+                    ]
+                );
+                assert_eq!(artifact, format!("{}/logging.par.gz", TESTDIR));
+                assert!(opts.insecure);
+                assert!(allow_latest);
+                assert_eq!(config.unwrap(), format!("{}/config.json", TESTDIR));
+                assert_eq!(opts.user.unwrap(), "localuser");
+                assert_eq!(opts.password.unwrap(), "supers3cr3t");
+                assert_eq!(output.kind, OutputKind::Json);
+            }
+            _ => panic!("`reg push` constructed incorrect command"),
+        };
+    }
+
+    #[test]
+    /// Enumerates the options of the `list`, `tags` and `delete` commands to ensure the API
+    /// doesn't change between versions
+    fn test_list_tags_delete_comprehensive() {
+        const ECHO_REPO: &str = "wasmcloud.azurecr.io/echo";
+
+        let list = RegCli::from_iter(&[
+            "reg",
+            "list",
+            ECHO_REPO,
+            "-o",
+            "json",
+            "-u",
+            "user",
+            "-p",
+            "password",
+            "--insecure",
+            "--registry-config",
+            "./registries.toml",
+        ]);
+        match list.command {
+            RegCliCommand::List(ListCommand {
+                repository,
+                output,
+                opts,
+                registry_config,
+            }) => {
+                assert_eq!(registry_config.unwrap(), "./registries.toml");
+                assert_eq!(repository, ECHO_REPO);
+                assert_eq!(output.kind, OutputKind::Json);
+                assert_eq!(opts.user.unwrap(), "user");
+                assert_eq!(opts.password.unwrap(), "password");
+                assert!(opts.insecure);
+            }
+            _ => panic!("`reg list` constructed incorrect command"),
+        };
+
+        let tags = RegCli::from_iter(&[
+            "reg",
+            "tags",
+            ECHO_REPO,
+            "--limit",
+            "2",
+            "--last",
+            "0.1.0",
+            "--filter",
+            "0.*",
+            "--output",
+            "json",
+            "--user",
+            "user",
+            "--password",
+            "password",
+            "--insecure",
+            "--registry-config",
+            "./registries.toml",
+        ]);
+        match tags.command {
+            RegCliCommand::Tags(TagsCommand {
+                repository,
+                limit,
+                last,
+                filter,
+                output,
+                opts,
+                registry_config,
+            }) => {
+                assert_eq!(registry_config.unwrap(), "./registries.toml");
+                assert_eq!(repository, ECHO_REPO);
+                assert_eq!(limit, Some(2));
+                assert_eq!(last.unwrap(), "0.1.0");
+                assert_eq!(filter.unwrap(), "0.*");
+                assert_eq!(output.kind, OutputKind::Json);
+                assert_eq!(opts.user.unwrap(), "user");
+                assert_eq!(opts.password.unwrap(), "password");
+                assert!(opts.insecure);
+            }
+            _ => panic!("`reg tags` constructed incorrect command"),
+        };
+
+        let delete_basic = RegCli::from_iter(&["reg", "delete", ECHO_WASM]);
+        match delete_basic.command {
+            RegCliCommand::Delete(DeleteCommand { url, force, .. }) => {
+                assert_eq!(url, ECHO_WASM);
+                assert!(!force);
+            }
+            _ => panic!("`reg delete` constructed incorrect command"),
+        };
+        let delete_all = RegCli::from_iter(&[
+            "reg",
+            "delete",
+            ECHO_WASM,
+            "--force",
+            "--output",
+            "json",
+            "--user",
+            "user",
+            "--password",
+            "password",
+            "--registry-config",
+            "./registries.toml",
+        ]);
+        match delete_all.command {
+            RegCliCommand::Delete(DeleteCommand {
+                url,
+                force,
+                output,
+                opts,
+                registry_config,
+            }) => {
+                assert_eq!(registry_config.unwrap(), "./registries.toml");
+                assert_eq!(url, ECHO_WASM);
+                assert!(force);
+                assert_eq!(output.kind, OutputKind::Json);
+                assert_eq!(opts.user.unwrap(), "user");
+                assert_eq!(opts.password.unwrap(), "password");
+            }
+            _ => panic!("`reg delete` constructed incorrect command"),
+        };
+        for flag in &["--yes", "-y"] {
+            match RegCli::from_iter(&["reg", "delete", ECHO_WASM, flag]).command {
+                RegCliCommand::Delete(DeleteCommand { force, .. }) => assert!(force),
+                _ => panic!("`reg delete` constructed incorrect command"),
+            }
+        }
+
+        let manifest_basic = RegCli::from_iter(&["reg", "manifest", ECHO_WASM]);
+        match manifest_basic.command {
+            RegCliCommand::Manifest(ManifestCommand { url, raw, .. }) => {
+                assert_eq!(url, ECHO_WASM);
+                assert!(!raw);
+            }
+            _ => panic!("`reg manifest` constructed incorrect command"),
+        };
+        let manifest_all = RegCli::from_iter(&[
+            "reg",
+            "manifest",
+            ECHO_WASM,
+            "--raw",
+            "--output",
+            "json",
+            "--user",
+            "user",
+            "--password",
+            "password",
+            "--insecure",
+            "--registry-config",
+            "./registries.toml",
+        ]);
+        match manifest_all.command {
+            RegCliCommand::Manifest(ManifestCommand {
+                url,
+                raw,
+                output,
+                opts,
+                registry_config,
+            }) => {
+                assert_eq!(registry_config.unwrap(), "./registries.toml");
+                assert_eq!(url, ECHO_WASM);
+                assert!(raw);
+                assert_eq!(output.kind, OutputKind::Json);
+                assert_eq!(opts.user.unwrap(), "user");
+                assert_eq!(opts.password.unwrap(), "password");
+                assert!(opts.insecure);
+            }
+            _ => panic!("`reg manifest` constructed incorrect command"),
+        };
+    }
+
+    #[test]
+    fn test_copy_comprehensive() {
+        const STAGING: &str = "staging.azurecr.io/echo:0.2.0";
+        const PROD: &str = "prod.azurecr.io/echo:0.2.0";
+        let copy = RegCli::from_iter(&[
+            "reg",
+            "copy",
+            STAGING,
+            PROD,
+            "--src-user",
+            "stage",
+            "--src-password",
+            "stagepass",
+            "--src-insecure",
+            "--dst-user",
+            "prod",
+            "--dst-password",
+            "prodpass",
+            "--dst-insecure",
+            "--proxy",
+            "http://proxy:3128",
+            "--timeout",
+            "60",
+            "--registry-config",
+            "./registries.toml",
+            "-o",
+            "json",
+        ]);
+        match copy.command {
+            RegCliCommand::Copy(cmd) => {
+                assert_eq!(cmd.source, STAGING);
+                assert_eq!(cmd.destination, PROD);
+                assert_eq!(cmd.timeout, 60);
+                assert_eq!(cmd.registry_config.as_deref(), Some("./registries.toml"));
+                assert_eq!(cmd.output.kind, OutputKind::Json);
+                let source = cmd.source_opts();
+                assert_eq!(source.user.unwrap(), "stage");
+                assert_eq!(source.password.unwrap(), "stagepass");
+                assert!(source.insecure);
+                assert_eq!(source.proxy.unwrap(), "http://proxy:3128");
+                let destination = cmd.destination_opts();
+                assert_eq!(destination.user.unwrap(), "prod");
+                assert_eq!(destination.password.unwrap(), "prodpass");
+                assert!(destination.insecure);
+            }
+            _ => panic!("`reg copy` constructed incorrect command"),
+        };
+
+        match RegCli::from_iter(&["reg", "copy", STAGING, PROD]).command {
+            RegCliCommand::Copy(CopyCommand {
+                src_user,
+                dst_user,
+                src_insecure,
+                dst_insecure,
+                timeout,
+                ..
+            }) => {
+                assert!(src_user.is_none());
+                assert!(dst_user.is_none());
+                assert!(!src_insecure);
+                assert!(!dst_insecure);
+                assert_eq!(timeout, 300);
+            }
+            _ => panic!("`reg copy` constructed incorrect command"),
+        };
+    }
+
+    #[test]
+    fn test_verify_claims() {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(4);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "x86_64-linux.bin", &b"\x7fELF"[..])
+            .unwrap();
+        let unsigned = builder.into_inner().unwrap();
+
+        let err = verify_claims(&unsigned).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Claims verification failed: Provider archive does not contain claims"
+        );
+        assert_eq!(crate::util::exit_code(&*err), 6);
+        assert!(verify_claims(b"x").is_err());
+    }
+
+    #[test]
+    fn test_default_config() {
+        assert_eq!(
+            default_config(b"\0asm", &SupportedArtifacts::Wasm),
+            b"{}".to_vec()
+        );
+        // An archive without readable claims has no contract ID to record
+        assert_eq!(
+            default_config(b"not an archive", &SupportedArtifacts::Par),
+            b"{}".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_read_config() {
+        let dir = std::env::temp_dir().join(format!("wash-reg-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let valid = dir.join("valid.json");
+        std::fs::write(&valid, b"{\"env\": \"prod\"}").unwrap();
+        assert_eq!(
+            read_config(valid.to_str().unwrap()).unwrap(),
+            b"{\"env\": \"prod\"}".to_vec()
+        );
+
+        let invalid = dir.join("invalid.json");
+        std::fs::write(&invalid, b"{\n  \"env\": prod\n}").unwrap();
+        let err = read_config(invalid.to_str().unwrap()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Config file {} is not valid JSON: expected value at line 2 column 10",
+                invalid.display()
+            )
+        );
+        assert!(read_config(dir.join("missing.json").to_str().unwrap()).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_push_from_stdin() {
+        match RegCli::from_iter_safe(&["reg", "push", "localhost:5000/echo:0.1", "-"])
+            .unwrap()
+            .command
+        {
+            RegCliCommand::Push(PushCommand { artifact, .. }) => assert_eq!(artifact, "-"),
+            cmd => panic!("reg push constructed incorrect command {:?}", cmd),
+        }
+
+        let mut stdin = std::io::Cursor::new(b"\0asm module".to_vec());
+        assert_eq!(
+            read_artifact("-", &mut stdin).unwrap(),
+            b"\0asm module".to_vec()
+        );
+        assert_eq!(
+            read_artifact("-", &mut std::io::Cursor::new(vec![]))
+                .unwrap_err()
+                .to_string(),
+            "No artifact was provided on stdin"
+        );
+        assert!(read_artifact("./missing.wasm", &mut std::io::Cursor::new(vec![])).is_err());
+    }
+
+    #[test]
+    fn test_password_stdin() {
+        assert!(RegCli::from_iter_safe(&[
+            "reg",
+            "push",
+            "localhost:5000/echo:0.2.0",
+            "echo.wasm",
             "--password",
-            "password",
-            "--user",
+            "secret",
+            "--password-stdin",
+        ])
+        .is_err());
+        assert_eq!(read_password(&mut "s3cr3t\n".as_bytes()).unwrap(), "s3cr3t");
+        assert_eq!(
+            read_password(&mut "pass word\r\n".as_bytes()).unwrap(),
+            "pass word"
+        );
+        assert!(read_password(&mut "\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_login_comprehensive() {
+        let login = RegCli::from_iter(&[
+            "reg",
+            "login",
+            LOCAL_REGISTRY,
+            "-u",
             "user",
+            "--password-stdin",
+            "--insecure",
+            "-o",
+            "json",
+            "--registry-config",
+            "./registries.toml",
         ]);
-        match pull_basic.command {
-            RegCliCommand::Pull(PullCommand { url, .. }) => {
-                assert_eq!(url, ECHO_WASM);
+        match login.command {
+            RegCliCommand::Login(LoginCommand {
+                registry,
+                output,
+                opts,
+                registry_config,
+            }) => {
+                assert_eq!(registry_config.unwrap(), "./registries.toml");
+                assert_eq!(registry, LOCAL_REGISTRY);
+                assert_eq!(output.kind, OutputKind::Json);
+                assert_eq!(opts.user.unwrap(), "user");
+                assert!(opts.password_stdin);
+                assert!(opts.insecure);
+            }
+            cmd => panic!("reg login constructed incorrect command {:?}", cmd),
+        }
+        assert!(RegCli::from_iter_safe(&["reg", "login"]).is_err());
+
+        assert_eq!(prompt_user(&mut "user\n".as_bytes()).unwrap(), "user");
+        assert!(prompt_user(&mut "\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_store_credentials() {
+        let dir = temp_dir().join("wash_store_credentials_test");
+        let _ = remove_dir_all(&dir);
+        let path = dir.join("credentials.json");
+
+        store_credentials(&path, "https://index.docker.io/v1/", "user", "old").unwrap();
+        store_credentials(&path, LOCAL_REGISTRY, "local", "secret").unwrap();
+        store_credentials(&path, "docker.io", "user", "pass:word").unwrap();
+
+        match docker_config_auth(&path, "docker.io") {
+            Some(RegistryAuth::Basic(user, password)) => {
+                assert_eq!(user, "user");
+                assert_eq!(password, "pass:word");
+            }
+            _ => panic!("stored credentials were not found"),
+        }
+        match docker_config_auth(&path, LOCAL_REGISTRY) {
+            Some(RegistryAuth::Basic(user, _)) => assert_eq!(user, "local"),
+            _ => panic!("stored credentials were not found"),
+        }
+        let stored: serde_json::Value =
+            serde_json::from_reader(File::open(&path).unwrap()).unwrap();
+        assert_eq!(stored["auths"].as_object().unwrap().len(), 2);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        write(&path, "not json").unwrap();
+        assert!(store_credentials(&path, LOCAL_REGISTRY, "user", "pass").is_err());
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_logout_comprehensive() {
+        let logout = RegCli::from_iter(&["reg", "logout", LOCAL_REGISTRY, "-o", "json"]);
+        match logout.command {
+            RegCliCommand::Logout(LogoutCommand {
+                registry,
+                all,
+                output,
+            }) => {
+                assert_eq!(registry.unwrap(), LOCAL_REGISTRY);
+                assert!(!all);
+                assert_eq!(output.kind, OutputKind::Json);
+            }
+            cmd => panic!("reg logout constructed incorrect command {:?}", cmd),
+        }
+        let logout_all = RegCli::from_iter(&["reg", "logout", "--all"]);
+        match logout_all.command {
+            RegCliCommand::Logout(LogoutCommand { registry, all, .. }) => {
+                assert!(registry.is_none());
+                assert!(all);
+            }
+            cmd => panic!("reg logout constructed incorrect command {:?}", cmd),
+        }
+        assert!(RegCli::from_iter_safe(&["reg", "logout"]).is_err());
+        assert!(RegCli::from_iter_safe(&["reg", "logout", LOCAL_REGISTRY, "--all"]).is_err());
+    }
+
+    #[test]
+    fn test_remove_credentials() {
+        let dir = temp_dir().join("wash_remove_credentials_test");
+        let _ = remove_dir_all(&dir);
+        let path = dir.join("credentials.json");
+        assert!(remove_credentials(&path, Some(LOCAL_REGISTRY))
+            .unwrap()
+            .is_empty());
+
+        store_credentials(&path, LOCAL_REGISTRY, "user", "pass").unwrap();
+        store_credentials(&path, "docker.io", "user", "pass").unwrap();
+        store_credentials(&path, "wasmcloud.azurecr.io", "user", "pass").unwrap();
+
+        assert_eq!(
+            remove_credentials(&path, Some(LOCAL_REGISTRY)).unwrap(),
+            vec![LOCAL_REGISTRY]
+        );
+        assert!(remove_credentials(&path, Some(LOCAL_REGISTRY))
+            .unwrap()
+            .is_empty());
+        assert!(docker_config_auth(&path, LOCAL_REGISTRY).is_none());
+        assert!(docker_config_auth(&path, "docker.io").is_some());
+
+        assert_eq!(
+            remove_credentials(&path, None).unwrap(),
+            vec!["docker.io", "wasmcloud.azurecr.io"]
+        );
+        assert!(!path.exists());
+        assert!(remove_credentials(&path, None).unwrap().is_empty());
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn test_validate_login() {
+        let client = reqwest::Client::new();
+        let registry = mock_bearer_registry();
+        let valid = RegistryAuth::Basic("user".to_string(), "pass".to_string());
+        assert!(validate_login(&client, &registry, &valid, true)
+            .await
+            .is_ok());
+        let invalid = RegistryAuth::Basic("user".to_string(), "wrong".to_string());
+        assert!(validate_login(&client, &registry, &invalid, true)
+            .await
+            .unwrap_err()
+            .to_string()
+            .contains("rejected the provided credentials"));
+
+        let anonymous = mock_artifact_registry();
+        assert!(validate_login(&client, &anonymous, &valid, true)
+            .await
+            .is_ok());
+    }
+
+    #[test]
+    fn test_strip_oci_scheme() {
+        assert_eq!(
+            strip_oci_scheme("oci://wasmcloud.azurecr.io/echo:0.2.0"),
+            Some(ECHO_WASM)
+        );
+        assert_eq!(strip_oci_scheme(ECHO_WASM), None);
+        assert_eq!(strip_oci_scheme("./echo.wasm"), None);
+
+        let image = parse_reference("oci://wasmcloud.azurecr.io/echo:0.2.0").unwrap();
+        assert_eq!(image.whole(), ECHO_WASM);
+        assert!(parse_reference("oci://")
+            .unwrap_err()
+            .to_string()
+            .starts_with("Invalid OCI reference 'oci://'"));
+    }
+
+    #[test]
+    fn test_push_tags() {
+        let tags = |additional: &[&str]| -> Vec<String> {
+            additional.iter().map(|t| t.to_string()).collect()
+        };
+        assert_eq!(push_tags("1.2.3", &[], false).unwrap(), vec!["1.2.3"]);
+        assert_eq!(
+            push_tags("1.2.3", &tags(&["1.2", "1.2.3", "latest"]), true).unwrap(),
+            vec!["1.2.3", "1.2", "latest"]
+        );
+        assert!(push_tags("1.2.3", &tags(&["latest"]), false)
+            .unwrap_err()
+            .to_string()
+            .starts_with("Pushing artifacts with tag 'latest' is prohibited"));
+        assert!(push_tags("latest", &[], false).is_err());
+        for invalid in &["", ".hidden", "-rc", "1.2/3", "tag:1", &"a".repeat(129)] {
+            assert!(
+                push_tags("1.2.3", &tags(&[invalid]), true).is_err(),
+                "{} should be rejected",
+                invalid
+            );
+        }
+        assert!(push_tags("1.2.3", &tags(&["_v1-rc.1"]), false).is_ok());
+    }
+
+    #[test]
+    fn test_pinned_reference() {
+        let image = parse_reference(ECHO_WASM).unwrap();
+        assert_eq!(
+            pinned_reference(&image, "sha256:123456"),
+            "wasmcloud.azurecr.io/echo@sha256:123456"
+        );
+    }
+
+    #[test]
+    fn test_create_parent_dir() {
+        let root = temp_dir().join("wash_reg_pull_parent_test");
+        let _ = remove_dir_all(&root);
+        let nested = root.join("build").join("artifacts").join("echo.wasm");
+        create_parent_dir(&nested.to_string_lossy()).unwrap();
+        assert!(root.join("build").join("artifacts").is_dir());
+
+        let file_parent = root.join("file");
+        File::create(&file_parent).unwrap();
+        let invalid = file_parent.join("echo.wasm");
+        assert!(create_parent_dir(&invalid.to_string_lossy()).is_err());
+
+        assert!(create_parent_dir("echo.wasm").is_ok());
+        remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_artifact_annotations() {
+        let annotations = artifact_annotations(
+            Some("wasmCloud Team".to_string()),
+            Some("2021-04-01T12:00:00Z".to_string()),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            annotations.get(OCI_CREATED_ANNOTATION).unwrap(),
+            "2021-04-01T12:00:00+00:00"
+        );
+        assert_eq!(
+            annotations.get(OCI_AUTHORS_ANNOTATION).unwrap(),
+            "wasmCloud Team"
+        );
+
+        let defaults = artifact_annotations(None, None, &[]).unwrap();
+        assert!(defaults.contains_key(OCI_CREATED_ANNOTATION));
+        assert!(!defaults.contains_key(OCI_AUTHORS_ANNOTATION));
+
+        assert!(artifact_annotations(None, Some("yesterday".to_string()), &[]).is_err());
+
+        let custom = artifact_annotations(
+            None,
+            None,
+            &[
+                "org.opencontainers.image.revision=abc".to_string(),
+                "org.opencontainers.image.url=https://wasmcloud.dev/?a=b".to_string(),
+                "org.opencontainers.image.revision=def".to_string(),
+            ],
+        )
+        .unwrap();
+        assert_eq!(custom["org.opencontainers.image.revision"], "def");
+        assert_eq!(
+            custom["org.opencontainers.image.url"],
+            "https://wasmcloud.dev/?a=b"
+        );
+        let err = artifact_annotations(None, None, &["revision".to_string()]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid annotation 'revision', expected KEY=VALUE"
+        );
+
+        let config = annotate_config(b"{\"author\":\"me\"}".to_vec(), &annotations);
+        let config: serde_json::Value = serde_json::from_slice(&config).unwrap();
+        assert_eq!(config["author"], "me");
+        assert_eq!(config["created"], "2021-04-01T12:00:00+00:00");
+        assert_eq!(
+            annotate_config(b"[]".to_vec(), &annotations),
+            b"[]".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_parse_reference() {
+        assert!(parse_reference(ECHO_WASM).is_ok());
+        let err = parse_reference("foo::bar").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Invalid OCI reference 'foo::bar'"));
+    }
+
+    #[test]
+    fn test_write_artifact_stage_errors() {
+        let image = parse_reference(ECHO_WASM).unwrap();
+        let downloaded = temp_dir().join("wash_write_artifact_test.part");
+        write(&downloaded, b"not an artifact").unwrap();
+        let destination = temp_dir().join("wash_write_artifact_test.wasm");
+        let err = write_artifact(
+            &downloaded,
+            &image,
+            OCI_MEDIA_TYPE,
+            Some(destination.to_string_lossy().to_string()),
+        )
+        .unwrap_err();
+        assert!(err.to_string().starts_with("Failed to validate"));
+        assert!(err.to_string().contains("artifact was downloaded"));
+        assert!(err.to_string().ends_with("Unsupported artifact type: echo"));
+        assert!(!destination.exists());
+        std::fs::remove_file(downloaded).unwrap();
+    }
+
+    #[test]
+    fn test_create_scratch_file() {
+        let first = create_scratch_file().unwrap();
+        let second = create_scratch_file().unwrap();
+        assert_ne!(first, second);
+        assert_eq!(std::fs::read(&first).unwrap(), b"");
+        assert!(first
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("wash-pull-"));
+        std::fs::remove_file(first).unwrap();
+        std::fs::remove_file(second).unwrap();
+    }
+
+    #[test]
+    fn test_write_layers() {
+        let image = parse_reference(ECHO_WASM).unwrap();
+        let layer = |data: &[u8], media_type: &str, annotation: Option<(&str, &str)>| {
+            let mut descriptor = OciDescriptor {
+                media_type: media_type.to_string(),
+                digest: format!("sha256:{:x}", Sha256::digest(data)),
+                size: data.len() as i64,
+                ..Default::default()
+            };
+            descriptor.annotations = annotation.map(|(k, v)| {
+                let mut annotations = HashMap::new();
+                annotations.insert(k.to_string(), v.to_string());
+                annotations
+            });
+            descriptor
+        };
+        let layers = vec![
+            layer(
+                b"linux",
+                PROVIDER_TARGET_MEDIA_TYPE,
+                Some((PROVIDER_TARGET_ANNOTATION, "x86_64-linux")),
+            ),
+            layer(
+                b"macos",
+                PROVIDER_TARGET_MEDIA_TYPE,
+                Some((PROVIDER_TARGET_ANNOTATION, "aarch64-macos")),
+            ),
+            layer(
+                b"readme",
+                OCI_MEDIA_TYPE,
+                Some((OCI_TITLE_ANNOTATION, "../../README.md")),
+            ),
+            layer(b"unnamed", OCI_MEDIA_TYPE, None),
+        ];
+        assert_eq!(layer_file_name(&layers[0], 0), "x86_64-linux.bin");
+        assert_eq!(layer_file_name(&layers[2], 2), "README.md");
+        assert_eq!(layer_file_name(&layers[3], 3), "layer-3");
+
+        let downloaded = temp_dir().join("wash_write_layers_test.part");
+        write(&downloaded, b"linuxmacosreadmeunnamed").unwrap();
+        let directory = temp_dir().join("wash_write_layers_test");
+        let (written_dir, files) = write_layers(
+            &downloaded,
+            &image,
+            &layers,
+            Some(directory.to_string_lossy().to_string()),
+        )
+        .unwrap();
+        assert_eq!(written_dir, directory.to_string_lossy());
+        assert_eq!(files.len(), 4);
+        assert_eq!(
+            std::fs::read(directory.join("aarch64-macos.bin")).unwrap(),
+            b"macos"
+        );
+        assert_eq!(
+            std::fs::read(directory.join("README.md")).unwrap(),
+            b"readme"
+        );
+        assert_eq!(
+            std::fs::read(directory.join("layer-3")).unwrap(),
+            b"unnamed"
+        );
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        // Layers are validated by their media type, and nothing is written if one fails
+        let invalid = vec![layers[0].clone(), layer(b"macos", WASM_MEDIA_TYPE, None)];
+        let err = write_layers(
+            &downloaded,
+            &image,
+            &invalid,
+            Some(directory.to_string_lossy().to_string()),
+        )
+        .unwrap_err();
+        assert!(err.to_string().starts_with("Failed to validate"));
+        assert!(!directory.exists());
+
+        let mut truncated = layers.clone();
+        truncated[3].size += 1;
+        let err = write_layers(
+            &downloaded,
+            &image,
+            &truncated,
+            Some(directory.to_string_lossy().to_string()),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("is truncated"));
+        assert!(!directory.exists());
+        std::fs::remove_file(downloaded).unwrap();
+
+        assert!(RegCli::from_iter_safe(&[
+            "reg",
+            "pull",
+            ECHO_WASM,
+            "--all-layers",
+            "--platform",
+            "x86_64-linux"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_assemble_split_archive() {
+        let layer = |data: &[u8], media_type: &str, annotation: (&str, &str)| OciDescriptor {
+            media_type: media_type.to_string(),
+            digest: format!("sha256:{:x}", Sha256::digest(data)),
+            size: data.len() as i64,
+            annotations: Some(
+                vec![(annotation.0.to_string(), annotation.1.to_string())]
+                    .into_iter()
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+        let layers = vec![
+            layer(
+                b"macos",
+                PROVIDER_TARGET_MEDIA_TYPE,
+                (PROVIDER_TARGET_ANNOTATION, "aarch64-macos"),
+            ),
+            layer(
+                b"linux",
+                PROVIDER_TARGET_MEDIA_TYPE,
+                (PROVIDER_TARGET_ANNOTATION, "x86_64-linux"),
+            ),
+            layer(
+                b"header.claims.signature",
+                PROVIDER_CLAIMS_MEDIA_TYPE,
+                (OCI_TITLE_ANNOTATION, "claims.jwt"),
+            ),
+        ];
+        assert!(is_split_archive(&layers));
+        // Without claims, or with other layers, the layers are written to a directory
+        assert!(!is_split_archive(&layers[..2]));
+        assert!(!is_split_archive(&layers[2..]));
+        let mut mixed = layers.clone();
+        mixed.push(layer(
+            b"readme",
+            OCI_MEDIA_TYPE,
+            (OCI_TITLE_ANNOTATION, "README.md"),
+        ));
+        assert!(!is_split_archive(&mixed));
+        // The claims layer doesn't count as a platform
+        assert_eq!(
+            platform_layers(&layers, "x86_64-windows")
+                .unwrap_err()
+                .to_string(),
+            "No layer for platform x86_64-windows, available platforms: aarch64-macos, x86_64-linux"
+        );
+
+        let downloaded = temp_dir().join("wash_assemble_split_archive_test.part");
+        write(&downloaded, b"macoslinuxheader.claims.signature").unwrap();
+        assemble_split_archive(&downloaded, &layers).unwrap();
+        let archive = std::fs::read(&downloaded).unwrap();
+        assert_eq!(
+            crate::par::archive_claims_token(&archive).unwrap(),
+            "header.claims.signature"
+        );
+        let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(&archive[..]));
+        let entries = tar
+            .entries()
+            .unwrap()
+            .map(|e| {
+                let mut e = e.unwrap();
+                let mut contents = Vec::new();
+                e.read_to_end(&mut contents).unwrap();
+                (e.path().unwrap().to_string_lossy().to_string(), contents)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries[1..],
+            [
+                ("aarch64-macos.bin".to_string(), b"macos".to_vec()),
+                ("x86_64-linux.bin".to_string(), b"linux".to_vec()),
+            ]
+        );
+
+        // Truncated layers can't be reassembled
+        write(&downloaded, b"macoslinux").unwrap();
+        assert!(assemble_split_archive(&downloaded, &layers).is_err());
+        std::fs::remove_file(downloaded).unwrap();
+    }
+
+    #[test]
+    fn test_write_artifact_media_type() {
+        assert_eq!(
+            media_type_artifact(WASM_MEDIA_TYPE),
+            Some(SupportedArtifacts::Wasm)
+        );
+        assert_eq!(
+            media_type_artifact(PROVIDER_ARCHIVE_MEDIA_TYPE),
+            Some(SupportedArtifacts::Par)
+        );
+        assert_eq!(
+            media_type_artifact(PROVIDER_TARGET_MEDIA_TYPE),
+            Some(SupportedArtifacts::Target)
+        );
+        assert_eq!(media_type_artifact(OCI_MEDIA_TYPE), None);
+        assert_eq!(media_type_artifact("application/vnd.example.wasm"), None);
+
+        // A provider archive media type is validated as an archive, even if the content
+        // looks like a WebAssembly module
+        let image = parse_reference(ECHO_WASM).unwrap();
+        let downloaded = temp_dir().join("wash_write_artifact_media_type.part");
+        write(&downloaded, MOCK_LAYER).unwrap();
+        let destination = temp_dir().join("wash_write_artifact_media_type.par.gz");
+        let err = write_artifact(
+            &downloaded,
+            &image,
+            PROVIDER_ARCHIVE_MEDIA_TYPE,
+            Some(destination.to_string_lossy().to_string()),
+        )
+        .unwrap_err();
+        assert!(err.to_string().ends_with("Invalid provider archive : echo"));
+        assert!(!destination.exists());
+
+        // A native library isn't labelled as a provider archive
+        let outfile =
+            write_artifact(&downloaded, &image, PROVIDER_TARGET_MEDIA_TYPE, None).unwrap();
+        assert_eq!(outfile, "echo.bin");
+        assert_eq!(std::fs::read(&outfile).unwrap(), MOCK_LAYER);
+        std::fs::remove_file(outfile).unwrap();
+        // Copied rather than moved when the temp dir is on another filesystem
+        let _ = std::fs::remove_file(downloaded);
+    }
+
+    #[test]
+    fn test_docker_config_auth() {
+        let config = temp_dir().join("wash_docker_config_test.json");
+        // "user:pass:word" base64 encoded
+        write(
+            &config,
+            r#"{"auths": {"https://index.docker.io/v1/": {"auth": "dXNlcjpwYXNzOndvcmQ="}, "localhost:5000": {}}}"#,
+        )
+        .unwrap();
+
+        match docker_config_auth(&config, "docker.io") {
+            Some(RegistryAuth::Basic(user, password)) => {
+                assert_eq!(user, "user");
+                assert_eq!(password, "pass:word");
+            }
+            _ => panic!("docker config credentials were not found"),
+        }
+        assert!(docker_config_auth(&config, "localhost:5000").is_none());
+        assert!(docker_config_auth(&config, "wasmcloud.azurecr.io").is_none());
+        assert!(docker_config_auth(&temp_dir().join("nonexistent.json"), "docker.io").is_none());
+
+        assert_eq!(
+            normalize_registry("https://index.docker.io/v1/"),
+            "docker.io"
+        );
+        assert_eq!(normalize_registry(LOCAL_REGISTRY), LOCAL_REGISTRY);
+        std::fs::remove_file(config).unwrap();
+    }
+
+    #[test]
+    fn test_host_env_credentials() {
+        assert_eq!(
+            host_credential_var("ghcr.io", "USER"),
+            "WASH_REG__GHCR_IO_USER"
+        );
+        assert_eq!(
+            host_credential_var("localhost:5000", "PASSWORD"),
+            "WASH_REG__LOCALHOST_5000_PASSWORD"
+        );
+        assert_eq!(
+            host_credential_var("my-registry.azurecr.io", "USER"),
+            "WASH_REG__MY_REGISTRY_AZURECR_IO_USER"
+        );
+
+        let vars: HashMap<&str, &str> = vec![
+            ("WASH_REG__GHCR_IO_USER", "ghcr-user"),
+            ("WASH_REG__GHCR_IO_PASSWORD", "ghcr-token"),
+            ("WASH_REG__DOCKER_IO_USER", "docker-user"),
+        ]
+        .into_iter()
+        .collect();
+        let var = |name: &str| vars.get(name).map(|v| v.to_string());
+        assert_eq!(
+            host_env_credentials("ghcr.io", var),
+            Some(("ghcr-user".to_string(), "ghcr-token".to_string()))
+        );
+        // Both the user and the password must be scoped to the host
+        assert_eq!(host_env_credentials("docker.io", var), None);
+        assert_eq!(host_env_credentials("localhost:5000", var), None);
+
+        let image = parse_reference("wash-env-creds.example:5000/echo:0.1").unwrap();
+        std::env::set_var("WASH_REG__WASH_ENV_CREDS_EXAMPLE_5000_USER", "scoped");
+        std::env::set_var("WASH_REG__WASH_ENV_CREDS_EXAMPLE_5000_PASSWORD", "secret");
+        match registry_auth(&image, None, None) {
+            RegistryAuth::Basic(user, password) => {
+                assert_eq!(user, "scoped");
+                assert_eq!(password, "secret");
+            }
+            _ => panic!("host scoped credentials were not used"),
+        }
+        match registry_auth(&image, Some("flag".to_string()), Some("pw".to_string())) {
+            RegistryAuth::Basic(user, _) => assert_eq!(user, "flag"),
+            _ => panic!("command line credentials were not used"),
+        }
+        // A user given on the command line wins even if it matches the global user
+        std::env::set_var("WASH_REG_USER", "flag");
+        match registry_auth(&image, Some("flag".to_string()), Some("pw".to_string())) {
+            RegistryAuth::Basic(user, password) => {
+                assert_eq!(user, "flag");
+                assert_eq!(password, "pw");
+            }
+            _ => panic!("command line credentials were not used"),
+        }
+        std::env::remove_var("WASH_REG_USER");
+        std::env::remove_var("WASH_REG__WASH_ENV_CREDS_EXAMPLE_5000_USER");
+        std::env::remove_var("WASH_REG__WASH_ENV_CREDS_EXAMPLE_5000_PASSWORD");
+    }
+
+    #[test]
+    fn test_credentials_precedence() {
+        let dir = temp_dir().join("wash_credentials_precedence_test");
+        let _ = remove_dir_all(&dir);
+        let path = dir.join("credentials.json");
+        store_credentials(&path, LOCAL_REGISTRY, "stored", "stored-pw").unwrap();
+        let stored = [None, Some(path)];
+
+        let env_vars = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        let all_vars = env_vars(&[
+            ("WASH_REG__LOCALHOST_5000_USER", "host"),
+            ("WASH_REG__LOCALHOST_5000_PASSWORD", "host-pw"),
+            ("WASH_REG_USER", "global"),
+            ("WASH_REG_PASSWORD", "global-pw"),
+        ]);
+        let global_vars = env_vars(&[
+            ("WASH_REG_USER", "global"),
+            ("WASH_REG_PASSWORD", "global-pw"),
+        ]);
+        let credentials = |auth: RegistryAuth| match auth {
+            RegistryAuth::Basic(user, password) => Some((user, password)),
+            RegistryAuth::Anonymous => None,
+        };
+        let basic = |user: &str, password: &str| Some((user.to_string(), password.to_string()));
+        let flag = |value: &str| Some(value.to_string());
+
+        assert_eq!(
+            credentials(credentials_for(
+                LOCAL_REGISTRY,
+                flag("flag"),
+                flag("flag-pw"),
+                all_vars,
+                &stored
+            )),
+            basic("flag", "flag-pw")
+        );
+        assert_eq!(
+            credentials(credentials_for(
+                LOCAL_REGISTRY,
+                None,
+                None,
+                all_vars,
+                &stored
+            )),
+            basic("host", "host-pw")
+        );
+        assert_eq!(
+            credentials(credentials_for(
+                LOCAL_REGISTRY,
+                None,
+                None,
+                global_vars,
+                &stored
+            )),
+            basic("stored", "stored-pw")
+        );
+        assert_eq!(
+            credentials(credentials_for(
+                LOCAL_REGISTRY,
+                None,
+                None,
+                global_vars,
+                &[]
+            )),
+            basic("global", "global-pw")
+        );
+        // A user on the command line skips the host credentials and takes the global password
+        assert_eq!(
+            credentials(credentials_for(
+                LOCAL_REGISTRY,
+                flag("flag"),
+                None,
+                all_vars,
+                &[]
+            )),
+            basic("flag", "global-pw")
+        );
+        assert_eq!(
+            credentials(credentials_for(
+                LOCAL_REGISTRY,
+                None,
+                None,
+                |_| None,
+                &stored[..1]
+            )),
+            None
+        );
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_bearer_challenge() {
+        let challenge = parse_bearer_challenge(
+            r#"Bearer realm="https://auth.docker.io/token",service="registry.docker.io",scope="repository:wasmcloud/echo:pull""#,
+        )
+        .unwrap();
+        assert_eq!(challenge["realm"], "https://auth.docker.io/token");
+        assert_eq!(challenge["service"], "registry.docker.io");
+        assert_eq!(challenge["scope"], "repository:wasmcloud/echo:pull");
+        assert!(parse_bearer_challenge(r#"Basic realm="registry""#).is_none());
+    }
+
+    /// Starts a registry on a random local port, answering each request with the response
+    /// built by `respond` from the registry address and the request's lines
+    fn mock_registry(respond: fn(&str, &[String]) -> Vec<u8>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let registry = addr.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = vec![];
+                let mut body_len = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(len) = line.to_lowercase().strip_prefix("content-length:") {
+                        body_len = len.trim().parse().unwrap();
+                    }
+                    request.push(line);
+                }
+                // Bodies are consumed so the connection isn't reset before the response is read
+                reader.read_exact(&mut vec![0; body_len]).unwrap();
+                stream.write_all(&respond(&registry, &request)).unwrap();
+            }
+        });
+        addr
+    }
+
+    fn http_response(status: &str, headers: &[(&str, &str)], body: &[u8]) -> Vec<u8> {
+        let mut response = format!("HTTP/1.1 {}\r\n", status);
+        for (name, value) in headers {
+            response.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        response.push_str(&format!(
+            "Content-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        ));
+        let mut response = response.into_bytes();
+        response.extend_from_slice(body);
+        response
+    }
+
+    /// Starts a registry that issues a bearer challenge and only grants tokens to `user:pass`
+    fn mock_bearer_registry() -> String {
+        mock_registry(|addr, request| {
+            if request[0].starts_with("GET /v2/ ") {
+                let challenge = format!("Bearer realm=\"http://{}/token\",service=\"mock\"", addr);
+                http_response("401 Unauthorized", &[("WWW-Authenticate", &challenge)], b"")
+            } else if request
+                .iter()
+                .any(|h| h.trim() == "authorization: Basic dXNlcjpwYXNz")
+            {
+                http_response("200 OK", &[], b"{\"token\":\"abc\"}")
+            } else {
+                http_response("401 Unauthorized", &[], b"invalid credentials")
+            }
+        })
+    }
+
+    const MOCK_LAYER: &[u8] = b"\0asm mock actor module";
+
+    /// Connection options for the plain HTTP mock registries
+    fn insecure_opts() -> AuthOpts {
+        AuthOpts {
+            insecure: true,
+            ..Default::default()
+        }
+    }
+
+    /// Digest of the manifest served by `mock_artifact_registry`
+    fn mock_manifest_digest() -> String {
+        format!("sha256:{:x}", Sha256::digest(mock_manifest().as_bytes()))
+    }
+
+    /// Manifest of the artifact served by `mock_artifact_registry`
+    fn mock_manifest() -> String {
+        json!({
+            "schemaVersion": 2,
+            "mediaType": OCI_IMAGE_MEDIA_TYPE,
+            "config": {
+                "mediaType": WASM_CONFIG_MEDIA_TYPE,
+                "digest": format!("sha256:{:x}", Sha256::digest(b"{}")),
+                "size": 2,
+            },
+            "layers": [{
+                "mediaType": WASM_MEDIA_TYPE,
+                "digest": format!("sha256:{:x}", Sha256::digest(MOCK_LAYER)),
+                "size": MOCK_LAYER.len(),
+            }],
+        })
+        .to_string()
+    }
+
+    /// Starts an anonymous registry serving a single wasm layer tagged `0.2.0` for any
+    /// repository, which forbids deleting artifacts
+    fn mock_artifact_registry() -> String {
+        mock_registry(|_, request| {
+            let layer_digest = format!("sha256:{:x}", Sha256::digest(MOCK_LAYER));
+            let config_digest = format!("sha256:{:x}", Sha256::digest(b"{}"));
+            if request[0].starts_with("GET /v2/ ") {
+                http_response("200 OK", &[], b"")
+            } else if request[0].contains("/tags/list ") {
+                http_response("200 OK", &[], b"{\"name\":\"echo\",\"tags\":[\"0.2.0\"]}")
+            } else if request[0].starts_with("DELETE ") {
+                http_response("405 Method Not Allowed", &[], b"")
+            } else if request[0].contains("/manifests/") {
+                http_response(
+                    "200 OK",
+                    &[("Docker-Content-Digest", &mock_manifest_digest())],
+                    mock_manifest().as_bytes(),
+                )
+            } else if request[0].contains(&format!("/blobs/{} ", layer_digest)) {
+                http_response("200 OK", &[], MOCK_LAYER)
+            } else if request[0].contains(&format!("/blobs/{} ", config_digest)) {
+                http_response("200 OK", &[], b"{}")
+            } else {
+                http_response("404 Not Found", &[], b"")
+            }
+        })
+    }
+
+    /// Starts an anonymous registry accepting blob uploads and manifests. Only the `echo`
+    /// repository supports the referrers API
+    fn mock_referrers_registry() -> String {
+        mock_registry(|_, request| {
+            if request[0].starts_with("GET /v2/ ") {
+                http_response("200 OK", &[], b"")
+            } else if request[0].starts_with("HEAD ") {
+                http_response("404 Not Found", &[], b"")
+            } else if request[0].starts_with("POST ") && request[0].contains("/blobs/uploads/ ") {
+                let location = "/v2/echo/blobs/uploads/1?state=abc";
+                http_response("202 Accepted", &[("Location", location)], b"")
+            } else if request[0].starts_with("PUT /v2/echo/blobs/uploads/1?state=abc&digest=") {
+                http_response("201 Created", &[], b"")
+            } else if request[0].starts_with("PUT /v2/echo/manifests/sha256:") {
+                http_response("201 Created", &[("OCI-Subject", "sha256:123456")], b"")
+            } else if request[0].starts_with("PUT /v2/echo/manifests/0.2.0 ")
+                || request[0].starts_with("PUT /v2/legacy/manifests/sha256:")
+            {
+                http_response("201 Created", &[], b"")
+            } else {
+                http_response("404 Not Found", &[], b"")
+            }
+        })
+    }
+
+    /// Starts an anonymous registry accepting copies into the `mirror` repository, which
+    /// already has the config blob of `mock_manifest`. Manifests pushed under `0.2.0` are
+    /// reported with the digest of `mock_manifest`, any other tag with a different digest
+    fn mock_copy_registry() -> String {
+        mock_registry(|_, request| {
+            let config_digest = format!("sha256:{:x}", Sha256::digest(b"{}"));
+            if request[0].starts_with("GET /v2/ ")
+                || request[0].starts_with(&format!("HEAD /v2/mirror/blobs/{} ", config_digest))
+            {
+                http_response("200 OK", &[], b"")
+            } else if request[0].starts_with("HEAD ") {
+                http_response("404 Not Found", &[], b"")
+            } else if request[0].starts_with("POST /v2/mirror/blobs/uploads/ ") {
+                let location = "/v2/mirror/blobs/uploads/1?state=abc";
+                http_response("202 Accepted", &[("Location", location)], b"")
+            } else if request[0].starts_with("PUT /v2/mirror/blobs/uploads/1?state=abc&digest=") {
+                http_response("201 Created", &[], b"")
+            } else if request[0].starts_with("PUT /v2/mirror/manifests/0.2.0 ") {
+                let digest = format!("sha256:{:x}", Sha256::digest(mock_manifest().as_bytes()));
+                http_response("201 Created", &[("Docker-Content-Digest", &digest)], b"")
+            } else if request[0].starts_with("PUT /v2/mirror/manifests/") {
+                http_response(
+                    "201 Created",
+                    &[("Docker-Content-Digest", "sha256:abcdef")],
+                    b"",
+                )
+            } else {
+                http_response("404 Not Found", &[], b"")
+            }
+        })
+    }
+
+    #[actix_rt::test]
+    async fn test_registry_token_bearer_challenge() {
+        let registry = mock_bearer_registry();
+        let image = parse_reference(&format!("{}/echo:0.2.0", registry)).unwrap();
+
+        let valid = RegistryAuth::Basic("user".to_string(), "pass".to_string());
+        assert_eq!(
+            registry_token(&reqwest::Client::new(), &image, &valid, true, "pull")
+                .await
+                .unwrap(),
+            Some("abc".to_string())
+        );
+
+        let invalid = RegistryAuth::Basic("user".to_string(), "wrong".to_string());
+        let err = registry_token(&reqwest::Client::new(), &image, &invalid, true, "pull")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Registry {} rejected the provided credentials: invalid credentials",
+                registry
+            )
+        );
+        assert_eq!(
+            err.downcast_ref::<WashError>(),
+            Some(&WashError::Auth(err.to_string()))
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_handle_manifest() {
+        let registry = mock_artifact_registry();
+        let url = format!("{}/echo:0.2.0", registry);
+        let manifest = |args: &[&str]| {
+            let mut cli = vec!["reg", "manifest", &url, "--insecure"];
+            cli.extend_from_slice(args);
+            match RegCli::from_iter(&cli).command {
+                RegCliCommand::Manifest(cmd) => handle_manifest(cmd),
+                _ => panic!("`reg manifest` constructed incorrect command"),
+            }
+        };
+
+        let pretty = manifest(&[]).await.unwrap();
+        assert!(pretty.contains("\n  \"layers\": ["));
+        let raw = manifest(&["--raw"]).await.unwrap();
+        assert!(!raw.contains('\n'));
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&raw).unwrap(),
+            serde_json::from_str::<serde_json::Value>(&pretty).unwrap()
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&manifest(&["-o", "json"]).await.unwrap()).unwrap();
+        assert_eq!(json["digest"], mock_manifest_digest());
+        assert_eq!(json["media_type"], OCI_IMAGE_MEDIA_TYPE);
+        assert_eq!(json["manifest"]["layers"][0]["mediaType"], WASM_MEDIA_TYPE);
+    }
+
+    #[actix_rt::test]
+    async fn test_handle_inspect_config() {
+        let registry = mock_artifact_registry();
+        let url = format!("{}/echo:0.2.0", registry);
+        let inspect = |args: &[&str]| {
+            let mut cli = vec!["reg", "inspect-config", &url, "--insecure"];
+            cli.extend_from_slice(args);
+            match RegCli::from_iter(&cli).command {
+                RegCliCommand::InspectConfig(cmd) => handle_inspect_config(cmd),
+                _ => panic!("`reg inspect-config` constructed incorrect command"),
             }
-            _ => panic!("`reg pull` constructed incorrect command"),
         };
 
-        match pull_all_flags.command {
-            RegCliCommand::Pull(PullCommand {
-                url,
-                allow_latest,
-                opts,
-                ..
-            }) => {
-                assert_eq!(url, ECHO_WASM);
-                assert!(allow_latest);
-                assert!(opts.insecure);
-            }
-            _ => panic!("`reg pull` constructed incorrect command"),
+        assert_eq!(inspect(&[]).await.unwrap(), "{}");
+        assert_eq!(inspect(&["--raw"]).await.unwrap(), "{}");
+        let json: serde_json::Value =
+            serde_json::from_str(&inspect(&["-o", "json"]).await.unwrap()).unwrap();
+        assert_eq!(
+            json["digest"],
+            format!("sha256:{:x}", Sha256::digest(b"{}"))
+        );
+        assert_eq!(json["media_type"], WASM_CONFIG_MEDIA_TYPE);
+        assert_eq!(json["config"], json!({}));
+    }
+
+    #[test]
+    fn test_config_output() {
+        let image = parse_reference("localhost:5000/echo:0.2.0").unwrap();
+        let descriptor = OciDescriptor {
+            media_type: WASM_CONFIG_MEDIA_TYPE.to_string(),
+            digest: "sha256:123456".to_string(),
+            ..Default::default()
         };
+        let text = OutputKind::Text { max_width: 0 };
+        let config = b"{\"env\":\"prod\"}".to_vec();
+        assert_eq!(
+            config_output(&image, &descriptor, config.clone(), false, &text).unwrap(),
+            "{\n  \"env\": \"prod\"\n}"
+        );
+        assert_eq!(
+            config_output(&image, &descriptor, config, true, &text).unwrap(),
+            "{\"env\":\"prod\"}"
+        );
+        let err =
+            config_output(&image, &descriptor, b"not json".to_vec(), false, &text).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Config sha256:123456 is not valid JSON"));
+        assert_eq!(
+            config_output(&image, &descriptor, b"not json".to_vec(), true, &text).unwrap(),
+            "not json"
+        );
+    }
 
-        match pull_all_options.command {
-            RegCliCommand::Pull(PullCommand {
-                url,
-                destination,
-                digest,
-                output,
-                opts,
-                ..
-            }) => {
-                assert_eq!(url, ECHO_WASM);
-                assert_eq!(destination.unwrap(), TESTDIR);
-                assert_eq!(
-                    digest.unwrap(),
-                    "sha256:a17a163afa8447622055deb049587641a9e23243a6cc4411eb33bd4267214cf3"
-                );
-                assert_eq!(output.kind, OutputKind::Text { max_width: 0 });
-                assert_eq!(opts.user.unwrap(), "user");
-                assert_eq!(opts.password.unwrap(), "password");
+    #[actix_rt::test]
+    async fn test_handle_copy() {
+        let source = mock_artifact_registry();
+        let destination = mock_copy_registry();
+        let copy = |tag: &str| {
+            let cli = RegCli::from_iter(&[
+                "reg",
+                "copy",
+                &format!("{}/echo:0.2.0", source),
+                &format!("{}/mirror:{}", destination, tag),
+                "--src-insecure",
+                "--dst-insecure",
+                "-o",
+                "json",
+            ]);
+            match cli.command {
+                RegCliCommand::Copy(cmd) => handle_copy(cmd),
+                _ => panic!("`reg copy` constructed incorrect command"),
             }
-            _ => panic!("`reg pull` constructed incorrect command"),
         };
+
+        let json: serde_json::Value = serde_json::from_str(&copy("0.2.0").await.unwrap()).unwrap();
+        assert_eq!(json["result"], "success");
+        assert_eq!(
+            json["digest"],
+            format!("sha256:{:x}", Sha256::digest(mock_manifest().as_bytes()))
+        );
+        assert_eq!(json["blobs_copied"], 1);
+        assert_eq!(json["blobs_existing"], 1);
+
+        let err = copy("0.2.1").await.unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Digest mismatch: expected sha256:"));
+        assert!(err.to_string().contains("but got sha256:abcdef"));
+    }
+
+    #[actix_rt::test]
+    async fn test_download_artifact() {
+        let registry = mock_artifact_registry();
+        let image = parse_reference(&format!("{}/echo:0.2.0", registry)).unwrap();
+        let auth = RegistryAuth::Anonymous;
+        let api = RegistryApi::connect(&image, &auth, &insecure_opts(), "pull")
+            .await
+            .unwrap();
+
+        let mut data = vec![];
+        let pulled = download_artifact(
+            &api,
+            Some(mock_manifest_digest()),
+            &[WASM_MEDIA_TYPE],
+            None,
+            false,
+            None,
+            &mut data,
+        )
+        .await
+        .unwrap();
+        assert_eq!(data, MOCK_LAYER);
+        assert_eq!(pulled.size, MOCK_LAYER.len() as u64);
+        assert_eq!(pulled.digest, mock_manifest_digest());
+        assert_eq!(pulled.media_type, WASM_MEDIA_TYPE);
+
+        let err = download_artifact(
+            &api,
+            Some("abcdef".to_string()),
+            &[WASM_MEDIA_TYPE],
+            None,
+            false,
+            None,
+            &mut vec![],
+        )
+        .await
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Digest mismatch: expected sha256:abcdef but got {}, aborting",
+                mock_manifest_digest()
+            )
+        );
+
+        let err = download_artifact(
+            &api,
+            None,
+            &["application/vnd.example.wasm"],
+            None,
+            false,
+            None,
+            &mut vec![],
+        )
+        .await
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.to_string(),
+            format!("Incompatible layer media type {}", WASM_MEDIA_TYPE)
+        );
     }
 
     #[test]
-    /// Enumerates multiple options of the `push` command to ensure API doesn't
-    /// change between versions. This test will fail if `wash reg push`
-    /// changes syntax, ordering of required elements, or flags.
-    fn test_push_comprehensive() {
-        // Not explicitly used, just a placeholder for a directory
-        const TESTDIR: &str = "./tests/fixtures";
+    fn test_pull_platform() {
+        let layer = |target: &str| OciDescriptor {
+            media_type: PROVIDER_TARGET_MEDIA_TYPE.to_string(),
+            digest: format!("sha256:{}", target),
+            annotations: Some(
+                vec![(PROVIDER_TARGET_ANNOTATION.to_string(), target.to_string())]
+                    .into_iter()
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+        let layers = vec![layer("aarch64-macos"), layer("x86_64-linux")];
+        let selected = platform_layers(&layers, "x86_64-linux").unwrap();
+        assert_eq!(selected, vec![layer("x86_64-linux")]);
+        assert_eq!(
+            platform_layers(&layers, "x86_64-windows")
+                .unwrap_err()
+                .to_string(),
+            "No layer for platform x86_64-windows, available platforms: aarch64-macos, x86_64-linux"
+        );
+        let unsplit = vec![OciDescriptor {
+            media_type: PROVIDER_ARCHIVE_MEDIA_TYPE.to_string(),
+            ..Default::default()
+        }];
+        assert_eq!(
+            platform_layers(&unsplit, "x86_64-linux")
+                .unwrap_err()
+                .to_string(),
+            "No layer for platform x86_64-linux, the artifact has no platform specific layers"
+        );
 
-        // Push echo.wasm and pull from local registry
-        let echo_push_basic = &format!("{}/echo:pushbasic", LOCAL_REGISTRY);
-        let push_basic = RegCli::from_iter(&[
-            "reg",
-            "push",
-            echo_push_basic,
-            &format!("{}/echopush.wasm", TESTDIR),
-            "--insecure",
-        ]);
-        match push_basic.command {
-            RegCliCommand::Push(PushCommand {
-                url,
-                artifact,
-                opts,
-                ..
-            }) => {
-                assert_eq!(&url, echo_push_basic);
-                assert_eq!(artifact, format!("{}/echopush.wasm", TESTDIR));
-                assert!(opts.insecure);
+        let index: ImageIndex = serde_json::from_value(json!({
+            "schemaVersion": 2,
+            "mediaType": OCI_INDEX_MEDIA_TYPE,
+            "manifests": [
+                {"digest": "sha256:amd", "platform": {"architecture": "amd64", "os": "linux"}},
+                {"digest": "sha256:arm", "platform": {"architecture": "arm64", "os": "darwin"}},
+                {"digest": "sha256:attestation"},
+            ],
+        }))
+        .unwrap();
+        assert_eq!(
+            index_platform_manifest(&index, "x86_64-linux").unwrap(),
+            "sha256:amd"
+        );
+        assert_eq!(
+            index_platform_manifest(&index, "aarch64-macos").unwrap(),
+            "sha256:arm"
+        );
+        assert_eq!(
+            index_platform_manifest(&index, "x86_64-windows")
+                .unwrap_err()
+                .to_string(),
+            "No layer for platform x86_64-windows, available platforms: x86_64-linux, aarch64-macos"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_manifest_digest_verified() {
+        let registry = mock_registry(|_, request| {
+            if request[0].starts_with("GET /v2/ ") {
+                http_response("200 OK", &[], b"")
+            } else if request[0].contains("/manifests/") {
+                // Claims a digest that doesn't match the manifest it serves
+                http_response(
+                    "200 OK",
+                    &[("Docker-Content-Digest", "sha256:123456")],
+                    mock_manifest().as_bytes(),
+                )
+            } else {
+                http_response("404 Not Found", &[], b"")
             }
-            _ => panic!("`reg push` constructed incorrect command"),
-        };
+        });
+        let image = parse_reference(&format!("{}/echo:0.2.0", registry)).unwrap();
+        let auth = RegistryAuth::Anonymous;
+        let api = RegistryApi::connect(&image, &auth, &insecure_opts(), "pull")
+            .await
+            .unwrap();
 
-        // Push logging.par.gz and pull from local registry
-        let logging_push_all_flags = &format!("{}/logging:allflags", LOCAL_REGISTRY);
-        let push_all_flags = RegCli::from_iter(&[
-            "reg",
-            "push",
-            logging_push_all_flags,
-            &format!("{}/logging.par.gz", TESTDIR),
-            "--insecure",
-            "--allow-latest",
-        ]);
-        match push_all_flags.command {
-            RegCliCommand::Push(PushCommand {
-                url,
-                artifact,
-                opts,
-                allow_latest,
-                ..
-            }) => {
-                assert_eq!(&url, logging_push_all_flags);
-                assert_eq!(artifact, format!("{}/logging.par.gz", TESTDIR));
-                assert!(opts.insecure);
-                assert!(allow_latest);
+        let err = api.manifest("0.2.0").await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Registry reported manifest digest sha256:123456 but served a manifest with digest {}, aborting",
+                mock_manifest_digest()
+            )
+        );
+        assert_eq!(crate::util::exit_code(&*err), 6);
+        // A pin on the reported digest can't be satisfied by a lying registry
+        assert!(download_artifact(
+            &api,
+            Some("123456".to_string()),
+            &[WASM_MEDIA_TYPE],
+            None,
+            false,
+            None,
+            &mut vec![],
+        )
+        .await
+        .is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_download_cached_artifact() {
+        let registry = mock_artifact_registry();
+        let image = parse_reference(&format!("{}/echo:0.2.0", registry)).unwrap();
+        let auth = RegistryAuth::Anonymous;
+        let api = RegistryApi::connect(&image, &auth, &insecure_opts(), "pull")
+            .await
+            .unwrap();
+        let cache = std::env::temp_dir().join(format!("wash-pull-cache-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&cache);
+
+        let mut data = vec![];
+        let pulled = download_artifact(
+            &api,
+            None,
+            &[WASM_MEDIA_TYPE],
+            None,
+            false,
+            Some(&cache),
+            &mut data,
+        )
+        .await
+        .unwrap();
+        assert!(!pulled.cached);
+        assert_eq!(data, MOCK_LAYER);
+
+        // Content that doesn't match the layers is never stored under the manifest digest
+        let entry = cached_artifact_path(&cache, &pulled.digest);
+        let err = store_cached_artifact(
+            &cache,
+            &pulled.digest,
+            &pulled.layers,
+            &mut &b"\0asm forged actor module"[..],
+        )
+        .unwrap_err();
+        assert_eq!(crate::util::exit_code(&*err), 6);
+        assert!(!entry.exists());
+        assert_eq!(std::fs::read_dir(&cache).unwrap().count(), 0);
+        store_cached_artifact(&cache, &pulled.digest, &pulled.layers, &mut &MOCK_LAYER[..])
+            .unwrap();
+        assert!(entry.is_file());
+
+        let mut data = vec![];
+        let pulled = download_artifact(
+            &api,
+            Some(mock_manifest_digest()),
+            &[WASM_MEDIA_TYPE],
+            None,
+            false,
+            Some(&cache),
+            &mut data,
+        )
+        .await
+        .unwrap();
+        assert!(pulled.cached);
+        assert_eq!(data, MOCK_LAYER);
+        assert_eq!(pulled.size, data.len() as u64);
+        assert_eq!(pulled.media_type, WASM_MEDIA_TYPE);
+
+        // Recorded references resolve from the cache alone, as offline pulls do
+        store_cached_reference(&cache, &image, &pulled).unwrap();
+        assert_eq!(cached_references_in(&cache), vec![image.whole()]);
+        let offline = cached_artifact(&cache, &image, None).unwrap();
+        assert_eq!(offline.digest, pulled.digest);
+        assert_eq!(offline.media_type, WASM_MEDIA_TYPE);
+        assert_eq!(offline.size, data.len() as u64);
+        assert_eq!(offline.layers, pulled.layers);
+        assert!(offline.cached);
+        let err = cached_artifact(&cache, &image, Some("abcdef".to_string())).unwrap_err();
+        assert_eq!(crate::util::exit_code(&*err), 6);
+        let uncached = parse_reference(&format!("{}/echo:0.3.0", registry)).unwrap();
+        let err = cached_artifact(&cache, &uncached, None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "{}/echo:0.3.0 is not in the local cache and wash is offline",
+                registry
+            )
+        );
+        assert_eq!(crate::util::exit_code(&*err), 5);
+
+        // A tampered entry is discarded and downloaded again, and can't be used offline
+        std::fs::write(&entry, b"\0asm forged actor module").unwrap();
+        assert!(cached_artifact(&cache, &image, None).is_err());
+        std::fs::write(&entry, b"\0asm forged actor module").unwrap();
+        let mut data = vec![];
+        let pulled = download_artifact(
+            &api,
+            None,
+            &[WASM_MEDIA_TYPE],
+            None,
+            false,
+            Some(&cache),
+            &mut data,
+        )
+        .await
+        .unwrap();
+        assert!(!pulled.cached);
+        assert_eq!(data, MOCK_LAYER);
+        assert!(!entry.exists());
+
+        // Digest verification still applies to cached artifacts
+        assert!(download_artifact(
+            &api,
+            Some("abcdef".to_string()),
+            &[WASM_MEDIA_TYPE],
+            None,
+            false,
+            Some(&cache),
+            &mut vec![],
+        )
+        .await
+        .is_err());
+        std::fs::remove_dir_all(&cache).unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn test_tags_page() {
+        let registry = mock_registry(|_, request| {
+            if request[0].starts_with("GET /v2/ ") {
+                http_response("200 OK", &[], b"")
+            } else if request[0].starts_with("GET /v2/echo/tags/list?n=2&last=0.1.0 ") {
+                let link = "</v2/echo/tags/list?n=2&last=0.3.0>; rel=\"next\"";
+                let body = b"{\"name\":\"echo\",\"tags\":[\"0.2.0\",\"0.3.0\"]}";
+                http_response("200 OK", &[("Link", link)], body)
+            } else if request[0].starts_with("GET /v2/echo/tags/list?n=1&last=0.3.0 ") {
+                // A registry ignoring `n` is trimmed client side
+                let body = b"{\"name\":\"echo\",\"tags\":[\"0.4.0\",\"1.0.0\"]}";
+                http_response("200 OK", &[], body)
+            } else if request[0].starts_with("GET /v2/echo/tags/list?last=1.0.0 ") {
+                http_response("200 OK", &[], b"{\"name\":\"echo\",\"tags\":[]}")
+            } else {
+                http_response("404 Not Found", &[], b"")
             }
-            _ => panic!("`reg push` constructed incorrect command"),
+        });
+        let image = parse_reference(&format!("{}/echo", registry)).unwrap();
+        let auth = RegistryAuth::Anonymous;
+        let api = RegistryApi::connect(&image, &auth, &insecure_opts(), "pull")
+            .await
+            .unwrap();
+
+        let page = api.tags_page(Some(2), Some("0.1.0")).await.unwrap();
+        assert_eq!(page.tags, vec!["0.2.0", "0.3.0"]);
+        assert!(page.more);
+        let page = api.tags_page(Some(1), Some("0.3.0")).await.unwrap();
+        assert_eq!(page.tags, vec!["0.4.0"]);
+        assert!(page.more);
+        let page = api.tags_page(None, Some("1.0.0")).await.unwrap();
+        assert!(page.tags.is_empty());
+        assert!(!page.more);
+    }
+
+    #[test]
+    fn test_tag_pagination_helpers() {
+        let tags = vec!["a".to_string(), "b".to_string()];
+        assert!(!tag_page(tags.clone(), None, false).more);
+        assert!(!tag_page(tags.clone(), Some(2), false).more);
+        assert!(tag_page(tags.clone(), Some(2), true).more);
+        assert_eq!(
+            tag_page(tags, Some(1), false),
+            TagPage {
+                tags: vec!["a".to_string()],
+                more: true
+            }
+        );
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert!(!has_next_link(&headers));
+        headers.insert(
+            reqwest::header::LINK,
+            "</v2/echo/tags/list?n=2&last=b>; rel=\"prev\""
+                .parse()
+                .unwrap(),
+        );
+        assert!(!has_next_link(&headers));
+        headers.append(
+            reqwest::header::LINK,
+            "</v2/echo/tags/list?n=2&last=d>; rel=\"next\""
+                .parse()
+                .unwrap(),
+        );
+        assert!(has_next_link(&headers));
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("0.2.0", "0.2.0"));
+        assert!(!glob_matches("0.2.0", "0.2.01"));
+        assert!(glob_matches("0.2.*", "0.2.15"));
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("*-rc?", "1.0.0-rc1"));
+        assert!(!glob_matches("*-rc?", "1.0.0-rc10"));
+        assert!(glob_matches("*.*.*", "1.10.0"));
+        assert!(glob_matches("a*b*c", "aXbYbZc"));
+        assert!(!glob_matches("a*b*c", "aXbYbZ"));
+        assert!(!glob_matches("?", ""));
+    }
+
+    #[actix_rt::test]
+    async fn test_list_and_delete_artifacts() {
+        let registry = mock_artifact_registry();
+        let image = parse_reference(&format!("{}/echo", registry)).unwrap();
+        let auth = RegistryAuth::Anonymous;
+        let api = RegistryApi::connect(&image, &auth, &insecure_opts(), "pull,delete")
+            .await
+            .unwrap();
+
+        assert_eq!(api.tags().await.unwrap(), vec!["0.2.0"]);
+        let artifacts = list_artifacts(&api).await.unwrap();
+        assert_eq!(artifacts.len(), 1);
+        assert_eq!(artifacts[0].tag, "0.2.0");
+        assert_eq!(artifacts[0].digest, mock_manifest_digest());
+        assert_eq!(artifacts[0].size, MOCK_LAYER.len() as u64);
+
+        let err = api
+            .delete_manifest(&mock_manifest_digest())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Registry {} does not allow deleting artifacts (405 Method Not Allowed)",
+                registry
+            )
+        );
+    }
+
+    #[test]
+    fn test_sbom_referrer_manifest() {
+        let spdx = br#"{"spdxVersion":"SPDX-2.3","name":"echo"}"#;
+        let cyclonedx = br#"{"bomFormat":"CycloneDX","specVersion":"1.4"}"#;
+        assert_eq!(sbom_media_type(spdx).unwrap(), SPDX_MEDIA_TYPE);
+        assert_eq!(sbom_media_type(cyclonedx).unwrap(), CYCLONEDX_MEDIA_TYPE);
+        assert_eq!(
+            sbom_media_type(br#"{"name":"echo"}"#)
+                .unwrap_err()
+                .to_string(),
+            "SBOM must be an SPDX or CycloneDX JSON document"
+        );
+        assert!(sbom_media_type(b"echo 0.2.0").is_err());
+
+        let manifest = referrer_manifest(
+            spdx,
+            SPDX_MEDIA_TYPE,
+            "sbom.spdx.json",
+            ("sha256:123456", 42),
+        );
+        assert_eq!(manifest["artifactType"], SPDX_MEDIA_TYPE);
+        assert_eq!(manifest["config"]["mediaType"], OCI_EMPTY_MEDIA_TYPE);
+        assert_eq!(manifest["config"]["size"], 2);
+        assert_eq!(manifest["layers"][0]["mediaType"], SPDX_MEDIA_TYPE);
+        assert_eq!(
+            manifest["layers"][0]["digest"],
+            format!("sha256:{:x}", Sha256::digest(spdx))
+        );
+        assert_eq!(
+            manifest["layers"][0]["annotations"]["org.opencontainers.image.title"],
+            "sbom.spdx.json"
+        );
+        assert_eq!(manifest["subject"]["digest"], "sha256:123456");
+        assert_eq!(manifest["subject"]["size"], 42);
+        assert_eq!(manifest["subject"]["mediaType"], OCI_IMAGE_MEDIA_TYPE);
+    }
+
+    #[actix_rt::test]
+    async fn test_push_referrer() {
+        let registry = mock_referrers_registry();
+        let auth = RegistryAuth::Anonymous;
+        let manifest = referrer_manifest(b"{}", SPDX_MEDIA_TYPE, "sbom", ("sha256:123456", 42));
+        let manifest = serde_json::to_vec(&manifest).unwrap();
+
+        let image = parse_reference(&format!("{}/echo:0.2.0", registry)).unwrap();
+        let api = RegistryApi::connect(&image, &auth, &insecure_opts(), "pull,push")
+            .await
+            .unwrap();
+        api.upload_blob(b"{}").await.unwrap();
+        assert_eq!(
+            api.put_referrer(&manifest).await.unwrap(),
+            format!("sha256:{:x}", Sha256::digest(&manifest))
+        );
+        api.put_manifest("0.2.0", &manifest).await.unwrap();
+
+        let image = parse_reference(&format!("{}/legacy:0.2.0", registry)).unwrap();
+        let api = RegistryApi::connect(&image, &auth, &insecure_opts(), "pull,push")
+            .await
+            .unwrap();
+        let err = api.put_referrer(&manifest).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "registry {} stored it as sha256:{:x} but does not support the OCI referrers API",
+                registry,
+                Sha256::digest(&manifest)
+            )
+        );
+        assert_eq!(
+            api.put_manifest("0.2.0", &manifest)
+                .await
+                .unwrap_err()
+                .to_string(),
+            "Failed to push manifest 0.2.0: 404 Not Found"
+        );
+    }
+
+    #[test]
+    fn test_proxy_for() {
+        let registry = reqwest::Url::parse("https://wasmcloud.azurecr.io/v2/").unwrap();
+        let local = reqwest::Url::parse("http://localhost:5000/v2/").unwrap();
+        let env = |name: &str| match name {
+            "HTTPS_PROXY" => Some("http://secure.proxy:3128".to_string()),
+            _ => None,
         };
+        assert_eq!(
+            proxy_for(&registry, None, "", env).unwrap().as_str(),
+            "http://secure.proxy:3128/"
+        );
+        assert_eq!(proxy_for(&local, None, "", env), None);
 
-        // Push logging.par.gz to different tag and pull to confirm successful push
-        let logging_push_all_options = &format!("{}/logging:alloptions", LOCAL_REGISTRY);
-        let push_all_options = RegCli::from_iter(&[
-            "reg",
-            "push",
-            logging_push_all_options,
-            &format!("{}/logging.par.gz", TESTDIR),
-            "--allow-latest",
-            "--insecure",
-            "--config",
-            &format!("{}/config.json", TESTDIR),
-            "--output",
-            "json",
-            "--password",
-            "supers3cr3t",
-            "--user",
-            "localuser",
-        ]);
-        match push_all_options.command {
-            RegCliCommand::Push(PushCommand {
-                url,
-                artifact,
-                opts,
-                allow_latest,
-                config,
-                output,
-                ..
-            }) => {
-                assert_eq!(&url, logging_push_all_options);
-                assert_eq!(artifact, format!("{}/logging.par.gz", TESTDIR));
-                assert!(opts.insecure);
-                assert!(allow_latest);
-                assert_eq!(config.unwrap(), format!("{}/config.json", TESTDIR));
-                assert_eq!(opts.user.unwrap(), "localuser");
-                assert_eq!(opts.password.unwrap(), "supers3cr3t");
-                assert_eq!(output.kind, OutputKind::Json);
+        let explicit = reqwest::Url::parse("http://explicit.proxy:8080").unwrap();
+        assert_eq!(
+            proxy_for(&local, Some(&explicit), "", env),
+            Some(explicit.clone())
+        );
+        assert_eq!(
+            proxy_for(&registry, Some(&explicit), "localhost, .azurecr.io", env),
+            None
+        );
+        assert_eq!(proxy_for(&local, Some(&explicit), "*", env), None);
+
+        assert!(no_proxy_matches("localhost", "localhost:5000"));
+        assert!(no_proxy_matches("wasmcloud.azurecr.io", "azurecr.io"));
+        assert!(no_proxy_matches("WASMCLOUD.azurecr.io", ".AzureCR.io"));
+        assert!(!no_proxy_matches("notazurecr.io", "azurecr.io"));
+        assert!(!no_proxy_matches("ghcr.io", ""));
+
+        assert!(registry_client(Some("http://proxy.internal:3128"), None).is_ok());
+        assert!(registry_client(Some("not a url"), None)
+            .unwrap_err()
+            .to_string()
+            .starts_with("Invalid proxy URL 'not a url'"));
+    }
+
+    #[test]
+    fn test_registry_config() {
+        let dir = std::env::temp_dir().join(format!("wash-registry-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let toml = dir.join("registries.toml");
+        std::fs::write(
+            &toml,
+            "[\"localhost:5000\"]\ninsecure = true\n\n[\"registry.internal\"]\nca_cert = \"/etc/ssl/internal.pem\"\nuser = \"ci\"\npassword = \"secret\"\n",
+        )
+        .unwrap();
+        let json = dir.join("registries.json");
+        std::fs::write(&json, r#"{"localhost:5000": {"insecure": true}}"#).unwrap();
+
+        let config = load_registry_config(&toml).unwrap();
+        assert!(config["localhost:5000"].insecure);
+        assert_eq!(
+            config["registry.internal"],
+            RegistrySettings {
+                insecure: false,
+                ca_cert: Some("/etc/ssl/internal.pem".to_string()),
+                user: Some("ci".to_string()),
+                password: Some("secret".to_string()),
             }
-            _ => panic!("`reg push` constructed incorrect command"),
+        );
+        assert!(load_registry_config(&json).unwrap()["localhost:5000"].insecure);
+
+        std::fs::write(&json, r#"{"localhost:5000": {"tls": false}}"#).unwrap();
+        assert!(load_registry_config(&json)
+            .unwrap_err()
+            .to_string()
+            .starts_with("Invalid registry config"));
+        assert!(load_registry_config(&dir.join("missing.toml"))
+            .unwrap_err()
+            .to_string()
+            .starts_with("Unable to read registry config"));
+
+        // Explicit flags override the file, and unknown hosts keep the flags as they are
+        let opts = AuthOpts::default()
+            .for_registry("localhost:5000", toml.to_str())
+            .unwrap();
+        assert!(opts.insecure);
+        let opts = AuthOpts::default()
+            .for_registry("ghcr.io", toml.to_str())
+            .unwrap();
+        assert!(!opts.insecure);
+        assert!(opts.user.is_none());
+        let opts = AuthOpts {
+            user: Some("me".to_string()),
+            ..Default::default()
+        }
+        .for_registry("registry.internal", toml.to_str())
+        .unwrap();
+        assert_eq!(opts.user.as_deref(), Some("me"));
+        assert!(opts.password.is_none());
+        assert_eq!(opts.ca_cert.as_deref(), Some("/etc/ssl/internal.pem"));
+        assert!(AuthOpts::default()
+            .for_registry("ghcr.io", dir.join("missing.toml").to_str())
+            .is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(registry_client(None, Some("/nonexistent/ca.pem"))
+            .unwrap_err()
+            .to_string()
+            .starts_with("Unable to read CA certificate"));
+    }
+
+    #[actix_rt::test]
+    async fn test_until_signal() {
+        let interrupted: Result<(), _> =
+            until_signal(std::future::pending(), async { Ok(()) }).await;
+        assert!(interrupted.unwrap_err().is::<Aborted>());
+
+        let completed = until_signal(async { Ok(42) }, std::future::pending()).await;
+        assert_eq!(completed.unwrap(), 42);
+
+        let unhandled = until_signal(async { Ok(42) }, async {
+            Err(std::io::ErrorKind::Other.into())
+        })
+        .await;
+        assert_eq!(unhandled.unwrap(), 42);
+    }
+
+    #[actix_rt::test]
+    async fn test_with_timeout() {
+        let result: Result<(), _> = with_timeout(1, std::future::pending()).await;
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Operation timed out after 1s"
+        );
+        assert_eq!(with_timeout(1, async { Ok(42) }).await.unwrap(), 42);
+    }
+
+    #[actix_rt::test]
+    async fn test_with_retries() {
+        let attempts = Cell::new(0);
+        let result: Result<(), _> = with_retries(3, Duration::from_millis(1), || {
+            attempts.set(attempts.get() + 1);
+            async {
+                Err(status_error(
+                    "Failed to fetch manifest".to_string(),
+                    StatusCode::BAD_GATEWAY,
+                ))
+            }
+        })
+        .await;
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Failed to fetch manifest: 502 Bad Gateway"
+        );
+        assert_eq!(attempts.get(), 4);
+
+        attempts.set(0);
+        let result: Result<(), _> = with_retries(3, Duration::from_millis(1), || {
+            attempts.set(attempts.get() + 1);
+            async {
+                Err(status_error(
+                    "Failed to fetch manifest".to_string(),
+                    StatusCode::UNAUTHORIZED,
+                ))
+            }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(attempts.get(), 1);
+
+        attempts.set(0);
+        let result = with_retries(3, Duration::from_millis(1), || {
+            attempts.set(attempts.get() + 1);
+            let attempt = attempts.get();
+            async move {
+                if attempt < 2 {
+                    Err(status_error(
+                        "Failed to fetch manifest".to_string(),
+                        StatusCode::SERVICE_UNAVAILABLE,
+                    ))
+                } else {
+                    Ok(attempt)
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 2);
+    }
+
+    #[test]
+    fn test_status_error_class() {
+        let class = |status| {
+            status_error("Failed to fetch manifest".to_string(), status)
+                .downcast_ref::<WashError>()
+                .cloned()
         };
+        assert!(matches!(
+            class(StatusCode::UNAUTHORIZED),
+            Some(WashError::Auth(_))
+        ));
+        assert!(matches!(
+            class(StatusCode::FORBIDDEN),
+            Some(WashError::Auth(_))
+        ));
+        assert!(matches!(
+            class(StatusCode::NOT_FOUND),
+            Some(WashError::NotFound(_))
+        ));
+        assert!(matches!(
+            class(StatusCode::BAD_GATEWAY),
+            Some(WashError::Network(_))
+        ));
+        assert_eq!(class(StatusCode::BAD_REQUEST), None);
+        assert!(matches!(
+            parse_reference("not a reference!")
+                .unwrap_err()
+                .downcast_ref::<WashError>(),
+            Some(WashError::InvalidInput(_))
+        ));
     }
 }
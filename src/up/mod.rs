@@ -4,7 +4,7 @@ use crate::drain::*;
 use crate::keys::*;
 use crate::par::*;
 use crate::reg::*;
-use crate::util::{convert_error, Result, WASH_CMD_INFO, WASH_LOG_INFO};
+use crate::util::{convert_error, Output, Result, WASH_CMD_INFO, WASH_LOG_INFO};
 use crossbeam_channel::unbounded;
 use log::{debug, error, info, warn, LevelFilter};
 use std::collections::HashMap;
@@ -30,7 +30,7 @@ use tui::{
 };
 use tui_logger::*;
 use wasmcloud_control_interface::{
-    ActorDescription, Claims, ClaimsList, Host, HostInventory, ProviderDescription,
+    ActorDescription, Claims, ClaimsList, HostInventory, ProviderDescription,
 };
 use wasmcloud_host::{Actor, HostBuilder, HostManifest};
 mod standalone;
@@ -45,6 +45,7 @@ const CTL_NS: &str = "default";
 const WASH_PROMPT: &str = "wash> ";
 const REPL_STANDALONE: &str = " REPL (Standalone) ";
 const REPL_LATTICE: &str = " REPL (Lattice connected) ";
+const REPL_CONNECTED: &str = " REPL (Attached to lattice) ";
 /// Option is unsupported for MacOS, the following byte slices correspond
 /// to [1;3A for Option+UP and [1;3B for Option+Down
 const OPTIONUP: &[u8] = &[27_u8, 91_u8, 49_u8, 59_u8, 51_u8, 65_u8];
@@ -61,29 +62,32 @@ pub(crate) struct UpCli {
 
 impl UpCli {
     pub(crate) fn command(self) -> UpCliCommand {
-        self.command
+        let mut command = self.command;
+        // `--host` and `--port` predate the shared connection options and take precedence
+        if let Some(host) = command.host.take() {
+            command.opts.rpc_host = host;
+        }
+        if let Some(port) = command.port.take() {
+            command.opts.rpc_port = port;
+        }
+        command
     }
 }
 
 #[derive(StructOpt, Debug, Clone)]
 pub(crate) struct UpCliCommand {
-    /// Host for lattice connections, defaults to 0.0.0.0
-    #[structopt(
-        short = "h",
-        long = "host",
-        default_value = "0.0.0.0",
-        env = "WASH_RPC_HOST"
-    )]
-    rpc_host: String,
-
-    /// Port for lattice connections, defaults to 4222
-    #[structopt(
-        short = "p",
-        long = "port",
-        default_value = "4222",
-        env = "WASH_RPC_PORT"
-    )]
-    rpc_port: String,
+    /// Connection to the lattice the host joins, or attaches to with --connect. `ctl` commands
+    /// in the REPL default to these options
+    #[structopt(flatten)]
+    opts: ConnectionOpts,
+
+    /// Host for lattice connections, same as --rpc-host
+    #[structopt(short = "h", long = "host")]
+    host: Option<String>,
+
+    /// Port for lattice connections, same as --rpc-port
+    #[structopt(long = "port")]
+    port: Option<String>,
 
     /// Log level verbosity, valid values are `error`, `warn`, `info`, `debug`, and `trace`
     #[structopt(short = "l", long = "log-level", default_value = "info")]
@@ -96,6 +100,31 @@ pub(crate) struct UpCliCommand {
     /// Specify signed actor modules to watch and update when the module changes
     #[structopt(long = "watch", short = "w", parse(from_os_str))]
     actors: Vec<PathBuf>,
+
+    /// Attach to the hosts already running in a lattice instead of starting an embedded host
+    #[structopt(long = "connect", conflicts_with_all = &["manifest", "actors"])]
+    connect: bool,
+
+    /// File to load and save REPL command history, defaults to $HOME/.wash/repl_history
+    #[structopt(long = "history-file", env = "WASH_REPL_HISTORY", parse(from_os_str))]
+    history_file: Option<PathBuf>,
+
+    /// Maximum number of commands kept in the history file, 0 disables saving history
+    #[structopt(long = "history-size", default_value = "1000")]
+    history_size: usize,
+
+    /// File of newline separated REPL commands to run in order once the host has started.
+    /// Blank lines and lines starting with `#` are ignored
+    #[structopt(long = "script", parse(from_os_str))]
+    script: Option<PathBuf>,
+
+    /// Run the remaining script commands after a command fails instead of stopping
+    #[structopt(long = "keep-going", requires = "script")]
+    keep_going: bool,
+
+    /// Enter the interactive REPL after the script has finished
+    #[structopt(long = "interactive", requires = "script")]
+    interactive: bool,
 }
 
 #[derive(StructOpt, Debug, Clone, PartialEq)]
@@ -185,395 +214,529 @@ async fn handle_up(cmd: UpCliCommand) -> Result<()> {
     init_logger(filter).unwrap();
     set_default_level(filter);
 
-    // Set global variable to show we're in REPL mode
-    // This ensures the rest of the modules can properly format output information
-    crate::util::REPL_MODE.set("true".to_string()).unwrap();
+    let script = match cmd.script {
+        Some(ref path) => Some(read_script(path)?),
+        None => None,
+    };
+    // A script without `--interactive` runs without the REPL interface and prints its output
+    let interactive = script.is_none() || cmd.interactive;
+    let keep_going = cmd.keep_going;
 
-    // Initialize terminal
-    let backend = {
-        let stdout = io::stdout().into_raw_mode().unwrap();
-        let stdout = AlternateScreen::from(stdout);
-        TermionBackend::new(stdout)
+    // Make sure there is a host to attach to before taking over the terminal
+    let attached_hosts = if cmd.connect {
+        Some(attach_to_lattice(&cmd.opts).await?)
+    } else {
+        None
+    };
+
+    let mut terminal = if interactive {
+        // Set global variable to show we're in REPL mode
+        // This ensures the rest of the modules can properly format output information
+        crate::util::REPL_MODE.set("true".to_string()).unwrap();
+
+        // Initialize terminal
+        let backend = {
+            let stdout = io::stdout().into_raw_mode().unwrap();
+            let stdout = AlternateScreen::from(stdout);
+            TermionBackend::new(stdout)
+        };
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal.clear().unwrap();
+        terminal.hide_cursor().unwrap();
+        Some(terminal)
+    } else {
+        None
     };
-    let mut terminal = Terminal::new(backend).unwrap();
-    terminal.clear().unwrap();
-    terminal.hide_cursor().unwrap();
 
     // Start REPL
-    let mut repl = WashRepl::default();
-    repl.draw_ui(&mut terminal)?;
+    let mut repl = WashRepl {
+        connection: cmd.opts.clone(),
+        ..Default::default()
+    };
+    if let Some(history) = ReplHistory::from_opts(cmd.history_file.clone(), cmd.history_size) {
+        repl.input_state.history = history.load();
+        repl.input_state.history_cursor = repl.input_state.history.len();
+        repl.history = Some(history);
+    }
+    repl.redraw(&mut terminal)?;
     info!(target: WASH_LOG_INFO, "Initializing REPL...");
     // Sending SPACE event to tui logger to hide disabled logs
     repl.tui_state.transition(&TuiWidgetEvent::SpaceKey);
-    repl.draw_ui(&mut terminal)?;
+    repl.redraw(&mut terminal)?;
 
     // Channel for host operations
     let (host_op_sender, host_op_receiver) = unbounded();
     // Channel for host output
     let (host_output_sender, host_output_receiver) = unbounded();
-
-    let nats_connection = nats::asynk::connect(&format!("{}:{}", cmd.rpc_host, cmd.rpc_port)).await;
-    let common_host = HostBuilder::new()
-        .with_namespace(CTL_NS)
-        .with_label("repl_mode", "true")
-        .oci_allow_latest()
-        .oci_allow_insecure(vec!["localhost:5000".to_string()])
-        .enable_live_updates();
-    let (mode, host) = match nats_connection {
-        // Launch a lattice-connected host
-        Ok(conn) => (
-            ReplMode::Lattice,
-            common_host
-                .with_rpc_client(conn.clone())
-                .with_control_client(conn)
-                .with_label("lattice_connected", "true")
-                .build(),
-        ),
-        // Launch a self-contained (e.g. not lattice connected) host
-        Err(_) => (
-            ReplMode::Standalone,
-            common_host.with_label("lattice_connected", "false").build(),
-        ),
-    };
-
-    let embedded_host = EmbeddedHost::new(host.id(), mode, host_op_sender);
-    // Ownership of the hotwatch vec is moved to this thread, where it won't be dropped.
-    // If the vec is dropped, the hotwatch objects will no longer watch for write events
-    let _hotwatch = if !cmd.actors.is_empty() {
-        embedded_host.watch_actors(cmd.actors.clone())
+    // Channel to signal that the host has started and applied its manifest
+    let (host_ready_sender, host_ready_receiver) = unbounded();
+
+    let (mode, _hotwatch) = if let Some(hosts) = attached_hosts {
+        info!(
+            target: WASH_LOG_INFO,
+            "Attached to lattice ({}) with host(s): {}",
+            cmd.opts.ns_prefix,
+            hosts.join(", ")
+        );
+        let _ = host_ready_sender.send(());
+        (ReplMode::Lattice, vec![])
     } else {
-        vec![]
-    };
-    repl.embedded_host = Some(embedded_host);
-
-    // Move host to separate thread to avoid blocking host operations
-    std::thread::spawn(move || {
-        let rt = actix_rt::System::new();
-        rt.block_on(async move {
-            if let Err(e) = host.start().await.map_err(convert_error) {
-                error!(target: WASH_LOG_INFO, "Error launching REPL host: {}", e);
-            } else {
-                info!(
-                    target: WASH_LOG_INFO,
-                    "Host ({}) started in namespace ({})", host.id(), CTL_NS
-                );
-            };
-            // If supplied, initialize the host with a manifest
-            if let Some(ref pb) = cmd.manifest {
-                let err = match HostManifest::from_path(pb.clone(), true) {
-                    Ok(mut hm) => {
-                        // Don't attempt to start watched actors twice
-                        hm.actors.retain(|act| !cmd.actors.contains(&PathBuf::from(act)));
-                        host_output_sender.send("Initializing host from manifest ...".to_string()).unwrap();
-                        host.apply_manifest(hm).await.err()
-                    },
-                    Err(e) => {
-                        Some(e)
-                    }
-                };
-                if let Some(e) = err {
-                    error!("Failed to load and apply manifest: {}", e);
+        let nats_connection = nats_connection(&cmd.opts).await;
+        let common_host = HostBuilder::new()
+            .with_namespace(CTL_NS)
+            .with_label("repl_mode", "true")
+            .oci_allow_latest()
+            .oci_allow_insecure(vec!["localhost:5000".to_string()])
+            .enable_live_updates();
+        let (mode, host) = match nats_connection {
+            // Launch a lattice-connected host
+            Ok(conn) => (
+                ReplMode::Lattice,
+                common_host
+                    .with_rpc_client(conn.clone())
+                    .with_control_client(conn)
+                    .with_label("lattice_connected", "true")
+                    .build(),
+            ),
+            // Launch a self-contained (e.g. not lattice connected) host
+            Err(_) => (
+                ReplMode::Standalone,
+                common_host.with_label("lattice_connected", "false").build(),
+            ),
+        };
+
+        let embedded_host = EmbeddedHost::new(host.id(), mode, host_op_sender);
+        // Ownership of the hotwatch vec is moved to this thread, where it won't be dropped.
+        // If the vec is dropped, the hotwatch objects will no longer watch for write events
+        let _hotwatch = if !cmd.actors.is_empty() {
+            embedded_host.watch_actors(cmd.actors.clone())
+        } else {
+            vec![]
+        };
+        repl.embedded_host = Some(embedded_host);
+
+        // Move host to separate thread to avoid blocking host operations
+        std::thread::spawn(move || {
+            let rt = actix_rt::System::new();
+            rt.block_on(async move {
+                if let Err(e) = host.start().await.map_err(convert_error) {
+                    error!(target: WASH_LOG_INFO, "Error launching REPL host: {}", e);
                 } else {
-                    host_output_sender.send("Successfully initialized host from manifest".to_string()).unwrap();
+                    info!(
+                        target: WASH_LOG_INFO,
+                        "Host ({}) started in namespace ({})", host.id(), CTL_NS
+                    );
+                };
+                // If supplied, initialize the host with a manifest
+                if let Some(ref pb) = cmd.manifest {
+                    let err = match HostManifest::from_path(pb.clone(), true) {
+                        Ok(mut hm) => {
+                            // Don't attempt to start watched actors twice
+                            hm.actors.retain(|act| !cmd.actors.contains(&PathBuf::from(act)));
+                            host_output_sender.send(Ok("Initializing host from manifest ...".to_string())).unwrap();
+                            host.apply_manifest(hm).await.err()
+                        },
+                        Err(e) => {
+                            Some(e)
+                        }
+                    };
+                    if let Some(e) = err {
+                        error!("Failed to load and apply manifest: {}", e);
+                    } else {
+                        host_output_sender.send(Ok("Successfully initialized host from manifest".to_string())).unwrap();
+                    }
                 }
-            }
-            match mode {
-                ReplMode::Lattice => {
-                    loop {
-                        // The lattice mode REPL host will only invoke the host API when starting an actor from disk
-                        // All other operations are done via the control interface
-                        match host_op_receiver.try_recv() {
-                            Ok(CtlCliCommand::Start(StartCommand::Actor(cmd))) => {
-                                debug!("Attempting to load actor from file");
-                                let failure = match Actor::from_file(cmd.actor_ref.clone()) {
-                                    Ok(actor) => host.start_actor(actor).await,
-                                    Err(file_err) => {
-                                        error!("Failed to load actor from file: {}", file_err);
-                                        Err(file_err)
-                                    },
+                let _ = host_ready_sender.send(());
+                match mode {
+                    ReplMode::Lattice => {
+                        loop {
+                            // The lattice mode REPL host will only invoke the host API when starting an actor from disk
+                            // All other operations are done via the control interface
+                            match host_op_receiver.try_recv() {
+                                Ok(CtlCliCommand::Start(StartCommand::Actor(cmd))) => {
+                                    debug!("Attempting to load actor from file");
+                                    let failure = match Actor::from_file(cmd.actor_ref.clone()) {
+                                        Ok(actor) => host.start_actor(actor).await,
+                                        Err(file_err) => {
+                                            error!("Failed to load actor from file: {}", file_err);
+                                            Err(file_err)
+                                        },
+                                    }
+                                    .map_or_else(|e| Some(format!("{}", e)), |_| None);
+                                    host_output_sender.send(host_result(failure, |failure| start_actor_output(
+                                        &cmd.actor_ref,
+                                        &host.id(),
+                                        failure,
+                                        &cmd.output.kind,
+                                    ))).unwrap()
                                 }
-                                .map_or_else(|e| Some(format!("{}", e)), |_| None);
-                                host_output_sender.send(start_actor_output(
-                                    &cmd.actor_ref,
-                                    &host.id(),
-                                    failure,
-                                    &cmd.output.kind,
-                                )).unwrap()
-                            }
-                            Ok(CtlCliCommand::Update(UpdateCommand::Actor(cmd))) => {
-                                debug!("Attempting to load actor from file");
-                                let failure = match File::open(cmd.new_actor_ref.clone()) {
-                                    Ok(mut actor) => {
-                                        let mut buf = Vec::new();
-                                        let _ = actor.read_to_end(&mut buf);
-                                        host.update_actor(&cmd.actor_id, None, &buf).await
-                                    },
-                                    Err(file_err) => {
-                                        error!("Failed to load actor from file: {}", file_err);
-                                        Err(file_err.into())
-                                    },
+                                Ok(CtlCliCommand::Update(UpdateCommand::Actor(cmd))) => {
+                                    debug!("Attempting to load actor from file");
+                                    let failure = match File::open(cmd.new_actor_ref.clone()) {
+                                        Ok(mut actor) => {
+                                            let mut buf = Vec::new();
+                                            let _ = actor.read_to_end(&mut buf);
+                                            host.update_actor(&cmd.actor_id, None, &buf).await
+                                        },
+                                        Err(file_err) => {
+                                            error!("Failed to load actor from file: {}", file_err);
+                                            Err(file_err.into())
+                                        },
+                                    }
+                                    .map_or_else(|e| Some(format!("{}", e)), |_| None);
+                                    host_output_sender.send(host_result(failure, |failure| update_actor_output(&cmd.actor_id, &cmd.new_actor_ref, failure, &cmd.output.kind))).unwrap()
+                                }
+                                _ => {
+                                    actix_rt::time::sleep(std::time::Duration::from_millis(100)).await;
                                 }
-                                .map_or_else(|e| Some(format!("{}", e)), |_| None);
-                                host_output_sender.send(update_actor_output(&cmd.actor_id, &cmd.new_actor_ref, failure, &cmd.output.kind)).unwrap()
-                            }
-                            _ => {
-                                actix_rt::time::sleep(std::time::Duration::from_millis(100)).await;
                             }
                         }
                     }
-                }
-                ReplMode::Standalone => {
-                    warn!(
-                        target: WASH_CMD_INFO,
-                        "REPL host started in standalone mode and is not connected to a lattice"
-                    );
-                    let host_started = std::time::Instant::now();
-                    // Await commands without blocking the host from operating
-                    loop {
-                        if let Ok(ctlcmd) = host_op_receiver.try_recv() {
-                            use HostCommand::*;
-                            let output = match HostCommand::from(ctlcmd) {
-                                Call { msg, .. } if msg.is_err() => {
-                                    format!("{}", msg.unwrap_err())
-                                }
-                                Call {
-                                    actor,
-                                    operation,
-                                    msg,
-                                    output_kind,
-                                } => {
-                                    let res =
-                                        host.call_actor(&actor, &operation, &msg.unwrap()).await;
-                                    match res {
-                                        Ok(bytes) => call_output(None, bytes, &output_kind),
-                                        Err(e) => {
-                                            call_output(Some(e.to_string()), vec![], &output_kind)
+                    ReplMode::Standalone => {
+                        warn!(
+                            target: WASH_CMD_INFO,
+                            "REPL host started in standalone mode and is not connected to a lattice"
+                        );
+                        let host_started = std::time::Instant::now();
+                        // Await commands without blocking the host from operating
+                        loop {
+                            if let Ok(ctlcmd) = host_op_receiver.try_recv() {
+                                use HostCommand::*;
+                                let output = match HostCommand::from(ctlcmd) {
+                                    Call { msg, .. } if msg.is_err() => {
+                                        Err(format!("{}", msg.unwrap_err()))
+                                    }
+                                    Call {
+                                        actor,
+                                        operation,
+                                        msg,
+                                        response_format,
+                                        output_kind,
+                                    } => {
+                                        let res =
+                                            host.call_actor(&actor, &operation, &msg.unwrap()).await;
+                                        match res {
+                                            Ok(bytes) => Ok(call_output(
+                                                None,
+                                                bytes,
+                                                response_format,
+                                                &output_kind,
+                                            )),
+                                            Err(e) => Err(call_output(
+                                                Some(e.to_string()),
+                                                vec![],
+                                                response_format,
+                                                &output_kind,
+                                            )),
                                         }
                                     }
-                                }
-                                GetHost { output_kind } => {
-                                    let standalone_host = Host {
-                                        id: host.id(),
-                                        uptime_seconds: host_started.elapsed().as_secs(),
-                                    };
-                                    crate::ctl::get_hosts_output(
-                                        vec![standalone_host],
-                                        &output_kind,
-                                    )
-                                }
-                                GetInventory { output_kind } => {
-                                    let mut actors: Vec<ActorDescription> = vec![];
-                                    // This is a for loop instead of utilizing an iter/map/collect chain
-                                    // because you cannot call `await` within an iterator's closure
-                                    for a in host.actors().await.unwrap_or_else(|_| vec![]) {
-                                        if let Ok((image_ref, name, revision)) =
-                                            host.get_actor_identity(&a).await
+                                    GetHost { output_kind } => {
+                                        let standalone_host = HostSummary {
+                                            id: host.id(),
+                                            uptime_seconds: host_started.elapsed().as_secs(),
+                                            labels: host.labels().await.into_iter().collect(),
+                                        };
+                                        Ok(crate::ctl::get_hosts_output(
+                                            vec![standalone_host],
+                                            &output_kind,
+                                        ))
+                                    }
+                                    GetInventory { output_kind } => {
+                                        let mut actors: Vec<ActorDescription> = vec![];
+                                        // This is a for loop instead of utilizing an iter/map/collect chain
+                                        // because you cannot call `await` within an iterator's closure
+                                        for a in host.actors().await.unwrap_or_else(|_| vec![]) {
+                                            if let Ok((image_ref, name, revision)) =
+                                                host.get_actor_identity(&a).await
+                                            {
+                                                actors.push(ActorDescription {
+                                                    id: a.clone(),
+                                                    image_ref,
+                                                    name: Some(name),
+                                                    revision,
+                                                })
+                                            }
+                                        }
+
+                                        let mut providers: Vec<ProviderDescription> = vec![];
+                                        for (id, _, link_name) in
+                                            host.providers().await.unwrap_or_else(|_| vec![])
                                         {
-                                            actors.push(ActorDescription {
-                                                id: a.clone(),
-                                                image_ref,
-                                                name: Some(name),
-                                                revision,
-                                            })
+                                            if let Ok((image_ref, name, revision)) = host
+                                                .get_provider_identity(&id, Some(link_name.clone()))
+                                                .await
+                                            {
+                                                providers.push(ProviderDescription {
+                                                    id: id.clone(),
+                                                    link_name,
+                                                    image_ref,
+                                                    name: Some(name),
+                                                    revision,
+                                                })
+                                            }
                                         }
-                                    }
 
-                                    let mut providers: Vec<ProviderDescription> = vec![];
-                                    for (id, _, link_name) in
-                                        host.providers().await.unwrap_or_else(|_| vec![])
-                                    {
-                                        if let Ok((image_ref, name, revision)) = host
-                                            .get_provider_identity(&id, Some(link_name.clone()))
+                                        let labels = host.labels().await;
+                                        Ok(crate::ctl::get_host_inventory_output(
+                                            HostInventory {
+                                                actors,
+                                                providers,
+                                                labels,
+                                                host_id: host.id(),
+                                            },
+                                            &output_kind,
+                                        ))
+                                    }
+                                    GetClaims { output_kind } => {
+                                        let wascap_claims =
+                                            host.actor_claims().await.unwrap_or_else(|_| vec![]);
+                                        let claims = wascap_claims
+                                            .iter()
+                                            .map(|wc| {
+                                                let mut values = HashMap::new();
+                                                let metadata = wc.metadata.as_ref().unwrap();
+                                                values.insert("iss".to_string(), wc.issuer.clone());
+                                                values.insert("sub".to_string(), wc.subject.clone());
+                                                if let Some(caps) = &metadata.caps {
+                                                    values.insert("caps".to_string(), caps.join(","));
+                                                }
+                                                if let Some(ver) = &metadata.ver {
+                                                    values
+                                                        .insert("version".to_string(), ver.to_string());
+                                                }
+                                                if let Some(rev) = &metadata.rev {
+                                                    values
+                                                        .insert("rev".to_string(), format!("{}", rev));
+                                                }
+                                                Claims { values }
+                                            })
+                                            .collect::<Vec<Claims>>();
+                                        Ok(crate::ctl::get_claims_output(
+                                            ClaimsList { claims },
+                                            &output_kind,
+                                        ))
+                                    }
+                                    Summary { output_kind } => {
+                                        let actors = host
+                                            .actors()
                                             .await
-                                        {
-                                            providers.push(ProviderDescription {
-                                                id: id.clone(),
+                                            .unwrap_or_else(|_| vec![])
+                                            .into_iter()
+                                            .map(|id| ActorDescription {
+                                                id,
+                                                image_ref: None,
+                                                name: None,
+                                                revision: 0,
+                                            })
+                                            .collect();
+                                        let providers = host
+                                            .providers()
+                                            .await
+                                            .unwrap_or_else(|_| vec![])
+                                            .into_iter()
+                                            .map(|(id, _, link_name)| ProviderDescription {
+                                                id,
                                                 link_name,
-                                                image_ref,
-                                                name: Some(name),
-                                                revision,
+                                                image_ref: None,
+                                                name: None,
+                                                revision: 0,
                                             })
-                                        }
-                                    }
-
-                                    let labels = host.labels().await;
-                                    crate::ctl::get_host_inventory_output(
-                                        HostInventory {
+                                            .collect();
+                                        let inventory = HostInventory {
                                             actors,
                                             providers,
-                                            labels,
+                                            labels: host.labels().await,
                                             host_id: host.id(),
-                                        },
-                                        &output_kind,
-                                    )
-                                }
-                                GetClaims { output_kind } => {
-                                    let wascap_claims =
-                                        host.actor_claims().await.unwrap_or_else(|_| vec![]);
-                                    let claims = wascap_claims
-                                        .iter()
-                                        .map(|wc| {
-                                            let mut values = HashMap::new();
-                                            let metadata = wc.metadata.as_ref().unwrap();
-                                            values.insert("iss".to_string(), wc.issuer.clone());
-                                            values.insert("sub".to_string(), wc.subject.clone());
-                                            if let Some(caps) = &metadata.caps {
-                                                values.insert("caps".to_string(), caps.join(","));
-                                            }
-                                            if let Some(ver) = &metadata.ver {
-                                                values
-                                                    .insert("version".to_string(), ver.to_string());
-                                            }
-                                            if let Some(rev) = &metadata.rev {
-                                                values
-                                                    .insert("rev".to_string(), format!("{}", rev));
-                                            }
-                                            Claims { values }
+                                        };
+                                        Ok(crate::ctl::summary_output(
+                                            crate::ctl::LatticeSummary::new(1, &[inventory]),
+                                            &output_kind,
+                                        ))
+                                    }
+                                    Link { values, .. } if values.is_err() => {
+                                        Err(format!("{}", values.unwrap_err()))
+                                    }
+                                    Link {
+                                        actor_id,
+                                        provider_id,
+                                        contract_id,
+                                        link_name,
+                                        values,
+                                        output_kind,
+                                    } => {
+                                        let failure = host
+                                            .set_link(
+                                                &actor_id,
+                                                &contract_id,
+                                                link_name,
+                                                provider_id.clone(),
+                                                values.unwrap(),
+                                            )
+                                            .await
+                                            .map_or_else(|e| Some(format!("{}", e)), |_| None);
+                                        host_result(failure, |failure| {
+                                            link_output(&actor_id, &provider_id, failure, &output_kind)
                                         })
-                                        .collect::<Vec<Claims>>();
-                                    crate::ctl::get_claims_output(
-                                        ClaimsList { claims },
-                                        &output_kind,
-                                    )
-                                }
-                                Link { values, .. } if values.is_err() => {
-                                    format!("{}", values.unwrap_err())
-                                }
-                                Link {
-                                    actor_id,
-                                    provider_id,
-                                    contract_id,
-                                    link_name,
-                                    values,
-                                    output_kind,
-                                } => {
-                                    let failure = host
-                                        .set_link(
-                                            &actor_id,
-                                            &contract_id,
-                                            link_name,
-                                            provider_id.clone(),
-                                            values.unwrap(),
-                                        )
-                                        .await
-                                        .map_or_else(|e| Some(format!("{}", e)), |_| None);
-                                    link_output(&actor_id, &provider_id, failure, &output_kind)
-                                }
-                                StartActor {
-                                    actor_ref,
-                                    output_kind,
-                                } => {
-                                    debug!("Attempting to load actor from file");
-                                    let failure = match Actor::from_file(actor_ref.clone()) {
-                                        Ok(actor) => host.start_actor(actor).await,
-                                        Err(file_err) => {
-                                            debug!("Actor failed to load from file: \"{}\". Trying from registry", file_err);
-                                            if let Err(_reg_err) = host.start_actor_from_registry(&actor_ref).await {
-                                                Err("Actor reference was not a valid file or OCI reference".into())
-                                            } else {
-                                                debug!("Successfully loaded actor from registry");
-                                                Ok(())
-                                            }
-                                        },
                                     }
-                                    .map_or_else(|e| Some(format!("{}", e)), |_| None);
-                                    start_actor_output(
-                                        &actor_ref,
-                                        &host.id(),
-                                        failure,
-                                        &output_kind,
-                                    )
-                                }
-                                StartProvider {
-                                    provider_ref,
-                                    link_name,
-                                    output_kind,
-                                } => {
-                                    let failure = host
-                                        .start_capability_from_registry(
-                                            &provider_ref,
-                                            Some(link_name),
-                                        )
-                                        .await
-                                        .map_or_else(|e| Some(format!("{}", e)), |_| None);
-                                    start_provider_output(
-                                        &provider_ref,
-                                        &host.id(),
-                                        failure,
-                                        &output_kind,
-                                    )
-                                }
-                                StopActor {
-                                    actor_ref,
-                                    output_kind,
-                                } => {
-                                    let failure = host
-                                        .stop_actor(&actor_ref)
-                                        .await
-                                        .map_or_else(|e| Some(format!("{}", e)), |_| None);
-                                    stop_actor_output(&actor_ref, failure, &output_kind)
-                                }
-                                StopProvider {
-                                    provider_ref,
-                                    contract_id,
-                                    link_name,
-                                    output_kind,
-                                } => {
-                                    let failure = host
-                                        .stop_provider(&provider_ref, &contract_id, Some(link_name))
-                                        .await
+                                    StartActor {
+                                        actor_ref,
+                                        output_kind,
+                                    } => {
+                                        debug!("Attempting to load actor from file");
+                                        let failure = match Actor::from_file(actor_ref.clone()) {
+                                            Ok(actor) => host.start_actor(actor).await,
+                                            Err(file_err) => {
+                                                debug!("Actor failed to load from file: \"{}\". Trying from registry", file_err);
+                                                if let Err(_reg_err) = host.start_actor_from_registry(&actor_ref).await {
+                                                    Err("Actor reference was not a valid file or OCI reference".into())
+                                                } else {
+                                                    debug!("Successfully loaded actor from registry");
+                                                    Ok(())
+                                                }
+                                            },
+                                        }
                                         .map_or_else(|e| Some(format!("{}", e)), |_| None);
-                                    stop_provider_output(&provider_ref, failure, &output_kind)
-                                }
-                                UpdateActor {
-                                    actor_id,
-                                    new_actor_ref,
-                                    output_kind,
-                                } => {
-                                    // If the actor is not local, we have to download it from the OCI registry
-                                    // Providing OCI authentication parameters here will depend on https://github.com/wasmCloud/wasmCloud/issues/158
-
-                                    // actor_bytes are required regardless to update an actor, but the actor reference is only an OCI reference
-                                    // if we use it to download the image from an OCI registry.
-                                    let (oci_ref, actor_bytes) = if let Ok(mut actor_bytes) = File::open(new_actor_ref.clone()) {
-                                        let mut buf = Vec::new();
-                                        let _ = actor_bytes.read_to_end(&mut buf);
-                                        (None, buf)
-                                    } else {
-                                        info!("Downloading new actor module for update");
-                                        (Some(new_actor_ref.clone()), crate::reg::pull_artifact(
-                                            new_actor_ref.clone(),
-                                            None,
-                                            false,
-                                            None,
-                                            None,
-                                            false,
-                                        )
-                                        .await
-                                        .unwrap_or_else(|_| vec![]))
-                                    };
-
-                                    let ack = host
-                                        .update_actor(&actor_id, oci_ref.clone(), &actor_bytes)
-                                        .await;
-                                    update_actor_output(
-                                        &actor_id,
-                                        &new_actor_ref.to_string(),
-                                        ack.map_or_else(|e| Some(format!("{}", e)), |_| None),
-                                        &output_kind,
-                                    )
-                                }
-                            };
-                            host_output_sender.send(output).unwrap();
-                        } else {
-                            actix_rt::time::sleep(std::time::Duration::from_millis(100)).await;
+                                        host_result(failure, |failure| {
+                                            start_actor_output(
+                                                &actor_ref,
+                                                &host.id(),
+                                                failure,
+                                                &output_kind,
+                                            )
+                                        })
+                                    }
+                                    StartProvider {
+                                        provider_ref,
+                                        link_name,
+                                        output_kind,
+                                    } => {
+                                        let failure = host
+                                            .start_capability_from_registry(
+                                                &provider_ref,
+                                                Some(link_name),
+                                            )
+                                            .await
+                                            .map_or_else(|e| Some(format!("{}", e)), |_| None);
+                                        host_result(failure, |failure| {
+                                            start_provider_output(
+                                                &provider_ref,
+                                                &host.id(),
+                                                failure,
+                                                &output_kind,
+                                            )
+                                        })
+                                    }
+                                    StopActor {
+                                        actor_ref,
+                                        output_kind,
+                                    } => {
+                                        let failure = host
+                                            .stop_actor(&actor_ref)
+                                            .await
+                                            .map_or_else(|e| Some(format!("{}", e)), |_| None);
+                                        host_result(failure, |failure| {
+                                            stop_actor_output(&actor_ref, failure, &output_kind)
+                                        })
+                                    }
+                                    StopProvider {
+                                        provider_ref,
+                                        contract_id,
+                                        link_name,
+                                        output_kind,
+                                    } => {
+                                        let failure = host
+                                            .stop_provider(&provider_ref, &contract_id, Some(link_name))
+                                            .await
+                                            .map_or_else(|e| Some(format!("{}", e)), |_| None);
+                                        host_result(failure, |failure| {
+                                            stop_provider_output(&provider_ref, failure, &output_kind)
+                                        })
+                                    }
+                                    UpdateActor {
+                                        actor_id,
+                                        new_actor_ref,
+                                        output_kind,
+                                    } => {
+                                        // If the actor is not local, we have to download it from the OCI registry
+                                        // Providing OCI authentication parameters here will depend on https://github.com/wasmCloud/wasmCloud/issues/158
+
+                                        // actor_bytes are required regardless to update an actor, but the actor reference is only an OCI reference
+                                        // if we use it to download the image from an OCI registry.
+                                        let (oci_ref, actor_bytes) = if let Ok(mut actor_bytes) = File::open(new_actor_ref.clone()) {
+                                            let mut buf = Vec::new();
+                                            let _ = actor_bytes.read_to_end(&mut buf);
+                                            (None, buf)
+                                        } else {
+                                            info!("Downloading new actor module for update");
+                                            (Some(new_actor_ref.clone()), crate::reg::pull_artifact(
+                                                new_actor_ref.clone(),
+                                                None,
+                                                false,
+                                                None,
+                                                None,
+                                                false,
+                                                None,
+                                            )
+                                            .await
+                                            .unwrap_or_else(|_| vec![]))
+                                        };
+
+                                        let ack = host
+                                            .update_actor(&actor_id, oci_ref.clone(), &actor_bytes)
+                                            .await;
+                                        let failure =
+                                            ack.map_or_else(|e| Some(format!("{}", e)), |_| None);
+                                        host_result(failure, |failure| {
+                                            update_actor_output(
+                                                &actor_id,
+                                                &new_actor_ref.to_string(),
+                                                failure,
+                                                &output_kind,
+                                            )
+                                        })
+                                    }
+                                    Unsupported { command } => Err(format!(
+                                        "ctl {} requires a lattice and is not available in standalone mode",
+                                        command
+                                    )),
+                                };
+                                host_output_sender.send(output).unwrap();
+                            } else {
+                                actix_rt::time::sleep(std::time::Duration::from_millis(100)).await;
+                            }
                         }
                     }
                 }
+            })
+        });
+        (mode, _hotwatch)
+    };
+    repl.redraw(&mut terminal)?;
+
+    if let Some(script) = script {
+        // Wait for the host so script commands can rely on it and its manifest
+        let _ = host_ready_receiver.recv();
+        while let Ok(output) = host_output_receiver.try_recv() {
+            repl.report(output.unwrap_or_else(|e| e));
+        }
+        let res = repl
+            .run_script(script, keep_going, &host_output_receiver)
+            .await;
+        let terminal = match terminal.as_mut() {
+            Some(terminal) => terminal,
+            None => return res.map(|_| ()),
+        };
+        match res {
+            Ok(true) => (),
+            Ok(false) => {
+                cleanup_terminal(terminal);
+                return Ok(());
             }
-        })
-    });
-    repl.draw_ui(&mut terminal)?;
+            Err(e) => error!(target: WASH_CMD_INFO, "{}", e),
+        }
+        repl.draw_ui(terminal)?;
+    }
+    let mut terminal = terminal.unwrap();
 
     // Use a channel to asynchronously receive stdin events
     let (tui_sender, tui_receiver) = unbounded();
@@ -586,8 +749,9 @@ async fn handle_up(cmd: UpCliCommand) -> Result<()> {
         }
     });
 
-    // Set REPL title to the corresponding host mode (Standalone / Lattice)
+    // Set REPL title to the corresponding host mode (Standalone / Lattice / Attached)
     repl.input_state.title = match mode {
+        ReplMode::Lattice if repl.embedded_host.is_none() => REPL_CONNECTED.to_string(),
         ReplMode::Lattice => REPL_LATTICE.to_string(),
         ReplMode::Standalone => REPL_STANDALONE.to_string(),
     };
@@ -595,7 +759,7 @@ async fn handle_up(cmd: UpCliCommand) -> Result<()> {
     loop {
         // If any output is sent by a non-lattice connected host, log to output
         if let Ok(output) = host_output_receiver.try_recv() {
-            log_to_output(Arc::clone(&repl.output_state), output);
+            log_to_output(Arc::clone(&repl.output_state), output.unwrap_or_else(|e| e));
         }
         if let Ok(evt) = tui_receiver.recv_timeout(std::time::Duration::from_millis(50)) {
             let res = match evt? {
@@ -694,6 +858,41 @@ async fn handle_reg(reg_cmd: RegCliCommand, output_state: Arc<Mutex<OutputState>
     Ok(())
 }
 
+/// Formats the output of a host operation, which is an error if the operation failed so a
+/// script waiting on the host can tell its commands apart
+fn host_result(
+    failure: Option<String>,
+    output: impl FnOnce(Option<String>) -> String,
+) -> HostOutput {
+    let failed = failure.is_some();
+    let output = output(failure);
+    if failed {
+        Err(output)
+    } else {
+        Ok(output)
+    }
+}
+
+/// Checks that hosts are running in the lattice given to `--connect`, returning their IDs
+async fn attach_to_lattice(opts: &ConnectionOpts) -> Result<Vec<String>> {
+    let hosts = get_hosts(GetHostsCommand::new(opts.clone(), Output::default(), 2))
+        .await
+        .map_err(|e| {
+            format!(
+                "Unable to connect to lattice at {}:{}: {}",
+                opts.rpc_host, opts.rpc_port, e
+            )
+        })?;
+    if hosts.is_empty() {
+        return Err(format!(
+            "No hosts are running in lattice ({}) at {}:{}",
+            opts.ns_prefix, opts.rpc_host, opts.rpc_port
+        )
+        .into());
+    }
+    Ok(hosts.into_iter().map(|h| h.id).collect())
+}
+
 /// Helper function to exit the alternate tui terminal without corrupting the user terminal
 pub(crate) fn cleanup_terminal(terminal: &mut Terminal<ReplTermionBackend>) {
     terminal.show_cursor().unwrap();
@@ -939,16 +1138,16 @@ mod test {
         ])?;
 
         #[allow(unreachable_patterns)]
-        match up_all_options.command {
+        match up_all_options.command() {
             UpCliCommand {
-                rpc_host,
-                rpc_port,
+                opts,
                 log_level,
                 manifest,
                 actors,
+                ..
             } => {
-                assert_eq!(rpc_host, RPC_HOST);
-                assert_eq!(rpc_port, RPC_PORT);
+                assert_eq!(opts.rpc_host, RPC_HOST);
+                assert_eq!(opts.rpc_port, RPC_PORT);
                 assert_eq!(log_level, LogLevel::Info);
                 assert_eq!(manifest.unwrap().to_str().unwrap(), "mani.yaml");
                 assert_eq!(actors, vec![PathBuf::from("myactor_s.wasm")])
@@ -957,16 +1156,16 @@ mod test {
         }
 
         #[allow(unreachable_patterns)]
-        match up_all_short_options.command {
+        match up_all_short_options.command() {
             UpCliCommand {
-                rpc_host,
-                rpc_port,
+                opts,
                 log_level,
                 manifest,
                 actors,
+                ..
             } => {
-                assert_eq!(rpc_host, RPC_HOST);
-                assert_eq!(rpc_port, RPC_PORT);
+                assert_eq!(opts.rpc_host, RPC_HOST);
+                assert_eq!(opts.rpc_port, RPC_PORT);
                 assert_eq!(log_level, LogLevel::Info);
                 assert_eq!(manifest.unwrap().to_str().unwrap(), "mani.yaml");
                 assert_eq!(actors, vec![PathBuf::from("myactor_s.wasm")])
@@ -1137,6 +1336,175 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_repl_history() -> Result<()> {
+        let dir = std::env::temp_dir().join("wash_repl_history");
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("nested").join("repl_history");
+
+        let up = UpCli::from_iter_safe(&[
+            "up",
+            "--history-file",
+            path.to_str().unwrap(),
+            "--history-size",
+            "2",
+        ])?;
+        let cmd = up.command();
+        let history = ReplHistory::from_opts(cmd.history_file, cmd.history_size).unwrap();
+        assert!(history.load().is_empty());
+
+        history.append("ctl get hosts")?;
+        history.append("   ")?;
+        history.append("keys gen account")?;
+        history.append("claims inspect echo_s.wasm")?;
+        assert_eq!(
+            history.load(),
+            vec![
+                "keys gen account".chars().collect::<Vec<char>>(),
+                "claims inspect echo_s.wasm".chars().collect::<Vec<char>>(),
+            ]
+        );
+        assert!(ReplHistory::from_opts(Some(path), 0).is_none());
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_up_connect() -> Result<()> {
+        let up = UpCli::from_iter_safe(&[
+            "up",
+            "--connect",
+            "--host",
+            "127.0.0.1",
+            "--lattice-prefix",
+            "dev",
+            "--rpc-credsfile",
+            "./dev.creds",
+        ])?;
+        let cmd = up.command();
+        assert!(cmd.connect);
+        assert_eq!(cmd.opts.rpc_host, "127.0.0.1");
+        assert_eq!(cmd.opts.ns_prefix, "dev");
+        assert_eq!(cmd.opts.rpc_credsfile.as_deref(), Some("./dev.creds"));
+
+        // The shared connection options can be used as well
+        let shared =
+            UpCli::from_iter_safe(&["up", "-r", "10.0.0.2", "--rpc-port", "4333"])?.command();
+        assert_eq!(shared.opts.rpc_host, "10.0.0.2");
+        assert_eq!(shared.opts.rpc_port, "4333");
+
+        // `ctl` commands in the REPL default to the connection options given to `wash up`,
+        // while flags on the command still take precedence
+        let repl = WashRepl {
+            connection: cmd.opts.clone(),
+            ..Default::default()
+        };
+        let opts = |line: &str| match repl.parse(line).unwrap().cmd {
+            ReplCliCommand::Ctl(CtlCliCommand::Get(GetCommand::Hosts(get))) => get.opts,
+            cmd => panic!("REPL parsed incorrect command {:?}", cmd),
+        };
+        let defaulted = opts("ctl get hosts");
+        assert_eq!(defaulted.rpc_host, "127.0.0.1");
+        assert_eq!(defaulted.ns_prefix, "dev");
+        assert_eq!(defaulted.rpc_credsfile.as_deref(), Some("./dev.creds"));
+        let explicit = opts("ctl get hosts --rpc-host 10.0.0.1 -n prod");
+        assert_eq!(explicit.rpc_host, "10.0.0.1");
+        assert_eq!(explicit.ns_prefix, "prod");
+        assert_eq!(explicit.rpc_credsfile.as_deref(), Some("./dev.creds"));
+
+        assert!(UpCli::from_iter_safe(&["up", "--connect", "--manifest", "mani.yaml"]).is_err());
+        assert!(UpCli::from_iter_safe(&["up", "--connect", "--watch", "echo_s.wasm"]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_up_script() -> Result<()> {
+        let dir = std::env::temp_dir().join("wash_up_script");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join("setup.wash");
+        std::fs::write(
+            &path,
+            "# start the echo actor\nctl start actor wasmcloud.azurecr.io/echo:0.2.0\n\n  ctl get hosts -o json  \n",
+        )?;
+
+        let up = UpCli::from_iter_safe(&[
+            "up",
+            "--script",
+            path.to_str().unwrap(),
+            "--keep-going",
+            "--interactive",
+        ])?;
+        let cmd = up.command();
+        assert_eq!(cmd.script.as_ref(), Some(&path));
+        assert!(cmd.keep_going);
+        assert!(cmd.interactive);
+        assert_eq!(
+            read_script(&path)?,
+            vec![
+                (
+                    2,
+                    "ctl start actor wasmcloud.azurecr.io/echo:0.2.0".to_string()
+                ),
+                (4, "ctl get hosts -o json".to_string()),
+            ]
+        );
+        assert!(read_script(&dir.join("missing.wash")).is_err());
+        assert!(UpCli::from_iter_safe(&["up", "--keep-going"]).is_err());
+        assert!(UpCli::from_iter_safe(&["up", "--interactive"]).is_err());
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[actix_rt::test]
+    async fn test_up_script_host_failure() {
+        let (op_sender, op_receiver) = unbounded();
+        let (output_sender, output_receiver) = unbounded();
+        // Stands in for the standalone host, failing every operation it's sent
+        std::thread::spawn(move || {
+            for _ in op_receiver.iter() {
+                let output = host_result(Some("no such actor".to_string()), |failure| {
+                    stop_actor_output("MBECHO", failure, &crate::util::OutputKind::Json)
+                });
+                output_sender.send(output).unwrap();
+            }
+        });
+        let script = || {
+            vec![
+                (1, "ctl stop actor NHOST MBECHO".to_string()),
+                (2, "ctl get hosts".to_string()),
+            ]
+        };
+        let mut repl = WashRepl {
+            embedded_host: Some(EmbeddedHost::new(
+                "NHOST".to_string(),
+                ReplMode::Standalone,
+                op_sender,
+            )),
+            ..Default::default()
+        };
+
+        let err = repl
+            .run_script(script(), false, &output_receiver)
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Script line 1 `ctl stop actor NHOST MBECHO` failed: "));
+        assert!(err.to_string().contains("no such actor"));
+        // Only the first line ran
+        assert_eq!(repl.input_state.history.len(), 1);
+
+        let err = repl
+            .run_script(script(), true, &output_receiver)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "2 script command(s) failed");
+        assert_eq!(repl.input_state.history.len(), 3);
+    }
+
     #[test]
     fn test_log_level_from_str() -> Result<()> {
         use std::str::FromStr;
@@ -1,10 +1,11 @@
 use super::*;
 use crate::ctl::{StartActorCommand, UpdateActorCommand};
 use crate::util::{Output, Result, WASH_CMD_INFO, WASH_LOG_INFO};
-use crossbeam_channel::Sender;
+use crossbeam_channel::{Receiver, Sender};
 use hotwatch::{Event, Hotwatch};
 use log::{debug, error, info};
 use std::fs::metadata;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use structopt::StructOpt;
 use termion::event::Key;
@@ -110,6 +111,9 @@ pub(crate) enum ReplMode {
     Lattice,
 }
 
+/// Output of an operation of the embedded host, which is an error if the operation failed
+pub(crate) type HostOutput = std::result::Result<String, String>;
+
 #[derive(Debug, Clone)]
 pub(crate) struct EmbeddedHost {
     pub(crate) id: String,
@@ -199,11 +203,88 @@ impl EmbeddedHost {
     }
 }
 
+/// Reads the commands of a `--script` file, skipping blank lines and `#` comments.
+/// Commands are returned with their line number to help locate failures
+pub(crate) fn read_script(path: &Path) -> Result<Vec<(usize, String)>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Unable to read script {}: {}", path.display(), e))?;
+    Ok(contents
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(i, line)| (i, line.to_string()))
+        .collect())
+}
+
+/// Command history saved between REPL sessions
+#[derive(Debug, Clone)]
+pub(crate) struct ReplHistory {
+    path: PathBuf,
+    max_lines: usize,
+}
+
+impl ReplHistory {
+    /// Returns the history file to use, or None if history is disabled or $HOME isn't set
+    pub(crate) fn from_opts(path: Option<PathBuf>, max_lines: usize) -> Option<Self> {
+        if max_lines == 0 {
+            return None;
+        }
+        let path = path.or_else(|| {
+            std::env::var("HOME")
+                .ok()
+                .map(|home| PathBuf::from(home).join(".wash").join("repl_history"))
+        })?;
+        Some(ReplHistory { path, max_lines })
+    }
+
+    /// Reads the saved commands, oldest first. A missing or unreadable file is an empty history
+    pub(crate) fn load(&self) -> Vec<Vec<char>> {
+        self.lines()
+            .into_iter()
+            .map(|line| line.chars().collect())
+            .collect()
+    }
+
+    /// Appends a command to the history file, dropping the oldest commands past the limit
+    pub(crate) fn append(&self, cmd: &str) -> Result<()> {
+        let cmd = cmd.trim();
+        if cmd.is_empty() {
+            return Ok(());
+        }
+        let mut lines = self.lines();
+        lines.push(cmd.to_string());
+        let start = lines.len().saturating_sub(self.max_lines);
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut contents = lines[start..].join("\n");
+        contents.push('\n');
+        std::fs::write(&self.path, contents)?;
+        Ok(())
+    }
+
+    fn lines(&self) -> Vec<String> {
+        std::fs::read_to_string(&self.path)
+            .map(|contents| {
+                contents
+                    .lines()
+                    .filter(|l| !l.trim().is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
 pub(crate) struct WashRepl {
     pub(crate) input_state: InputState,
     pub(crate) output_state: Arc<Mutex<OutputState>>,
     pub(crate) tui_state: TuiWidgetState,
     pub(crate) embedded_host: Option<EmbeddedHost>,
+    pub(crate) history: Option<ReplHistory>,
+    /// Connection options that `ctl` commands default to, those given to `wash up`
+    pub(crate) connection: ConnectionOpts,
 }
 
 impl Default for WashRepl {
@@ -213,6 +294,8 @@ impl Default for WashRepl {
             output_state: Arc::new(Mutex::new(OutputState::default())),
             tui_state: TuiWidgetState::new(),
             embedded_host: None,
+            history: None,
+            connection: ConnectionOpts::default(),
         }
     }
 }
@@ -248,6 +331,143 @@ impl WashRepl {
         Ok(())
     }
 
+    /// Draws the REPL interface, if one is displayed
+    pub(crate) fn redraw(
+        &mut self,
+        terminal: &mut Option<Terminal<ReplTermionBackend>>,
+    ) -> Result<()> {
+        match terminal {
+            Some(terminal) => self.draw_ui(terminal),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the embedded host if a `ctl` command should be sent to its host API rather
+    /// than the lattice control interface.
+    ///
+    /// This handles loading an actor from disk instead of from an OCI registry.
+    /// When a Start or Update Actor `ctl` command is sent, we send the `ctl` command to the host API for the following cases:
+    /// 1. The Host is running in standalone mode (all ctl commands are delegated to host API)
+    /// 2. The actor_ref exists as a file on disk AND:
+    ///    a. The host ID specified is the embedded host
+    ///    b. The host ID is not specified (the embedded host is a suitable host for a local actor)
+    fn host_for(&self, ctlcmd: &CtlCliCommand) -> Option<&EmbeddedHost> {
+        let host = self.embedded_host.as_ref()?;
+        let local = match (host.mode, ctlcmd) {
+            (ReplMode::Standalone, _) => true,
+            (ReplMode::Lattice, CtlCliCommand::Start(StartCommand::Actor(cmd))) => {
+                metadata(&cmd.actor_ref).is_ok() // File exists
+                    && (cmd.host_id.is_none() || cmd.host_id.as_ref() == Some(&host.id))
+            }
+            (ReplMode::Lattice, CtlCliCommand::Update(UpdateCommand::Actor(cmd))) => {
+                metadata(&cmd.new_actor_ref).is_ok() // File exists
+                    && cmd.host_id == host.id
+            }
+            _ => false,
+        };
+        if local {
+            Some(host)
+        } else {
+            None
+        }
+    }
+
+    /// Parses a line of REPL input. Connection flags left out of `ctl` commands default to the
+    /// connection options of the REPL rather than their environment variables
+    pub(crate) fn parse(&self, line: &str) -> std::result::Result<ReplCli, structopt::clap::Error> {
+        // The connection of the REPL takes precedence over the config defaults
+        let app = crate::util::with_env_defaults(ReplCli::clap(), &self.connection.env_defaults());
+        let app = crate::config::with_config_defaults(app);
+        app.get_matches_from_safe(line.split_ascii_whitespace())
+            .map(|matches| ReplCli::from_clap(&matches))
+    }
+
+    /// Runs the commands of a `--script` in order, waiting for each to finish before starting
+    /// the next. Stops at the first failing command unless `keep_going` is set, in which case
+    /// an error is returned once every command has run. Returns `Ok(false)` if the script quits
+    pub(crate) async fn run_script(
+        &mut self,
+        script: Vec<(usize, String)>,
+        keep_going: bool,
+        host_output: &Receiver<HostOutput>,
+    ) -> Result<bool> {
+        let mut failures = 0;
+        for (line_no, line) in script {
+            self.input_state.history.push(line.chars().collect());
+            self.input_state.history_cursor = self.input_state.history.len();
+            let res = match self.parse(&line) {
+                Ok(ReplCli {
+                    cmd: ReplCliCommand::Quit,
+                }) => return Ok(false),
+                Ok(ReplCli { cmd }) => self.execute(cmd, host_output).await,
+                Err(e) => Err(e.message.into()),
+            };
+            match res {
+                Ok(output) => self.report(output),
+                Err(e) if keep_going => {
+                    failures += 1;
+                    self.report_error(format!("Script line {} `{}` failed: {}", line_no, line, e));
+                }
+                Err(e) => {
+                    return Err(format!("Script line {} `{}` failed: {}", line_no, line, e).into())
+                }
+            }
+        }
+        if failures > 0 {
+            Err(format!("{} script command(s) failed", failures).into())
+        } else {
+            Ok(true)
+        }
+    }
+
+    /// Runs a single command to completion and returns its output. Commands delegated to
+    /// the embedded host wait for the host to respond on `host_output`, and fail if the
+    /// host reports a failure
+    async fn execute(
+        &mut self,
+        cmd: ReplCliCommand,
+        host_output: &Receiver<HostOutput>,
+    ) -> Result<String> {
+        use ReplCliCommand::*;
+        match cmd {
+            Drain(draincmd) => crate::drain::handle_command(draincmd),
+            Claims(claimscmd) => crate::claims::handle_command(claimscmd).await,
+            Ctl(ctlcmd) => match self.host_for(&ctlcmd) {
+                Some(host) => {
+                    host.op_sender.send(ctlcmd)?;
+                    host_output.recv()?.map_err(|e| e.into())
+                }
+                None => crate::ctl::handle_command(ctlcmd).await,
+            },
+            Keys(keyscmd) => crate::keys::handle_command(keyscmd),
+            Par(parcmd) => crate::par::handle_command(parcmd).await,
+            Reg(regcmd) => crate::reg::handle_command(regcmd).await,
+            Clear => {
+                self.input_state.history.clear();
+                self.input_state.history_cursor = 0;
+                Ok(String::new())
+            }
+            Quit => Ok(String::new()),
+        }
+    }
+
+    /// Displays command output in the REPL, or prints it when running a script without the REPL
+    pub(crate) fn report(&self, output: String) {
+        if crate::util::REPL_MODE.get().is_some() {
+            log_to_output(Arc::clone(&self.output_state), output);
+        } else if !output.is_empty() {
+            println!("{}", output);
+        }
+    }
+
+    fn report_error(&self, msg: String) {
+        if crate::util::REPL_MODE.get().is_some() {
+            error!(target: WASH_CMD_INFO, "{}", msg);
+        } else {
+            eprintln!("{}", msg);
+        }
+    }
+
     /// Handles key input by the user into the REPL
     pub(crate) async fn handle_key_event(&mut self, key: Key) -> Result<()> {
         match key {
@@ -314,12 +534,17 @@ impl WashRepl {
             // }
             Key::Char(c) if c == '\n' => {
                 let cmd: String = self.input_state.input.iter().collect();
-                let iter = cmd.split_ascii_whitespace();
 
                 crate::util::set_max_text_output_width(
                     self.output_state.lock().unwrap().output_width - 2,
                 );
-                let cli = ReplCli::from_iter_safe(iter);
+                let cli = self.parse(&cmd);
+
+                if let Some(history) = &self.history {
+                    if let Err(e) = history.append(&cmd) {
+                        debug!(target: WASH_CMD_INFO, "Failed to save REPL history: {}", e);
+                    }
+                }
 
                 self.input_state
                     .history
@@ -362,43 +587,11 @@ impl WashRepl {
                                 });
                             }
                             ReplCliCommand::Ctl(ctlcmd) => {
-                                // This match statement handles loading an actor from disk instead of from an OCI registry
-                                //
-                                // When a Start or Update Actor `ctl` command is sent, we send the `ctl` command to the host API for the following cases:
-                                // 1. The Host is running in standalone mode (all ctl commands are delegated to host API)
-                                // 2. The actor_ref exists as a file on disk AND:
-                                //    a. The host ID specified is the embedded host
-                                //    b. The host ID is not specified (the embedded host is a suitable host for a local actor)
-                                match (self.embedded_host.as_ref(), ctlcmd.clone()) {
-                                    (
-                                        Some(host),
-                                        CtlCliCommand::Start(StartCommand::Actor(cmd)),
-                                    ) if host.mode == ReplMode::Lattice => {
-                                        if metadata(&cmd.actor_ref).is_ok() // File exists
-                                            && (cmd.host_id.is_none()
-                                                || cmd.host_id.unwrap() == host.id)
-                                        {
-                                            host.op_sender.send(ctlcmd)?;
-                                            return Ok(());
-                                        }
-                                    }
-                                    (
-                                        Some(host),
-                                        CtlCliCommand::Update(UpdateCommand::Actor(cmd))
-                                    ) if host.mode == ReplMode::Lattice => {
-                                        if metadata(&cmd.new_actor_ref).is_ok() // File exists
-                                            && cmd.host_id == host.id
-                                        {
-                                            host.op_sender.send(ctlcmd)?;
-                                            return Ok(());
-                                        }
-                                    }
-                                    (Some(host), cmd) if host.mode == ReplMode::Standalone => {
-                                        host.op_sender.send(cmd)?;
-                                        return Ok(());
-                                    }
-                                    _ => debug!("Dispatching command to lattice control interface (actor not found locally)"),
+                                if let Some(host) = self.host_for(&ctlcmd) {
+                                    host.op_sender.send(ctlcmd)?;
+                                    return Ok(());
                                 }
+                                debug!("Dispatching command to lattice control interface (actor not found locally)");
                                 let output_state = Arc::clone(&self.output_state);
                                 std::thread::spawn(|| {
                                     let rt = actix_rt::System::new();
@@ -8,6 +8,7 @@ pub(crate) enum HostCommand {
         actor: String,
         operation: String,
         msg: Result<Vec<u8>>,
+        response_format: CallResponseFormat,
         output_kind: OutputKind,
     },
     GetHost {
@@ -19,6 +20,9 @@ pub(crate) enum HostCommand {
     GetClaims {
         output_kind: OutputKind,
     },
+    Summary {
+        output_kind: OutputKind,
+    },
     Link {
         actor_id: String,
         provider_id: String,
@@ -51,23 +55,22 @@ pub(crate) enum HostCommand {
         new_actor_ref: String,
         output_kind: OutputKind,
     },
+    /// A ctl command that requires a lattice connection
+    Unsupported {
+        command: &'static str,
+    },
 }
 
 impl From<CtlCliCommand> for HostCommand {
     /// Transforms a CtlCliCommand to a command to invoke on a standalone host
     fn from(cmd: CtlCliCommand) -> Self {
         match cmd {
-            Call(CallCommand {
-                actor_id,
-                operation,
-                data,
-                output,
-                ..
-            }) => HostCommand::Call {
-                actor: actor_id,
-                operation,
-                msg: crate::util::json_str_to_msgpack_bytes(data),
-                output_kind: output.kind,
+            Call(cmd) => HostCommand::Call {
+                msg: cmd.payload(),
+                actor: cmd.actor_id,
+                operation: cmd.operation,
+                response_format: cmd.response_format,
+                output_kind: cmd.output.kind,
             },
             Get(GetCommand::Hosts(cmd)) => HostCommand::GetHost {
                 output_kind: cmd.output.kind,
@@ -78,6 +81,9 @@ impl From<CtlCliCommand> for HostCommand {
             Get(GetCommand::Claims(cmd)) => HostCommand::GetClaims {
                 output_kind: cmd.output.kind,
             },
+            Summary(cmd) => HostCommand::Summary {
+                output_kind: cmd.output.kind,
+            },
             Start(StartCommand::Actor(cmd)) => HostCommand::StartActor {
                 actor_ref: cmd.actor_ref,
                 output_kind: cmd.output.kind,
@@ -118,6 +124,13 @@ impl From<CtlCliCommand> for HostCommand {
                 new_actor_ref: cmd.new_actor_ref,
                 output_kind: cmd.output.kind,
             },
+            Apply(_) => HostCommand::Unsupported { command: "apply" },
+            Auction(_) => HostCommand::Unsupported { command: "auction" },
+            Scale(_) => HostCommand::Unsupported { command: "scale" },
+            Watch(_) => HostCommand::Unsupported { command: "watch" },
+            Stop(StopCommand::Host(_)) => HostCommand::Unsupported {
+                command: "stop host",
+            },
         }
     }
 }
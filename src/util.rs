@@ -1,13 +1,19 @@
-use log::info;
+use indicatif::{ProgressBar, ProgressFinish, ProgressStyle};
+use log::{info, LevelFilter};
 use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::cell::Cell;
 use std::collections::HashMap;
 use std::error::Error;
+use std::ffi::OsStr;
 use std::fmt;
 use std::fs::File;
 use std::io::Read;
+use std::path::Path;
 use std::str::FromStr;
+use std::time::Duration;
+use structopt::clap::App;
 use structopt::StructOpt;
 use term_table::{Table, TableStyle};
 
@@ -16,7 +22,20 @@ pub(crate) type Result<T> = ::std::result::Result<T, Box<dyn ::std::error::Error
 /// Environment variable to show when user is in REPL mode
 pub(crate) static REPL_MODE: OnceCell<String> = OnceCell::new();
 
+/// Set by the global `--quiet` flag to suppress spinners and decorative output
+pub(crate) static QUIET_MODE: OnceCell<bool> = OnceCell::new();
+
+/// Set at startup when output must not contain ANSI escape codes
+pub(crate) static NO_COLOR_MODE: OnceCell<bool> = OnceCell::new();
+
+/// Set at startup when output must stick to plain ASCII, leaving out emoji
+pub(crate) static NO_EMOJI_MODE: OnceCell<bool> = OnceCell::new();
+
+/// Set by the global `--offline` flag or `WASH_OFFLINE` to resolve artifacts only from the cache
+pub(crate) static OFFLINE_MODE: OnceCell<bool> = OnceCell::new();
+
 pub(crate) const WASH_LOG_INFO: &str = "WASH_LOG";
+const CHECKSUM_FILE_EXTENSION: &str = ".sha256";
 pub(crate) const WASH_CMD_INFO: &str = "WASH_CMD";
 
 thread_local! {
@@ -30,6 +49,7 @@ pub(crate) struct Output {
         short = "o",
         long = "output",
         default_value = "text",
+        env = "WASH_OUTPUT",
         help = "Specify output format (text, json or wide)"
     )]
     pub(crate) kind: OutputKind,
@@ -76,6 +96,134 @@ impl FromStr for OutputKind {
     }
 }
 
+/// Returned when the user interrupts a command with Ctrl-C
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Aborted;
+
+impl Error for Aborted {}
+
+impl fmt::Display for Aborted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Aborted")
+    }
+}
+
+/// Class of a failure, which determines the exit code of wash so that scripts can tell
+/// bad input apart from an unreachable host or denied credentials
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum WashError {
+    /// Arguments or input files that can't be used as given
+    InvalidInput(String),
+    /// Credentials that are missing or were rejected
+    Auth(String),
+    /// A registry, NATS server or host that couldn't be reached or didn't respond in time
+    Network(String),
+    /// A file, artifact or other resource that doesn't exist
+    NotFound(String),
+    /// An artifact, signature or claims that failed verification
+    Validation(String),
+    /// Any other failure to read or write local files
+    Io(String),
+}
+
+/// Exit code of a command that failed without a more specific class of error
+pub(crate) const EXIT_FAILURE: i32 = 1;
+/// Exit code of a command interrupted with Ctrl-C, the conventional status for SIGINT
+pub(crate) const EXIT_ABORTED: i32 = 130;
+
+impl WashError {
+    /// Process exit code for the class of error
+    pub(crate) fn exit_code(&self) -> i32 {
+        match self {
+            WashError::InvalidInput(_) => 2,
+            WashError::Auth(_) => 3,
+            WashError::Network(_) => 4,
+            WashError::NotFound(_) => 5,
+            WashError::Validation(_) => 6,
+            WashError::Io(_) => 7,
+        }
+    }
+
+    /// Returns an error of the same class with a different message
+    fn with_message(&self, message: String) -> WashError {
+        match self {
+            WashError::InvalidInput(_) => WashError::InvalidInput(message),
+            WashError::Auth(_) => WashError::Auth(message),
+            WashError::Network(_) => WashError::Network(message),
+            WashError::NotFound(_) => WashError::NotFound(message),
+            WashError::Validation(_) => WashError::Validation(message),
+            WashError::Io(_) => WashError::Io(message),
+        }
+    }
+
+    /// Determines the class of an arbitrary error, looking through its sources. Besides
+    /// errors raised as a `WashError`, I/O and HTTP errors are classified by their kind
+    pub(crate) fn classify(e: &(dyn Error + 'static)) -> Option<WashError> {
+        let mut current = Some(e);
+        while let Some(e) = current {
+            if let Some(e) = e.downcast_ref::<WashError>() {
+                return Some(e.clone());
+            }
+            if let Some(e) = e.downcast_ref::<reqwest::Error>() {
+                let message = e.to_string();
+                return Some(match e.status().map(|s| s.as_u16()) {
+                    Some(401) | Some(403) => WashError::Auth(message),
+                    Some(404) => WashError::NotFound(message),
+                    _ => WashError::Network(message),
+                });
+            }
+            if let Some(e) = e.downcast_ref::<std::io::Error>() {
+                use std::io::ErrorKind::*;
+                let message = e.to_string();
+                return Some(match e.kind() {
+                    NotFound => WashError::NotFound(message),
+                    PermissionDenied => WashError::Io(message),
+                    ConnectionRefused | ConnectionReset | ConnectionAborted | NotConnected
+                    | AddrNotAvailable | BrokenPipe | TimedOut => WashError::Network(message),
+                    InvalidInput | InvalidData => WashError::InvalidInput(message),
+                    _ => WashError::Io(message),
+                });
+            }
+            current = e.source();
+        }
+        None
+    }
+}
+
+impl Error for WashError {}
+
+impl fmt::Display for WashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WashError::InvalidInput(message)
+            | WashError::Auth(message)
+            | WashError::Network(message)
+            | WashError::NotFound(message)
+            | WashError::Validation(message)
+            | WashError::Io(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// Exit code for a failed command, see `WashError::exit_code` for the classified ones
+pub(crate) fn exit_code(e: &(dyn Error + 'static)) -> i32 {
+    if e.is::<Aborted>() {
+        return EXIT_ABORTED;
+    }
+    if let Some(e) = e.downcast_ref::<JsonError>() {
+        return e.1;
+    }
+    WashError::classify(e).map_or(EXIT_FAILURE, |e| e.exit_code())
+}
+
+/// Rewords an error while keeping its class, and so the exit code it results in
+pub(crate) fn with_context(e: Box<dyn Error>, message: String) -> Box<dyn Error> {
+    match WashError::classify(&*e) {
+        Some(class) => Box::new(class.with_message(message)),
+        None => message.into(),
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct OutputParseErr;
 
@@ -112,6 +260,46 @@ pub(crate) fn format_output(
     }
 }
 
+/// An error rendered as JSON for commands run with `--output json`, which is printed as is,
+/// along with the exit code of the original error
+#[derive(Debug)]
+pub(crate) struct JsonError(serde_json::Value, i32);
+
+impl Error for JsonError {}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Renders an error as `{"result": "error", "error": "..."}` when the output is JSON, so
+/// scripts can parse failures the same way as results
+pub(crate) fn format_error(e: Box<dyn Error>, output_kind: &OutputKind) -> Box<dyn Error> {
+    format_error_with(e, output_kind, serde_json::json!({}))
+}
+
+/// Like `format_error`, adding the fields of `details` to the JSON rendering, e.g. the
+/// results that were gathered before the command failed
+pub(crate) fn format_error_with(
+    e: Box<dyn Error>,
+    output_kind: &OutputKind,
+    details: serde_json::Value,
+) -> Box<dyn Error> {
+    match output_kind {
+        OutputKind::Text { .. } => e,
+        OutputKind::Json => {
+            let mut json = serde_json::json!({"result": "error", "error": e.to_string()});
+            if let (Some(json), serde_json::Value::Object(details)) =
+                (json.as_object_mut(), details)
+            {
+                json.extend(details);
+            }
+            Box::new(JsonError(json, exit_code(&*e)))
+        }
+    }
+}
+
 pub(crate) fn format_ellipsis(id: String, max_width: usize) -> String {
     if id.len() > max_width {
         let ellipsis = "...";
@@ -147,6 +335,15 @@ pub(crate) fn convert_error(
     Box::<dyn std::error::Error>::from(format!("{}", e))
 }
 
+/// Converts an error of the control interface client to a network error. The client fails
+/// when the lattice can't be reached over NATS or no host responds in time, while hosts report
+/// the failure of an operation in its acknowledgement
+pub(crate) fn convert_network_error(
+    e: Box<dyn ::std::error::Error + Send + Sync>,
+) -> Box<dyn ::std::error::Error> {
+    Box::new(WashError::Network(e.to_string()))
+}
+
 /// Transforms a list of labels in the form of (label=value) to a hashmap
 pub(crate) fn labels_vec_to_hashmap(constraints: Vec<String>) -> Result<HashMap<String, String>> {
     let mut hm: HashMap<String, String> = HashMap::new();
@@ -170,6 +367,39 @@ pub(crate) fn json_str_to_msgpack_bytes(payload: Vec<String>) -> Result<Vec<u8>>
     Ok(payload)
 }
 
+/// Parses a human duration made of a whole number and a unit suffix, e.g. `90s`, `15m`, `12h`,
+/// `30d` or `2w`
+pub(crate) fn parse_duration(input: &str) -> ::std::result::Result<Duration, String> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (amount, unit) = input.split_at(split);
+    let amount: u64 = amount.parse().map_err(|_| {
+        format!(
+            "Invalid duration '{}', expected a value like 30d or 12h",
+            input
+        )
+    })?;
+    let unit_secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 60 * 60 * 24,
+        "w" => 60 * 60 * 24 * 7,
+        _ => {
+            return Err(format!(
+                "Invalid duration unit in '{}', expected one of s, m, h, d or w",
+                input
+            ))
+        }
+    };
+    amount
+        .checked_mul(unit_secs)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("Duration '{}' is too large", input))
+}
+
 /// Helper function to either display input to stdout or log the output in the REPL
 pub(crate) fn print_or_log(output: String) {
     match output_destination() {
@@ -178,6 +408,132 @@ pub(crate) fn print_or_log(output: String) {
     }
 }
 
+/// Writes a `sha256sum` compatible `<path>.sha256` file next to the file at `path`, holding
+/// its hex digest and file name so it can be checked with `sha256sum -c`. Returns the path
+/// of the checksum file
+pub(crate) fn write_checksum_file(path: &str) -> Result<String> {
+    let digest = Sha256::digest(&std::fs::read(path)?);
+    let name = Path::new(path)
+        .file_name()
+        .ok_or_else(|| format!("Can't write a checksum for {}", path))?
+        .to_string_lossy();
+    let checksum_file = format!("{}{}", path, CHECKSUM_FILE_EXTENSION);
+    std::fs::write(&checksum_file, format!("{:x}  {}\n", digest, name))?;
+    Ok(checksum_file)
+}
+
+/// Returns true when the global `--quiet` flag is set
+pub(crate) fn quiet_mode() -> bool {
+    QUIET_MODE.get().copied().unwrap_or(false)
+}
+
+/// Returns true when wash must not contact registries, see `--offline`
+pub(crate) fn offline_mode() -> bool {
+    OFFLINE_MODE.get().copied().unwrap_or(false)
+}
+
+/// Offline mode is also turned on by setting `WASH_OFFLINE`, for air-gapped machines
+pub(crate) fn detect_offline() -> bool {
+    matches!(std::env::var_os("WASH_OFFLINE"), Some(v) if !v.is_empty())
+}
+
+/// Makes each value the default of the flags that read the paired environment variable,
+/// throughout the app and its subcommands. Flags given on the command line still take
+/// precedence, while the environment variable is no longer consulted
+pub(crate) fn with_env_defaults<'a, 'b>(
+    mut app: App<'a, 'b>,
+    defaults: &[(&str, String)],
+) -> App<'a, 'b> {
+    for opt in app.p.opts.iter_mut() {
+        let default = opt.v.env.as_ref().and_then(|(var, _)| {
+            defaults
+                .iter()
+                .find(|(default_var, _)| OsStr::new(default_var) == *var)
+        });
+        if let Some((_, value)) = default {
+            // Clap borrows default values for as long as the app lives
+            let value: &'static str = Box::leak(value.clone().into_boxed_str());
+            opt.v.default_val = Some(OsStr::new(value));
+            opt.v.env = None;
+        }
+    }
+    app.p.subcommands = app
+        .p
+        .subcommands
+        .into_iter()
+        .map(|sub| with_env_defaults(sub, defaults))
+        .collect();
+    app
+}
+
+/// Returns true if colors and other ANSI escape codes should be left out of the output
+pub(crate) fn no_color_mode() -> bool {
+    NO_COLOR_MODE.get().copied().unwrap_or(false)
+}
+
+/// Follows the `NO_COLOR` convention (https://no-color.org), also disabling colors when
+/// stdout is redirected to a file or another program
+pub(crate) fn detect_no_color() -> bool {
+    matches!(std::env::var_os("NO_COLOR"), Some(v) if !v.is_empty())
+        || !termion::is_tty(&std::io::stdout())
+}
+
+/// Returns true if emoji and other non-ASCII decorations should be left out of the output
+pub(crate) fn no_emoji_mode() -> bool {
+    NO_EMOJI_MODE.get().copied().unwrap_or(false)
+}
+
+/// Emoji are left out when `WASH_NO_EMOJI` is set, or when the terminal is dumb or the
+/// locale isn't UTF-8, as those would print them as garbage
+pub(crate) fn detect_no_emoji() -> bool {
+    let var = |name| std::env::var(name).ok().filter(|v| !v.is_empty());
+    var("WASH_NO_EMOJI").is_some()
+        || var("TERM").as_deref() == Some("dumb")
+        || !is_utf8_locale(
+            var("LC_ALL")
+                .or_else(|| var("LC_CTYPE"))
+                .or_else(|| var("LANG")),
+        )
+}
+
+fn is_utf8_locale(locale: Option<String>) -> bool {
+    match locale {
+        Some(locale) => {
+            let locale = locale.to_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        }
+        // Windows consoles don't advertise their encoding through the locale
+        None => cfg!(windows),
+    }
+}
+
+/// Returns true if spinners and progress bars can be drawn without corrupting the output,
+/// which requires text output on a color capable CLI without `--quiet`
+pub(crate) fn show_progress(kind: &OutputKind) -> bool {
+    matches!(kind, OutputKind::Text { .. })
+        && output_destination() == OutputDestination::Cli
+        && !quiet_mode()
+        && !no_color_mode()
+}
+
+/// Starts a spinner showing `msg`. Like all progress output it's drawn on stderr, leaving
+/// stdout for the result of the command
+pub(crate) fn start_spinner(msg: String) -> ProgressBar {
+    let spinner = ProgressBar::new_spinner();
+    // A spinner dropped by an interrupted command is cleared rather than left behind
+    let style = ProgressStyle::default_spinner()
+        .template(" {spinner} {msg}")
+        .on_finish(ProgressFinish::AndClear);
+    spinner.set_style(if no_emoji_mode() {
+        style.tick_chars("-\\|/ ")
+    } else {
+        style
+    });
+    spinner.enable_steady_tick(80);
+    spinner.set_message(msg);
+    spinner
+}
+
 /// Helper function to retrieve REPL_MODE environment variable to determine output destination
 pub(crate) fn output_destination() -> OutputDestination {
     // REPL_MODE is Some("true") when in REPL, otherwise CLI
@@ -221,9 +577,71 @@ fn empty_table_style() -> TableStyle {
     }
 }
 
+/// Level of wash's own log output for the number of `-v` flags given
+pub(crate) fn verbosity_level(verbose: u8) -> LevelFilter {
+    match verbose {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+/// Logs to stderr when wash runs as a CLI. Nothing is logged unless `RUST_LOG` is set, and
+/// `-v` flags raise the level of wash's own log targets above it
+pub(crate) fn init_cli_logger(verbose: u8) {
+    let mut builder = env_logger::Builder::new();
+    builder.filter_level(LevelFilter::Off);
+    if let Ok(filters) = std::env::var("RUST_LOG") {
+        builder.parse_filters(&filters);
+    }
+    if verbose > 0 {
+        let level = verbosity_level(verbose);
+        for target in &["wash", WASH_CMD_INFO, WASH_LOG_INFO] {
+            builder.filter_module(target, level);
+        }
+    }
+    // Only fails if a logger is already set, in which case that one is kept
+    let _ = builder.try_init();
+}
+
+/// Prompts on stderr and reads a yes/no answer, defaulting to no
+pub(crate) fn confirm(prompt: &str, input: &mut impl std::io::BufRead) -> Result<bool> {
+    use std::io::Write;
+    eprint!("{}", prompt);
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    Ok(matches!(
+        answer.trim().to_ascii_lowercase().as_str(),
+        "y" | "yes"
+    ))
+}
+
+/// Prompts on stderr for a secret and reads it without echoing it to the terminal
+pub(crate) fn prompt_secret(prompt: &str) -> Result<String> {
+    use std::io::Write;
+    use termion::input::TermRead;
+    eprint!("{}", prompt);
+    std::io::stderr().flush()?;
+    let secret = std::io::stdin()
+        .lock()
+        .read_passwd(&mut std::io::stderr())?;
+    eprintln!();
+    Ok(secret.unwrap_or_default())
+}
+
 #[cfg(test)]
 mod test {
-    use super::{configure_table_style, format_ellipsis};
+    use super::{
+        configure_table_style, confirm, convert_network_error, detect_no_color, detect_no_emoji,
+        exit_code, format_ellipsis, format_error, format_error_with, is_utf8_locale,
+        parse_duration, verbosity_level, with_context, with_env_defaults, write_checksum_file,
+        Aborted, OutputKind, WashError, EXIT_ABORTED, EXIT_FAILURE,
+    };
+    use log::LevelFilter;
+    use std::error::Error;
+    use std::time::Duration;
     use term_table::{row::Row, table_cell::TableCell, Table};
 
     #[test]
@@ -236,6 +654,61 @@ mod test {
         assert_eq!("hello world", &format_ellipsis("hello world".into(), 11));
     }
 
+    #[test]
+    fn no_color_env() {
+        std::env::set_var("NO_COLOR", "1");
+        assert!(detect_no_color());
+        std::env::remove_var("NO_COLOR");
+    }
+
+    #[test]
+    fn checksum_file_is_sha256sum_compatible() {
+        let dir = std::env::temp_dir().join(format!("wash-checksum-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let artifact = dir.join("echo.wasm");
+        std::fs::write(&artifact, b"hello world").unwrap();
+
+        let checksum_file = write_checksum_file(artifact.to_str().unwrap()).unwrap();
+        assert_eq!(checksum_file, format!("{}.sha256", artifact.display()));
+        assert_eq!(
+            std::fs::read_to_string(&checksum_file).unwrap(),
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9  echo.wasm\n"
+        );
+        assert!(write_checksum_file(dir.join("missing.wasm").to_str().unwrap()).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn no_emoji_detection() {
+        std::env::set_var("WASH_NO_EMOJI", "1");
+        assert!(detect_no_emoji());
+        std::env::remove_var("WASH_NO_EMOJI");
+
+        assert!(is_utf8_locale(Some("en_US.UTF-8".into())));
+        assert!(is_utf8_locale(Some("C.utf8".into())));
+        assert!(!is_utf8_locale(Some("C".into())));
+        assert!(!is_utf8_locale(Some("en_US.ISO-8859-1".into())));
+    }
+
+    #[test]
+    fn parse_duration_units() {
+        assert_eq!(parse_duration("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("15m").unwrap(), Duration::from_secs(900));
+        assert_eq!(parse_duration("12h").unwrap(), Duration::from_secs(43_200));
+        assert_eq!(
+            parse_duration("30d").unwrap(),
+            Duration::from_secs(2_592_000)
+        );
+        assert_eq!(
+            parse_duration("2w").unwrap(),
+            Duration::from_secs(1_209_600)
+        );
+        assert!(parse_duration("30").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("30y").is_err());
+        assert!(parse_duration("-1d").is_err());
+    }
+
     #[test]
     fn max_table_width_one_column() {
         let mut table = Table::new();
@@ -274,4 +747,123 @@ mod test {
 
         assert_eq!(9, max_line_width);
     }
+
+    #[test]
+    fn test_verbosity_level() {
+        assert_eq!(verbosity_level(0), LevelFilter::Off);
+        assert_eq!(verbosity_level(1), LevelFilter::Info);
+        assert_eq!(verbosity_level(2), LevelFilter::Debug);
+        assert_eq!(verbosity_level(3), LevelFilter::Trace);
+        assert_eq!(verbosity_level(9), LevelFilter::Trace);
+    }
+
+    #[test]
+    fn test_confirm() {
+        assert!(confirm("", &mut "y\n".as_bytes()).unwrap());
+        assert!(confirm("", &mut "YES\n".as_bytes()).unwrap());
+        assert!(!confirm("", &mut "n\n".as_bytes()).unwrap());
+        assert!(!confirm("", &mut "\n".as_bytes()).unwrap());
+        assert!(!confirm("", &mut "".as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn test_exit_code() {
+        let code = |e: Box<dyn Error>| exit_code(&*e);
+        assert_eq!(code(Box::new(Aborted)), EXIT_ABORTED);
+        assert_eq!(code("failed".into()), EXIT_FAILURE);
+        assert_eq!(code(Box::new(WashError::InvalidInput("bad".into()))), 2);
+        assert_eq!(code(Box::new(WashError::Auth("denied".into()))), 3);
+        assert_eq!(code(Box::new(WashError::Network("down".into()))), 4);
+        assert_eq!(code(Box::new(WashError::NotFound("gone".into()))), 5);
+        assert_eq!(code(Box::new(WashError::Validation("tampered".into()))), 6);
+        assert_eq!(code(Box::new(WashError::Io("full".into()))), 7);
+
+        let io = |kind| Box::new(std::io::Error::from(kind));
+        assert_eq!(code(io(std::io::ErrorKind::NotFound)), 5);
+        assert_eq!(code(io(std::io::ErrorKind::ConnectionRefused)), 4);
+        assert_eq!(code(io(std::io::ErrorKind::PermissionDenied)), 7);
+        assert_eq!(code(convert_network_error("timed out".into())), 4);
+
+        // Rewording an error or rendering it as JSON keeps its exit code
+        let reworded = with_context(io(std::io::ErrorKind::TimedOut), "Pull failed".into());
+        assert_eq!(reworded.to_string(), "Pull failed");
+        assert_eq!(code(reworded), 4);
+        assert_eq!(
+            code(with_context("failed".into(), "Pull failed".into())),
+            EXIT_FAILURE
+        );
+        let json = format_error(
+            Box::new(WashError::Auth("denied".into())),
+            &OutputKind::Json,
+        );
+        assert_eq!(json.to_string(), r#"{"error":"denied","result":"error"}"#);
+        assert_eq!(code(json), 3);
+        let json = format_error_with(
+            Box::new(WashError::Network("timed out".into())),
+            &OutputKind::Json,
+            serde_json::json!({"failed": 1}),
+        );
+        assert_eq!(
+            json.to_string(),
+            r#"{"error":"timed out","failed":1,"result":"error"}"#
+        );
+        assert_eq!(code(json), 4);
+    }
+
+    #[test]
+    fn test_with_env_defaults() {
+        use structopt::clap::{App, Arg, SubCommand};
+        std::env::set_var("WASH_TEST_ENV_DEFAULTS_HOST", "from-env");
+        let app = || {
+            App::new("wash").subcommand(
+                SubCommand::with_name("ctl")
+                    .arg(
+                        Arg::with_name("host")
+                            .long("host")
+                            .takes_value(true)
+                            .env("WASH_TEST_ENV_DEFAULTS_HOST"),
+                    )
+                    .arg(
+                        Arg::with_name("port")
+                            .long("port")
+                            .default_value("4222")
+                            .env("WASH_TEST_ENV_DEFAULTS_PORT"),
+                    ),
+            )
+        };
+        let defaults = vec![
+            (
+                "WASH_TEST_ENV_DEFAULTS_HOST",
+                "nats.example.com".to_string(),
+            ),
+            ("WASH_TEST_ENV_DEFAULTS_PORT", "4223".to_string()),
+        ];
+        let value = |args: &[&str], name: &str| {
+            let matches = with_env_defaults(app(), &defaults)
+                .get_matches_from_safe(args)
+                .unwrap();
+            matches
+                .subcommand_matches("ctl")
+                .and_then(|m| m.value_of(name))
+                .map(String::from)
+        };
+        assert_eq!(
+            value(&["wash", "ctl"], "host").as_deref(),
+            Some("nats.example.com")
+        );
+        assert_eq!(value(&["wash", "ctl"], "port").as_deref(), Some("4223"));
+        assert_eq!(
+            value(&["wash", "ctl", "--host", "127.0.0.1"], "host").as_deref(),
+            Some("127.0.0.1")
+        );
+        // Without a default the environment variable is still read
+        let matches = with_env_defaults(app(), &[])
+            .get_matches_from_safe(["wash", "ctl"])
+            .unwrap();
+        assert_eq!(
+            matches.subcommand_matches("ctl").unwrap().value_of("host"),
+            Some("from-env")
+        );
+        std::env::remove_var("WASH_TEST_ENV_DEFAULTS_HOST");
+    }
 }
@@ -1,6 +1,8 @@
 mod common;
 use common::{output_to_string, test_dir_file, test_dir_with_subfolder, wash};
+use sha2::{Digest, Sha256};
 use std::fs::remove_dir_all;
+use wascap::jwt::{CapabilityProvider, Claims};
 
 #[test]
 fn integration_claims_sign() {
@@ -57,6 +59,101 @@ fn integration_claims_sign() {
     remove_dir_all(sign_dir).unwrap();
 }
 
+#[test]
+fn integration_claims_sign_provider() {
+    const SUBFOLDER: &str = "claims_sign_provider";
+    const ISSUER: &str = "SAACTTUPKR55VUWUDK7GJ5SU5KGED455FR7BDO46RUVOTHUWKBLECLH2UU";
+    const SUBJECT: &str = "SVAOZUSBWWFL65P255DOHIETPTXUQMM5ETLSYPITI5G4K4HI6M2CDAPWAU";
+    // The smallest valid module, a header without any sections
+    const MODULE: &[u8] = b"\0asm\x01\0\0\0";
+    let sign_dir = test_dir_with_subfolder(SUBFOLDER);
+
+    let module = test_dir_file(SUBFOLDER, "provider.wasm");
+    std::fs::write(&module, MODULE).unwrap();
+    let signed = test_dir_file(SUBFOLDER, "provider_signed.wasm");
+    let sign_provider = wash()
+        .args([
+            "claims",
+            "sign",
+            module.to_str().unwrap(),
+            "--provider",
+            "--capid",
+            "wasmcloud:testing",
+            "--vendor",
+            "TestRunner",
+            "--rev",
+            "3",
+            "--ver",
+            "1.2.3",
+            "--issuer",
+            ISSUER,
+            "--subject",
+            SUBJECT,
+            "--disable-keygen",
+            "--destination",
+            signed.to_str().unwrap(),
+        ])
+        .output()
+        .expect("failed to sign provider module");
+    assert!(sign_provider.status.success());
+
+    // Signing appends the token to the module as a "jwt" custom section, which is the
+    // custom section id, its size, the length of its name, its name and the token
+    let signed_module = std::fs::read(&signed).unwrap();
+    let (header, section) = signed_module.split_at(MODULE.len());
+    assert_eq!(header, MODULE);
+    assert_eq!(section[0], 0);
+    let name = section
+        .windows(4)
+        .position(|w| w == b"\x03jwt")
+        .expect("signed module has no jwt section");
+    let jwt = std::str::from_utf8(&section[name + 4..]).unwrap();
+
+    let claims = Claims::<CapabilityProvider>::decode(jwt).unwrap();
+    assert_eq!(
+        claims.issuer,
+        "AA7R5L74E45BJ4XVUYTELQ56P5VCOSPOAA474L7QWH4ZAILLKTZFWYYW"
+    );
+    assert_eq!(
+        claims.subject,
+        "VBM5JMFOVUJDHGTOJSPUJ33ZGHCRCJ3LYHUJ3HND5ZMRVORYCMAVPZQF"
+    );
+    let metadata = claims.metadata.unwrap();
+    assert_eq!(metadata.capid, "wasmcloud:testing");
+    assert_eq!(metadata.vendor, "TestRunner");
+    assert_eq!(metadata.rev, Some(3));
+    assert_eq!(metadata.ver, Some("1.2.3".to_string()));
+    assert_eq!(
+        metadata.target_hashes.get("wasm32-unknown"),
+        Some(&format!("{:X}", Sha256::digest(MODULE)))
+    );
+
+    // Provider claims aren't actor claims, so neither inspect nor push accept the module
+    let inspect_provider = wash()
+        .args(["claims", "inspect", signed.to_str().unwrap()])
+        .output()
+        .expect("failed to run claims inspect");
+    assert!(!inspect_provider.status.success());
+    assert!(String::from_utf8_lossy(&inspect_provider.stderr)
+        .contains("is a wasm-based capability provider"));
+
+    let push_provider = wash()
+        .args([
+            "reg",
+            "push",
+            "localhost:5000/provider:claimssign",
+            signed.to_str().unwrap(),
+            "--insecure",
+        ])
+        .output()
+        .expect("failed to run reg push");
+    assert!(!push_provider.status.success());
+    assert!(String::from_utf8_lossy(&push_provider.stderr)
+        .contains("is a wasm-based capability provider"));
+
+    remove_dir_all(sign_dir).unwrap();
+}
+
 #[test]
 fn integration_claims_inspect() {
     const SUBFOLDER: &str = "claims_inspect";
@@ -107,6 +204,7 @@ fn integration_claims_inspect() {
     assert!(local_inspect_output.contains(&format!("\"module\":\"{}\"", ECHO_MOD)));
     assert!(local_inspect_output.contains("\"can_be_used\":\"immediately\""));
     assert!(local_inspect_output.contains("\"capabilities\":[\"HTTP Server\"]"));
+    assert!(local_inspect_output.contains("\"contract_ids\":[\"wasmcloud:httpserver\"]"));
     assert!(local_inspect_output.contains("\"expires\":\"never\""));
     assert!(local_inspect_output.contains("\"tags\":\"None\""));
     assert!(local_inspect_output.contains("\"version\":\"0.2.1\""));
@@ -128,10 +226,25 @@ fn integration_claims_inspect() {
     assert!(local_reg_inspect_output.contains(&format!("\"module\":\"{}\"", ECHO_MOD)));
     assert!(local_reg_inspect_output.contains("\"can_be_used\":\"immediately\""));
     assert!(local_reg_inspect_output.contains("\"capabilities\":[\"HTTP Server\"]"));
+    assert!(local_reg_inspect_output.contains("\"contract_ids\":[\"wasmcloud:httpserver\"]"));
     assert!(local_reg_inspect_output.contains("\"expires\":\"never\""));
     assert!(local_reg_inspect_output.contains("\"tags\":\"None\""));
     assert!(local_reg_inspect_output.contains("\"version\":\"0.2.1\""));
 
+    let oci_scheme_inspect = wash()
+        .args(&[
+            "claims",
+            "inspect",
+            "oci://localhost:5000/echo:claimsinspect",
+            "--insecure",
+            "-o",
+            "json",
+        ])
+        .output()
+        .expect("failed to inspect local registry wasm with oci:// prefix");
+    assert!(oci_scheme_inspect.status.success());
+    assert!(output_to_string(oci_scheme_inspect).contains(&format!("\"module\":\"{}\"", ECHO_MOD)));
+
     let remote_inspect = wash()
         .args(&[
             "claims",
@@ -1,7 +1,8 @@
 mod common;
-use common::{output_to_string, test_dir_file, test_dir_with_subfolder, wash};
-use std::fs::{remove_dir_all, File};
+use common::{output_to_string, wash};
+use std::fs::{create_dir_all, remove_dir_all, File};
 use std::io::prelude::*;
+use std::path::{Path, PathBuf};
 
 const LIB: &str = "wasmcloudcache";
 const OCI: &str = "wasmcloud_ocicache";
@@ -12,23 +13,39 @@ const OCI: &str = "wasmcloud_ocicache";
 /// these tests cannot be run concurrently as the interactions with
 /// std::env can affect other tests
 fn integration_drain_comprehensive() {
-    integration_drain_lib();
-    integration_drain_oci();
-    integration_drain_all();
+    // The caches are created under the system temporary directory, which the tests then
+    // point TMPDIR at, so it's resolved once up front
+    let tmp = std::env::temp_dir();
+    integration_drain_lib(&tmp);
+    integration_drain_oci(&tmp);
+    integration_drain_all(&tmp);
+}
+
+/// Creates an empty directory for a test under `tmp`, replacing any left by a previous run
+fn test_dir(tmp: &Path, name: &str) -> PathBuf {
+    let dir = tmp.join(format!("wash_integration_{}", name));
+    let _ = remove_dir_all(&dir);
+    create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Creates a cache directory in a test directory, with empty nested directories
+fn cache_dir(test_dir: &Path, name: &str) -> PathBuf {
+    let dir = test_dir.join(name);
+    create_dir_all(dir.join("a/b/c/d/e")).unwrap();
+    dir
 }
 
 /// Ensures that `wash drain` empties the `wasmcloudcache` directory
-fn integration_drain_lib() {
-    let test_dir = test_dir_with_subfolder("drain_lib");
-    let lib_subdir = &format!("drain_lib/{}", LIB);
-    let lib_dir = test_dir_with_subfolder(lib_subdir);
-    let _nested_dir = test_dir_with_subfolder(&format!("{}/a/b/c/d/e", lib_subdir));
+fn integration_drain_lib(tmp: &Path) {
+    let test_dir = test_dir(tmp, "drain_lib");
+    let lib_dir = cache_dir(&test_dir, LIB);
 
     // Create dummy wasm and parJEEzy files
-    let wasm = test_dir_file(lib_subdir, "hello.wasm");
+    let wasm = lib_dir.join("hello.wasm");
     let mut wasm_file = File::create(wasm).unwrap();
     wasm_file.write_all(b"bytes_or_something_idk").unwrap();
-    let provider = test_dir_file(lib_subdir, "world.par.gz");
+    let provider = lib_dir.join("world.par.gz");
     let mut provider_file = File::create(provider).unwrap();
     provider_file.write_all(b"parcheesi").unwrap();
 
@@ -37,6 +54,18 @@ fn integration_drain_lib() {
     // Set TMP for Windows based systems
     std::env::set_var("TMP", test_dir.clone());
 
+    let drain_dry_run = wash()
+        .args(&["drain", "lib", "--dry-run", "-o", "json"])
+        .output()
+        .unwrap_or_else(|_| panic!("failed to dry run drain {:?}", lib_dir.clone()));
+    assert!(drain_dry_run.status.success());
+    let dry_run_output = output_to_string(drain_dry_run);
+    assert!(dry_run_output.contains("\"dry_run\":true"));
+    assert!(dry_run_output.contains("\"bytes\":31"));
+    assert!(dry_run_output.contains("hello.wasm"));
+    // Ensures that a dry run leaves the cache untouched
+    assert_eq!(lib_dir.read_dir().unwrap().count(), 3);
+
     let drain_basic = wash()
         .args(&["drain", "lib", "-o", "json"])
         .output()
@@ -44,7 +73,10 @@ fn integration_drain_lib() {
     assert!(drain_basic.status.success());
     assert_eq!(
         output_to_string(drain_basic),
-        format!("{{\"drained\":[\"{}\"]}}\n", lib_dir.to_str().unwrap())
+        format!(
+            "{{\"bytes_freed\":31,\"drained\":[\"{}\"],\"files_removed\":2}}\n",
+            lib_dir.to_str().unwrap()
+        )
     );
     // Ensures that the directory is empty (files have been removed)
     assert!(lib_dir.read_dir().unwrap().next().is_none());
@@ -53,18 +85,15 @@ fn integration_drain_lib() {
 }
 
 /// Ensures that `wash drain` empties the `wasmcloudcache` directory
-fn integration_drain_oci() {
-    let test_dir = test_dir_with_subfolder("drain_oci");
-    let oci_subdir = &format!("drain_oci/{}", OCI);
-    let oci_dir = test_dir_with_subfolder(oci_subdir);
-
-    let _nested_dir = test_dir_with_subfolder(&format!("{}/a/b/c/d/e", oci_subdir));
+fn integration_drain_oci(tmp: &Path) {
+    let test_dir = test_dir(tmp, "drain_oci");
+    let oci_dir = cache_dir(&test_dir, OCI);
 
     // Create dummy wasm and parJEEzy files
-    let wasm = test_dir_file(oci_subdir, "hello.wasm");
+    let wasm = oci_dir.join("hello.wasm");
     let mut wasm_file = File::create(wasm).unwrap();
     wasm_file.write_all(b"bytes_or_something_idk").unwrap();
-    let provider = test_dir_file(oci_subdir, "world.par.gz");
+    let provider = oci_dir.join("world.par.gz");
     let mut provider_file = File::create(provider).unwrap();
     provider_file.write_all(b"parcheesi").unwrap();
 
@@ -80,7 +109,10 @@ fn integration_drain_oci() {
     assert!(drain_basic.status.success());
     assert_eq!(
         output_to_string(drain_basic),
-        format!("{{\"drained\":[\"{}\"]}}\n", oci_dir.to_str().unwrap())
+        format!(
+            "{{\"bytes_freed\":31,\"drained\":[\"{}\"],\"files_removed\":2}}\n",
+            oci_dir.to_str().unwrap()
+        )
     );
     // Ensures that the directory is empty (files have been removed)
     assert!(oci_dir.read_dir().unwrap().next().is_none());
@@ -89,21 +121,16 @@ fn integration_drain_oci() {
 }
 
 /// Ensures that `wash drain` empties the `wasmcloudcache` directory
-fn integration_drain_all() {
-    let test_dir = test_dir_with_subfolder("drain_all");
-    let oci_subdir = &format!("drain_all/{}", OCI);
-    let oci_dir = test_dir_with_subfolder(oci_subdir);
-    let lib_subdir = &format!("drain_all/{}", LIB);
-    let lib_dir = test_dir_with_subfolder(lib_subdir);
-
-    let _nested_dir = test_dir_with_subfolder(&format!("{}/a/b/c/d/e", oci_subdir));
-    let _nested_dir = test_dir_with_subfolder(&format!("{}/a/b/c/d/e", lib_subdir));
+fn integration_drain_all(tmp: &Path) {
+    let test_dir = test_dir(tmp, "drain_all");
+    let oci_dir = cache_dir(&test_dir, OCI);
+    let lib_dir = cache_dir(&test_dir, LIB);
 
     // Create dummy wasm and parJEEzy files
-    let wasm = test_dir_file(oci_subdir, "hello.wasm");
+    let wasm = oci_dir.join("hello.wasm");
     let mut wasm_file = File::create(wasm).unwrap();
     wasm_file.write_all(b"bytes_or_something_idk").unwrap();
-    let provider = test_dir_file(lib_subdir, "world.par.gz");
+    let provider = lib_dir.join("world.par.gz");
     let mut provider_file = File::create(provider).unwrap();
     provider_file.write_all(b"parcheesi").unwrap();
 
@@ -120,7 +147,7 @@ fn integration_drain_all() {
     assert_eq!(
         output_to_string(drain_basic),
         format!(
-            "{{\"drained\":[\"{}\",\"{}\"]}}\n",
+            "{{\"bytes_freed\":31,\"drained\":[\"{}\",\"{}\"],\"files_removed\":2}}\n",
             lib_dir.to_str().unwrap(),
             oci_dir.to_str().unwrap()
         )
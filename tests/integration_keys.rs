@@ -40,6 +40,7 @@ fn integration_keys_gen_comprehensive() {
         let output = output_to_string(key_gen_command);
         assert!(output.contains("\"public_key\":"));
         assert!(output.contains("\"seed\":"));
+        assert!(output.contains(&format!("\"key_type\":\"{}\"", cmd)));
     });
 }
 
@@ -166,6 +167,29 @@ fn integration_list_comprehensive() {
     assert!(output_json.contains(KEYONE));
     assert!(output_json.contains(KEYTWO));
     assert!(output_json.contains(KEYTHREE));
+    assert!(output_json.contains("\"details\":["));
+    assert!(output_json.contains("\"key_type\":"));
 
     remove_dir_all(list_comprehensive_dir).unwrap();
 }
+
+#[test]
+fn integration_keys_convert() {
+    const SEED: &str = "SAADMA65NBETHOHQTXKV7XKQMXYDUS65JOWQORDR3IOMOB3UFZSDOU7TAA";
+    const PUBLIC_KEY: &str = "AALSO6EPE54BWUHXTVJLDIABLYOTXMCOTK52THAIKMKHD32YYWWGQQPW";
+
+    let convert = wash()
+        .args(&["keys", "convert", SEED, "-o", "json"])
+        .output()
+        .expect("failed to convert seed with keys convert");
+    assert!(convert.status.success());
+    let output = output_to_string(convert);
+    assert!(output.contains(&format!("\"public_key\":\"{}\"", PUBLIC_KEY)));
+    assert!(output.contains("\"key_type\":\"account\""));
+
+    let convert_invalid = wash()
+        .args(&["keys", "convert", "SAnotaseed"])
+        .output()
+        .expect("failed to run keys convert");
+    assert!(!convert_invalid.status.success());
+}
@@ -14,6 +14,8 @@ fn integration_create_and_insert() {
 
     integration_par_create(ISSUER, SUBJECT, pargz.to_str().unwrap());
     integration_par_insert(ISSUER, SUBJECT, pargz.to_str().unwrap());
+    integration_par_replace(ISSUER, SUBJECT, pargz.to_str().unwrap());
+    integration_par_repack(ISSUER, SUBJECT, pargz.to_str().unwrap());
 
     remove_dir_all(test_dir).unwrap();
 }
@@ -57,10 +59,11 @@ fn integration_par_create(issuer: &str, subject: &str, archive: &str) {
         .output()
         .expect("failed to create provider archive file");
     assert!(create.status.success());
-    assert_eq!(
-        output_to_string(create),
-        format!("Successfully created archive {}\n", archive)
-    );
+    let output = output_to_string(create);
+    assert!(output.starts_with(&format!(
+        "Successfully created archive {}\nDigest: sha256:",
+        archive
+    )));
 
     let inspect_created = wash()
         .args(&["par", "inspect", archive, "-o", "json"])
@@ -78,8 +81,38 @@ fn integration_par_create(issuer: &str, subject: &str, archive: &str) {
     );
     assert!(output.contains("\"rev\":\"42\""));
     assert!(output.contains("\"targets\":[\"x86_64-linux\"]"));
+    assert!(output.contains("\"expires\":null"));
     assert!(output.contains("\"vendor\":\"TestRunner\""));
     assert!(output.contains("\"ver\":\"3.2.1\""));
+    assert!(output.contains(
+        "\"libraries\":[{\"sha256\":\"8253e6be7c5370ae137da5067e8160670bd0736d51e61cb96e848ee3e985e70a\",\"size\":26,\"target\":\"x86_64-linux\"}]"
+    ));
+
+    let verify_issuer = wash()
+        .args(&[
+            "par",
+            "verify",
+            archive,
+            "--issuer",
+            "AA7R5L74E45BJ4XVUYTELQ56P5VCOSPOAA474L7QWH4ZAILLKTZFWYYW",
+        ])
+        .output()
+        .expect("failed to verify provider archive issuer");
+    assert!(verify_issuer.status.success());
+
+    let verify_wrong_issuer = wash()
+        .args(&[
+            "par",
+            "verify",
+            archive,
+            "--issuer",
+            "ACOJJN6WUP4ODD75XEBKKTCCUJJCY5ZKQ56XVKYK4BEJWGVAOOQHZMCW",
+        ])
+        .output()
+        .expect("failed to verify provider archive issuer");
+    assert!(!verify_wrong_issuer.status.success());
+    assert!(String::from_utf8_lossy(&verify_wrong_issuer.stderr)
+        .contains("expected ACOJJN6WUP4ODD75XEBKKTCCUJJCY5ZKQ56XVKYK4BEJWGVAOOQHZMCW"));
 
     remove_dir_all(bin_folder).unwrap();
 }
@@ -114,6 +147,7 @@ fn integration_par_insert(issuer: &str, subject: &str, archive: &str) {
             "-s",
             subject,
             "--disable-keygen",
+            "--quiet",
         ])
         .output()
         .expect("failed to insert binary into provider archive");
@@ -165,14 +199,11 @@ fn integration_par_insert(issuer: &str, subject: &str, archive: &str) {
         .output()
         .expect("failed to insert binary into provider archive");
     assert!(insert_bin2.status.success());
-    assert_eq!(
-        output_to_string(insert_bin2),
-        format!(
-            "Successfully inserted {} into archive {}\n",
-            bin2.to_str().unwrap(),
-            archive
-        )
-    );
+    assert!(output_to_string(insert_bin2).starts_with(&format!(
+        "Successfully inserted {} into archive {}\nDigest: sha256:",
+        bin2.to_str().unwrap(),
+        archive
+    )));
 
     let inspect_after_bin2 = wash()
         .args(&["par", "inspect", archive, "-o", "json"])
@@ -196,9 +227,242 @@ fn integration_par_insert(issuer: &str, subject: &str, archive: &str) {
     assert!(output.contains("\"vendor\":\"TestRunner\""));
     assert!(output.contains("\"ver\":\"3.2.1\""));
 
+    let insert_clobber = wash()
+        .args(&[
+            "par",
+            "insert",
+            archive,
+            "-a",
+            ARCH1,
+            "-b",
+            bin2.to_str().unwrap(),
+            "-i",
+            issuer,
+            "-s",
+            subject,
+            "--disable-keygen",
+            "--no-clobber",
+        ])
+        .output()
+        .expect("failed to run par insert");
+    assert!(!insert_clobber.status.success());
+
+    let extracted = test_dir_file(SUBFOLDER, "extracted.so");
+    let extract_bin1 = wash()
+        .args(&[
+            "par",
+            "extract",
+            archive,
+            "-a",
+            ARCH1,
+            "--destination",
+            extracted.to_str().unwrap(),
+        ])
+        .output()
+        .expect("failed to extract binary from provider archive");
+    assert!(extract_bin1.status.success());
+    assert_eq!(
+        std::fs::read(&extracted).unwrap(),
+        b"01101100 01100111".to_vec()
+    );
+
+    let extract_missing = wash()
+        .args(&[
+            "par",
+            "extract",
+            archive,
+            "-a",
+            "riscv64-linux",
+            "--destination",
+            extracted.to_str().unwrap(),
+        ])
+        .output()
+        .expect("failed to run par extract");
+    assert!(!extract_missing.status.success());
+
+    let remove_bin2 = wash()
+        .args(&[
+            "par",
+            "remove",
+            archive,
+            "-a",
+            ARCH2,
+            "-i",
+            issuer,
+            "-s",
+            subject,
+            "--disable-keygen",
+            "-o",
+            "json",
+        ])
+        .output()
+        .expect("failed to remove binary from provider archive");
+    assert!(remove_bin2.status.success());
+    let inspect_after_remove = wash()
+        .args(&["par", "inspect", archive, "-o", "json"])
+        .output()
+        .expect("failed to inspect provider archive file");
+    assert!(inspect_after_remove.status.success());
+    let output = output_to_string(inspect_after_remove);
+    assert!(output.contains("\"mips64-android\""));
+    assert!(!output.contains(&format!("\"{}\"", ARCH2)));
+
+    let remove_missing = wash()
+        .args(&[
+            "par",
+            "remove",
+            archive,
+            "-a",
+            ARCH2,
+            "-i",
+            issuer,
+            "-s",
+            subject,
+            "--disable-keygen",
+        ])
+        .output()
+        .expect("failed to run par remove");
+    assert!(!remove_missing.status.success());
+
     remove_dir_all(insert_dir).unwrap();
 }
 
+/// Tests rebuilding an existing provider archive with a new binary for one of its targets
+fn integration_par_replace(issuer: &str, subject: &str, archive: &str) {
+    const SUBFOLDER: &str = "replace_bin_folder";
+    const ARCH: &str = "x86_64-linux";
+    let replace_dir = test_dir_with_subfolder(SUBFOLDER);
+    let binary = test_dir_file(SUBFOLDER, "linux.so");
+    let mut bin_file = File::create(binary.clone()).unwrap();
+    bin_file.write_all(b"01110010 01100101").unwrap();
+
+    let create_existing = wash()
+        .args([
+            "par",
+            "create",
+            "-a",
+            ARCH,
+            "-b",
+            binary.to_str().unwrap(),
+            "-c",
+            "wasmcloud:testing",
+            "-n",
+            "Test parJEEzy",
+            "-v",
+            "TestRunner",
+            "--issuer",
+            issuer,
+            "--subject",
+            subject,
+            "--disable-keygen",
+            "--destination",
+            archive,
+        ])
+        .output()
+        .expect("failed to run par create");
+    assert!(!create_existing.status.success());
+
+    let replace = wash()
+        .args([
+            "par",
+            "create",
+            "-a",
+            ARCH,
+            "-b",
+            binary.to_str().unwrap(),
+            "--issuer",
+            issuer,
+            "--subject",
+            subject,
+            "--disable-keygen",
+            "--destination",
+            archive,
+            "--replace",
+        ])
+        .output()
+        .expect("failed to rebuild provider archive");
+    assert!(replace.status.success());
+    assert!(output_to_string(replace).starts_with(&format!(
+        "Successfully rebuilt archive {}\nDigest: sha256:",
+        archive
+    )));
+
+    let inspect_replaced = wash()
+        .args(["par", "inspect", archive, "-o", "json"])
+        .output()
+        .expect("failed to inspect rebuilt provider archive file");
+    assert!(inspect_replaced.status.success());
+    let output = output_to_string(inspect_replaced);
+    assert!(output.contains("\"capability_contract_id\":\"wasmcloud:testing\""));
+    assert!(output.contains("\"rev\":\"42\""));
+    assert!(output.contains("\"ver\":\"3.2.1\""));
+    assert!(output.contains("\"mips64-android\""));
+    assert!(output.contains(&format!("\"size\":17,\"target\":\"{}\"", ARCH)));
+
+    remove_dir_all(replace_dir).unwrap();
+}
+
+/// Tests that repacking an archive at a different compression level keeps its claims and targets
+fn integration_par_repack(issuer: &str, subject: &str, archive: &str) {
+    let repacked = archive.replace(".par.gz", "_repacked.par.gz");
+
+    let repack = wash()
+        .args([
+            "par",
+            "repack",
+            archive,
+            "--destination",
+            &repacked,
+            "--compression",
+            "9",
+            "--issuer",
+            issuer,
+            "--subject",
+            subject,
+            "--disable-keygen",
+        ])
+        .output()
+        .expect("failed to repack provider archive");
+    assert!(repack.status.success());
+    assert!(output_to_string(repack).starts_with(&format!(
+        "Successfully repacked archive {} to {}",
+        archive, repacked
+    )));
+
+    // Metadata, targets and library hashes are all compared by `par diff`
+    let diff = wash()
+        .args(["par", "diff", archive, &repacked, "-o", "json"])
+        .output()
+        .expect("failed to diff repacked provider archive");
+    assert!(diff.status.success());
+    assert!(output_to_string(diff).contains("\"identical\":true"));
+
+    let inspect_repacked = wash()
+        .args(["par", "inspect", &repacked, "-o", "json"])
+        .output()
+        .expect("failed to inspect repacked provider archive file");
+    assert!(inspect_repacked.status.success());
+    let output = output_to_string(inspect_repacked);
+    assert!(
+        output.contains("\"service\":\"VBM5JMFOVUJDHGTOJSPUJ33ZGHCRCJ3LYHUJ3HND5ZMRVORYCMAVPZQF\"")
+    );
+    assert!(
+        output.contains("\"issuer\":\"AA7R5L74E45BJ4XVUYTELQ56P5VCOSPOAA474L7QWH4ZAILLKTZFWYYW\"")
+    );
+
+    let verify_repacked = wash()
+        .args([
+            "par",
+            "verify",
+            &repacked,
+            "--issuer",
+            "AA7R5L74E45BJ4XVUYTELQ56P5VCOSPOAA474L7QWH4ZAILLKTZFWYYW",
+        ])
+        .output()
+        .expect("failed to verify repacked provider archive issuer");
+    assert!(verify_repacked.status.success());
+}
+
 #[test]
 fn integration_par_inspect() {
     const SUBFOLDER: &str = "claims_inspect";
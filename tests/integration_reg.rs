@@ -26,8 +26,12 @@ fn integration_pull_basic() {
         .output()
         .unwrap_or_else(|_| panic!("failed to pull {}", ECHO_WASM));
     assert!(pull_basic.status.success());
-    // Very important
-    assert!(output_to_string(pull_basic).contains('\u{1F6BF}'));
+    // Very important, but decorations stay on stderr so stdout only holds the pulled file
+    assert!(String::from_utf8_lossy(&pull_basic.stderr).contains('\u{1F6BF}'));
+    assert_eq!(
+        output_to_string(pull_basic),
+        format!("{}\n", basic_echo.to_str().unwrap())
+    );
 
     remove_dir_all(pull_dir).unwrap();
 }
@@ -207,5 +211,97 @@ fn integration_push_comprehensive() {
     assert!(output.contains("\"result\":\"success\""));
     assert!(output.contains(&format!("\"url\":\"{}\"", logging_push_all_options)));
 
+    // Without --config, a provider archive's config records its capability contract ID
+    let logging_push_default_config = &format!("{}/logging:defaultconfig", LOCAL_REGISTRY);
+    let push_default_config = wash()
+        .args([
+            "reg",
+            "push",
+            logging_push_default_config,
+            pull_logging_par.to_str().unwrap(),
+            "--insecure",
+        ])
+        .output()
+        .unwrap_or_else(|_| panic!("failed to push {} without a config", LOGGING_PAR));
+    assert!(push_default_config.status.success());
+    let inspect_config = wash()
+        .args([
+            "reg",
+            "inspect-config",
+            logging_push_default_config,
+            "--insecure",
+            "--output",
+            "json",
+        ])
+        .output()
+        .expect("failed to inspect the config of the pushed archive");
+    assert!(inspect_config.status.success());
+    let output = output_to_string(inspect_config);
+    assert!(output.contains("\"capid\":\"wasmcloud:logging\""));
+
+    remove_dir_all(push_dir).unwrap();
+}
+
+#[test]
+fn integration_push_split_targets() {
+    const SUBFOLDER: &str = "push_split_targets";
+    let push_dir = test_dir_with_subfolder(SUBFOLDER);
+
+    let logging_par = test_dir_file(SUBFOLDER, "logging.par.gz");
+    let pull_logging = wash()
+        .args([
+            "reg",
+            "pull",
+            LOGGING_PAR,
+            "--destination",
+            logging_par.to_str().unwrap(),
+        ])
+        .output()
+        .unwrap_or_else(|_| panic!("failed to pull {} for split targets", LOGGING_PAR));
+    assert!(pull_logging.status.success());
+
+    let logging_split = &format!("{}/logging:splittargets", LOCAL_REGISTRY);
+    let push_split = wash()
+        .args([
+            "reg",
+            "push",
+            logging_split,
+            logging_par.to_str().unwrap(),
+            "--split-targets",
+            "--insecure",
+        ])
+        .output()
+        .expect("failed to push logging.par.gz with split targets");
+    assert!(push_split.status.success());
+
+    // Pulling the split archive reassembles the signed provider archive
+    let reassembled = test_dir_file(SUBFOLDER, "reassembled.par.gz");
+    let pull_split = wash()
+        .args([
+            "reg",
+            "pull",
+            logging_split,
+            "--insecure",
+            "--no-cache",
+            "--verify-claims",
+            "--destination",
+            reassembled.to_str().unwrap(),
+        ])
+        .output()
+        .expect("failed to pull split provider archive");
+    assert!(pull_split.status.success());
+
+    let inspect = |archive: &str| {
+        let output = wash()
+            .args(["par", "inspect", archive, "-o", "json"])
+            .output()
+            .expect("failed to inspect provider archive");
+        assert!(output.status.success());
+        output_to_string(output)
+    };
+    let inspected = inspect(reassembled.to_str().unwrap());
+    assert!(inspected.contains("\"capability_contract_id\":\"wasmcloud:logging\""));
+    assert_eq!(inspected, inspect(logging_par.to_str().unwrap()));
+
     remove_dir_all(push_dir).unwrap();
 }